@@ -78,44 +78,44 @@ impl NetlinkAudit {
             match rx_packet.payload {
                 NetlinkPayload::InnerMessage(AuditMessage::Event((event_id, message))) => {
                     match event_id {
-                        1300 => {
+                        1300
                             // syscall event - 321 is sys_bpf
-                            if message.contains("syscall=321") {
+                            if message.contains("syscall=321") => {
                                 let parts: Vec<&str> = message.split_whitespace().collect();
                                 let a0 = parts
                                     .iter()
                                     .find(|s| s.starts_with("a0="))
                                     .unwrap()
                                     .split('=')
-                                    .last()
+                                    .next_back()
                                     .unwrap();
                                 let uid = parts
                                     .iter()
                                     .find(|s| s.starts_with("uid="))
                                     .expect("uid not found")
                                     .split('=')
-                                    .last()
+                                    .next_back()
                                     .unwrap();
                                 let pid = parts
                                     .iter()
                                     .find(|s| s.starts_with("pid="))
                                     .expect("pid not found")
                                     .split('=')
-                                    .last()
+                                    .next_back()
                                     .unwrap();
                                 let gid = parts
                                     .iter()
                                     .find(|s| s.starts_with("gid="))
                                     .expect("gid not found")
                                     .split('=')
-                                    .last()
+                                    .next_back()
                                     .unwrap();
                                 let comm = parts
                                     .iter()
                                     .find(|s| s.starts_with("comm="))
                                     .unwrap()
                                     .split('=')
-                                    .last()
+                                    .next_back()
                                     .unwrap();
 
                                 log_paylod.syscall_op = a0.parse().unwrap();
@@ -124,7 +124,6 @@ impl NetlinkAudit {
                                 log_paylod.gid = gid.parse().unwrap();
                                 log_paylod.comm = comm.replace('"', "").to_string();
                             }
-                        }
                         1334 => {
                             // ebpf event
                             let parts: Vec<&str> = message.split_whitespace().collect();
@@ -133,14 +132,14 @@ impl NetlinkAudit {
                                 .find(|s| s.starts_with("prog-id="))
                                 .unwrap()
                                 .split('=')
-                                .last()
+                                .next_back()
                                 .unwrap();
                             let op = parts
                                 .iter()
                                 .find(|s| s.starts_with("op="))
                                 .unwrap()
                                 .split('=')
-                                .last()
+                                .next_back()
                                 .unwrap();
                             log_paylod.prog_id = prog_id.parse().unwrap();
                             log_paylod.op = op.to_string();
@@ -162,7 +161,7 @@ impl NetlinkAudit {
                                 .find(|s| s.starts_with("proctitle="))
                                 .unwrap()
                                 .split('=')
-                                .last()
+                                .next_back()
                                 .unwrap();
                             if is_hex.is_match(proctile) {
                                 log_paylod.cmdline = from_utf8(&hex::decode(proctile).unwrap())
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Requests that change state, such as Load and Unload, can be restricted to
+// an allowlist of users and groups from the [authz] config section. Peers are
+// identified by the credentials the kernel records for a Unix socket
+// connection (SO_PEERCRED), so only the primary group of the peer counts.
+// Peers on the TCP listeners have no such credentials, so while the allowlist
+// is set they are only let through if they presented a TLS client
+// certificate that was verified against the [grpc.tls] ca.
+
+use std::sync::RwLock;
+
+use bpfman::config::{AuthzConfig, GrpcConfig};
+use log::warn;
+use tonic::{
+    transport::server::{TcpConnectInfo, UdsConnectInfo},
+    Request, Status,
+};
+
+use crate::tls::TlsConnectInfo;
+
+// Replaced on reload once the new config has been validated, so a config file
+// that fails to parse never lifts the restriction.
+static AUTHZ: RwLock<Option<AuthzConfig>> = RwLock::new(None);
+
+/// Sets the allowlist. `grpc` is the config the TCP listeners were started
+/// with, whose peers are all refused if they aren't asked for a client
+/// certificate.
+pub(crate) fn set_authz(config: Option<AuthzConfig>, grpc: Option<&GrpcConfig>) {
+    let enabled = config.as_ref().is_some_and(|a| a.is_enabled());
+    if let Some(grpc) = grpc.filter(|_| enabled) {
+        let ca = grpc.tls().as_ref().is_some_and(|tls| tls.ca.is_some());
+        if !ca && grpc.listen_addrs().is_ok_and(|addrs| !addrs.is_empty()) {
+            warn!(
+                "[authz] is set but [grpc.tls] has no ca, requests over TCP that change state will be refused"
+            );
+        }
+    }
+    *AUTHZ.write().unwrap() = config;
+}
+
+/// Checks that the peer that sent `request` may make requests that change
+/// state. `action` describes the request in errors, such as "load programs".
+pub(crate) fn authorize<T>(request: &Request<T>, action: &str) -> Result<(), Denied> {
+    let authz = AUTHZ.read().unwrap();
+    let Some(authz) = authz.as_ref().filter(|a| a.is_enabled()) else {
+        return Ok(());
+    };
+    let extensions = request.extensions();
+    let Some(info) = extensions.get::<UdsConnectInfo>() else {
+        let tls = extensions.get::<TlsConnectInfo>();
+        if tls.is_some_and(|tls| tls.client_verified) {
+            return Ok(());
+        }
+        let addr = tls
+            .map(|tls| &tls.tcp)
+            .or_else(|| extensions.get::<TcpConnectInfo>())
+            .and_then(TcpConnectInfo::remote_addr);
+        match addr {
+            Some(addr) => warn!(
+                "Denying request to {action} from {addr}, it has no verified TLS client certificate"
+            ),
+            None => warn!("Denying request to {action}, it has no verified TLS client certificate"),
+        }
+        return Err(Denied {
+            action: action.to_string(),
+            peer: Peer::Unverified,
+        });
+    };
+
+    let Some(cred) = info.peer_cred else {
+        warn!("Denying request to {action}, the peer's credentials are unknown");
+        return Err(Denied {
+            action: action.to_string(),
+            peer: Peer::Unknown,
+        });
+    };
+    if authz.allows(cred.uid(), cred.gid()) {
+        return Ok(());
+    }
+    warn!(
+        "Denying request to {action} from uid {} gid {}",
+        cred.uid(),
+        cred.gid()
+    );
+    Err(Denied {
+        action: action.to_string(),
+        peer: Peer::Cred(cred.uid(), cred.gid()),
+    })
+}
+
+/// Returns the user of the Unix socket peer that sent `request`, for scoping
+/// what the peer sends to it. Requests over TCP have none.
+pub(crate) fn peer_uid<T>(request: &Request<T>) -> Option<u32> {
+    request
+        .extensions()
+        .get::<UdsConnectInfo>()
+        .and_then(|info| info.peer_cred)
+        .map(|cred| cred.uid())
+}
+
+/// Returned when the peer isn't allowed to make a request. It converts to a
+/// `PermissionDenied` status.
+pub(crate) struct Denied {
+    action: String,
+    peer: Peer,
+}
+
+// Who a denied request came from.
+enum Peer {
+    // The user and primary group of a Unix socket peer.
+    Cred(u32, u32),
+    // A Unix socket peer whose credentials couldn't be read.
+    Unknown,
+    // A TCP peer without a verified TLS client certificate.
+    Unverified,
+}
+
+impl From<Denied> for Status {
+    fn from(e: Denied) -> Self {
+        Status::permission_denied(match e.peer {
+            Peer::Cred(uid, gid) => format!("uid {uid} gid {gid} is not allowed to {}", e.action),
+            Peer::Unknown => format!(
+                "unable to read the peer's credentials, not allowed to {}",
+                e.action
+            ),
+            Peer::Unverified => format!(
+                "a verified TLS client certificate is needed to {} over TCP",
+                e.action
+            ),
+        })
+    }
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// tonic has no limit on the number of connections it serves, so each
+// listener's incoming stream is wrapped here. A connection holds a permit for
+// as long as it is open, and connections accepted while none are left are
+// closed straight away rather than left waiting, so clients see the failure.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use log::warn;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::server::Connected;
+
+/// Bounds the number of connections open at once, shared by all listeners.
+#[derive(Clone)]
+pub(crate) struct ConnectionLimit {
+    permits: Arc<Semaphore>,
+    max: usize,
+}
+
+impl ConnectionLimit {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max)),
+            max,
+        }
+    }
+
+    /// Passes on the connections from `incoming` that fit within the limit.
+    pub(crate) fn limit<S, IO, E>(&self, incoming: S) -> impl Stream<Item = Result<Limited<IO>, E>>
+    where
+        S: Stream<Item = Result<IO, E>>,
+    {
+        let limit = self.clone();
+        incoming.filter_map(move |accepted| match accepted {
+            Ok(io) => match limit.permits.clone().try_acquire_owned() {
+                Ok(permit) => Some(Ok(Limited {
+                    io,
+                    _permit: permit,
+                })),
+                Err(_) => {
+                    warn!(
+                        "Closing new connection, {} connections are already open",
+                        limit.max
+                    );
+                    None
+                }
+            },
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
+
+/// A connection admitted by a [`ConnectionLimit`], which gives its permit
+/// back when dropped.
+pub(crate) struct Limited<IO> {
+    io: IO,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<IO: Connected> Connected for Limited<IO> {
+    type ConnectInfo = IO::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.io.connect_info()
+    }
+}
+
+impl<IO: AsyncRead + Unpin> AsyncRead for Limited<IO> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for Limited<IO> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
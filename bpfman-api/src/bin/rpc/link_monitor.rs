@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Watches netlink for interfaces coming back up and has bpfman attach the
+// dispatchers on them again if the kernel dropped them while the interface
+// was down. Only interfaces seen going down are acted on, so interfaces that
+// are created, or whose other settings change, are left alone.
+
+use std::collections::HashMap;
+
+use bpfman::reattach_programs;
+use futures::StreamExt;
+use log::{debug, info, warn};
+use netlink_packet_core::NetlinkPayload;
+use netlink_packet_route::{
+    link::{LinkFlag, LinkMessage},
+    RouteNetlinkMessage,
+};
+use netlink_sys::{AsyncSocket, SocketAddr};
+use rtnetlink::{constants::RTMGRP_LINK, new_connection};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+pub(crate) fn watch_links(
+    mut shutdown_channel: broadcast::Receiver<()>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let (mut connection, _, mut messages) = new_connection()?;
+    connection
+        .socket_mut()
+        .socket_mut()
+        .bind(&SocketAddr::new(0, RTMGRP_LINK))?;
+    tokio::spawn(connection);
+
+    Ok(tokio::spawn(async move {
+        info!("Watching for interfaces coming back up");
+        // Whether each interface seen so far was up.
+        let mut links: HashMap<u32, bool> = HashMap::new();
+        loop {
+            let message = tokio::select! {
+                _ = shutdown_channel.recv() => break,
+                message = messages.next() => match message {
+                    Some((message, _)) => message,
+                    None => {
+                        warn!("Netlink link events stopped, interfaces are no longer watched");
+                        break;
+                    }
+                },
+            };
+
+            match message.payload {
+                NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewLink(link)) => {
+                    let if_index = link.header.index;
+                    let up = is_up(&link);
+                    if links.insert(if_index, up) == Some(false) && up {
+                        reattach(if_index).await;
+                    }
+                }
+                NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelLink(link)) => {
+                    links.remove(&link.header.index);
+                }
+                _ => (),
+            }
+        }
+        debug!("Stopped watching interfaces");
+    }))
+}
+
+// An interface is up once it is both enabled and has a carrier, so pulling a
+// cable counts as going down as well as `ip link set down`.
+fn is_up(link: &LinkMessage) -> bool {
+    link.header.flags.contains(&LinkFlag::Up) && link.header.flags.contains(&LinkFlag::LowerUp)
+}
+
+async fn reattach(if_index: u32) {
+    debug!("Interface {if_index} came back up");
+    match reattach_programs(if_index).await {
+        Ok(ids) if !ids.is_empty() => {
+            info!("Attached programs {ids:?} on interface {if_index} again")
+        }
+        Ok(_) => (),
+        Err(e) => warn!("Unable to attach programs on interface {if_index} again: {e}"),
+    }
+}
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// When not logging to journald, messages are written to stderr either in
+// env_logger's text format or as JSON lines for log collectors. The format
+// can be switched when the config is reloaded, so a single logger is
+// installed that handles both. RUST_LOG filters messages in either format,
+// unless the [logging] config section sets a level, which then replaces it.
+
+use std::{
+    env,
+    io::{self, Write},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use bpfman::config::LogFormat;
+use chrono::{SecondsFormat, Utc};
+use log::{
+    kv::{Error as KvError, Key, Value, VisitSource, VisitValue},
+    LevelFilter, Log, Metadata, Record, SetLoggerError,
+};
+use serde_json::{Map, Value as JsonValue};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// The level set by the config, or NO_LEVEL to filter with RUST_LOG.
+static LEVEL: AtomicUsize = AtomicUsize::new(NO_LEVEL);
+const NO_LEVEL: usize = usize::MAX;
+
+struct Logger {
+    // Filtered by RUST_LOG.
+    text: env_logger::Logger,
+    // Not filtered, for when the config sets the level.
+    unfiltered: env_logger::Logger,
+}
+
+/// Installs the stderr logger, initially logging in the text format.
+pub(crate) fn init() -> Result<(), SetLoggerError> {
+    let text = env_logger::Builder::from_default_env().build();
+    let unfiltered = env_logger::Builder::new()
+        .filter_level(LevelFilter::Trace)
+        .parse_write_style(&env::var(env_logger::DEFAULT_WRITE_STYLE_ENV).unwrap_or_default())
+        .build();
+    let max_level = text.filter();
+    log::set_boxed_logger(Box::new(Logger { text, unfiltered }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Sets the level of messages logged to stderr, replacing the RUST_LOG
+/// filter, or goes back to RUST_LOG if `level` is None.
+pub(crate) fn set_level(level: Option<LevelFilter>) {
+    LEVEL.store(level.map_or(NO_LEVEL, |l| l as usize), Ordering::Relaxed);
+}
+
+fn level() -> Option<LevelFilter> {
+    let level = LEVEL.load(Ordering::Relaxed);
+    LevelFilter::iter().find(|l| *l as usize == level)
+}
+
+/// Switches the format of messages logged to stderr.
+pub(crate) fn set_format(format: LogFormat) {
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match level() {
+            Some(level) => metadata.level() <= level,
+            None => self.text.enabled(metadata),
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        // RUST_LOG can also filter on the message, so check the record.
+        let logger = match level() {
+            Some(level) if record.level() <= level => &self.unfiltered,
+            Some(_) => return,
+            None if self.text.matches(record) => &self.text,
+            None => return,
+        };
+        if !JSON.load(Ordering::Relaxed) {
+            return logger.log(record);
+        }
+        // Write the whole line at once so that lines from different threads
+        // don't interleave.
+        let mut line = to_json(record);
+        line.push('\n');
+        let _ = io::stderr().write_all(line.as_bytes());
+    }
+
+    fn flush(&self) {
+        self.text.flush()
+    }
+}
+
+// Renders the record as a JSON object. Key-value pairs on the record become
+// top level fields alongside the standard ones, which they can't replace.
+fn to_json(record: &Record) -> String {
+    let mut fields = Fields(Map::new());
+    let _ = record.key_values().visit(&mut fields);
+    let mut fields = fields.0;
+
+    fields.insert(
+        "timestamp".to_string(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    fields.insert(
+        "level".to_string(),
+        record.level().as_str().to_lowercase().into(),
+    );
+    fields.insert("target".to_string(), record.target().into());
+    fields.insert("message".to_string(), record.args().to_string().into());
+
+    JsonValue::Object(fields).to_string()
+}
+
+struct Fields(Map<String, JsonValue>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), KvError> {
+        let mut json = Json(JsonValue::Null);
+        value.visit(&mut json)?;
+        self.0.insert(key.to_string(), json.0);
+        Ok(())
+    }
+}
+
+// Keeps numbers and booleans as JSON types, and renders anything else as a
+// string. Unset optional fields become null.
+struct Json(JsonValue);
+
+impl<'v> VisitValue<'v> for Json {
+    fn visit_any(&mut self, value: Value) -> Result<(), KvError> {
+        self.0 = value.to_string().into();
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), KvError> {
+        self.0 = JsonValue::Null;
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), KvError> {
+        self.0 = value.into();
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), KvError> {
+        self.0 = value.into();
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), KvError> {
+        self.0 = value.into();
+        Ok(())
+    }
+}
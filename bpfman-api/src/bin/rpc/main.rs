@@ -1,21 +1,37 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
-use std::{env, fs::create_dir_all, path::PathBuf, str::FromStr};
+use std::{env, fs::create_dir_all, path::PathBuf, str::FromStr, sync::OnceLock};
 
 use anyhow::Context;
+use bpfman::{
+    config::{LogFormat, LoggingConfig},
+    errors::BpfmanError,
+    utils::{check_capabilities, config_path, missing_capabilities, open_config_file},
+};
 use clap::{Args, Parser};
-use log::debug;
+use log::{debug, error, LevelFilter};
 use systemd_journal_logger::{connected_to_journal, JournalLog};
 
 use crate::serve::serve;
 
+mod authz;
+mod connections;
+mod link_monitor;
+mod logger;
+mod metrics;
+mod queue;
+mod reload;
 mod rpc;
 mod serve;
 mod storage;
+mod timeout;
+mod tls;
+mod version;
 
 const BPFMAN_ENV_LOG_LEVEL: &str = "RUST_LOG";
 
 const RTDIR_SOCK: &str = "/run/bpfman-sock";
+const DEFAULT_SOCKET_PATH: &str = "/run/bpfman-sock/bpfman.sock";
 // The CSI socket must be in it's own sub directory so we can easily create a dedicated
 // K8s volume mount for it.
 const RTDIR_BPFMAN_CSI: &str = "/run/bpfman/csi";
@@ -31,9 +47,15 @@ pub(crate) struct Rpc {
     /// Optional: Shutdown after N seconds of inactivity. Use 0 to disable.
     #[clap(long, verbatim_doc_comment, default_value = "15")]
     pub(crate) timeout: u64,
-    #[clap(long, default_value = "/run/bpfman-sock/bpfman.sock")]
+    #[clap(long, verbatim_doc_comment)]
     /// Optional: Configure the location of the bpfman unix socket.
-    pub(crate) socket_path: PathBuf,
+    /// Takes precedence over the [grpc] unix_socket config setting.
+    /// [default: /run/bpfman-sock/bpfman.sock]
+    pub(crate) socket_path: Option<PathBuf>,
+    /// Optional: Check that bpfman-rpc has the capabilities it needs, print
+    /// any that are missing and exit.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) check_caps: bool,
 }
 
 #[derive(Args, Debug)]
@@ -60,6 +82,24 @@ fn manage_rpc_journal_log_level() {
     }
 }
 
+// The log level in effect before the config file was applied, which is used
+// when the config doesn't set one.
+static DEFAULT_LOG_LEVEL: OnceLock<LevelFilter> = OnceLock::new();
+
+/// Applies the [logging] config section. The log level falls back to the
+/// level set at startup from RUST_LOG if the section doesn't set one.
+pub(crate) fn set_logging(logging: &Option<LoggingConfig>) -> Result<(), BpfmanError> {
+    let default = *DEFAULT_LOG_LEVEL.get_or_init(log::max_level);
+    let (level, format) = match logging {
+        Some(logging) => (logging.level_filter()?, logging.format),
+        None => (None, LogFormat::default()),
+    };
+    log::set_max_level(level.unwrap_or(default));
+    logger::set_level(level);
+    logger::set_format(format);
+    Ok(())
+}
+
 fn initialize_rpc(csi_support: bool) -> anyhow::Result<()> {
     if connected_to_journal() {
         // If bpfman is running as a service, log to journald.
@@ -71,7 +111,7 @@ fn initialize_rpc(csi_support: bool) -> anyhow::Result<()> {
         debug!("Log using journald");
     } else {
         // Ignore error if already initialized.
-        let _ = env_logger::try_init();
+        let _ = logger::init();
         debug!("Log using env_logger");
     }
 
@@ -83,12 +123,49 @@ fn initialize_rpc(csi_support: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn print_missing_capabilities() -> anyhow::Result<()> {
+    let missing = missing_capabilities();
+    if missing.is_empty() {
+        println!("bpfman-rpc has all the capabilities it needs");
+        return Ok(());
+    }
+    for (cap, needed_for) in &missing {
+        println!("missing {cap}: needed {needed_for}");
+    }
+    anyhow::bail!("bpfman-rpc is missing capabilities it needs")
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Rpc::parse();
+    if args.check_caps {
+        return print_missing_capabilities();
+    }
     initialize_rpc(args.csi_support)?;
+    // Fail at startup rather than in the mount or load that needs them.
+    check_capabilities()?;
     //TODO https://github.com/bpfman/bpfman/issues/881
-    serve(args.csi_support, args.timeout, &args.socket_path).await?;
+    let config = open_config_file();
+    if let Err(e) = set_logging(config.logging()) {
+        error!("Unable to apply logging config: {e}");
+    }
+    let socket_path = args
+        .socket_path
+        .or_else(|| {
+            config
+                .grpc()
+                .as_ref()
+                .and_then(|grpc| grpc.unix_socket().clone())
+        })
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SOCKET_PATH));
+    serve(
+        &config,
+        config_path(),
+        args.csi_support,
+        args.timeout,
+        &socket_path,
+    )
+    .await?;
 
     Ok(())
 }
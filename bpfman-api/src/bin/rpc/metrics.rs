@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use anyhow::anyhow;
+use bpfman::metrics;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{debug, error, info};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+use crate::queue;
+
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+
+pub(crate) async fn serve_metrics(
+    addr: SocketAddr,
+    mut shutdown_channel: broadcast::Receiver<()>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle)) });
+
+    let server = Server::try_bind(&addr)
+        .map_err(|e| anyhow!("unable to serve metrics on {addr}: {e}"))?
+        .serve(make_svc)
+        .with_graceful_shutdown(async move {
+            match shutdown_channel.recv().await {
+                Ok(()) => debug!("Metrics: Received shutdown signal"),
+                Err(e) => error!("Error receiving shutdown signal {:?}", e),
+            };
+        });
+
+    Ok(tokio::spawn(async move {
+        info!("Serving metrics on {addr}");
+        if let Err(e) = server.await {
+            error!("Metrics listener on {addr} failed: {e}");
+        }
+        info!("Shutdown Metrics Handler {addr}");
+    }))
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => match metrics().await {
+            Ok(m) => Response::builder()
+                .header(CONTENT_TYPE, TEXT_FORMAT)
+                .body(Body::from(m + &queue::encode())),
+            Err(e) => {
+                error!("Unable to gather metrics: {e}");
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(e.to_string()))
+            }
+        },
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+
+    Ok(response.expect("unable to build metrics response"))
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Requests are handled by calling straight into the library, where each one
+// waits for its turn at the database lock. When more requests arrive than
+// the queue has room for, they are turned away with a retryable status
+// instead of piling up behind the lock. Map watches outlive the request that
+// starts them, so they are bounded separately.
+
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use log::warn;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::Status;
+
+// Exported as metrics. There is a single queue per process.
+static CAPACITY: AtomicUsize = AtomicUsize::new(0);
+static OCCUPANCY: AtomicUsize = AtomicUsize::new(0);
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+
+// Each map watch is read on a blocking thread for as long as its client is
+// watching, so only this many may run at once.
+const MAX_MAP_WATCHES: usize = 32;
+
+/// Bounds the number of requests being handled at once.
+pub(crate) struct CommandQueue {
+    permits: Arc<Semaphore>,
+    depth: usize,
+    watches: Arc<Semaphore>,
+}
+
+impl CommandQueue {
+    pub(crate) fn new(depth: usize) -> Self {
+        CAPACITY.store(depth, Ordering::Relaxed);
+        Self {
+            permits: Arc::new(Semaphore::new(depth)),
+            depth,
+            watches: Arc::new(Semaphore::new(MAX_MAP_WATCHES)),
+        }
+    }
+
+    /// Admits a map watch, which may run until the returned permit is
+    /// dropped.
+    pub(crate) fn admit_watch(&self) -> Result<OwnedSemaphorePermit, WatchesFull> {
+        self.watches.clone().try_acquire_owned().map_err(|_| {
+            warn!("Rejecting map watch, {MAX_MAP_WATCHES} maps are already being watched");
+            WatchesFull
+        })
+    }
+
+    /// Admits a request to the queue, where it stays until the returned
+    /// guard is dropped.
+    pub(crate) fn admit(&self) -> Result<Admitted, QueueFull> {
+        match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => {
+                OCCUPANCY.fetch_add(1, Ordering::Relaxed);
+                Ok(Admitted { _permit: permit })
+            }
+            Err(_) => {
+                REJECTED.fetch_add(1, Ordering::Relaxed);
+                warn!("Rejecting request, the command queue is full");
+                Err(QueueFull { depth: self.depth })
+            }
+        }
+    }
+}
+
+/// A request that has been admitted to the [`CommandQueue`].
+pub(crate) struct Admitted {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        OCCUPANCY.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Returned when a request can't be admitted to a full [`CommandQueue`]. It
+/// converts to a `ResourceExhausted` status, which clients can retry.
+pub(crate) struct QueueFull {
+    depth: usize,
+}
+
+impl From<QueueFull> for Status {
+    fn from(e: QueueFull) -> Self {
+        Status::resource_exhausted(format!(
+            "bpfman is busy with {} requests, retry later",
+            e.depth
+        ))
+    }
+}
+
+/// Returned when [`MAX_MAP_WATCHES`] maps are already being watched. It
+/// converts to a `ResourceExhausted` status, which clients can retry.
+pub(crate) struct WatchesFull;
+
+impl From<WatchesFull> for Status {
+    fn from(_: WatchesFull) -> Self {
+        Status::resource_exhausted(format!(
+            "bpfman is already watching {MAX_MAP_WATCHES} maps, retry later"
+        ))
+    }
+}
+
+/// Renders the command queue metrics in the Prometheus text exposition
+/// format.
+pub(crate) fn encode() -> String {
+    let mut out = String::new();
+
+    for (name, help, value) in [
+        (
+            "bpfman_command_queue_depth",
+            "Number of requests bpfman-rpc handles at once.",
+            CAPACITY.load(Ordering::Relaxed),
+        ),
+        (
+            "bpfman_command_queue_occupancy",
+            "Number of requests currently being handled by bpfman-rpc.",
+            OCCUPANCY.load(Ordering::Relaxed),
+        ),
+    ] {
+        writeln!(out, "# HELP {name} {help}").unwrap();
+        writeln!(out, "# TYPE {name} gauge").unwrap();
+        writeln!(out, "{name} {value}").unwrap();
+    }
+
+    let name = "bpfman_command_queue_rejected_total";
+    writeln!(
+        out,
+        "# HELP {name} Number of requests rejected because the command queue was full."
+    )
+    .unwrap();
+    writeln!(out, "# TYPE {name} counter").unwrap();
+    writeln!(out, "{name} {}", REJECTED.load(Ordering::Relaxed)).unwrap();
+
+    out
+}
@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// The library reads the config file again for every request, so changes to
+// sections such as [signing] or [verifier] already take effect on the next
+// request. Only the settings bpfman-rpc holds on to itself need to be applied
+// here.
+
+use std::{net::SocketAddr, path::PathBuf, sync::RwLock};
+
+use anyhow::Context;
+use bpfman::{config::Config, utils::config_from_file};
+use log::{error, info, warn};
+use tokio::{
+    signal::unix::{signal, Signal, SignalKind},
+    sync::broadcast,
+    task::JoinHandle,
+};
+
+use crate::{authz::set_authz, metrics::serve_metrics, set_logging};
+
+// The config last loaded, with the sections that changed since startup but
+// only take effect on restart, as returned by GetConfig.
+static CURRENT: RwLock<Option<(Config, Vec<String>)>> = RwLock::new(None);
+
+pub(crate) fn set_current_config(config: Config, restart_required: Vec<String>) {
+    *CURRENT.write().unwrap() = Some((config, restart_required));
+}
+
+/// Returns the config last loaded and the sections of it that are waiting
+/// for a restart.
+pub(crate) fn current_config() -> (Config, Vec<String>) {
+    CURRENT.read().unwrap().clone().unwrap_or_default()
+}
+
+/// A running metrics listener that can be stopped independently of the gRPC
+/// listeners.
+pub(crate) struct MetricsListener {
+    addr: SocketAddr,
+    shutdown_tx: broadcast::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl MetricsListener {
+    pub(crate) async fn start(addr: SocketAddr) -> anyhow::Result<Self> {
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        let handle = serve_metrics(addr, shutdown_rx).await?;
+        Ok(Self {
+            addr,
+            shutdown_tx,
+            handle,
+        })
+    }
+
+    async fn stop(self) {
+        let _ = self.shutdown_tx.send(());
+        if let Err(e) = self.handle.await {
+            error!("Error stopping metrics listener {}: {e}", self.addr);
+        }
+    }
+}
+
+/// Listens for the SIGHUP that asks for the config to be reloaded.
+pub(crate) fn listen_for_reload() -> anyhow::Result<Signal> {
+    signal(SignalKind::hangup()).context("unable to listen for SIGHUP")
+}
+
+/// Reloads the config file at `path` on each signal from `sighup` until
+/// `shutdown_channel` fires, then stops the metrics listener and returns the
+/// config in effect.
+pub(crate) async fn reload_handler(
+    path: PathBuf,
+    config: Config,
+    mut sighup: Signal,
+    mut metrics: Option<MetricsListener>,
+    mut shutdown_channel: broadcast::Receiver<()>,
+) -> Config {
+    // Settings that only take effect on restart are compared against the
+    // config bpfman-rpc was started with.
+    let started = config.clone();
+    let mut current = config;
+
+    loop {
+        tokio::select! {
+            _ = shutdown_channel.recv() => break,
+            _ = sighup.recv() => {
+                info!("Received SIGHUP, reloading {}", path.display());
+                match config_from_file(&path).and_then(|c| validate(&c).map(|_| c)) {
+                    Ok(new) => {
+                        metrics = apply(&started, &current, &new, metrics).await;
+                        current = new;
+                    }
+                    Err(e) => error!("Keeping the current config, reload failed: {e}"),
+                }
+            }
+        }
+    }
+
+    if let Some(metrics) = metrics {
+        metrics.stop().await;
+    }
+    current
+}
+
+// Check everything that is applied on reload up front, so that a bad value
+// doesn't leave the config half applied.
+fn validate(config: &Config) -> Result<(), bpfman::errors::BpfmanError> {
+    if let Some(logging) = config.logging() {
+        logging.level_filter()?;
+    }
+    if let Some(metrics) = config.metrics() {
+        metrics.listen_addr()?;
+    }
+    if let Some(permissions) = config.permissions() {
+        permissions.socket_mode()?;
+        permissions.pin_dir_mode()?;
+    }
+    Ok(())
+}
+
+async fn apply(
+    started: &Config,
+    current: &Config,
+    new: &Config,
+    mut metrics: Option<MetricsListener>,
+) -> Option<MetricsListener> {
+    if let Err(e) = set_logging(new.logging()) {
+        error!("Unable to apply logging config: {e}");
+    }
+    set_authz(new.authz().clone(), started.grpc().as_ref());
+
+    if current.metrics() != new.metrics() {
+        if let Some(m) = metrics.take() {
+            info!("Stopping metrics listener on {}", m.addr);
+            m.stop().await;
+        }
+        if let Some(config) = new.metrics() {
+            // Validated before being applied.
+            let addr = config.listen_addr().expect("metrics listen address");
+            match MetricsListener::start(addr).await {
+                Ok(m) => metrics = Some(m),
+                Err(e) => error!("Unable to start metrics listener: {e}"),
+            }
+        }
+    }
+
+    let restart_required = restart_required(started, new);
+    for section in &restart_required {
+        warn!("Ignoring changes to [{section}], restart bpfman-rpc to apply them");
+    }
+    set_current_config(new.clone(), restart_required);
+
+    metrics
+}
+
+// Returns the sections of new that changed since startup but are only
+// applied on restart.
+fn restart_required(started: &Config, new: &Config) -> Vec<String> {
+    [
+        ("grpc", started.grpc() != new.grpc()),
+        ("database", !same_pin_dir(started, new)),
+        ("bpffs", !same_bpffs(started, new)),
+        ("features", started.features() != new.features()),
+        ("permissions", !same_socket_mode(started, new)),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(section, _)| section.to_string())
+    .collect()
+}
+
+// The rest of [database] is read by the library on each request, so only the
+// directory bpffs is mounted on needs a restart.
+fn same_pin_dir(started: &Config, new: &Config) -> bool {
+    let dir = |c: &Config| c.database().clone().unwrap_or_default().dir;
+    dir(started) == dir(new)
+}
+
+// per_interface_dirs is read by the library on each load, so only the mount
+// needs a restart.
+fn same_bpffs(started: &Config, new: &Config) -> bool {
+    let mount_flags = |c: &Config| c.bpffs().clone().unwrap_or_default().mount_flags;
+    mount_flags(started) == mount_flags(new)
+}
+
+// pin_dir_mode is read by the library on each request, so only the mode the
+// Unix socket was created with needs a restart.
+fn same_socket_mode(started: &Config, new: &Config) -> bool {
+    let socket_mode = |c: &Config| c.permissions().clone().unwrap_or_default().socket_mode();
+    socket_mode(started).ok() == socket_mode(new).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_restart_required() {
+        let config = |s: &str| s.parse::<Config>().unwrap();
+        let started = config(
+            r#"
+            [permissions]
+            socket_mode = 0o660
+            pin_dir_mode = 0o660
+            "#,
+        );
+
+        assert!(restart_required(&started, &started).is_empty());
+        // The default socket mode is the one set.
+        assert!(restart_required(&started, &config("")).is_empty());
+        let pin_dir_mode = config(
+            r#"
+            [permissions]
+            pin_dir_mode = 0o770
+            "#,
+        );
+        assert!(restart_required(&started, &pin_dir_mode).is_empty());
+        let socket_mode = config(
+            r#"
+            [permissions]
+            socket_mode = 0o600
+            "#,
+        );
+        assert_eq!(
+            restart_required(&started, &socket_mode),
+            vec!["permissions".to_string()]
+        );
+    }
+}
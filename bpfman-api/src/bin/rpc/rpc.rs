@@ -1,199 +1,700 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
 use bpfman::{
-    add_program, get_program, list_programs, pull_bytecode, remove_program,
+    add_object_programs, add_programs, apply_batch, attach_program, check_dispatcher_capacity,
+    detach_program, diff_state, discard_inline_bytecode, dispatcher_info, dump_state,
+    errors::BpfmanError,
+    find_program, get_program, health, infer_program, inline_bytecode_path, link_status,
+    list_kernel, list_maps, list_programs, map_delete, map_dump, map_freeze, map_lookup,
+    map_update, map_watch, maps_bytes_memlock, ping, program_counts, program_stats,
+    prune_orphaned_pins, pull_bytecode, relink_program, reload_program, remove_all_programs,
+    remove_program, reorder_program, replace_program, set_program_enabled, subscribe_events,
     types::{
-        FentryProgram, FexitProgram, KprobeProgram, ListFilter, Location, Program, ProgramData,
-        TcProceedOn, TcProgram, TracepointProgram, UprobeProgram, XdpProceedOn, XdpProgram,
+        BatchOp, BatchResult, CgroupProgram, FentryProgram, FexitProgram, IdempotencyKey,
+        KprobeProgram, LinkStatus, ListFilter, Location, MapKey, MapKeyType, MapValueFormat,
+        Program, ProgramData, ProgramSelector, TailCallProgram, TcProceedOn, TcProgram,
+        TracepointProgram, UprobeProgram, XdpModePreference, XdpProceedOn, XdpProgram,
+    },
+    utils::{config_path, get_up_interfaces, open_config_file},
+    validate_bytecode, validate_bytes, verify_program, write_inline_bytecode,
+};
+use bpfman_api::{
+    v1::{
+        attach_info::Info,
+        batch_request::operation::Op,
+        batch_response::Result as BatchOperationResult,
+        bpfman_server::Bpfman,
+        bytecode_location::Location as RpcLocation,
+        diff_state_response::{Change, Match},
+        list_kernel_response::{KernelLink, KernelProgram},
+        list_response::ListResult,
+        unload_all_response::UnloadResult,
+        validate_response, AttachInfo, AttachRequest, AttachResponse, BatchRequest, BatchResponse,
+        BytecodeLocation, CgroupAttachInfo, DetachRequest, DetachResponse, DiffStateRequest,
+        DiffStateResponse, DispatcherInfoRequest, DispatcherInfoResponse, DumpStateRequest,
+        DumpStateResponse, FentryAttachInfo, FexitAttachInfo, GetConfigRequest, GetConfigResponse,
+        GetInfoRequest, GetInfoResponse, GetRequest, GetResponse, HealthRequest, HealthResponse,
+        KernelProgramInfo, KprobeAttachInfo, ListKernelRequest, ListKernelResponse,
+        ListMapsRequest, ListMapsResponse, ListRequest, ListResponse, LoadObjectRequest,
+        LoadObjectResponse, LoadRequest, LoadResponse, LoadResult, MapDeleteRequest,
+        MapDeleteResponse, MapDumpRequest, MapDumpResponse, MapEntry, MapFreezeRequest,
+        MapFreezeResponse, MapLookupRequest, MapLookupResponse, MapUpdateRequest,
+        MapUpdateResponse, MapWatchRequest, MapWatchResponse, PingRequest, PingResponse,
+        ProgramCounts, PrunePinsRequest, PrunePinsResponse, PullBytecodeRequest,
+        PullBytecodeResponse, RelinkRequest, RelinkResponse, ReloadRequest, ReloadResponse,
+        ReorderRequest, ReorderResponse, ReplaceRequest, ReplaceResponse, SetEnabledRequest,
+        SetEnabledResponse, StatsRequest, StatsResponse, SubscribeEventsRequest,
+        SubscribeEventsResponse, TailCallAttachInfo, TcAttachInfo, TracepointAttachInfo,
+        UnloadAllRequest, UnloadAllResponse, UnloadRequest, UnloadResponse, UprobeAttachInfo,
+        ValidateRequest, ValidateResponse, XdpAttachInfo,
     },
+    API_VERSION, MIN_API_VERSION,
 };
-use bpfman_api::v1::{
-    attach_info::Info, bpfman_server::Bpfman, bytecode_location::Location as RpcLocation,
-    list_response::ListResult, FentryAttachInfo, FexitAttachInfo, GetRequest, GetResponse,
-    KprobeAttachInfo, ListRequest, ListResponse, LoadRequest, LoadResponse, PullBytecodeRequest,
-    PullBytecodeResponse, TcAttachInfo, TracepointAttachInfo, UnloadRequest, UnloadResponse,
-    UprobeAttachInfo, XdpAttachInfo,
+use tokio::sync::{
+    broadcast::error::RecvError,
+    mpsc::{self, error::TrySendError},
 };
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
-pub struct BpfmanLoader {}
+use crate::{
+    authz::{authorize, peer_uid},
+    queue::CommandQueue,
+    reload::current_config,
+};
+
+// Number of map dump batches that may be read ahead of the client.
+const MAP_DUMP_QUEUE_DEPTH: usize = 4;
+
+// Number of batches of map watch records that may be queued for the client.
+const MAP_WATCH_QUEUE_DEPTH: usize = 64;
+
+// How long a map watch waits for records before checking whether the client
+// is still there.
+const MAP_WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Number of program events that may be queued for the client. Events back up
+// in bpfman's own event queue behind these, until the client is dropped.
+const EVENTS_QUEUE_DEPTH: usize = 16;
+
+pub struct BpfmanLoader {
+    started: Instant,
+    queue: CommandQueue,
+}
 
 impl BpfmanLoader {
-    pub(crate) fn new() -> BpfmanLoader {
-        BpfmanLoader {}
+    pub(crate) fn new(queue: CommandQueue) -> BpfmanLoader {
+        BpfmanLoader {
+            started: Instant::now(),
+            queue,
+        }
     }
 }
 
-#[tonic::async_trait]
-impl Bpfman for BpfmanLoader {
-    async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
-        let request = request.into_inner();
+// Returns the interfaces an XDP or TC program should be attached to.
+fn attach_ifaces(
+    iface: String,
+    ifaces: Vec<String>,
+    all_interfaces: bool,
+    netns: Option<&Path>,
+) -> Result<Vec<String>, BpfmanError> {
+    let ifaces: Vec<String> = if all_interfaces {
+        get_up_interfaces(netns)?
+    } else {
+        std::iter::once(iface)
+            .chain(ifaces)
+            .filter(|i| !i.is_empty())
+            .collect()
+    };
 
-        let bytecode_source = match request
-            .bytecode
+    if ifaces.is_empty() {
+        return Err(BpfmanError::Error("no interfaces to attach to".to_string()));
+    }
+    Ok(ifaces)
+}
+
+// Per-CPU values are summed as u64 counters unless another width is given.
+fn counter_width(sum_width: u32) -> usize {
+    match sum_width {
+        0 => 8,
+        w => w as usize,
+    }
+}
+
+// How a map watch handles a client that falls behind.
+#[derive(Clone, Copy)]
+enum Backpressure {
+    Block,
+    Drop,
+}
+
+#[allow(clippy::result_large_err)]
+fn backpressure(mode: Option<String>) -> Result<Backpressure, Status> {
+    match mode.as_deref() {
+        None | Some("") | Some("block") => Ok(Backpressure::Block),
+        Some("drop") => Ok(Backpressure::Drop),
+        Some(mode) => Err(Status::invalid_argument(format!(
+            "{mode} is not a valid backpressure mode"
+        ))),
+    }
+}
+
+// Returns where to load bytecode from, along with the bytecode to write out
+// first if it was sent inline.
+#[allow(clippy::result_large_err)]
+fn bytecode_location(
+    bytecode: Option<BytecodeLocation>,
+) -> Result<(Location, Option<Vec<u8>>), Status> {
+    Ok(
+        match bytecode
             .ok_or(Status::aborted("missing bytecode info"))?
             .location
             .ok_or(Status::aborted("missing location"))?
         {
-            RpcLocation::Image(i) => Location::Image(i.into()),
-            RpcLocation::File(p) => Location::File(p),
-        };
-
-        let data = ProgramData::new(
-            bytecode_source,
-            request.name,
-            request.metadata,
-            request.global_data,
-            request.map_owner_id,
-        )
-        .map_err(|e| Status::aborted(format!("failed to create ProgramData: {e}")))?;
+            RpcLocation::Image(i) => (Location::Image(i.into()), None),
+            RpcLocation::File(p) => (Location::File(p), None),
+            RpcLocation::Url(u) => (Location::Url(u.into()), None),
+            RpcLocation::Inline(b) => (Location::File(inline_bytecode_path(&b)), Some(b)),
+        },
+    )
+}
 
-        let program = match request
-            .attach
-            .ok_or(Status::aborted("missing attach info"))?
-            .info
-            .ok_or(Status::aborted("missing info"))?
-        {
-            Info::XdpAttachInfo(XdpAttachInfo {
-                priority,
-                iface,
-                position: _,
-                proceed_on,
-            }) => Program::Xdp(
-                XdpProgram::new(
-                    data,
+// Returns the programs to load for attach, one per interface for XDP and TC
+// programs and a single program otherwise. new_data is called for each.
+#[allow(clippy::result_large_err)]
+fn attach_programs<F>(attach: Option<AttachInfo>, new_data: F) -> Result<Vec<Program>, Status>
+where
+    F: Fn() -> Result<ProgramData, BpfmanError>,
+{
+    let mut programs = vec![];
+    match attach
+        .ok_or(Status::aborted("missing attach info"))?
+        .info
+        .ok_or(Status::aborted("missing info"))?
+    {
+        Info::XdpAttachInfo(XdpAttachInfo {
+            priority,
+            iface,
+            position: _,
+            proceed_on,
+            netns,
+            ifaces,
+            all_interfaces,
+            dispatcher_revision: _,
+            detached: _,
+            mode,
+            if_index: _,
+            members: _,
+            interface_missing: _,
+            dispatcher_replaced: _,
+            enabled,
+        }) => {
+            let priority = open_config_file().priority_or_default(priority);
+            let netns = netns.map(PathBuf::from);
+            let proceed_on = XdpProceedOn::from_int32s(proceed_on)
+                .map_err(|e| Status::aborted(format!("failed to parse proceed_on: {e}")))?;
+            let mode = mode
+                .map(XdpModePreference::try_from)
+                .transpose()
+                .map_err(|e| Status::aborted(format!("failed to parse mode: {e}")))?;
+            let ifaces = attach_ifaces(iface, ifaces, all_interfaces, netns.as_deref())?;
+            for iface in ifaces {
+                let mut program = XdpProgram::new(
+                    new_data()?,
                     priority,
                     iface,
-                    XdpProceedOn::from_int32s(proceed_on)
-                        .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
-                )
-                .map_err(|e| Status::aborted(format!("failed to create xdpprogram: {e}")))?,
-            ),
-            Info::TcAttachInfo(TcAttachInfo {
-                priority,
-                iface,
-                position: _,
-                direction,
-                proceed_on,
-            }) => {
-                let direction = direction
-                    .try_into()
-                    .map_err(|_| Status::aborted("direction is not a string"))?;
-                Program::Tc(
-                    TcProgram::new(
-                        data,
-                        priority,
-                        iface,
-                        TcProceedOn::from_int32s(proceed_on)
-                            .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
-                        direction,
-                    )
-                    .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
-                )
+                    proceed_on.clone(),
+                    netns.clone(),
+                )?;
+                if let Some(mode) = mode {
+                    program.set_mode_preference(mode)?;
+                }
+                if let Some(enabled) = enabled {
+                    program.set_enabled(enabled)?;
+                }
+                programs.push(Program::Xdp(program));
+            }
+        }
+        Info::TcAttachInfo(TcAttachInfo {
+            priority,
+            iface,
+            position: _,
+            direction,
+            proceed_on,
+            netns,
+            ifaces,
+            all_interfaces,
+            dispatcher_revision: _,
+            detached: _,
+            if_index: _,
+            interface_missing: _,
+            enabled,
+        }) => {
+            let direction = direction
+                .try_into()
+                .map_err(|_| Status::aborted("direction is not a string"))?;
+            let priority = open_config_file().priority_or_default(priority);
+            let netns = netns.map(PathBuf::from);
+            let proceed_on = TcProceedOn::from_int32s(proceed_on)
+                .map_err(|e| Status::aborted(format!("failed to parse proceed_on: {e}")))?;
+            let ifaces = attach_ifaces(iface, ifaces, all_interfaces, netns.as_deref())?;
+            for iface in ifaces {
+                let mut program = TcProgram::new(
+                    new_data()?,
+                    priority,
+                    iface,
+                    proceed_on.clone(),
+                    direction,
+                    netns.clone(),
+                )?;
+                if let Some(enabled) = enabled {
+                    program.set_enabled(enabled)?;
+                }
+                programs.push(Program::Tc(program));
             }
-            Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => Program::Tracepoint(
-                TracepointProgram::new(data, tracepoint)
-                    .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
-            ),
-            Info::KprobeAttachInfo(KprobeAttachInfo {
-                fn_name,
-                offset,
-                retprobe,
-                container_pid,
-            }) => Program::Kprobe(
-                KprobeProgram::new(data, fn_name, offset, retprobe, container_pid)
-                    .map_err(|e| Status::aborted(format!("failed to create kprobeprogram: {e}")))?,
-            ),
-            Info::UprobeAttachInfo(UprobeAttachInfo {
-                fn_name,
-                offset,
-                target,
-                retprobe,
-                pid,
-                container_pid,
-            }) => Program::Uprobe(
-                UprobeProgram::new(data, fn_name, offset, target, retprobe, pid, container_pid)
-                    .map_err(|e| Status::aborted(format!("failed to create uprobeprogram: {e}")))?,
-            ),
-            Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => Program::Fentry(
-                FentryProgram::new(data, fn_name)
-                    .map_err(|e| Status::aborted(format!("failed to create fentryprogram: {e}")))?,
-            ),
-            Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => Program::Fexit(
-                FexitProgram::new(data, fn_name)
-                    .map_err(|e| Status::aborted(format!("failed to create fexitprogram: {e}")))?,
-            ),
+        }
+        Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => programs.push(
+            Program::Tracepoint(TracepointProgram::new(new_data()?, tracepoint)?),
+        ),
+        Info::KprobeAttachInfo(KprobeAttachInfo {
+            fn_name,
+            offset,
+            retprobe,
+            container_pid,
+        }) => programs.push(Program::Kprobe(KprobeProgram::new(
+            new_data()?,
+            fn_name,
+            offset,
+            retprobe,
+            container_pid,
+        )?)),
+        Info::UprobeAttachInfo(UprobeAttachInfo {
+            fn_name,
+            offset,
+            target,
+            retprobe,
+            pid,
+            container_pid,
+        }) => programs.push(Program::Uprobe(UprobeProgram::new(
+            new_data()?,
+            fn_name,
+            offset,
+            target,
+            retprobe,
+            pid,
+            container_pid,
+        )?)),
+        Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => {
+            programs.push(Program::Fentry(FentryProgram::new(new_data()?, fn_name)?))
+        }
+        Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => {
+            programs.push(Program::Fexit(FexitProgram::new(new_data()?, fn_name)?))
+        }
+        Info::CgroupAttachInfo(CgroupAttachInfo { path, attach_type }) => {
+            let attach_type = attach_type.try_into()?;
+            programs.push(Program::Cgroup(CgroupProgram::new(
+                new_data()?,
+                path,
+                attach_type,
+            )?))
+        }
+        Info::TailCallAttachInfo(TailCallAttachInfo { prog_array, index }) => programs.push(
+            Program::TailCall(TailCallProgram::new(new_data()?, prog_array, index)?),
+        ),
+    };
+    Ok(programs)
+}
+
+fn with_link_status(kernel_info: KernelProgramInfo, status: &LinkStatus) -> KernelProgramInfo {
+    KernelProgramInfo {
+        link_id: status.link_id,
+        link_attached: status.attached,
+        ..kernel_info
+    }
+}
+
+// Reads operation i of a batch. On error, returns the programs that were read
+// for it, whose inline bytecode has been written.
+async fn batch_op(i: usize, op: Option<Op>) -> Result<BatchOp, (Status, Vec<Program>)> {
+    match op {
+        Some(Op::Load(load)) => {
+            if load.dry_run {
+                return Err((
+                    Status::invalid_argument(format!(
+                        "operation {i}: loads in a batch can't be dry runs"
+                    )),
+                    vec![],
+                ));
+            }
+            if load.idempotency_key.is_some() {
+                return Err((
+                    Status::invalid_argument(format!(
+                        "operation {i}: loads in a batch can't have an idempotency key"
+                    )),
+                    vec![],
+                ));
+            }
+            let mut programs = request_programs(load, None)
+                .await
+                .map_err(|e| (e, vec![]))?;
+            if programs.len() != 1 {
+                return Err((
+                    Status::invalid_argument(format!(
+                        "operation {i}: loads in a batch can only load a single program"
+                    )),
+                    programs,
+                ));
+            }
+            Ok(BatchOp::Load(programs.remove(0)))
+        }
+        Some(Op::Unload(id)) => Ok(BatchOp::Unload(id)),
+        None => Err((
+            Status::invalid_argument(format!("operation {i}: missing operation")),
+            vec![],
+        )),
+    }
+}
+
+// Returns the programs a LoadRequest asks for, one per interface for XDP and
+// TC programs.
+#[allow(clippy::result_large_err)]
+async fn request_programs(
+    request: LoadRequest,
+    idempotency_key: Option<&IdempotencyKey>,
+) -> Result<Vec<Program>, Status> {
+    // Inline bytecode is only written out once the rest of the request
+    // has been checked.
+    let (bytecode_source, inline_bytecode) = bytecode_location(request.bytecode)?;
+
+    // Each program in a group needs its own ProgramData.
+    let new_data = || {
+        let mut data = ProgramData::new(
+            bytecode_source.clone(),
+            request.name.clone(),
+            request.metadata.clone(),
+            request.global_data.clone(),
+            request.map_owner_id,
+        )?;
+        data.set_shared_maps(&request.shared_maps)?;
+        data.set_maps_to_pin(&request.maps_to_pin)?;
+        data.set_maps_to_skip(&request.maps_to_skip)?;
+        data.set_map_max_entries(&request.map_max_entries)?;
+        data.set_allow_duplicate(request.allow_duplicate)?;
+        if let Some(key) = idempotency_key {
+            data.set_idempotency_key(key)?;
+        }
+        if let Some(namespace) = &request.pin_namespace {
+            data.set_pin_namespace(namespace)?;
+        }
+        Ok::<_, BpfmanError>(data)
+    };
+
+    let programs = match request.attach {
+        Some(attach) => Some(attach_programs(Some(attach), new_data)?),
+        None => None,
+    };
+    if let (Some(programs), Some(capacity)) = (&programs, request.dispatcher_capacity) {
+        check_dispatcher_capacity(programs, capacity).await?;
+    }
+
+    if let Some(bytecode) = &inline_bytecode {
+        write_inline_bytecode(bytecode)?;
+    }
+
+    // Without attach info, the program is attached where its section
+    // name says, which takes the bytecode to find out.
+    match programs {
+        Some(programs) => Ok(programs),
+        None => Ok(vec![infer_program(new_data()?).await?]),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn load_result(program: &Program) -> Result<LoadResult, Status> {
+    Ok(LoadResult {
+        info: Some(
+            program
+                .try_into()
+                .map_err(|e| Status::aborted(format!("convert Program to GRPC program: {e}")))?,
+        ),
+        kernel_info: Some(program.try_into().map_err(|e| {
+            Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+        })?),
+        pin_paths: Some(
+            program
+                .pin_paths()
+                .map_err(|e| Status::aborted(format!("failed to get pin paths: {e}")))?
+                .into(),
+        ),
+    })
+}
+
+#[tonic::async_trait]
+impl Bpfman for BpfmanLoader {
+    async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
+        authorize(&request, "load programs")?;
+        let _admitted = self.queue.admit()?;
+        let owner = peer_uid(&request)
+            .map(|uid| format!("uid {uid}"))
+            .unwrap_or_default();
+        let request = request.into_inner();
+
+        let dry_run = request.dry_run;
+        let idempotency_key = request
+            .idempotency_key
+            .clone()
+            .filter(|k| !k.is_empty())
+            .map(|key| IdempotencyKey { owner, key });
+        let programs = request_programs(request, idempotency_key.as_ref()).await?;
+
+        // Every program in a group is loaded from the same bytecode, so only
+        // the first needs to be verified.
+        if dry_run {
+            let program = programs
+                .into_iter()
+                .next()
+                .ok_or(Status::aborted("no programs to verify"))?;
+            verify_program(program).await?;
+            return Ok(Response::new(LoadResponse::default()));
+        }
+
+        let programs = add_programs(programs).await?;
+
+        let mut group = programs
+            .iter()
+            .map(load_result)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The group is only reported when more than one program was loaded.
+        let first = if group.len() == 1 {
+            group.remove(0)
+        } else {
+            group
+                .first()
+                .cloned()
+                .ok_or(Status::aborted("no programs were loaded"))?
         };
 
-        let program = add_program(program)
-            .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
+        let reply_entry = LoadResponse {
+            info: first.info,
+            kernel_info: first.kernel_info,
+            pin_paths: first.pin_paths,
+            group,
+        };
 
-        let reply_entry =
-            LoadResponse {
-                info: Some((&program).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC program: {e}"))
-                })?),
-                kernel_info: Some((&program).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
-                })?),
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn load_object(
+        &self,
+        request: Request<LoadObjectRequest>,
+    ) -> Result<Response<LoadObjectResponse>, Status> {
+        authorize(&request, "load programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        if request.programs.is_empty() {
+            return Err(Status::aborted("no programs to load"));
+        }
+        let (bytecode_source, inline_bytecode) = bytecode_location(request.bytecode)?;
+
+        // The maps belong to the first program, so the options for them only
+        // apply to it. The programs after it use it as their map owner.
+        let mut programs = vec![];
+        for (i, program) in request.programs.into_iter().enumerate() {
+            let new_data = || {
+                let mut data = ProgramData::new(
+                    bytecode_source.clone(),
+                    program.name.clone(),
+                    request.metadata.clone(),
+                    request.global_data.clone(),
+                    request.map_owner_id,
+                )?;
+                if i == 0 {
+                    data.set_shared_maps(&request.shared_maps)?;
+                    data.set_maps_to_pin(&request.maps_to_pin)?;
+                    data.set_maps_to_skip(&request.maps_to_skip)?;
+                    data.set_map_max_entries(&request.map_max_entries)?;
+                }
+                data.set_allow_duplicate(request.allow_duplicate)?;
+                if let Some(namespace) = &request.pin_namespace {
+                    data.set_pin_namespace(namespace)?;
+                }
+                Ok::<_, BpfmanError>(data)
             };
+            programs.extend(attach_programs(program.attach, new_data)?);
+        }
 
-        Ok(Response::new(reply_entry))
+        if let Some(bytecode) = &inline_bytecode {
+            write_inline_bytecode(bytecode)?;
+        }
+
+        let programs = add_object_programs(programs).await?;
+
+        let reply = LoadObjectResponse {
+            programs: programs
+                .iter()
+                .map(load_result)
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        Ok(Response::new(reply))
+    }
+    async fn batch(
+        &self,
+        request: Request<BatchRequest>,
+    ) -> Result<Response<BatchResponse>, Status> {
+        authorize(&request, "load and unload programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        // A batch that can't be read is refused as a whole, before any of it
+        // is applied, and the inline bytecode written for the loads read so
+        // far is removed again.
+        let mut ops = vec![];
+        let mut loads = vec![];
+        for (i, operation) in request.operations.into_iter().enumerate() {
+            let op = match batch_op(i, operation.op).await {
+                Ok(op) => op,
+                Err((e, mut programs)) => {
+                    loads.append(&mut programs);
+                    discard_inline_bytecode(&loads).await?;
+                    return Err(e);
+                }
+            };
+            if let BatchOp::Load(program) = &op {
+                loads.push(program.clone());
+            }
+            ops.push(op);
+        }
+
+        let mut results = vec![];
+        for result in apply_batch(ops, request.atomic).await? {
+            results.push(match result {
+                Ok(BatchResult::Loaded(program)) => BatchOperationResult {
+                    load: Some(load_result(&program)?),
+                    ..Default::default()
+                },
+                Ok(BatchResult::Unloaded) => BatchOperationResult::default(),
+                Err(e) => BatchOperationResult {
+                    error: Some(e.to_string()),
+                    error_code: Some(e.code().1.to_string()),
+                    load: None,
+                },
+            });
+        }
+        Ok(Response::new(BatchResponse { results }))
     }
 
     async fn unload(
         &self,
         request: Request<UnloadRequest>,
     ) -> Result<Response<UnloadResponse>, Status> {
+        authorize(&request, "unload programs")?;
+        let _admitted = self.queue.admit()?;
         let reply = UnloadResponse {};
         let request = request.into_inner();
 
-        remove_program(request.id)
-            .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
+        let id = match request.selector {
+            Some(s) => find_program(&ProgramSelector::new(s.name, s.iface, s.priority)).await?,
+            None => request.id,
+        };
+        remove_program(id).await?;
 
         Ok(Response::new(reply))
     }
 
+    async fn unload_all(
+        &self,
+        request: Request<UnloadAllRequest>,
+    ) -> Result<Response<UnloadAllResponse>, Status> {
+        authorize(&request, "unload programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let results = remove_all_programs(request.iface)
+            .await?
+            .into_iter()
+            .map(|(id, result)| UnloadResult {
+                id,
+                error: result.err().map(|e| e.to_string()),
+            })
+            .collect();
+
+        Ok(Response::new(UnloadAllResponse { results }))
+    }
+
     async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let _admitted = self.queue.admit()?;
         let request = request.into_inner();
         let id = request.id;
 
-        let program = get_program(id)
-            .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
+        let program = get_program(id).await?;
+        let status = link_status(std::slice::from_ref(&program)).await?;
+        let maps = match program {
+            Program::Unsupported(_) => vec![],
+            _ => list_maps(id)
+                .await
+                .map_err(|e| Status::aborted(format!("failed to list maps: {e}")))?,
+        };
 
-        let reply_entry =
-            GetResponse {
-                info: if let Program::Unsupported(_) = program {
-                    None
-                } else {
-                    Some((&program).try_into().map_err(|e| {
+        let reply_entry = GetResponse {
+            info: if let Program::Unsupported(_) = program {
+                None
+            } else {
+                Some(
+                    (&program).try_into().map_err(|e| {
                         Status::aborted(format!("failed to get program metadata: {e}"))
-                    })?)
-                },
-                kernel_info: Some((&program).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
-                })?),
-            };
+                    })?,
+                )
+            },
+            kernel_info: Some(KernelProgramInfo {
+                maps_bytes_memlock: Some(maps_bytes_memlock(&program)?),
+                ..with_link_status(
+                    (&program).try_into().map_err(|e| {
+                        Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                    })?,
+                    &status[0],
+                )
+            }),
+            maps: maps.into_iter().map(|m| m.into()).collect(),
+        };
         Ok(Response::new(reply_entry))
     }
 
     async fn list(&self, request: Request<ListRequest>) -> Result<Response<ListResponse>, Status> {
-        let mut reply = ListResponse { results: vec![] };
+        let _admitted = self.queue.admit()?;
+        let counts = program_counts()
+            .await
+            .map_err(|e| Status::aborted(format!("failed to count programs: {e}")))?;
+        let mut reply = ListResponse {
+            results: vec![],
+            counts: Some(ProgramCounts {
+                programs: counts.programs,
+                max_programs: counts.max_programs,
+                programs_per_iface: counts.per_iface.into_iter().collect(),
+                max_programs_per_iface: counts.max_programs_per_iface,
+            }),
+        };
 
         let filter = ListFilter::new(
             request.get_ref().program_type,
             request.get_ref().match_metadata.clone(),
             request.get_ref().bpfman_programs_only(),
+            request.get_ref().name.clone(),
+            request.get_ref().pin_namespace.clone(),
         );
 
         // Await the response
-        for r in list_programs(filter)
+        let programs = list_programs(filter)
             .await
-            .map_err(|e| Status::aborted(format!("failed to list programs: {e}")))?
-        {
+            .map_err(|e| Status::aborted(format!("failed to list programs: {e}")))?;
+        let statuses = link_status(&programs).await?;
+        for (r, status) in programs.into_iter().zip(statuses) {
             // Populate the response with the Program Info and the Kernel Info.
             let reply_entry = ListResult {
                 info: if let Program::Unsupported(_) = r {
@@ -203,9 +704,12 @@ impl Bpfman for BpfmanLoader {
                         Status::aborted(format!("failed to get program metadata: {e}"))
                     })?)
                 },
-                kernel_info: Some((&r).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
-                })?),
+                kernel_info: Some(with_link_status(
+                    (&r).try_into().map_err(|e| {
+                        Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                    })?,
+                    &status,
+                )),
             };
             reply.results.push(reply_entry)
         }
@@ -216,6 +720,8 @@ impl Bpfman for BpfmanLoader {
         &self,
         request: tonic::Request<PullBytecodeRequest>,
     ) -> std::result::Result<tonic::Response<PullBytecodeResponse>, tonic::Status> {
+        authorize(&request, "pull bytecode")?;
+        let _admitted = self.queue.admit()?;
         let request = request.into_inner();
         let image = match request.image {
             Some(i) => i.into(),
@@ -229,4 +735,700 @@ impl Bpfman for BpfmanLoader {
         let reply = PullBytecodeResponse {};
         Ok(Response::new(reply))
     }
+
+    async fn map_lookup(
+        &self,
+        request: Request<MapLookupRequest>,
+    ) -> Result<Response<MapLookupResponse>, Status> {
+        // Entries are only read, unlike MapWatch, so like other reads this
+        // isn't restricted by [authz].
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let format = MapValueFormat::try_from(request.format.unwrap_or_default())
+            .map_err(|e| Status::aborted(format!("failed to parse format: {e}")))?;
+        let key = match request.key_type {
+            Some(_) if !request.key.is_empty() => {
+                return Err(Status::invalid_argument(
+                    "key can't be given along with key_type, use typed_key",
+                ))
+            }
+            Some(key_type) => MapKey::Typed(MapKeyType::try_from(key_type)?, request.typed_key),
+            None => MapKey::Bytes(request.key),
+        };
+        let entry = map_lookup(
+            request.id,
+            &request.map_name,
+            &key,
+            &format,
+            request.sum_width.map(counter_width),
+        )
+        .await?;
+
+        Ok(Response::new(MapLookupResponse {
+            values: entry.values,
+            decoded: entry.decoded,
+            sums: entry.sums,
+            key: entry.key,
+        }))
+    }
+
+    async fn map_update(
+        &self,
+        request: Request<MapUpdateRequest>,
+    ) -> Result<Response<MapUpdateResponse>, Status> {
+        authorize(&request, "update maps")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        map_update(request.id, &request.map_name, &request.key, &request.values).await?;
+
+        Ok(Response::new(MapUpdateResponse {}))
+    }
+
+    async fn map_delete(
+        &self,
+        request: Request<MapDeleteRequest>,
+    ) -> Result<Response<MapDeleteResponse>, Status> {
+        authorize(&request, "delete map entries")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        map_delete(request.id, &request.map_name, &request.key).await?;
+
+        Ok(Response::new(MapDeleteResponse {}))
+    }
+
+    async fn map_freeze(
+        &self,
+        request: Request<MapFreezeRequest>,
+    ) -> Result<Response<MapFreezeResponse>, Status> {
+        authorize(&request, "freeze maps")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let frozen = map_freeze(request.id, &request.map_name).await?;
+
+        Ok(Response::new(MapFreezeResponse {
+            already_frozen: !frozen,
+        }))
+    }
+
+    async fn health(
+        &self,
+        _request: Request<HealthRequest>,
+    ) -> Result<Response<HealthResponse>, Status> {
+        let health = health();
+
+        Ok(Response::new(HealthResponse {
+            version: health.version,
+            uptime_secs: self.started.elapsed().as_secs(),
+            programs_loaded: health.programs_loaded,
+            bpffs_mounted: health.bpffs_mounted,
+            btf_available: health.features.btf,
+            bpf_link_supported: health.features.bpf_link,
+            xdp_dispatcher_supported: health.features.xdp_dispatcher,
+            trampoline_supported: health.features.trampoline,
+        }))
+    }
+
+    async fn ping(&self, _request: Request<PingRequest>) -> Result<Response<PingResponse>, Status> {
+        let received = Instant::now();
+        let _admitted = self.queue.admit()?;
+        ping().await?;
+
+        let timestamp_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Ok(Response::new(PingResponse {
+            timestamp_ns,
+            wait_us: received.elapsed().as_micros() as u64,
+        }))
+    }
+
+    async fn get_config(
+        &self,
+        _request: Request<GetConfigRequest>,
+    ) -> Result<Response<GetConfigResponse>, Status> {
+        let (config, restart_required) = current_config();
+
+        Ok(Response::new(GetConfigResponse {
+            path: config_path().display().to_string(),
+            config: config.to_toml()?,
+            restart_required,
+        }))
+    }
+
+    async fn dump_state(
+        &self,
+        _request: Request<DumpStateRequest>,
+    ) -> Result<Response<DumpStateResponse>, Status> {
+        let _admitted = self.queue.admit()?;
+
+        Ok(Response::new(DumpStateResponse {
+            state: dump_state().await?,
+        }))
+    }
+
+    async fn list_kernel(
+        &self,
+        _request: Request<ListKernelRequest>,
+    ) -> Result<Response<ListKernelResponse>, Status> {
+        let _admitted = self.queue.admit()?;
+
+        let kernel = list_kernel().await?;
+        Ok(Response::new(ListKernelResponse {
+            programs: kernel
+                .programs
+                .into_iter()
+                .map(|p| KernelProgram {
+                    id: p.id,
+                    name: p.name,
+                    program_type: p.program_type,
+                    managed: p.managed,
+                    dispatcher: p.dispatcher,
+                })
+                .collect(),
+            links: kernel
+                .links
+                .into_iter()
+                .map(|l| KernelLink {
+                    id: l.id,
+                    prog_id: l.prog_id,
+                    link_type: l.link_type,
+                    if_index: l.if_index,
+                    iface: l.iface,
+                    managed: l.managed,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn validate(
+        &self,
+        request: Request<ValidateRequest>,
+    ) -> Result<Response<ValidateResponse>, Status> {
+        // Images are pulled into the image store and URLs fetched, as
+        // PullBytecode does.
+        let location = request.get_ref().bytecode.as_ref();
+        if matches!(
+            location.and_then(|b| b.location.as_ref()),
+            Some(RpcLocation::Image(_) | RpcLocation::Url(_))
+        ) {
+            authorize(&request, "pull bytecode")?;
+        }
+        let _admitted = self.queue.admit()?;
+
+        // Inline bytecode is checked as it is, rather than written out.
+        let report = match bytecode_location(request.into_inner().bytecode)? {
+            (_, Some(bytes)) => validate_bytes(&bytes)?,
+            (location, None) => validate_bytecode(&location).await?,
+        };
+        Ok(Response::new(ValidateResponse {
+            programs: report
+                .programs
+                .into_iter()
+                .map(|p| validate_response::Program {
+                    name: p.name,
+                    section: p.section,
+                    program_type: p.program_type,
+                })
+                .collect(),
+            maps: report
+                .maps
+                .into_iter()
+                .map(|m| validate_response::Map {
+                    name: m.name,
+                    map_type: m.map_type,
+                    key_size: m.key_size,
+                    value_size: m.value_size,
+                    max_entries: m.max_entries,
+                })
+                .collect(),
+            license: report.license,
+            btf: report.btf,
+            uses_core: report.uses_core,
+            issues: report.issues,
+        }))
+    }
+
+    async fn get_info(
+        &self,
+        _request: Request<GetInfoRequest>,
+    ) -> Result<Response<GetInfoResponse>, Status> {
+        Ok(Response::new(GetInfoResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version: API_VERSION,
+            min_api_version: MIN_API_VERSION,
+        }))
+    }
+
+    async fn stats(
+        &self,
+        request: Request<StatsRequest>,
+    ) -> Result<Response<StatsResponse>, Status> {
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let stats = program_stats(request.id).await?;
+
+        Ok(Response::new(StatsResponse {
+            id: stats.id,
+            run_count: stats.run_count,
+            run_time_ns: stats.run_time_ns,
+            stats_enabled: stats.stats_enabled,
+            loaded_at: stats.loaded_at,
+            attach_type: stats.attach_type,
+        }))
+    }
+
+    async fn reorder(
+        &self,
+        request: Request<ReorderRequest>,
+    ) -> Result<Response<ReorderResponse>, Status> {
+        authorize(&request, "reorder programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let program = reorder_program(request.id, request.priority).await?;
+
+        let reply_entry =
+            ReorderResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn set_enabled(
+        &self,
+        request: Request<SetEnabledRequest>,
+    ) -> Result<Response<SetEnabledResponse>, Status> {
+        authorize(&request, "enable and disable programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let program = set_program_enabled(request.id, request.enabled).await?;
+
+        let reply_entry =
+            SetEnabledResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn diff_state(
+        &self,
+        request: Request<DiffStateRequest>,
+    ) -> Result<Response<DiffStateResponse>, Status> {
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let mut desired = vec![];
+        for (i, load) in request.programs.into_iter().enumerate() {
+            let mut programs = request_programs(load, None).await?;
+            if programs.len() != 1 {
+                return Err(Status::invalid_argument(format!(
+                    "program {i}: each desired program can only load a single program"
+                )));
+            }
+            desired.push(programs.remove(0));
+        }
+
+        let diff = diff_state(desired, request.selector).await?;
+
+        Ok(Response::new(DiffStateResponse {
+            add: diff.add.into_iter().map(|i| i as u32).collect(),
+            remove: diff.remove,
+            change: diff
+                .change
+                .into_iter()
+                .map(|c| Change {
+                    index: c.index as u32,
+                    id: c.id,
+                    fields: c.fields,
+                })
+                .collect(),
+            unchanged: diff
+                .unchanged
+                .into_iter()
+                .map(|(index, id)| Match {
+                    index: index as u32,
+                    id,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn detach(
+        &self,
+        request: Request<DetachRequest>,
+    ) -> Result<Response<DetachResponse>, Status> {
+        authorize(&request, "detach programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let program = detach_program(request.id).await?;
+
+        let reply_entry =
+            DetachResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn attach(
+        &self,
+        request: Request<AttachRequest>,
+    ) -> Result<Response<AttachResponse>, Status> {
+        authorize(&request, "attach programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let program = attach_program(request.id).await?;
+
+        let reply_entry =
+            AttachResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn relink(
+        &self,
+        request: Request<RelinkRequest>,
+    ) -> Result<Response<RelinkResponse>, Status> {
+        authorize(&request, "relink programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let program = relink_program(request.id).await?;
+
+        let reply_entry =
+            RelinkResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn prune_pins(
+        &self,
+        request: Request<PrunePinsRequest>,
+    ) -> Result<Response<PrunePinsResponse>, Status> {
+        authorize(&request, "prune pins")?;
+        let _admitted = self.queue.admit()?;
+
+        let removed = prune_orphaned_pins().await?;
+
+        Ok(Response::new(PrunePinsResponse {
+            removed: removed
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+        }))
+    }
+
+    async fn replace(
+        &self,
+        request: Request<ReplaceRequest>,
+    ) -> Result<Response<ReplaceResponse>, Status> {
+        authorize(&request, "replace programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let mut inline_bytecode = None;
+        let bytecode_source = match request
+            .bytecode
+            .ok_or(Status::aborted("missing bytecode info"))?
+            .location
+            .ok_or(Status::aborted("missing location"))?
+        {
+            RpcLocation::Image(i) => Location::Image(i.into()),
+            RpcLocation::File(p) => Location::File(p),
+            RpcLocation::Url(u) => Location::Url(u.into()),
+            RpcLocation::Inline(b) => {
+                let path = inline_bytecode_path(&b);
+                inline_bytecode = Some(b);
+                Location::File(path)
+            }
+        };
+
+        let data = ProgramData::new(
+            bytecode_source,
+            request.name,
+            request.metadata,
+            request.global_data,
+            request.map_owner_id,
+        )
+        .map_err(|e| Status::aborted(format!("failed to create ProgramData: {e}")))?;
+
+        if let Some(bytecode) = &inline_bytecode {
+            write_inline_bytecode(bytecode)?;
+        }
+
+        let program = replace_program(request.id, data).await?;
+
+        let reply_entry =
+            ReplaceResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn reload(
+        &self,
+        request: Request<ReloadRequest>,
+    ) -> Result<Response<ReloadResponse>, Status> {
+        authorize(&request, "reload programs")?;
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let program = reload_program(request.id).await?;
+
+        let reply_entry =
+            ReloadResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn list_maps(
+        &self,
+        request: Request<ListMapsRequest>,
+    ) -> Result<Response<ListMapsResponse>, Status> {
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let maps = list_maps(request.id).await?;
+
+        Ok(Response::new(ListMapsResponse {
+            maps: maps.into_iter().map(|m| m.into()).collect(),
+        }))
+    }
+
+    async fn dispatcher_info(
+        &self,
+        request: Request<DispatcherInfoRequest>,
+    ) -> Result<Response<DispatcherInfoResponse>, Status> {
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let dispatchers = dispatcher_info(&request.iface).await?;
+
+        Ok(Response::new(DispatcherInfoResponse {
+            dispatchers: dispatchers.into_iter().map(|d| d.into()).collect(),
+        }))
+    }
+
+    type MapDumpStream = ReceiverStream<Result<MapDumpResponse, Status>>;
+
+    async fn map_dump(
+        &self,
+        request: Request<MapDumpRequest>,
+    ) -> Result<Response<Self::MapDumpStream>, Status> {
+        // Entries are only read, unlike MapWatch, so like other reads this
+        // isn't restricted by [authz].
+        let _admitted = self.queue.admit()?;
+        let request = request.into_inner();
+
+        let format = MapValueFormat::try_from(request.format.unwrap_or_default())
+            .map_err(|e| Status::aborted(format!("failed to parse format: {e}")))?;
+        let mut dump = map_dump(
+            request.id,
+            &request.map_name,
+            request.batch_size as usize,
+            &format,
+            request.sum_width.map(counter_width),
+        )
+        .await?;
+        let per_cpu = dump.is_per_cpu();
+
+        // Reading the map is a series of blocking syscalls, so do it off the
+        // runtime and hand each batch over as it's read. The channel is
+        // bounded so a slow client holds up the dump rather than bpfman's
+        // memory.
+        let (tx, rx) = mpsc::channel(MAP_DUMP_QUEUE_DEPTH);
+        tokio::task::spawn_blocking(move || {
+            let mut total = 0u64;
+            loop {
+                let reply = match dump.next_batch() {
+                    Ok(Some(batch)) => {
+                        total += batch.len() as u64;
+                        Ok(MapDumpResponse {
+                            entries: batch
+                                .into_iter()
+                                .map(|e| MapEntry {
+                                    key: e.key,
+                                    values: e.values,
+                                    decoded: e.decoded,
+                                    sums: e.sums,
+                                })
+                                .collect(),
+                            per_cpu,
+                            total_entries: None,
+                        })
+                    }
+                    Ok(None) => {
+                        let _ = tx.blocking_send(Ok(MapDumpResponse {
+                            entries: vec![],
+                            per_cpu,
+                            total_entries: Some(total),
+                        }));
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = tx.blocking_send(Err(Status::from(e)));
+                        return;
+                    }
+                };
+                // The client has gone away.
+                if tx.blocking_send(reply).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type MapWatchStream = ReceiverStream<Result<MapWatchResponse, Status>>;
+
+    async fn map_watch(
+        &self,
+        request: Request<MapWatchRequest>,
+    ) -> Result<Response<Self::MapWatchStream>, Status> {
+        // Records read by the watch are taken from the map.
+        authorize(&request, "watch maps")?;
+        let _admitted = self.queue.admit()?;
+        let watching = self.queue.admit_watch()?;
+        let request = request.into_inner();
+
+        let backpressure = backpressure(request.backpressure)?;
+        let mut watch = map_watch(request.id, &request.map_name).await?;
+
+        // Each watch is read on its own blocking task, which stops reading
+        // the map and closes it once the client has gone away.
+        let (tx, rx) = mpsc::channel(MAP_WATCH_QUEUE_DEPTH);
+        tokio::task::spawn_blocking(move || {
+            let _watching = watching;
+            let (mut lost, mut dropped) = (0, 0);
+            while !tx.is_closed() {
+                let events = match watch.next_events(MAP_WATCH_POLL_INTERVAL) {
+                    Ok(events) => events,
+                    Err(e) => {
+                        let _ = tx.blocking_send(Err(Status::from(e)));
+                        return;
+                    }
+                };
+                if events.records.is_empty() && events.lost == 0 {
+                    continue;
+                }
+                lost += events.lost;
+                let count = events.records.len() as u64;
+                let reply = Ok(MapWatchResponse {
+                    records: events.records,
+                    lost,
+                    dropped,
+                });
+                let sent = match backpressure {
+                    Backpressure::Block => tx.blocking_send(reply).is_ok(),
+                    Backpressure::Drop => match tx.try_send(reply) {
+                        Ok(()) => true,
+                        Err(TrySendError::Full(_)) => {
+                            dropped += count;
+                            continue;
+                        }
+                        Err(TrySendError::Closed(_)) => false,
+                    },
+                };
+                if !sent {
+                    return;
+                }
+                (lost, dropped) = (0, 0);
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type SubscribeEventsStream = ReceiverStream<Result<SubscribeEventsResponse, Status>>;
+
+    async fn subscribe_events(
+        &self,
+        request: Request<SubscribeEventsRequest>,
+    ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
+        let _admitted = self.queue.admit()?;
+        let _ = request.into_inner();
+
+        let mut events = subscribe_events();
+        let (tx, rx) = mpsc::channel(EVENTS_QUEUE_DEPTH);
+        tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    _ = tx.closed() => return,
+                    event = events.recv() => event,
+                };
+                let reply = match event {
+                    Ok(event) => Ok(SubscribeEventsResponse {
+                        event: event.kind.to_string(),
+                        id: event.id,
+                        name: event.name,
+                        program_type: event.program_type as u32,
+                        iface: event.iface,
+                        timestamp_ns: event
+                            .timestamp
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |d| d.as_nanos() as u64),
+                    }),
+                    // Rather than hold up the programs' changes, clients that
+                    // can't keep up are dropped.
+                    Err(RecvError::Lagged(missed)) => Err(Status::resource_exhausted(format!(
+                        "subscriber fell {missed} events behind, subscribe again and list the programs to catch up"
+                    ))),
+                    Err(RecvError::Closed) => return,
+                };
+                let failed = reply.is_err();
+                if tx.send(reply).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
 }
@@ -2,69 +2,269 @@
 // Copyright Authors of bpfman
 
 use std::{
+    collections::HashMap,
     fs::remove_file,
+    future::Future,
+    io::ErrorKind,
+    net::SocketAddr,
     os::unix::prelude::{FromRawFd, IntoRawFd},
     path::Path,
+    pin::Pin,
+    time::Duration,
 };
 
 use anyhow::anyhow;
-use bpfman::utils::{set_file_permissions, SOCK_MODE};
+use bpfman::{
+    config::{Config, DEFAULT_COMMAND_QUEUE_DEPTH},
+    disable_stats, kernel_features, list_programs, prune_orphaned_pins, prune_stale_programs,
+    remove_program,
+    types::ListFilter,
+    utils::set_file_permissions,
+};
 use bpfman_api::v1::bpfman_server::BpfmanServer;
 use libsystemd::activation::IsType;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use tokio::{
     join,
-    net::UnixListener,
+    net::{TcpListener, UnixListener},
     signal::unix::{signal, SignalKind},
-    sync::broadcast,
+    sync::{broadcast, mpsc},
     task::{JoinHandle, JoinSet},
 };
+use tokio_rustls::TlsAcceptor;
 use tokio_stream::wrappers::UnixListenerStream;
-use tonic::transport::Server;
+use tonic::{
+    codegen::InterceptedService,
+    transport::{server::TcpIncoming, Server},
+    Request, Status,
+};
+use tower::layer::util::{Identity, Stack};
+
+use crate::{
+    authz::set_authz,
+    connections::ConnectionLimit,
+    link_monitor::watch_links,
+    queue::CommandQueue,
+    reload::{listen_for_reload, reload_handler, set_current_config, MetricsListener},
+    rpc::BpfmanLoader,
+    storage::StorageManager,
+    timeout::RequestTimeoutLayer,
+    tls,
+    version::check_api_version,
+};
+
+type Service =
+    InterceptedService<BpfmanServer<BpfmanLoader>, fn(Request<()>) -> Result<Request<()>, Status>>;
 
-use crate::{rpc::BpfmanLoader, storage::StorageManager};
+// A listener that stops because of an error reports it here, which shuts
+// bpfman-rpc down so that whatever supervises it sees it exit with the error.
+type FailureSender = mpsc::Sender<String>;
 
-pub async fn serve(csi_support: bool, timeout: u64, socket_path: &Path) -> anyhow::Result<()> {
+// The [grpc] limits, which apply to every listener.
+#[derive(Clone)]
+struct ServerLimits {
+    connections: ConnectionLimit,
+    request_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+}
+
+impl ServerLimits {
+    fn server(&self) -> Server<Stack<RequestTimeoutLayer, Identity>> {
+        Server::builder().layer(RequestTimeoutLayer::new(self.request_timeout))
+    }
+}
+
+pub async fn serve(
+    config: &Config,
+    config_path: &Path,
+    csi_support: bool,
+    timeout: u64,
+    socket_path: &Path,
+) -> anyhow::Result<()> {
     let (shutdown_tx, shutdown_rx1) = broadcast::channel(32);
     let shutdown_rx3 = shutdown_tx.subscribe();
-    let shutdown_handle = tokio::spawn(shutdown_handler(timeout, shutdown_tx));
 
-    let loader = BpfmanLoader::new();
-    let service = BpfmanServer::new(loader);
+    // Parse the TCP listen addresses up front so that an invalid config
+    // is reported before any listener has been started.
+    let tcp_addrs = match config.grpc() {
+        Some(grpc) => grpc.listen_addrs()?,
+        None => Vec::new(),
+    };
+    let tls_acceptor = match config.grpc().as_ref().and_then(|grpc| grpc.tls().as_ref()) {
+        Some(tls) => Some(tls::acceptor(tls)?),
+        None => None,
+    };
+    let queue_depth = match config.grpc() {
+        Some(grpc) => grpc.command_queue_depth()?,
+        None => DEFAULT_COMMAND_QUEUE_DEPTH,
+    };
+    // The limits apply even when there is no [grpc] section.
+    let grpc = config.grpc().clone().unwrap_or_default();
+    let limits = ServerLimits {
+        connections: ConnectionLimit::new(grpc.max_connections()?),
+        request_timeout: grpc.request_timeout(),
+        tcp_keepalive: grpc.tcp_keepalive(),
+    };
+    let metrics_addr = match config.metrics() {
+        Some(metrics) => Some(metrics.listen_addr()?),
+        None => None,
+    };
+    // The bpffs is only mounted once the first request is handled.
+    if let Some(bpffs) = config.bpffs() {
+        bpffs.ms_flags()?;
+    }
+    let permissions = config.permissions().clone().unwrap_or_default();
+    let socket_mode = permissions.socket_mode()?;
+    permissions.pin_dir_mode()?;
+
+    // Probe the kernel up front so that missing features are logged at
+    // startup rather than on the first request that needs them.
+    kernel_features();
+    set_authz(config.authz().clone(), config.grpc().as_ref());
+    set_current_config(config.clone(), vec![]);
+
+    // Forget the programs that went away while bpfman wasn't running, such as
+    // across a reboot, so that their pins are removed as orphans below.
+    match prune_stale_programs().await {
+        Ok(pruned) if !pruned.is_empty() => {
+            info!("Removed {} programs no longer in the kernel", pruned.len())
+        }
+        Ok(_) => (),
+        Err(e) => warn!("Unable to remove programs no longer in the kernel: {e}"),
+    }
+
+    // Clean up after a bpfman that was stopped part way through a request.
+    match prune_orphaned_pins().await {
+        Ok(removed) if !removed.is_empty() => {
+            info!("Removed {} orphaned pins", removed.len())
+        }
+        Ok(_) => (),
+        Err(e) => warn!("Unable to remove orphaned pins: {e}"),
+    }
+
+    let loader = BpfmanLoader::new(CommandQueue::new(queue_depth));
+    let service: Service = BpfmanServer::with_interceptor(loader, check_api_version);
 
     let mut listeners: Vec<_> = Vec::new();
+    let (failure_tx, failure_rx) = mpsc::channel(1);
 
-    let handle = serve_unix(socket_path, service.clone(), shutdown_rx1).await?;
+    let handle = serve_unix(
+        socket_path,
+        socket_mode,
+        service.clone(),
+        limits.clone(),
+        shutdown_rx1,
+        failure_tx.clone(),
+    )
+    .await?;
     listeners.push(handle);
 
-    if csi_support {
+    for addr in tcp_addrs {
+        let handle = serve_tcp(
+            addr,
+            service.clone(),
+            tls_acceptor.clone(),
+            limits.clone(),
+            shutdown_tx.subscribe(),
+            failure_tx.clone(),
+        )
+        .await?;
+        listeners.push(handle);
+    }
+    drop(failure_tx);
+
+    let metrics = match metrics_addr {
+        Some(addr) => Some(MetricsListener::start(addr).await?),
+        None => None,
+    };
+    let reload_handle = tokio::spawn(reload_handler(
+        config_path.to_path_buf(),
+        config.clone(),
+        listen_for_reload()?,
+        metrics,
+        shutdown_tx.subscribe(),
+    ));
+
+    let link_monitor = if config.features().clone().unwrap_or_default().auto_reattach {
+        Some(watch_links(shutdown_tx.subscribe())?)
+    } else {
+        None
+    };
+
+    let shutdown_handle = tokio::spawn(shutdown_handler(timeout, shutdown_tx, failure_rx));
+
+    let (reason, config) = if csi_support {
         let storage_manager = StorageManager::new();
         let storage_manager_handle =
             tokio::spawn(async move { storage_manager.run(shutdown_rx3).await });
-        let (_, res_storage, _) = join!(
+        let (_, res_storage, res_reload, res) = join!(
             join_listeners(listeners),
             storage_manager_handle,
+            reload_handle,
             shutdown_handle
         );
         if let Some(e) = res_storage.err() {
             return Err(e.into());
         }
+        (res?, res_reload?)
     } else {
-        let (_, res) = join!(join_listeners(listeners), shutdown_handle);
-        if let Some(e) = res.err() {
-            return Err(e.into());
+        let (_, res_reload, res) = join!(join_listeners(listeners), reload_handle, shutdown_handle);
+        (res?, res_reload?)
+    };
+    if let Some(handle) = link_monitor {
+        if let Err(e) = handle.await {
+            error!("Error stopping the interface monitor: {e}");
+        }
+    }
+    // Stop collecting BPF statistics if a Stats request turned them on.
+    disable_stats();
+
+    if let ShutdownReason::Failed(failure) = reason {
+        return Err(anyhow!("shutting down, {failure}"));
+    }
+
+    // All listeners have stopped at this point, so no new requests will be
+    // started. The shutdown settings are taken from the config as last
+    // reloaded. Only detach when asked to stop, not on the inactivity timeout,
+    // since bpfman-rpc is restarted on demand and programs must outlive it.
+    let shutdown_config = config.shutdown().to_owned().unwrap_or_default();
+    if reason == ShutdownReason::Signal && shutdown_config.detach_on_exit {
+        let duration = Duration::from_secs(shutdown_config.detach_timeout);
+        if tokio::time::timeout(duration, detach_all_programs())
+            .await
+            .is_err()
+        {
+            warn!(
+                "Timed out after {} seconds detaching programs on exit",
+                shutdown_config.detach_timeout
+            );
         }
     }
 
     Ok(())
 }
 
-pub(crate) async fn shutdown_handler(timeout: u64, shutdown_tx: broadcast::Sender<()>) {
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ShutdownReason {
+    Timeout,
+    Signal,
+    // A listener stopped because of the error described.
+    Failed(String),
+}
+
+pub(crate) async fn shutdown_handler(
+    timeout: u64,
+    shutdown_tx: broadcast::Sender<()>,
+    mut failures: mpsc::Receiver<String>,
+) -> ShutdownReason {
     let mut joinset = JoinSet::new();
     if timeout > 0 {
         info!("Using inactivity timer of {} seconds", timeout);
         let duration: std::time::Duration = std::time::Duration::from_secs(timeout);
-        joinset.spawn(Box::pin(tokio::time::sleep(duration)));
+        joinset.spawn(async move {
+            tokio::time::sleep(duration).await;
+            ShutdownReason::Timeout
+        });
     } else {
         info!("Using no inactivity timer");
     }
@@ -72,41 +272,85 @@ pub(crate) async fn shutdown_handler(timeout: u64, shutdown_tx: broadcast::Sende
     joinset.spawn(async move {
         sigint.recv().await;
         debug!("Received SIGINT");
+        ShutdownReason::Signal
     });
 
     let mut sigterm = signal(SignalKind::terminate()).unwrap();
     joinset.spawn(async move {
         sigterm.recv().await;
         debug!("Received SIGTERM");
+        ShutdownReason::Signal
     });
 
-    joinset.join_next().await;
+    joinset.spawn(async move {
+        match failures.recv().await {
+            Some(failure) => ShutdownReason::Failed(failure),
+            // The listeners only stop without an error once shut down.
+            None => std::future::pending().await,
+        }
+    });
+
+    let reason = joinset
+        .join_next()
+        .await
+        .and_then(|r| r.ok())
+        .unwrap_or(ShutdownReason::Signal);
     shutdown_tx.send(()).unwrap();
+    reason
+}
+
+async fn detach_all_programs() {
+    let filter = ListFilter::new(None, HashMap::new(), true, None, None);
+    let programs = match list_programs(filter).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("Unable to list programs to detach on exit: {e}");
+            return;
+        }
+    };
+
+    for p in programs {
+        let id = match p.get_data().get_id() {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Unable to get program id to detach on exit: {e}");
+                continue;
+            }
+        };
+        info!("Detaching program {id} on exit");
+        if let Err(e) = remove_program(id).await {
+            warn!("Unable to detach program {id} on exit: {e}");
+        }
+    }
 }
 
 async fn join_listeners(listeners: Vec<JoinHandle<()>>) {
     for listener in listeners {
         match listener.await {
             Ok(()) => {}
-            Err(e) => eprintln!("Error = {e:?}"),
+            Err(e) => error!("Listener task failed: {e}"),
         }
     }
 }
 
 async fn serve_unix(
     path: &Path,
-    service: BpfmanServer<BpfmanLoader>,
+    mode: u32,
+    service: Service,
+    limits: ServerLimits,
     mut shutdown_channel: broadcast::Receiver<()>,
+    failures: FailureSender,
 ) -> anyhow::Result<JoinHandle<()>> {
     let uds_stream = if let Ok(stream) = systemd_unix_stream() {
         stream
     } else {
-        std_unix_stream(path).await?
+        std_unix_stream(path, mode).await?
     };
 
-    let serve = Server::builder()
+    let serve = limits
+        .server()
         .add_service(service)
-        .serve_with_incoming_shutdown(uds_stream, async move {
+        .serve_with_incoming_shutdown(limits.connections.limit(uds_stream), async move {
             match shutdown_channel.recv().await {
                 Ok(()) => debug!("Unix Socket: Received shutdown signal"),
                 Err(e) => error!("Error receiving shutdown signal {:?}", e),
@@ -117,7 +361,9 @@ async fn serve_unix(
     Ok(tokio::spawn(async move {
         info!("Listening on {}", socket_path.to_path_buf().display());
         if let Err(e) = serve.await {
-            eprintln!("Error = {e:?}");
+            let failure = format!("the listener on {} failed: {e}", socket_path.display());
+            error!("Unix Socket: {failure}");
+            let _ = failures.send(failure).await;
         }
         info!(
             "Shutdown Unix Handler {}",
@@ -126,6 +372,51 @@ async fn serve_unix(
     }))
 }
 
+async fn serve_tcp(
+    addr: SocketAddr,
+    service: Service,
+    tls_acceptor: Option<TlsAcceptor>,
+    limits: ServerLimits,
+    mut shutdown_channel: broadcast::Receiver<()>,
+    failures: FailureSender,
+) -> anyhow::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr).await.map_err(|e| match e.kind() {
+        ErrorKind::AddrInUse => anyhow!("unable to listen on {addr}: address already in use"),
+        _ => anyhow::Error::new(e).context(format!("unable to listen on {addr}")),
+    })?;
+
+    let incoming = TcpIncoming::from_listener(listener, false, limits.tcp_keepalive)
+        .map_err(|e| anyhow!("unable to listen on {addr}: {e}"))?;
+    let router = limits.server().add_service(service);
+    let shutdown = async move {
+        match shutdown_channel.recv().await {
+            Ok(()) => debug!("TCP Socket: Received shutdown signal"),
+            Err(e) => error!("Error receiving shutdown signal {:?}", e),
+        };
+    };
+    let scheme = if tls_acceptor.is_some() { "TLS" } else { "TCP" };
+    let serve: Pin<Box<dyn Future<Output = Result<(), tonic::transport::Error>> + Send>> =
+        match tls_acceptor {
+            Some(acceptor) => Box::pin(router.serve_with_incoming_shutdown(
+                limits.connections.limit(tls::incoming(incoming, acceptor)),
+                shutdown,
+            )),
+            None => Box::pin(
+                router.serve_with_incoming_shutdown(limits.connections.limit(incoming), shutdown),
+            ),
+        };
+
+    Ok(tokio::spawn(async move {
+        info!("Listening on {addr} ({scheme})");
+        if let Err(e) = serve.await {
+            let failure = format!("the listener on {addr} failed: {e}");
+            error!("TCP Socket: {failure}");
+            let _ = failures.send(failure).await;
+        }
+        info!("Shutdown TCP Handler {addr}");
+    }))
+}
+
 fn systemd_unix_stream() -> anyhow::Result<UnixListenerStream> {
     let listen_fds = libsystemd::activation::receive_descriptors(true)?;
     if listen_fds.len() == 1 {
@@ -145,7 +436,19 @@ fn systemd_unix_stream() -> anyhow::Result<UnixListenerStream> {
     Err(anyhow!("Unable to retrieve fd from systemd"))
 }
 
-async fn std_unix_stream(path: &Path) -> anyhow::Result<UnixListenerStream> {
+async fn std_unix_stream(path: &Path, mode: u32) -> anyhow::Result<UnixListenerStream> {
+    // Fail early with a useful message rather than the bare ENOENT from bind.
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+            return Err(anyhow!(
+                "unable to listen on {}: directory {} does not exist",
+                path.display(),
+                parent.display()
+            ));
+        }
+        _ => {}
+    }
+
     // Listen on Unix socket
     if path.exists() {
         // Attempt to remove the socket, since bind fails if it exists
@@ -155,7 +458,7 @@ async fn std_unix_stream(path: &Path) -> anyhow::Result<UnixListenerStream> {
     let uds = UnixListener::bind(path)?;
     let stream = UnixListenerStream::new(uds);
     // Always set the file permissions of our listening socket.
-    set_file_permissions(path, SOCK_MODE);
+    set_file_permissions(path, mode);
 
     info!("Using default Unix socket");
     Ok(stream)
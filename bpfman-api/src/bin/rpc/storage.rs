@@ -177,7 +177,11 @@ impl Node for CsiNode {
                             format!("failed creating target path {target_path:?}: {e}"),
                         )
                     })?;
-                    set_dir_permissions(target_path, OWNER_READ_WRITE);
+                    set_dir_permissions(target_path, OWNER_READ_WRITE).map_err(|e| {
+                        Status::aborted(format!(
+                            "failed to set permissions on {target_path:?}: {e}"
+                        ))
+                    })?;
                 }
 
                 // Make a new bpf fs specifically for the pod.
@@ -206,7 +210,7 @@ impl Node for CsiNode {
                 };
 
                 // Load the desired maps from the fs and re-pin to new fs.
-                maps.iter().try_for_each(|m| {
+                for m in maps.iter() {
                     debug!("Loading map {m} from {core_map_path:?}");
                     let map = MapData::from_pin(core_map_path.join(m)).map_err(|e| {
                         Status::new(
@@ -234,8 +238,7 @@ impl Node for CsiNode {
                         chown(&map_path, None, fs_group.parse().ok())?;
                         set_file_permissions(&map_path, 0o0660)
                     };
-                    Ok::<(), Status>(())
-                })?;
+                }
 
                 // mount the bpffs into the container
                 mount_fs_in_container(path.to_str().unwrap(), target_path).map_err(|e| {
@@ -405,7 +408,7 @@ impl StorageManager {
         tokio::spawn(async move {
             info!("CSI Plugin Listening on {}", path.display());
             if let Err(e) = serve.await {
-                eprintln!("Error = {e:?}");
+                error!("CSI Plugin listener on {} failed: {e}", path.display());
             }
             info!("Shutdown CSI Plugin Handler {}", path.display());
         });
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// The [grpc] request_timeout_secs limit. Cancelling a request part way, such
+// as an Unload that has removed the program from the database but not yet
+// from its dispatcher, leaves nobody to clean up after it, so tonic's own
+// timeout isn't used. Requests run on a task of their own instead, and a
+// timeout only stops waiting for the reply. The requests that load programs
+// or pull bytecode are left to bpfman's own [limits] load_timeout_ms.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tonic::{codegen::http, Status};
+use tower::{Layer, Service};
+
+// The methods that load programs or pull bytecode.
+const EXEMPT_METHODS: [&str; 7] = [
+    "/bpfman.v1.Bpfman/Load",
+    "/bpfman.v1.Bpfman/LoadObject",
+    "/bpfman.v1.Bpfman/Batch",
+    "/bpfman.v1.Bpfman/Replace",
+    "/bpfman.v1.Bpfman/Reload",
+    "/bpfman.v1.Bpfman/Validate",
+    "/bpfman.v1.Bpfman/PullBytecode",
+];
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Cancels requests, other than those in [`EXEMPT_METHODS`], that are still
+/// running after `timeout`.
+#[derive(Clone)]
+pub(crate) struct RequestTimeoutLayer {
+    timeout: Option<Duration>,
+}
+
+impl RequestTimeoutLayer {
+    pub(crate) fn new(timeout: Option<Duration>) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for RequestTimeoutLayer {
+    type Service = RequestTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestTimeout {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RequestTimeout<S> {
+    inner: S,
+    timeout: Option<Duration>,
+}
+
+impl<S, B> Service<http::Request<B>> for RequestTimeout<S>
+where
+    S: Service<http::Request<B>>,
+    S::Response: Send + 'static,
+    S::Error: Into<BoxError> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let timeout = self
+            .timeout
+            .filter(|_| !EXEMPT_METHODS.contains(&request.uri().path()));
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let Some(timeout) = timeout else {
+                return response.await.map_err(Into::into);
+            };
+            // Dropping the handle leaves the request running to the end.
+            let mut response = tokio::spawn(response);
+            match tokio::time::timeout(timeout, &mut response).await {
+                Ok(Ok(response)) => response.map_err(Into::into),
+                Ok(Err(e)) => Err(e.into()),
+                // tonic turns a Status error into the response.
+                Err(_) => Err(Status::cancelled("Timeout expired").into()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+
+    // Replies after a delay, and records that it got to the end.
+    #[derive(Clone)]
+    struct Slow {
+        delay: Duration,
+        finished: Arc<AtomicBool>,
+    }
+
+    impl Service<http::Request<()>> for Slow {
+        type Response = ();
+        type Error = Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<(), Infallible>> + Send>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: http::Request<()>) -> Self::Future {
+            let delay = self.delay;
+            let finished = self.finished.clone();
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                finished.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    fn request(path: &str) -> http::Request<()> {
+        http::Request::builder().uri(path).body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let mut service = RequestTimeoutLayer::new(Some(Duration::from_millis(50))).layer(Slow {
+            delay: Duration::from_millis(200),
+            finished: finished.clone(),
+        });
+
+        // The reply is given up on, but the request runs to the end.
+        let e = service
+            .call(request("/bpfman.v1.Bpfman/Unload"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<Status>().unwrap().code(),
+            tonic::Code::Cancelled
+        );
+        assert!(!finished.load(Ordering::SeqCst));
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(finished.load(Ordering::SeqCst));
+
+        // Loads aren't cut off.
+        finished.store(false, Ordering::SeqCst);
+        service
+            .call(request("/bpfman.v1.Bpfman/Load"))
+            .await
+            .unwrap();
+        assert!(finished.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// TLS for the gRPC TCP listeners is terminated here rather than with tonic's
+// ServerTlsConfig, which is built on a newer rustls than the one the rest of
+// the workspace already depends on. Accepted connections are handed to tonic
+// as an incoming stream once the handshake has completed.
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use bpfman::config::TlsConfig;
+use hyper::server::conn::AddrStream;
+use log::{debug, warn};
+use rustls::{
+    server::AllowAnyAuthenticatedClient, sign::any_supported_type, Certificate, PrivateKey,
+    RootCertStore, ServerConfig, SignatureScheme,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::mpsc,
+};
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::transport::server::{Connected, TcpConnectInfo, TcpIncoming};
+
+// Bounds how long a client can hold a connection open without completing the
+// handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Signed with the private key and verified against the certificate to make
+// sure the two belong together.
+const KEY_CHECK_MESSAGE: &[u8] = b"bpfman tls key check";
+
+/// Builds the TLS configuration for the gRPC TCP listeners. Client
+/// certificates are required and verified against `ca` when it is set.
+pub(crate) fn acceptor(tls: &TlsConfig) -> anyhow::Result<TlsAcceptor> {
+    let certs = load_certs(&tls.cert)?;
+    let key = load_key(&tls.key)?;
+    check_key_matches(&certs[0], &key).map_err(|e| {
+        anyhow!(
+            "TLS certificate {} and key {} don't match: {e}",
+            tls.cert.display(),
+            tls.key.display()
+        )
+    })?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &tls.ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca)? {
+                roots
+                    .add(&cert)
+                    .map_err(|e| anyhow!("invalid TLS CA certificate in {}: {e}", ca.display()))?;
+            }
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| anyhow!("unable to use TLS certificate {}: {e}", tls.cert.display()))?;
+    config.alpn_protocols = vec![b"h2".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Accepts connections from `incoming` and yields them once the TLS handshake
+/// has completed. Failed handshakes are logged and the connection dropped.
+pub(crate) fn incoming(
+    mut incoming: TcpIncoming,
+    acceptor: TlsAcceptor,
+) -> ReceiverStream<Result<TlsConnection, io::Error>> {
+    let (tx, rx) = mpsc::channel(32);
+
+    tokio::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                accepted = incoming.next() => match accepted {
+                    Some(accepted) => accepted,
+                    None => return,
+                },
+                // The server has shut down.
+                _ = tx.closed() => return,
+            };
+            let stream = match accepted {
+                Ok(stream) => stream,
+                Err(e) => {
+                    if tx.send(Err(e)).await.is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            // Handshake in the background so that a slow client doesn't hold
+            // up other connections.
+            let peer = stream.remote_addr();
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        debug!("TLS handshake with {peer} complete");
+                        let _ = tx.send(Ok(TlsConnection(stream))).await;
+                    }
+                    Ok(Err(e)) => warn!("TLS handshake with {peer} failed: {e}"),
+                    Err(_) => warn!("TLS handshake with {peer} timed out"),
+                }
+            });
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+/// A TCP connection on which the TLS handshake has completed.
+pub(crate) struct TlsConnection(TlsStream<AddrStream>);
+
+/// The connect info of a [`TlsConnection`], which tonic adds to the requests
+/// sent on it.
+#[derive(Clone, Debug)]
+pub(crate) struct TlsConnectInfo {
+    pub(crate) tcp: TcpConnectInfo,
+    /// Whether the client presented a certificate that was verified against
+    /// the [grpc.tls] ca. Clients are only asked for one when ca is set.
+    pub(crate) client_verified: bool,
+}
+
+impl Connected for TlsConnection {
+    type ConnectInfo = TlsConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        let (stream, session) = self.0.get_ref();
+        TlsConnectInfo {
+            tcp: stream.connect_info(),
+            client_verified: session.peer_certificates().is_some(),
+        }
+    }
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+fn open(path: &Path) -> anyhow::Result<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| anyhow!("unable to open {}: {e}", path.display()))
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .map_err(|e| anyhow!("unable to read certificates from {}: {e}", path.display()))?;
+    if certs.is_empty() {
+        return Err(anyhow!("no PEM certificates found in {}", path.display()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let items = rustls_pemfile::read_all(&mut open(path)?)
+        .map_err(|e| anyhow!("unable to read private key from {}: {e}", path.display()))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or(anyhow!("no PEM private key found in {}", path.display()))
+}
+
+fn check_key_matches(cert: &Certificate, key: &PrivateKey) -> Result<(), String> {
+    let key = any_supported_type(key).map_err(|e| e.to_string())?;
+    let cert = webpki::EndEntityCert::try_from(cert.0.as_slice()).map_err(|e| e.to_string())?;
+
+    for (scheme, alg) in [
+        (
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            &webpki::ECDSA_P256_SHA256,
+        ),
+        (
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            &webpki::ECDSA_P384_SHA384,
+        ),
+        (SignatureScheme::ED25519, &webpki::ED25519),
+        (
+            SignatureScheme::RSA_PKCS1_SHA256,
+            &webpki::RSA_PKCS1_2048_8192_SHA256,
+        ),
+    ] {
+        if let Some(signer) = key.choose_scheme(&[scheme]) {
+            let signature = signer.sign(KEY_CHECK_MESSAGE).map_err(|e| e.to_string())?;
+            return cert
+                .verify_signature(alg, KEY_CHECK_MESSAGE, &signature)
+                .map_err(|_| "the key is not the certificate's private key".to_string());
+        }
+    }
+    Err("unsupported private key type".to_string())
+}
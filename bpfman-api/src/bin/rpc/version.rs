@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Clients send the API version they were built against with every request.
+// Rejecting the ones bpfman-rpc no longer understands up front gives them a
+// deterministic failure rather than having fields they rely on ignored.
+
+use bpfman_api::{API_VERSION, API_VERSION_METADATA_KEY, MIN_API_VERSION};
+use log::warn;
+use tonic::{Request, Status};
+
+/// Checks the API version a request was sent with, if any. Requests without
+/// one are accepted so that clients predating versioning keep working.
+// The signature is the one tonic expects of an interceptor.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_api_version(request: Request<()>) -> Result<Request<()>, Status> {
+    let Some(value) = request.metadata().get(API_VERSION_METADATA_KEY) else {
+        return Ok(request);
+    };
+
+    let version = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .ok_or_else(|| {
+            Status::invalid_argument(format!("invalid {API_VERSION_METADATA_KEY} metadata"))
+        })?;
+
+    if !(MIN_API_VERSION..=API_VERSION).contains(&version) {
+        warn!("Rejecting request from client using API version {version}");
+        return Err(Status::failed_precondition(format!(
+            "client API version {version} is not supported, bpfman supports API versions \
+             {MIN_API_VERSION} to {API_VERSION}"
+        )));
+    }
+    Ok(request)
+}
@@ -10,13 +10,26 @@ pub struct BytecodeImage {
     #[prost(string, optional, tag = "4")]
     pub password: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// BytecodeUrl represents an ELF object file to download from an http:// or
+/// https:// URL. If sha256 is set, the download must have that SHA-256
+/// checksum, given in hex.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BytecodeUrl {
+    #[prost(string, tag = "1")]
+    pub url: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub sha256: ::core::option::Option<::prost::alloc::string::String>,
+}
 /// BytecodeLocation is either:
 /// - Parameters to pull an eBPF program stored in an OCI container image.
 /// - Local file path for an image.
+/// - The contents of an ELF object file, sent with the request.
+/// - A URL to download an ELF object file from.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BytecodeLocation {
-    #[prost(oneof = "bytecode_location::Location", tags = "2, 3")]
+    #[prost(oneof = "bytecode_location::Location", tags = "2, 3, 4, 5")]
     pub location: ::core::option::Option<bytecode_location::Location>,
 }
 /// Nested message and enum types in `BytecodeLocation`.
@@ -28,6 +41,10 @@ pub mod bytecode_location {
         Image(super::BytecodeImage),
         #[prost(string, tag = "3")]
         File(::prost::alloc::string::String),
+        #[prost(bytes, tag = "4")]
+        Inline(::prost::alloc::vec::Vec<u8>),
+        #[prost(message, tag = "5")]
+        Url(super::BytecodeUrl),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -61,6 +78,20 @@ pub struct KernelProgramInfo {
     pub bytes_memlock: u32,
     #[prost(uint32, tag = "13")]
     pub verified_insns: u32,
+    /// The kernel link the program is attached by, and whether it's still
+    /// attached. Other tools, such as bpftool, can detach a program behind
+    /// bpfman's back, which Relink undoes. Only set by List and Get, and
+    /// link_attached is left unset for tail call programs, which have no
+    /// link.
+    #[prost(uint32, optional, tag = "14")]
+    pub link_id: ::core::option::Option<u32>,
+    #[prost(bool, optional, tag = "15")]
+    pub link_attached: ::core::option::Option<bool>,
+    /// The memory the kernel has locked for the program's maps. Kernels
+    /// before 5.11 charge it against RLIMIT_MEMLOCK, later ones account it to
+    /// the memory cgroup. Only set by Get.
+    #[prost(uint64, optional, tag = "16")]
+    pub maps_bytes_memlock: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -88,24 +119,105 @@ pub struct ProgramInfo {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
+    #[prost(uint32, optional, tag = "9")]
+    pub group_id: ::core::option::Option<u32>,
+    #[prost(string, repeated, tag = "10")]
+    pub shared_maps: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "11")]
+    pub maps_to_pin: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "12")]
+    pub maps_to_skip: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "13")]
+    pub pin_namespace: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(map = "string, uint32", tag = "14")]
+    pub map_max_entries: ::std::collections::HashMap<::prost::alloc::string::String, u32>,
+    /// ID of the program already loaded at the same hook from the same
+    /// bytecode when this one was loaded with allow_duplicate set.
+    #[prost(uint32, optional, tag = "15")]
+    pub duplicate_of: ::core::option::Option<u32>,
+    /// When bpfman loaded the program. It is kept across restarts of bpfman,
+    /// unlike the kernel's load time in KernelProgramInfo, which is reset each
+    /// time the dispatcher of an XDP or TC program is rebuilt.
+    #[prost(string, optional, tag = "16")]
+    pub loaded_at: ::core::option::Option<::prost::alloc::string::String>,
+    /// When bpfman last attached the program, unset while it is detached.
+    #[prost(string, optional, tag = "17")]
+    pub attached_at: ::core::option::Option<::prost::alloc::string::String>,
+    /// Seconds since loaded_at.
+    #[prost(uint64, optional, tag = "18")]
+    pub uptime_secs: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct XdpAttachInfo {
-    #[prost(int32, tag = "1")]
-    pub priority: i32,
+    /// Priority to run the program at in its chain. Load uses the priority set
+    /// in the [defaults] config section if it isn't given.
+    #[prost(int32, optional, tag = "1")]
+    pub priority: ::core::option::Option<i32>,
     #[prost(string, tag = "2")]
     pub iface: ::prost::alloc::string::String,
     #[prost(int32, tag = "3")]
     pub position: i32,
     #[prost(int32, repeated, tag = "4")]
     pub proceed_on: ::prost::alloc::vec::Vec<i32>,
+    #[prost(string, optional, tag = "5")]
+    pub netns: ::core::option::Option<::prost::alloc::string::String>,
+    /// Additional interfaces to attach to. Only used by Load.
+    #[prost(string, repeated, tag = "6")]
+    pub ifaces: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Attach to every interface that is up, other than loopback. Only used by Load.
+    #[prost(bool, tag = "7")]
+    pub all_interfaces: bool,
+    /// Revision of the dispatcher the program is attached to; position is the
+    /// zero-based slot it occupies in that dispatcher. Set in the replies to
+    /// Load, Get and List.
+    #[prost(uint32, optional, tag = "8")]
+    pub dispatcher_revision: ::core::option::Option<u32>,
+    /// Set when the program is loaded but detached from its dispatcher. Only
+    /// set by Get and List.
+    #[prost(bool, tag = "9")]
+    pub detached: bool,
+    /// XDP mode: one of "skb", "drv", "hw" or "auto". Load uses it as the mode
+    /// to attach in, with "auto" trying drv first and falling back to skb. Get
+    /// and List report the mode the program actually runs in.
+    #[prost(string, optional, tag = "10")]
+    pub mode: ::core::option::Option<::prost::alloc::string::String>,
+    /// Index of iface. Load also accepts an index in place of the name in iface
+    /// and ifaces. Set in the replies to Load, Get and List.
+    #[prost(uint32, optional, tag = "11")]
+    pub if_index: ::core::option::Option<u32>,
+    /// Interfaces the kernel runs the program on in place of iface, which are
+    /// the members of a bond the program is attached to in drv mode. Set in the
+    /// replies to Load, Get and List.
+    #[prost(string, repeated, tag = "12")]
+    pub members: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Set when iface has been removed since the program was attached to it.
+    /// The kernel detached the program along with the interface, so unloading
+    /// it only drops what bpfman tracks for it. Only set by Get and List.
+    #[prost(bool, tag = "13")]
+    pub interface_missing: bool,
+    /// Set when the dispatcher the program is attached to took over iface from
+    /// the previous revision in place, with BPF_F_REPLACE, so that packets were
+    /// processed throughout the rebuild. Unset for the first dispatcher on an
+    /// interface, and when the previous one had to be detached first. Set in
+    /// the replies to Load, Get, List and Reorder.
+    #[prost(bool, tag = "14")]
+    pub dispatcher_replaced: bool,
+    /// Whether the program acts on packets. A disabled program still runs, but
+    /// its dispatcher carries on to the next program whatever it returns, as if
+    /// it proceeded on every action. Load loads the program enabled unless this
+    /// is false, and it can be changed later with SetEnabled. Set in the replies
+    /// to Load, Get and List.
+    #[prost(bool, optional, tag = "15")]
+    pub enabled: ::core::option::Option<bool>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TcAttachInfo {
-    #[prost(int32, tag = "1")]
-    pub priority: i32,
+    /// Priority to run the program at in its chain. Load uses the priority set
+    /// in the [defaults] config section if it isn't given.
+    #[prost(int32, optional, tag = "1")]
+    pub priority: ::core::option::Option<i32>,
     #[prost(string, tag = "2")]
     pub iface: ::prost::alloc::string::String,
     #[prost(int32, tag = "3")]
@@ -114,6 +226,39 @@ pub struct TcAttachInfo {
     pub direction: ::prost::alloc::string::String,
     #[prost(int32, repeated, tag = "5")]
     pub proceed_on: ::prost::alloc::vec::Vec<i32>,
+    #[prost(string, optional, tag = "6")]
+    pub netns: ::core::option::Option<::prost::alloc::string::String>,
+    /// Additional interfaces to attach to. Only used by Load.
+    #[prost(string, repeated, tag = "7")]
+    pub ifaces: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Attach to every interface that is up, other than loopback. Only used by Load.
+    #[prost(bool, tag = "8")]
+    pub all_interfaces: bool,
+    /// Revision of the dispatcher the program is attached to; position is the
+    /// zero-based slot it occupies in that dispatcher. Set in the replies to
+    /// Load, Get and List.
+    #[prost(uint32, optional, tag = "9")]
+    pub dispatcher_revision: ::core::option::Option<u32>,
+    /// Set when the program is loaded but detached from its dispatcher. Only
+    /// set by Get and List.
+    #[prost(bool, tag = "10")]
+    pub detached: bool,
+    /// Index of iface. Load also accepts an index in place of the name in iface
+    /// and ifaces. Set in the replies to Load, Get and List.
+    #[prost(uint32, optional, tag = "11")]
+    pub if_index: ::core::option::Option<u32>,
+    /// Set when iface has been removed since the program was attached to it.
+    /// The kernel detached the program along with the interface, so unloading
+    /// it only drops what bpfman tracks for it. Only set by Get and List.
+    #[prost(bool, tag = "12")]
+    pub interface_missing: bool,
+    /// Whether the program acts on packets. A disabled program still runs, but
+    /// its dispatcher carries on to the next program whatever it returns, as if
+    /// it proceeded on every action. Load loads the program enabled unless this
+    /// is false, and it can be changed later with SetEnabled. Set in the replies
+    /// to Load, Get and List.
+    #[prost(bool, optional, tag = "13")]
+    pub enabled: ::core::option::Option<bool>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -161,10 +306,35 @@ pub struct FexitAttachInfo {
     #[prost(string, tag = "1")]
     pub fn_name: ::prost::alloc::string::String,
 }
+/// CgroupAttachInfo represents the program specific metadata which bpfman
+/// needs to attach a cgroup_skb or cgroup_sock program to a cgroup v2
+/// directory. attach_type is one of "ingress", "egress", "sock_create",
+/// "sock_release", "post_bind4" or "post_bind6".
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CgroupAttachInfo {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub attach_type: ::prost::alloc::string::String,
+}
+/// TailCallAttachInfo represents the program specific metadata which bpfman
+/// needs to add a program to a prog array map for tail calls, rather than
+/// attaching it. The prog array belongs to the program given by map_owner_id,
+/// which must be set, and index must be less than its max entries. The
+/// program is taken out of the prog array again when it is unloaded.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TailCallAttachInfo {
+    #[prost(string, tag = "1")]
+    pub prog_array: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub index: u32,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AttachInfo {
-    #[prost(oneof = "attach_info::Info", tags = "2, 3, 4, 5, 6, 7, 8")]
+    #[prost(oneof = "attach_info::Info", tags = "2, 3, 4, 5, 6, 7, 8, 9, 10")]
     pub info: ::core::option::Option<attach_info::Info>,
 }
 /// Nested message and enum types in `AttachInfo`.
@@ -186,6 +356,10 @@ pub mod attach_info {
         FentryAttachInfo(super::FentryAttachInfo),
         #[prost(message, tag = "8")]
         FexitAttachInfo(super::FexitAttachInfo),
+        #[prost(message, tag = "9")]
+        CgroupAttachInfo(super::CgroupAttachInfo),
+        #[prost(message, tag = "10")]
+        TailCallAttachInfo(super::TailCallAttachInfo),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -197,6 +371,11 @@ pub struct LoadRequest {
     pub name: ::prost::alloc::string::String,
     #[prost(uint32, tag = "3")]
     pub program_type: u32,
+    /// How to attach the program. If it isn't set, the program's type and
+    /// attach point are taken from its ELF section name, following the libbpf
+    /// conventions, for kprobe/<function>, kretprobe/<function>,
+    /// tracepoint/<category>/<name>, tp/<category>/<name>, fentry/<function>
+    /// and fexit/<function> sections.
     #[prost(message, optional, tag = "4")]
     pub attach: ::core::option::Option<AttachInfo>,
     #[prost(map = "string, string", tag = "5")]
@@ -213,6 +392,53 @@ pub struct LoadRequest {
     pub uuid: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(uint32, optional, tag = "8")]
     pub map_owner_id: ::core::option::Option<u32>,
+    #[prost(bool, tag = "9")]
+    pub dry_run: bool,
+    /// Names of maps to share with every other program that names the same
+    /// maps. The first program loaded creates each map and the rest reuse it,
+    /// until the last of them is unloaded. The maps must be declared with
+    /// LIBBPF_PIN_BY_NAME, and can't be combined with map_owner_id.
+    #[prost(string, repeated, tag = "10")]
+    pub shared_maps: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Names of maps to pin even though they wouldn't be by default, such as
+    /// the .data map, and of maps to leave unpinned. Maps named in neither are
+    /// pinned unless they are .rodata, .bss or .data maps. A map can't be named
+    /// in both.
+    #[prost(string, repeated, tag = "11")]
+    pub maps_to_pin: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "12")]
+    pub maps_to_skip: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Directory in the bpffs to keep the program's pins in, so that the pins
+    /// of programs in different pin namespaces can't clash. The map owner and
+    /// the programs shared maps are shared with must be in the same one.
+    #[prost(string, optional, tag = "13")]
+    pub pin_namespace: ::core::option::Option<::prost::alloc::string::String>,
+    /// The number of programs the dispatchers of an XDP or TC program must
+    /// have room for. The load fails before anything is loaded if that is more
+    /// than a dispatcher can hold, or if the dispatchers don't have enough free
+    /// slots for every program being loaded.
+    #[prost(uint32, optional, tag = "14")]
+    pub dispatcher_capacity: ::core::option::Option<u32>,
+    /// The number of entries to create maps with in place of the max_entries
+    /// the bytecode declares, by map name. Only maps the program creates
+    /// itself can be resized, not those of its map owner or shared maps, and
+    /// the size must suit the type of map, such as a power of 2 for a ringbuf.
+    #[prost(map = "string, uint32", tag = "15")]
+    pub map_max_entries: ::std::collections::HashMap<::prost::alloc::string::String, u32>,
+    /// Load an XDP or TC program even though a program of the same name from
+    /// the same file, image or inline bytecode is already loaded on the
+    /// interface, in the same direction for TC. Without it such a load fails,
+    /// so that a client that retries a load doesn't run the program twice.
+    #[prost(bool, tag = "16")]
+    pub allow_duplicate: bool,
+    /// Makes retrying the load safe. If programs were already loaded with the
+    /// same key by the same user, and the key hasn't expired after \[limits\]
+    /// idempotency_ttl_secs, they are returned instead of loading the programs
+    /// again. A retry made while the first load is still running waits for
+    /// it. Reusing a key for other programs fails with FAILED_PRECONDITION.
+    /// Requests over TCP share one scope.
+    #[prost(string, optional, tag = "17")]
+    pub idempotency_key: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -221,18 +447,212 @@ pub struct LoadResponse {
     pub info: ::core::option::Option<ProgramInfo>,
     #[prost(message, optional, tag = "2")]
     pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+    /// Every program loaded when attaching to more than one interface,
+    /// including the one in info.
+    #[prost(message, repeated, tag = "3")]
+    pub group: ::prost::alloc::vec::Vec<LoadResult>,
+    #[prost(message, optional, tag = "4")]
+    pub pin_paths: ::core::option::Option<PinPaths>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadResult {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+    #[prost(message, optional, tag = "3")]
+    pub pin_paths: ::core::option::Option<PinPaths>,
+}
+/// PinPaths represents where a program, its link and its maps are pinned on the
+/// bpffs. The link is not set for programs in a prog array.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PinPaths {
+    #[prost(string, tag = "1")]
+    pub program: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub link: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(map = "string, string", tag = "3")]
+    pub maps: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+/// LoadObjectRequest represents a request to load several programs from the
+/// same bytecode object at once, in the order given. The programs are loaded
+/// as a group: the ID of the first identifies the group, and unloading it
+/// unloads every program in the group. Programs after the first use the first
+/// program's maps, so the object's maps are only created once, unless
+/// map_owner_id is set, in which case every program uses the map owner's maps.
+/// If a program fails to load, the programs loaded before it are unloaded
+/// again.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadObjectRequest {
+    #[prost(message, optional, tag = "1")]
+    pub bytecode: ::core::option::Option<BytecodeLocation>,
+    #[prost(message, repeated, tag = "2")]
+    pub programs: ::prost::alloc::vec::Vec<LoadObjectProgram>,
+    #[prost(map = "string, string", tag = "3")]
+    pub metadata: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(map = "string, bytes", tag = "4")]
+    pub global_data: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::vec::Vec<u8>,
+    >,
+    #[prost(uint32, optional, tag = "5")]
+    pub map_owner_id: ::core::option::Option<u32>,
+    /// Maps to share, pin and leave unpinned, as for LoadRequest. They apply to
+    /// the first program, which the maps belong to.
+    #[prost(string, repeated, tag = "6")]
+    pub shared_maps: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "7")]
+    pub maps_to_pin: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "8")]
+    pub maps_to_skip: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Pin namespace of every program, as for LoadRequest.
+    #[prost(string, optional, tag = "9")]
+    pub pin_namespace: ::core::option::Option<::prost::alloc::string::String>,
+    /// Map sizes, as for LoadRequest. They apply to the first program.
+    #[prost(map = "string, uint32", tag = "10")]
+    pub map_max_entries: ::std::collections::HashMap<::prost::alloc::string::String, u32>,
+    /// Allow duplicate XDP and TC programs, as for LoadRequest.
+    #[prost(bool, tag = "11")]
+    pub allow_duplicate: bool,
+}
+/// LoadObjectProgram represents one of the programs to load from the bytecode
+/// object, named as in LoadRequest. XDP and TC programs attached to more than
+/// one interface are loaded once per interface.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadObjectProgram {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub attach: ::core::option::Option<AttachInfo>,
+}
+/// LoadObjectResponse represents a response from loading the programs of a
+/// bytecode object, with one result per program loaded.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadObjectResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub programs: ::prost::alloc::vec::Vec<LoadResult>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProgramSelector {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub iface: ::prost::alloc::string::String,
+    /// Only match the program attached at this priority.
+    #[prost(int32, optional, tag = "3")]
+    pub priority: ::core::option::Option<i32>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UnloadRequest {
     #[prost(uint32, tag = "1")]
     pub id: u32,
+    /// Used instead of id when set.
+    #[prost(message, optional, tag = "2")]
+    pub selector: ::core::option::Option<ProgramSelector>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UnloadResponse {}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnloadAllRequest {
+    #[prost(string, optional, tag = "1")]
+    pub iface: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnloadAllResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<unload_all_response::UnloadResult>,
+}
+/// Nested message and enum types in `UnloadAllResponse`.
+pub mod unload_all_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct UnloadResult {
+        #[prost(uint32, tag = "1")]
+        pub id: u32,
+        /// Set when the program could not be unloaded.
+        #[prost(string, optional, tag = "2")]
+        pub error: ::core::option::Option<::prost::alloc::string::String>,
+    }
+}
+/// BatchRequest loads and unloads programs, rebuilding each dispatcher they
+/// touch once rather than once per program.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub operations: ::prost::alloc::vec::Vec<batch_request::Operation>,
+    /// Only apply the batch if every operation in it succeeds. The changes it
+    /// made are undone otherwise, and the operations that didn't fail report
+    /// BATCH_ABORTED.
+    #[prost(bool, tag = "2")]
+    pub atomic: bool,
+}
+/// Nested message and enum types in `BatchRequest`.
+pub mod batch_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Operation {
+        #[prost(oneof = "operation::Op", tags = "1, 2")]
+        pub op: ::core::option::Option<operation::Op>,
+    }
+    /// Nested message and enum types in `Operation`.
+    pub mod operation {
+        #[allow(clippy::derive_partial_eq_without_eq)]
+        #[derive(Clone, PartialEq, ::prost::Oneof)]
+        pub enum Op {
+            /// Loads a single program. Loads in a batch can't be dry runs or
+            /// load the program on more than one interface.
+            #[prost(message, tag = "1")]
+            Load(super::super::LoadRequest),
+            /// Unloads the program with this ID, along with the rest of its
+            /// group.
+            #[prost(uint32, tag = "2")]
+            Unload(u32),
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchResponse {
+    /// One result per operation, in the order of the request.
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<batch_response::Result>,
+}
+/// Nested message and enum types in `BatchResponse`.
+pub mod batch_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Result {
+        /// Set when the operation failed.
+        #[prost(string, optional, tag = "1")]
+        pub error: ::core::option::Option<::prost::alloc::string::String>,
+        /// Name of the error, as sent in the bpfman-error-code metadata of a
+        /// failed request.
+        #[prost(string, optional, tag = "2")]
+        pub error_code: ::core::option::Option<::prost::alloc::string::String>,
+        /// Set for loads that succeeded.
+        #[prost(message, optional, tag = "3")]
+        pub load: ::core::option::Option<super::LoadResult>,
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListRequest {
     #[prost(uint32, optional, tag = "1")]
     pub program_type: ::core::option::Option<u32>,
@@ -243,12 +663,19 @@ pub struct ListRequest {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
+    #[prost(string, optional, tag = "4")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+    /// Only list the programs in this pin namespace.
+    #[prost(string, optional, tag = "5")]
+    pub pin_namespace: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListResponse {
     #[prost(message, repeated, tag = "3")]
     pub results: ::prost::alloc::vec::Vec<list_response::ListResult>,
+    #[prost(message, optional, tag = "4")]
+    pub counts: ::core::option::Option<ProgramCounts>,
 }
 /// Nested message and enum types in `ListResponse`.
 pub mod list_response {
@@ -261,6 +688,23 @@ pub mod list_response {
         pub kernel_info: ::core::option::Option<super::KernelProgramInfo>,
     }
 }
+/// ProgramCounts represents the number of programs loaded by bpfman, in total
+/// and for each interface XDP and TC programs are loaded on, along with the
+/// limits set in the \[limits\] config. A limit is unset if it isn't enforced.
+/// The counts cover every program loaded by bpfman, whatever the ListRequest
+/// filters.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProgramCounts {
+    #[prost(uint32, tag = "1")]
+    pub programs: u32,
+    #[prost(uint32, optional, tag = "2")]
+    pub max_programs: ::core::option::Option<u32>,
+    #[prost(map = "string, uint32", tag = "3")]
+    pub programs_per_iface: ::std::collections::HashMap<::prost::alloc::string::String, u32>,
+    #[prost(uint32, optional, tag = "4")]
+    pub max_programs_per_iface: ::core::option::Option<u32>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PullBytecodeRequest {
@@ -283,311 +727,2836 @@ pub struct GetResponse {
     pub info: ::core::option::Option<ProgramInfo>,
     #[prost(message, optional, tag = "2")]
     pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+    /// Maps pinned for the program, as ListMaps returns them. Only set for
+    /// programs loaded by bpfman.
+    #[prost(message, repeated, tag = "3")]
+    pub maps: ::prost::alloc::vec::Vec<list_maps_response::MapInfo>,
 }
-/// Generated client implementations.
-pub mod bpfman_client {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    use tonic::codegen::http::Uri;
-    #[derive(Debug, Clone)]
-    pub struct BpfmanClient<T> {
-        inner: tonic::client::Grpc<T>,
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapLookupRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    /// How to decode the values, in addition to returning their bytes: "raw"
+    /// (the default), "u32" or "u64" for arrays of native endian integers, "btf"
+    /// for the map's value type in the program's BTF, or "btf:<type>" for the
+    /// named type. Values of programs without BTF aren't decoded.
+    #[prost(string, optional, tag = "4")]
+    pub format: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, the values of a per-CPU map are also summed across CPUs as
+    /// arrays of native endian unsigned counters of sum_width bytes: 1, 2, 4 or
+    /// 8, with 0 meaning u64 counters. Ignored for other maps.
+    #[prost(uint32, optional, tag = "5")]
+    pub sum_width: ::core::option::Option<u32>,
+    /// If set, typed_key is looked up in place of key, encoded as key_type:
+    /// "ipv4" or "ipv6" for an address in network byte order, "mac" for a MAC
+    /// address such as 00:11:22:33:44:55, "u16", "u32" or "u64" for a native
+    /// endian integer, or "be16" or "be32" for a big endian integer, such as a
+    /// port as it appears in a packet. The encoded key must be the map's key
+    /// size.
+    #[prost(string, optional, tag = "6")]
+    pub key_type: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "7")]
+    pub typed_key: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapLookupResponse {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub values: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// Each value decoded as JSON, if decoding was asked for and possible.
+    #[prost(string, repeated, tag = "2")]
+    pub decoded: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The counters summed across CPUs, if sum_width was set for a per-CPU map.
+    #[prost(uint64, repeated, tag = "3")]
+    pub sums: ::prost::alloc::vec::Vec<u64>,
+    /// The key that was looked up, as encoded from typed_key if key_type was set.
+    #[prost(bytes = "vec", tag = "4")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapUpdateRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "4")]
+    pub values: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapUpdateResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapDeleteRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapDeleteResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapFreezeRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapFreezeResponse {
+    /// The map was frozen already, or is memory mapped for writing, which the
+    /// kernel can't tell apart.
+    #[prost(bool, tag = "1")]
+    pub already_frozen: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthResponse {
+    #[prost(string, tag = "1")]
+    pub version: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub uptime_secs: u64,
+    #[prost(uint32, tag = "3")]
+    pub programs_loaded: u32,
+    #[prost(bool, tag = "4")]
+    pub bpffs_mounted: bool,
+    #[prost(bool, tag = "5")]
+    pub btf_available: bool,
+    #[prost(bool, tag = "6")]
+    pub bpf_link_supported: bool,
+    #[prost(bool, tag = "7")]
+    pub xdp_dispatcher_supported: bool,
+    /// Whether fentry and fexit programs can be attached.
+    #[prost(bool, tag = "8")]
+    pub trampoline_supported: bool,
+}
+/// PingRequest checks that requests are being handled. Unlike a health check,
+/// a ping waits behind the requests already being handled, the way a load
+/// does, so the time it takes shows how long requests are being kept waiting,
+/// for example behind a slow load.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PingRequest {}
+/// PingResponse carries the time the ping was answered in nanoseconds since
+/// the Unix epoch, and how long it waited to be handled in microseconds.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PingResponse {
+    #[prost(uint64, tag = "1")]
+    pub timestamp_ns: u64,
+    #[prost(uint64, tag = "2")]
+    pub wait_us: u64,
+}
+/// GetConfigRequest represents a request for the config bpfman-rpc is running
+/// with.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetConfigRequest {}
+/// GetConfigResponse carries the config file bpfman-rpc last loaded, at
+/// startup or on SIGHUP, as TOML with the defaults of each section filled in.
+/// The path of the TLS key is left out. restart_required names the sections
+/// that have changed since bpfman-rpc started but only take effect on restart,
+/// for which the config shows the new settings.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetConfigResponse {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub config: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub restart_required: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// DumpStateRequest represents a request for everything bpfman tracks.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DumpStateRequest {}
+/// DumpStateResponse carries bpfman's programs, the maps and pins of each and
+/// the dispatchers on each interface as a JSON document, for backups and for
+/// inspecting what bpfman believes is loaded. Image registry credentials are
+/// left out.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DumpStateResponse {
+    #[prost(string, tag = "1")]
+    pub state: ::prost::alloc::string::String,
+}
+/// ListKernelRequest represents a request for every BPF program and link in
+/// the kernel, including those bpfman didn't load.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListKernelRequest {}
+/// ListKernelResponse lists the kernel's BPF programs and links, marking
+/// which ones bpfman manages, to find conflicts with other tools such as
+/// another agent attaching XDP to an interface bpfman uses. TC programs and
+/// XDP programs attached through netlink rather than a link only show up as
+/// programs.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListKernelResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub programs: ::prost::alloc::vec::Vec<list_kernel_response::KernelProgram>,
+    #[prost(message, repeated, tag = "2")]
+    pub links: ::prost::alloc::vec::Vec<list_kernel_response::KernelLink>,
+}
+/// Nested message and enum types in `ListKernelResponse`.
+pub mod list_kernel_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct KernelProgram {
+        #[prost(uint32, tag = "1")]
+        pub id: u32,
+        #[prost(string, tag = "2")]
+        pub name: ::prost::alloc::string::String,
+        #[prost(uint32, tag = "3")]
+        pub program_type: u32,
+        /// Whether bpfman loaded the program, as one of its programs or as the
+        /// dispatcher of an interface.
+        #[prost(bool, tag = "4")]
+        pub managed: bool,
+        #[prost(bool, tag = "5")]
+        pub dispatcher: bool,
     }
-    impl<T> BpfmanClient<T>
-    where
-        T: tonic::client::GrpcService<tonic::body::BoxBody>,
-        T::Error: Into<StdError>,
-        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
-        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
-    {
-        pub fn new(inner: T) -> Self {
-            let inner = tonic::client::Grpc::new(inner);
-            Self { inner }
-        }
-        pub fn with_origin(inner: T, origin: Uri) -> Self {
-            let inner = tonic::client::Grpc::with_origin(inner, origin);
-            Self { inner }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> BpfmanClient<InterceptedService<T, F>>
-        where
-            F: tonic::service::Interceptor,
-            T::ResponseBody: Default,
-            T: tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-                Response = http::Response<
-                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
-                >,
-            >,
-            <T as tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-            >>::Error: Into<StdError> + Send + Sync,
-        {
-            BpfmanClient::new(InterceptedService::new(inner, interceptor))
-        }
-        /// Compress requests with the given encoding.
-        ///
-        /// This requires the server to support it otherwise it might respond with an
-        /// error.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.send_compressed(encoding);
-            self
-        }
-        /// Enable decompressing responses.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.accept_compressed(encoding);
-            self
-        }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.inner = self.inner.max_decoding_message_size(limit);
-            self
-        }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.inner = self.inner.max_encoding_message_size(limit);
-            self
-        }
-        pub async fn load(
-            &mut self,
-            request: impl tonic::IntoRequest<super::LoadRequest>,
-        ) -> std::result::Result<tonic::Response<super::LoadResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Load");
-            let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Load"));
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn unload(
-            &mut self,
-            request: impl tonic::IntoRequest<super::UnloadRequest>,
-        ) -> std::result::Result<tonic::Response<super::UnloadResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Unload");
-            let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Unload"));
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn list(
-            &mut self,
-            request: impl tonic::IntoRequest<super::ListRequest>,
-        ) -> std::result::Result<tonic::Response<super::ListResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/List");
-            let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "List"));
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn pull_bytecode(
-            &mut self,
-            request: impl tonic::IntoRequest<super::PullBytecodeRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::PullBytecodeResponse>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/bpfman.v1.Bpfman/PullBytecode",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "PullBytecode"));
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn get(
-            &mut self,
-            request: impl tonic::IntoRequest<super::GetRequest>,
-        ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Get");
-            let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Get"));
-            self.inner.unary(req, path, codec).await
-        }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct KernelLink {
+        #[prost(uint32, tag = "1")]
+        pub id: u32,
+        #[prost(uint32, tag = "2")]
+        pub prog_id: u32,
+        #[prost(string, tag = "3")]
+        pub link_type: ::prost::alloc::string::String,
+        /// The interface an XDP link is attached to.
+        #[prost(uint32, optional, tag = "4")]
+        pub if_index: ::core::option::Option<u32>,
+        #[prost(string, optional, tag = "5")]
+        pub iface: ::core::option::Option<::prost::alloc::string::String>,
+        /// Whether the program the link attaches is managed by bpfman.
+        #[prost(bool, tag = "6")]
+        pub managed: bool,
     }
 }
-/// Generated server implementations.
-pub mod bpfman_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with BpfmanServer.
-    #[async_trait]
-    pub trait Bpfman: Send + Sync + 'static {
-        async fn load(
-            &self,
-            request: tonic::Request<super::LoadRequest>,
-        ) -> std::result::Result<tonic::Response<super::LoadResponse>, tonic::Status>;
-        async fn unload(
-            &self,
-            request: tonic::Request<super::UnloadRequest>,
-        ) -> std::result::Result<tonic::Response<super::UnloadResponse>, tonic::Status>;
-        async fn list(
-            &self,
-            request: tonic::Request<super::ListRequest>,
-        ) -> std::result::Result<tonic::Response<super::ListResponse>, tonic::Status>;
-        async fn pull_bytecode(
-            &self,
-            request: tonic::Request<super::PullBytecodeRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::PullBytecodeResponse>,
-            tonic::Status,
-        >;
-        async fn get(
-            &self,
-            request: tonic::Request<super::GetRequest>,
-        ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status>;
-    }
-    #[derive(Debug)]
-    pub struct BpfmanServer<T: Bpfman> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
+/// ValidateRequest represents a request to check bytecode without loading
+/// it. Unlike a dry run, the kernel isn't involved.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateRequest {
+    #[prost(message, optional, tag = "1")]
+    pub bytecode: ::core::option::Option<BytecodeLocation>,
+}
+/// ValidateResponse lists the programs and maps in the bytecode, and the
+/// problems that would stop it, or some of its programs, from being loaded.
+/// The bytecode is valid if there are no issues.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub programs: ::prost::alloc::vec::Vec<validate_response::Program>,
+    #[prost(message, repeated, tag = "2")]
+    pub maps: ::prost::alloc::vec::Vec<validate_response::Map>,
+    #[prost(string, tag = "3")]
+    pub license: ::prost::alloc::string::String,
+    /// Whether the bytecode has BTF, and CO-RE relocations that need the BTF
+    /// of the kernel it is loaded on.
+    #[prost(bool, tag = "4")]
+    pub btf: bool,
+    #[prost(bool, tag = "5")]
+    pub uses_core: bool,
+    #[prost(string, repeated, tag = "6")]
+    pub issues: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Nested message and enum types in `ValidateResponse`.
+pub mod validate_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Program {
+        #[prost(string, tag = "1")]
+        pub name: ::prost::alloc::string::String,
+        #[prost(string, tag = "2")]
+        pub section: ::prost::alloc::string::String,
+        #[prost(string, tag = "3")]
+        pub program_type: ::prost::alloc::string::String,
     }
-    struct _Inner<T>(Arc<T>);
-    impl<T: Bpfman> BpfmanServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
-        }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
-        }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
-        }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
-        }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
-        }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Map {
+        #[prost(string, tag = "1")]
+        pub name: ::prost::alloc::string::String,
+        #[prost(string, tag = "2")]
+        pub map_type: ::prost::alloc::string::String,
+        #[prost(uint32, tag = "3")]
+        pub key_size: u32,
+        #[prost(uint32, tag = "4")]
+        pub value_size: u32,
+        #[prost(uint32, tag = "5")]
+        pub max_entries: u32,
     }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for BpfmanServer<T>
-    where
-        T: Bpfman,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
-            &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
-        }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/bpfman.v1.Bpfman/Load" => {
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatsRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatsResponse {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(uint64, tag = "2")]
+    pub run_count: u64,
+    #[prost(uint64, tag = "3")]
+    pub run_time_ns: u64,
+    #[prost(bool, tag = "4")]
+    pub stats_enabled: bool,
+    #[prost(string, tag = "5")]
+    pub loaded_at: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "6")]
+    pub attach_type: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReorderRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(int32, tag = "2")]
+    pub priority: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReorderResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapDumpRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "3")]
+    pub batch_size: u32,
+    /// How to decode the values, as for MapLookupRequest.
+    #[prost(string, optional, tag = "4")]
+    pub format: ::core::option::Option<::prost::alloc::string::String>,
+    /// How to sum the values of a per-CPU map, as for MapLookupRequest.
+    #[prost(uint32, optional, tag = "5")]
+    pub sum_width: ::core::option::Option<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapEntry {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub values: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// Each value decoded as JSON, if decoding was asked for and possible.
+    #[prost(string, repeated, tag = "3")]
+    pub decoded: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The counters summed across CPUs, if sum_width was set for a per-CPU map.
+    #[prost(uint64, repeated, tag = "4")]
+    pub sums: ::prost::alloc::vec::Vec<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapDumpResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub entries: ::prost::alloc::vec::Vec<MapEntry>,
+    #[prost(bool, tag = "2")]
+    pub per_cpu: bool,
+    #[prost(uint64, optional, tag = "3")]
+    pub total_entries: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapWatchRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    /// What to do when the client falls behind: "block", the default, stops
+    /// reading the map until the client catches up, leaving the kernel to drop
+    /// records once the map is full. "drop" keeps reading the map and drops the
+    /// records that don't fit in the stream's queue.
+    #[prost(string, optional, tag = "3")]
+    pub backpressure: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapWatchResponse {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub records: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// Records the kernel dropped since the previous response because a perf
+    /// buffer was full.
+    #[prost(uint64, tag = "2")]
+    pub lost: u64,
+    /// Records bpfman dropped since the previous response because the client
+    /// fell behind.
+    #[prost(uint64, tag = "3")]
+    pub dropped: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DetachRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DetachResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttachRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttachResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+/// SetEnabledRequest represents a request to enable or disable an XDP or TC
+/// program without unloading it or taking it off its dispatcher, which is
+/// rebuilt to apply the change. Programs can be loaded disabled and enabled
+/// once they are in place, for staged rollouts.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetEnabledRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(bool, tag = "2")]
+    pub enabled: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetEnabledResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+/// DiffStateRequest compares a desired set of programs with the programs
+/// bpfman has loaded, without loading or unloading anything. Programs are
+/// matched up by their type, name and where they are attached, and compared on
+/// the rest of what they are loaded with. The response can be applied with a
+/// Batch.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffStateRequest {
+    /// The programs wanted, each of which must load a single program.
+    #[prost(message, repeated, tag = "1")]
+    pub programs: ::prost::alloc::vec::Vec<LoadRequest>,
+    /// Only the loaded programs with all of this metadata are compared, so
+    /// that programs loaded by others are neither changed nor removed.
+    #[prost(map = "string, string", tag = "2")]
+    pub selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffStateResponse {
+    /// Indexes of the desired programs that aren't loaded.
+    #[prost(uint32, repeated, tag = "1")]
+    pub add: ::prost::alloc::vec::Vec<u32>,
+    /// IDs of the loaded programs that aren't desired. Unloading a program
+    /// unloads the rest of its group too.
+    #[prost(uint32, repeated, tag = "2")]
+    pub remove: ::prost::alloc::vec::Vec<u32>,
+    /// Desired programs that are loaded with other settings, which are
+    /// changed by unloading the program and loading it again.
+    #[prost(message, repeated, tag = "3")]
+    pub change: ::prost::alloc::vec::Vec<diff_state_response::Change>,
+    /// Desired programs that are loaded as they are.
+    #[prost(message, repeated, tag = "4")]
+    pub unchanged: ::prost::alloc::vec::Vec<diff_state_response::Match>,
+}
+/// Nested message and enum types in `DiffStateResponse`.
+pub mod diff_state_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Change {
+        /// Index of the desired program in the request.
+        #[prost(uint32, tag = "1")]
+        pub index: u32,
+        /// ID of the loaded program it matches.
+        #[prost(uint32, tag = "2")]
+        pub id: u32,
+        /// The settings that differ, such as "priority" or "bytecode".
+        #[prost(string, repeated, tag = "3")]
+        pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Match {
+        #[prost(uint32, tag = "1")]
+        pub index: u32,
+        #[prost(uint32, tag = "2")]
+        pub id: u32,
+    }
+}
+/// SubscribeEventsRequest represents a request to stream an event each time a
+/// program is loaded, unloaded, detached or attached again, until the client
+/// cancels the stream. Clients that fall too far behind are sent a
+/// RESOURCE_EXHAUSTED error and have to subscribe again, and List again to
+/// catch up on the events they missed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeEventsRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeEventsResponse {
+    /// "loaded", "unloaded", "attached" or "detached".
+    #[prost(string, tag = "1")]
+    pub event: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub id: u32,
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "4")]
+    pub program_type: u32,
+    /// The interface of an XDP or TC program.
+    #[prost(string, optional, tag = "5")]
+    pub iface: ::core::option::Option<::prost::alloc::string::String>,
+    /// When the event happened, in nanoseconds since the Unix epoch.
+    #[prost(uint64, tag = "6")]
+    pub timestamp_ns: u64,
+}
+/// RelinkRequest represents a request to attach a program again after another
+/// tool detached it, using the program that is still loaded. XDP and TC
+/// programs are attached again by rebuilding their dispatcher. Programs that
+/// are still attached, or that were detached with Detach, are left alone.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RelinkRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RelinkResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetInfoRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetInfoResponse {
+    #[prost(string, tag = "1")]
+    pub version: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub api_version: u32,
+    #[prost(uint32, tag = "3")]
+    pub min_api_version: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PrunePinsRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PrunePinsResponse {
+    #[prost(string, repeated, tag = "1")]
+    pub removed: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplaceRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(message, optional, tag = "2")]
+    pub bytecode: ::core::option::Option<BytecodeLocation>,
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "4")]
+    pub metadata: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(map = "string, bytes", tag = "5")]
+    pub global_data: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::vec::Vec<u8>,
+    >,
+    #[prost(uint32, optional, tag = "6")]
+    pub map_owner_id: ::core::option::Option<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplaceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+/// ReloadRequest represents a request to reload an attached XDP or TC program
+/// from the file or image it was loaded from, and replace it with the new
+/// program as Replace does. The program keeps the name, metadata, global data
+/// and map settings it was loaded with, and images are pulled again.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReloadRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReloadResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListMapsRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListMapsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub maps: ::prost::alloc::vec::Vec<list_maps_response::MapInfo>,
+}
+/// Nested message and enum types in `ListMapsResponse`.
+pub mod list_maps_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MapInfo {
+        #[prost(string, tag = "1")]
+        pub name: ::prost::alloc::string::String,
+        /// The kind of map, named as bpftool names it, such as "hash" or
+        /// "percpu_array".
+        #[prost(string, tag = "2")]
+        pub map_type: ::prost::alloc::string::String,
+        #[prost(uint32, tag = "3")]
+        pub key_size: u32,
+        #[prost(uint32, tag = "4")]
+        pub value_size: u32,
+        #[prost(uint32, tag = "5")]
+        pub max_entries: u32,
+        #[prost(string, tag = "6")]
+        pub pin_path: ::prost::alloc::string::String,
+    }
+}
+/// DispatcherInfoRequest represents a request for the slots of the XDP and TC
+/// dispatchers on an interface in bpfman's own network namespace, given by
+/// name or by index. Every dispatcher has the same, fixed number of slots.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DispatcherInfoRequest {
+    #[prost(string, tag = "1")]
+    pub iface: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DispatcherInfoResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub dispatchers: ::prost::alloc::vec::Vec<dispatcher_info_response::Dispatcher>,
+}
+/// Nested message and enum types in `DispatcherInfoResponse`.
+pub mod dispatcher_info_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Dispatcher {
+        #[prost(uint32, tag = "1")]
+        pub program_type: u32,
+        /// "ingress" or "egress" for TC dispatchers.
+        #[prost(string, optional, tag = "2")]
+        pub direction: ::core::option::Option<::prost::alloc::string::String>,
+        #[prost(uint32, tag = "3")]
+        pub capacity: u32,
+        /// Slots taken by attached programs. Detached programs don't take one.
+        #[prost(uint32, tag = "4")]
+        pub used: u32,
+        #[prost(uint32, tag = "5")]
+        pub revision: u32,
+    }
+}
+/// Generated client implementations.
+pub mod bpfman_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct BpfmanClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl<T> BpfmanClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> BpfmanClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            BpfmanClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn load(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LoadRequest>,
+        ) -> std::result::Result<tonic::Response<super::LoadResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Load");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Load"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn unload(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UnloadRequest>,
+        ) -> std::result::Result<tonic::Response<super::UnloadResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Unload");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Unload"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/List");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "List"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn pull_bytecode(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PullBytecodeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullBytecodeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/PullBytecode",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "PullBytecode"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Get");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Get"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn map_lookup(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MapLookupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapLookupResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/MapLookup",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "MapLookup"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn map_update(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MapUpdateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapUpdateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/MapUpdate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "MapUpdate"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn map_delete(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MapDeleteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapDeleteResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/MapDelete",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "MapDelete"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn health(
+            &mut self,
+            request: impl tonic::IntoRequest<super::HealthRequest>,
+        ) -> std::result::Result<tonic::Response<super::HealthResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Health");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Health"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StatsRequest>,
+        ) -> std::result::Result<tonic::Response<super::StatsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Stats");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Stats"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn reorder(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReorderRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReorderResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Reorder");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Reorder"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn map_dump(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MapDumpRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::MapDumpResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/MapDump");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "MapDump"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn unload_all(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UnloadAllRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UnloadAllResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/UnloadAll");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "UnloadAll"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn detach(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DetachRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DetachResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Detach");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Detach"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn attach(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AttachRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::AttachResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Attach");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Attach"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetInfoRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetInfoResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/GetInfo");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "GetInfo"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn prune_pins(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PrunePinsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PrunePinsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/PrunePins");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "PrunePins"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn replace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReplaceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReplaceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Replace");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Replace"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_maps(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListMapsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListMapsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/ListMaps");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "ListMaps"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn load_object(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LoadObjectRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::LoadObjectResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/LoadObject");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "LoadObject"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn map_watch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MapWatchRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::MapWatchResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/MapWatch");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "MapWatch"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn reload(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReloadResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Reload");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Reload"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn dispatcher_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DispatcherInfoRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DispatcherInfoResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/DispatcherInfo");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "DispatcherInfo"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn relink(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RelinkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RelinkResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Relink");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Relink"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn batch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BatchRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BatchResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Batch");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Batch"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn ping(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PingRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PingResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Ping");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Ping"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_config(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetConfigRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetConfigResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/GetConfig");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "GetConfig"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn dump_state(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DumpStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DumpStateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/DumpState");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "DumpState"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_kernel(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListKernelRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListKernelResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/ListKernel");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "ListKernel"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn validate(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ValidateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ValidateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Validate");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Validate"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn set_enabled(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetEnabledRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetEnabledResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/SetEnabled");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "SetEnabled"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn diff_state(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DiffStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DiffStateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/DiffState");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "DiffState"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn subscribe_events(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SubscribeEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SubscribeEventsResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/SubscribeEvents");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "SubscribeEvents"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn map_freeze(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MapFreezeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapFreezeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/MapFreeze");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "MapFreeze"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod bpfman_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with BpfmanServer.
+    #[async_trait]
+    pub trait Bpfman: Send + Sync + 'static {
+        async fn load(
+            &self,
+            request: tonic::Request<super::LoadRequest>,
+        ) -> std::result::Result<tonic::Response<super::LoadResponse>, tonic::Status>;
+        async fn unload(
+            &self,
+            request: tonic::Request<super::UnloadRequest>,
+        ) -> std::result::Result<tonic::Response<super::UnloadResponse>, tonic::Status>;
+        async fn list(
+            &self,
+            request: tonic::Request<super::ListRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListResponse>, tonic::Status>;
+        async fn pull_bytecode(
+            &self,
+            request: tonic::Request<super::PullBytecodeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullBytecodeResponse>,
+            tonic::Status,
+        >;
+        async fn get(
+            &self,
+            request: tonic::Request<super::GetRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status>;
+        async fn map_lookup(
+            &self,
+            request: tonic::Request<super::MapLookupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapLookupResponse>,
+            tonic::Status,
+        >;
+        async fn map_update(
+            &self,
+            request: tonic::Request<super::MapUpdateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapUpdateResponse>,
+            tonic::Status,
+        >;
+        async fn map_delete(
+            &self,
+            request: tonic::Request<super::MapDeleteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MapDeleteResponse>,
+            tonic::Status,
+        >;
+        async fn health(
+            &self,
+            request: tonic::Request<super::HealthRequest>,
+        ) -> std::result::Result<tonic::Response<super::HealthResponse>, tonic::Status>;
+        async fn stats(
+            &self,
+            request: tonic::Request<super::StatsRequest>,
+        ) -> std::result::Result<tonic::Response<super::StatsResponse>, tonic::Status>;
+        async fn reorder(
+            &self,
+            request: tonic::Request<super::ReorderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReorderResponse>, tonic::Status>;
+        /// Server streaming response type for the MapDump method.
+        type MapDumpStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::MapDumpResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn map_dump(
+            &self,
+            request: tonic::Request<super::MapDumpRequest>,
+        ) -> std::result::Result<tonic::Response<Self::MapDumpStream>, tonic::Status>;
+        async fn unload_all(
+            &self,
+            request: tonic::Request<super::UnloadAllRequest>,
+        ) -> std::result::Result<tonic::Response<super::UnloadAllResponse>, tonic::Status>;
+        async fn detach(
+            &self,
+            request: tonic::Request<super::DetachRequest>,
+        ) -> std::result::Result<tonic::Response<super::DetachResponse>, tonic::Status>;
+        async fn attach(
+            &self,
+            request: tonic::Request<super::AttachRequest>,
+        ) -> std::result::Result<tonic::Response<super::AttachResponse>, tonic::Status>;
+        async fn get_info(
+            &self,
+            request: tonic::Request<super::GetInfoRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetInfoResponse>, tonic::Status>;
+        async fn prune_pins(
+            &self,
+            request: tonic::Request<super::PrunePinsRequest>,
+        ) -> std::result::Result<tonic::Response<super::PrunePinsResponse>, tonic::Status>;
+        async fn replace(
+            &self,
+            request: tonic::Request<super::ReplaceRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReplaceResponse>, tonic::Status>;
+        async fn list_maps(
+            &self,
+            request: tonic::Request<super::ListMapsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListMapsResponse>, tonic::Status>;
+        async fn load_object(
+            &self,
+            request: tonic::Request<super::LoadObjectRequest>,
+        ) -> std::result::Result<tonic::Response<super::LoadObjectResponse>, tonic::Status>;
+        /// Server streaming response type for the MapWatch method.
+        type MapWatchStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::MapWatchResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn map_watch(
+            &self,
+            request: tonic::Request<super::MapWatchRequest>,
+        ) -> std::result::Result<tonic::Response<Self::MapWatchStream>, tonic::Status>;
+        async fn reload(
+            &self,
+            request: tonic::Request<super::ReloadRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReloadResponse>, tonic::Status>;
+        async fn dispatcher_info(
+            &self,
+            request: tonic::Request<super::DispatcherInfoRequest>,
+        ) -> std::result::Result<tonic::Response<super::DispatcherInfoResponse>, tonic::Status>;
+        async fn relink(
+            &self,
+            request: tonic::Request<super::RelinkRequest>,
+        ) -> std::result::Result<tonic::Response<super::RelinkResponse>, tonic::Status>;
+        async fn batch(
+            &self,
+            request: tonic::Request<super::BatchRequest>,
+        ) -> std::result::Result<tonic::Response<super::BatchResponse>, tonic::Status>;
+        async fn ping(
+            &self,
+            request: tonic::Request<super::PingRequest>,
+        ) -> std::result::Result<tonic::Response<super::PingResponse>, tonic::Status>;
+        async fn get_config(
+            &self,
+            request: tonic::Request<super::GetConfigRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetConfigResponse>, tonic::Status>;
+        async fn dump_state(
+            &self,
+            request: tonic::Request<super::DumpStateRequest>,
+        ) -> std::result::Result<tonic::Response<super::DumpStateResponse>, tonic::Status>;
+        async fn list_kernel(
+            &self,
+            request: tonic::Request<super::ListKernelRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListKernelResponse>, tonic::Status>;
+        async fn validate(
+            &self,
+            request: tonic::Request<super::ValidateRequest>,
+        ) -> std::result::Result<tonic::Response<super::ValidateResponse>, tonic::Status>;
+        async fn set_enabled(
+            &self,
+            request: tonic::Request<super::SetEnabledRequest>,
+        ) -> std::result::Result<tonic::Response<super::SetEnabledResponse>, tonic::Status>;
+        async fn diff_state(
+            &self,
+            request: tonic::Request<super::DiffStateRequest>,
+        ) -> std::result::Result<tonic::Response<super::DiffStateResponse>, tonic::Status>;
+        /// Server streaming response type for the SubscribeEvents method.
+        type SubscribeEventsStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::SubscribeEventsResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn subscribe_events(
+            &self,
+            request: tonic::Request<super::SubscribeEventsRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeEventsStream>, tonic::Status>;
+        async fn map_freeze(
+            &self,
+            request: tonic::Request<super::MapFreezeRequest>,
+        ) -> std::result::Result<tonic::Response<super::MapFreezeResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct BpfmanServer<T: Bpfman> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Bpfman> BpfmanServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for BpfmanServer<T>
+    where
+        T: Bpfman,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/bpfman.v1.Bpfman/Load" => {
+                    #[allow(non_camel_case_types)]
+                    struct LoadSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::LoadRequest>
+                    for LoadSvc<T> {
+                        type Response = super::LoadResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::LoadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::load(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = LoadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Unload" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnloadSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::UnloadRequest>
+                    for UnloadSvc<T> {
+                        type Response = super::UnloadResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UnloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::unload(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnloadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/List" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ListRequest>
+                    for ListSvc<T> {
+                        type Response = super::ListResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::list(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/PullBytecode" => {
+                    #[allow(non_camel_case_types)]
+                    struct PullBytecodeSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::UnaryService<super::PullBytecodeRequest>
+                    for PullBytecodeSvc<T> {
+                        type Response = super::PullBytecodeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PullBytecodeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::pull_bytecode(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PullBytecodeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Get" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::GetRequest>
+                    for GetSvc<T> {
+                        type Response = super::GetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::get(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/MapLookup" => {
+                    #[allow(non_camel_case_types)]
+                    struct MapLookupSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::MapLookupRequest>
+                    for MapLookupSvc<T> {
+                        type Response = super::MapLookupResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MapLookupRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::map_lookup(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MapLookupSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/MapUpdate" => {
+                    #[allow(non_camel_case_types)]
+                    struct MapUpdateSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::MapUpdateRequest>
+                    for MapUpdateSvc<T> {
+                        type Response = super::MapUpdateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MapUpdateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::map_update(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MapUpdateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/MapDelete" => {
+                    #[allow(non_camel_case_types)]
+                    struct MapDeleteSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::MapDeleteRequest>
+                    for MapDeleteSvc<T> {
+                        type Response = super::MapDeleteResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MapDeleteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::map_delete(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MapDeleteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Health" => {
+                    #[allow(non_camel_case_types)]
+                    struct HealthSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::HealthRequest>
+                    for HealthSvc<T> {
+                        type Response = super::HealthResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::HealthRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::health(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = HealthSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Stats" => {
+                    #[allow(non_camel_case_types)]
+                    struct StatsSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::StatsRequest>
+                    for StatsSvc<T> {
+                        type Response = super::StatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::stats(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Reorder" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReorderSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ReorderRequest>
+                    for ReorderSvc<T> {
+                        type Response = super::ReorderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReorderRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::reorder(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReorderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/MapDump" => {
+                    #[allow(non_camel_case_types)]
+                    struct MapDumpSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::ServerStreamingService<super::MapDumpRequest>
+                    for MapDumpSvc<T> {
+                        type Response = super::MapDumpResponse;
+                        type ResponseStream = T::MapDumpStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MapDumpRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::map_dump(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MapDumpSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/UnloadAll" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnloadAllSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::UnloadAllRequest>
+                    for UnloadAllSvc<T> {
+                        type Response = super::UnloadAllResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UnloadAllRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::unload_all(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnloadAllSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Detach" => {
+                    #[allow(non_camel_case_types)]
+                    struct DetachSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DetachRequest>
+                    for DetachSvc<T> {
+                        type Response = super::DetachResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DetachRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::detach(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DetachSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Attach" => {
+                    #[allow(non_camel_case_types)]
+                    struct AttachSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::AttachRequest>
+                    for AttachSvc<T> {
+                        type Response = super::AttachResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AttachRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::attach(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AttachSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/GetInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetInfoSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::GetInfoRequest>
+                    for GetInfoSvc<T> {
+                        type Response = super::GetInfoResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetInfoRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::get_info(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/PrunePins" => {
+                    #[allow(non_camel_case_types)]
+                    struct PrunePinsSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::PrunePinsRequest>
+                    for PrunePinsSvc<T> {
+                        type Response = super::PrunePinsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PrunePinsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::prune_pins(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PrunePinsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Replace" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReplaceSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ReplaceRequest>
+                    for ReplaceSvc<T> {
+                        type Response = super::ReplaceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReplaceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::replace(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReplaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/ListMaps" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListMapsSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ListMapsRequest>
+                    for ListMapsSvc<T> {
+                        type Response = super::ListMapsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListMapsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::list_maps(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListMapsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/LoadObject" => {
+                    #[allow(non_camel_case_types)]
+                    struct LoadObjectSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::LoadObjectRequest>
+                    for LoadObjectSvc<T> {
+                        type Response = super::LoadObjectResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::LoadObjectRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::load_object(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = LoadObjectSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/MapWatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct MapWatchSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::ServerStreamingService<super::MapWatchRequest>
+                    for MapWatchSvc<T> {
+                        type Response = super::MapWatchResponse;
+                        type ResponseStream = T::MapWatchStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MapWatchRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::map_watch(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MapWatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Reload" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReloadSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ReloadRequest>
+                    for ReloadSvc<T> {
+                        type Response = super::ReloadResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::reload(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReloadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/DispatcherInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct DispatcherInfoSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DispatcherInfoRequest>
+                    for DispatcherInfoSvc<T> {
+                        type Response = super::DispatcherInfoResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DispatcherInfoRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::dispatcher_info(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DispatcherInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Relink" => {
+                    #[allow(non_camel_case_types)]
+                    struct RelinkSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::RelinkRequest>
+                    for RelinkSvc<T> {
+                        type Response = super::RelinkResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RelinkRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::relink(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RelinkSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Batch" => {
                     #[allow(non_camel_case_types)]
-                    struct LoadSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::LoadRequest>
-                    for LoadSvc<T> {
-                        type Response = super::LoadResponse;
+                    struct BatchSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::BatchRequest>
+                    for BatchSvc<T> {
+                        type Response = super::BatchResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::LoadRequest>,
+                            request: tonic::Request<super::BatchRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::load(&inner, request).await
+                                <T as Bpfman>::batch(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -599,7 +3568,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = LoadSvc(inner);
+                        let method = BatchSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -615,23 +3584,23 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/Unload" => {
+                "/bpfman.v1.Bpfman/Ping" => {
                     #[allow(non_camel_case_types)]
-                    struct UnloadSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::UnloadRequest>
-                    for UnloadSvc<T> {
-                        type Response = super::UnloadResponse;
+                    struct PingSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::PingRequest>
+                    for PingSvc<T> {
+                        type Response = super::PingResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::UnloadRequest>,
+                            request: tonic::Request<super::PingRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::unload(&inner, request).await
+                                <T as Bpfman>::ping(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -643,7 +3612,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = UnloadSvc(inner);
+                        let method = PingSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -659,23 +3628,23 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/List" => {
+                "/bpfman.v1.Bpfman/GetConfig" => {
                     #[allow(non_camel_case_types)]
-                    struct ListSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::ListRequest>
-                    for ListSvc<T> {
-                        type Response = super::ListResponse;
+                    struct GetConfigSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::GetConfigRequest>
+                    for GetConfigSvc<T> {
+                        type Response = super::GetConfigResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ListRequest>,
+                            request: tonic::Request<super::GetConfigRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::list(&inner, request).await
+                                <T as Bpfman>::get_config(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -687,7 +3656,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = ListSvc(inner);
+                        let method = GetConfigSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -703,25 +3672,23 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/PullBytecode" => {
+                "/bpfman.v1.Bpfman/DumpState" => {
                     #[allow(non_camel_case_types)]
-                    struct PullBytecodeSvc<T: Bpfman>(pub Arc<T>);
-                    impl<
-                        T: Bpfman,
-                    > tonic::server::UnaryService<super::PullBytecodeRequest>
-                    for PullBytecodeSvc<T> {
-                        type Response = super::PullBytecodeResponse;
+                    struct DumpStateSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DumpStateRequest>
+                    for DumpStateSvc<T> {
+                        type Response = super::DumpStateResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PullBytecodeRequest>,
+                            request: tonic::Request<super::DumpStateRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::pull_bytecode(&inner, request).await
+                                <T as Bpfman>::dump_state(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -733,7 +3700,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = PullBytecodeSvc(inner);
+                        let method = DumpStateSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -749,23 +3716,23 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/Get" => {
+                "/bpfman.v1.Bpfman/ListKernel" => {
                     #[allow(non_camel_case_types)]
-                    struct GetSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::GetRequest>
-                    for GetSvc<T> {
-                        type Response = super::GetResponse;
+                    struct ListKernelSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ListKernelRequest>
+                    for ListKernelSvc<T> {
+                        type Response = super::ListKernelResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetRequest>,
+                            request: tonic::Request<super::ListKernelRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::get(&inner, request).await
+                                <T as Bpfman>::list_kernel(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -777,7 +3744,230 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetSvc(inner);
+                        let method = ListKernelSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Validate" => {
+                    #[allow(non_camel_case_types)]
+                    struct ValidateSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ValidateRequest>
+                    for ValidateSvc<T> {
+                        type Response = super::ValidateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ValidateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::validate(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ValidateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/SetEnabled" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetEnabledSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::SetEnabledRequest>
+                    for SetEnabledSvc<T> {
+                        type Response = super::SetEnabledResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetEnabledRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::set_enabled(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetEnabledSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/DiffState" => {
+                    #[allow(non_camel_case_types)]
+                    struct DiffStateSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DiffStateRequest>
+                    for DiffStateSvc<T> {
+                        type Response = super::DiffStateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DiffStateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::diff_state(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DiffStateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/SubscribeEvents" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeEventsSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::ServerStreamingService<super::SubscribeEventsRequest>
+                    for SubscribeEventsSvc<T> {
+                        type Response = super::SubscribeEventsResponse;
+                        type ResponseStream = T::SubscribeEventsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SubscribeEventsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::subscribe_events(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubscribeEventsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/MapFreeze" => {
+                    #[allow(non_camel_case_types)]
+                    struct MapFreezeSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::MapFreezeRequest>
+                    for MapFreezeSvc<T> {
+                        type Response = super::MapFreezeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MapFreezeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::map_freeze(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MapFreezeSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -793,7 +3983,7 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                _ => {
+_ => {
                     Box::pin(async move {
                         Ok(
                             http::Response::builder()
@@ -3,15 +3,16 @@
 
 use bpfman::{
     errors::BpfmanError,
-    types::{BytecodeImage, Location, Program},
+    types::{BytecodeImage, BytecodeUrl, DispatcherSlots, Location, PinPaths, Program, ProgramMap},
 };
 
 use crate::v1::{
-    attach_info::Info, bytecode_location::Location as V1Location, AttachInfo,
-    BytecodeImage as V1BytecodeImage, BytecodeLocation, FentryAttachInfo, FexitAttachInfo,
-    KernelProgramInfo as V1KernelProgramInfo, KprobeAttachInfo, ProgramInfo,
-    ProgramInfo as V1ProgramInfo, TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo,
-    XdpAttachInfo,
+    attach_info::Info, bytecode_location::Location as V1Location,
+    dispatcher_info_response::Dispatcher as V1Dispatcher, list_maps_response::MapInfo as V1MapInfo,
+    AttachInfo, BytecodeImage as V1BytecodeImage, BytecodeLocation, BytecodeUrl as V1BytecodeUrl,
+    CgroupAttachInfo, FentryAttachInfo, FexitAttachInfo, KernelProgramInfo as V1KernelProgramInfo,
+    KprobeAttachInfo, PinPaths as V1PinPaths, ProgramInfo, ProgramInfo as V1ProgramInfo,
+    TailCallAttachInfo, TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo, XdpAttachInfo,
 };
 
 #[path = "bpfman.v1.rs"]
@@ -19,6 +20,17 @@ use crate::v1::{
 #[allow(clippy::all)]
 pub mod v1;
 
+/// The version of the bpfman.v1 API implemented by this crate. It is bumped
+/// whenever a change is made that clients need to know about, such as a new
+/// field in a request that bpfman acts upon.
+pub const API_VERSION: u32 = 1;
+
+/// The oldest client API version that bpfman still accepts requests from.
+pub const MIN_API_VERSION: u32 = 1;
+
+/// The request metadata key clients send their [`API_VERSION`] in.
+pub const API_VERSION_METADATA_KEY: &str = "bpfman-api-version";
+
 impl TryFrom<&Program> for ProgramInfo {
     type Error = BpfmanError;
 
@@ -40,22 +52,47 @@ impl TryFrom<&Program> for ProgramInfo {
             Location::File(m) => Some(BytecodeLocation {
                 location: Some(V1Location::File(m.to_string())),
             }),
+            Location::Url(m) => Some(BytecodeLocation {
+                location: Some(V1Location::Url(V1BytecodeUrl {
+                    url: m.url,
+                    sha256: m.sha256,
+                })),
+            }),
         };
 
         let attach_info = AttachInfo {
             info: match program.clone() {
                 Program::Xdp(p) => Some(Info::XdpAttachInfo(XdpAttachInfo {
-                    priority: p.get_priority()?,
+                    priority: Some(p.get_priority()?),
                     iface: p.get_iface()?.to_string(),
                     position: p.get_current_position()?.unwrap_or(0) as i32,
                     proceed_on: p.get_proceed_on()?.as_action_vec(),
+                    netns: p.get_netns()?.map(|n| n.to_string_lossy().to_string()),
+                    ifaces: vec![],
+                    all_interfaces: false,
+                    dispatcher_revision: p.get_dispatcher_revision()?,
+                    detached: p.get_detached()?,
+                    mode: p.get_mode()?.map(|m| m.to_string()),
+                    if_index: p.get_if_index()?,
+                    members: p.get_members()?,
+                    interface_missing: program.interface_missing()?,
+                    dispatcher_replaced: p.get_dispatcher_replaced()?,
+                    enabled: Some(p.get_enabled()?),
                 })),
                 Program::Tc(p) => Some(Info::TcAttachInfo(TcAttachInfo {
-                    priority: p.get_priority()?,
+                    priority: Some(p.get_priority()?),
                     iface: p.get_iface()?.to_string(),
                     position: p.get_current_position()?.unwrap_or(0) as i32,
                     direction: p.get_direction()?.to_string(),
                     proceed_on: p.get_proceed_on()?.as_action_vec(),
+                    netns: p.get_netns()?.map(|n| n.to_string_lossy().to_string()),
+                    ifaces: vec![],
+                    all_interfaces: false,
+                    dispatcher_revision: p.get_dispatcher_revision()?,
+                    detached: p.get_detached()?,
+                    if_index: p.get_if_index()?,
+                    interface_missing: program.interface_missing()?,
+                    enabled: Some(p.get_enabled()?),
                 })),
                 Program::Tracepoint(p) => Some(Info::TracepointAttachInfo(TracepointAttachInfo {
                     tracepoint: p.get_tracepoint()?.to_string(),
@@ -80,6 +117,14 @@ impl TryFrom<&Program> for ProgramInfo {
                 Program::Fexit(p) => Some(Info::FexitAttachInfo(FexitAttachInfo {
                     fn_name: p.get_fn_name()?.to_string(),
                 })),
+                Program::Cgroup(p) => Some(Info::CgroupAttachInfo(CgroupAttachInfo {
+                    path: p.get_path()?,
+                    attach_type: p.get_attach_type()?.to_string(),
+                })),
+                Program::TailCall(p) => Some(Info::TailCallAttachInfo(TailCallAttachInfo {
+                    prog_array: p.get_map_name()?,
+                    index: p.get_index()?,
+                })),
                 Program::Unsupported(_) => None,
             },
         };
@@ -100,10 +145,59 @@ impl TryFrom<&Program> for ProgramInfo {
                 .map(|m| m.to_string())
                 .collect(),
             metadata: data.get_metadata()?,
+            group_id: data.get_group_id()?,
+            shared_maps: data.get_shared_maps()?,
+            maps_to_pin: data.get_maps_to_pin()?,
+            maps_to_skip: data.get_maps_to_skip()?,
+            pin_namespace: data.get_pin_namespace()?,
+            map_max_entries: data.get_map_max_entries()?,
+            duplicate_of: data.get_duplicate_of()?,
+            loaded_at: data.get_loaded_at()?,
+            attached_at: data.get_attached_at()?,
+            uptime_secs: data.get_uptime()?.map(|d| d.as_secs()),
         })
     }
 }
 
+impl From<PinPaths> for V1PinPaths {
+    fn from(value: PinPaths) -> Self {
+        V1PinPaths {
+            program: value.program.to_string_lossy().to_string(),
+            link: value.link.map(|l| l.to_string_lossy().to_string()),
+            maps: value
+                .maps
+                .into_iter()
+                .map(|(name, path)| (name, path.to_string_lossy().to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl From<ProgramMap> for V1MapInfo {
+    fn from(value: ProgramMap) -> Self {
+        V1MapInfo {
+            name: value.name,
+            map_type: value.map_type,
+            key_size: value.key_size,
+            value_size: value.value_size,
+            max_entries: value.max_entries,
+            pin_path: value.pin_path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+impl From<DispatcherSlots> for V1Dispatcher {
+    fn from(value: DispatcherSlots) -> Self {
+        V1Dispatcher {
+            program_type: value.kind as u32,
+            direction: value.direction.map(|d| d.to_string()),
+            capacity: value.capacity,
+            used: value.used,
+            revision: value.revision,
+        }
+    }
+}
+
 impl TryFrom<&Program> for V1KernelProgramInfo {
     type Error = BpfmanError;
 
@@ -126,6 +220,10 @@ impl TryFrom<&Program> for V1KernelProgramInfo {
             bytes_jited: data.get_kernel_bytes_jited()?,
             bytes_memlock: data.get_kernel_bytes_memlock()?,
             verified_insns: data.get_kernel_verified_insns()?,
+            // Looked up from the kernel by the requests that report it.
+            link_id: None,
+            link_attached: None,
+            maps_bytes_memlock: None,
         })
     }
 }
@@ -134,16 +232,16 @@ impl From<V1BytecodeImage> for BytecodeImage {
     fn from(value: V1BytecodeImage) -> Self {
         // This function is mapping an empty string to None for
         // username and password.
-        let username = if value.username.is_some() {
-            match value.username.unwrap().as_ref() {
+        let username = if let Some(username) = value.username {
+            match username.as_ref() {
                 "" => None,
                 u => Some(u.to_string()),
             }
         } else {
             None
         };
-        let password = if value.password.is_some() {
-            match value.password.unwrap().as_ref() {
+        let password = if let Some(password) = value.password {
+            match password.as_ref() {
                 "" => None,
                 u => Some(u.to_string()),
             }
@@ -153,3 +251,13 @@ impl From<V1BytecodeImage> for BytecodeImage {
         BytecodeImage::new(value.url, value.image_pull_policy, username, password)
     }
 }
+
+impl From<V1BytecodeUrl> for BytecodeUrl {
+    fn from(value: V1BytecodeUrl) -> Self {
+        BytecodeUrl {
+            url: value.url,
+            // An empty checksum is the same as none.
+            sha256: value.sha256.filter(|s| !s.is_empty()),
+        }
+    }
+}
@@ -20,13 +20,46 @@ pub(crate) struct Cli {
 pub(crate) enum Commands {
     /// Load an eBPF program on the system.
     #[command(subcommand)]
-    Load(LoadSubcommand),
+    Load(Box<LoadSubcommand>),
     /// Unload an eBPF program using the Program Id.
     Unload(UnloadArgs),
+    /// Unload every eBPF program loaded via bpfman, or only those on an interface.
+    UnloadAll(UnloadAllArgs),
     /// List all eBPF programs loaded via bpfman.
     List(ListArgs),
     /// Get an eBPF program using the Program Id.
     Get(GetArgs),
+    /// List the maps pinned for an eBPF program using the Program Id.
+    ListMaps(ListMapsArgs),
+    /// Print everything bpfman tracks, its programs, maps, pins and dispatchers,
+    /// as JSON. Image registry credentials are left out.
+    DumpState,
+    /// List every eBPF program and link in the kernel, including those bpfman
+    /// didn't load, and which of them bpfman manages.
+    ListKernel,
+    /// Change the priority of an XDP or TC program using the Program Id.
+    Reorder(ReorderArgs),
+    /// Detach an XDP or TC program from its dispatcher without unloading it.
+    Detach(DetachArgs),
+    /// Attach a detached XDP or TC program to its dispatcher again.
+    Attach(AttachArgs),
+    /// Enable an XDP or TC program that was loaded or set disabled.
+    Enable(EnableArgs),
+    /// Disable an XDP or TC program without unloading it. It keeps running in its
+    /// dispatcher, but the programs after it run whatever it returns.
+    Disable(EnableArgs),
+    /// Attach a program again after another tool, such as bpftool, detached it.
+    Relink(RelinkArgs),
+    /// Replace an attached XDP or TC program with a new one, without a gap.
+    #[command(subcommand)]
+    Replace(ReplaceSubcommand),
+    /// Reload an attached XDP or TC program from the file or image it was loaded
+    /// from, and replace it with the new program without a gap.
+    Reload(ReloadArgs),
+    /// Check bytecode without loading it, listing its programs and maps and
+    /// anything in it bpfman can't load.
+    #[command(subcommand)]
+    Validate(ValidateSubcommand),
     /// eBPF Bytecode Image related commands.
     #[command(subcommand)]
     Image(ImageSubCommand),
@@ -43,13 +76,21 @@ pub(crate) enum LoadSubcommand {
 
 #[derive(Args, Debug)]
 pub(crate) struct LoadFileArgs {
-    /// Required: Location of local bytecode file
+    /// Required: Location of local bytecode file, or an http:// or https://
+    /// URL to download it from.
     /// Example: --path /run/bpfman/examples/go-xdp-counter/bpf_bpfel.o
     #[clap(short, long, verbatim_doc_comment)]
     pub(crate) path: String,
 
-    /// Required: The name of the function that is the entry point for the BPF program.
-    #[clap(short, long)]
+    /// Optional: SHA-256 checksum, in hex, that bytecode downloaded from a URL
+    /// given to --path must have.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) sha256: Option<String>,
+
+    /// Optional: The name of the function that is the entry point for the BPF program.
+    /// If not provided, the only program in the bytecode of the type being loaded
+    /// is used.
+    #[clap(short, long, verbatim_doc_comment, default_value = "")]
     pub(crate) name: String,
 
     /// Optional: Global variables to be set when program is loaded.
@@ -77,8 +118,59 @@ pub(crate) struct LoadFileArgs {
     #[clap(long, verbatim_doc_comment)]
     pub(crate) map_owner_id: Option<u32>,
 
+    /// Optional: Name of a map to share with every other eBPF program loaded with
+    /// the same name. The first program loaded creates the map and the rest reuse
+    /// it, until the last of them is unloaded. The map must be declared with
+    /// LIBBPF_PIN_BY_NAME. Can't be combined with --map-owner-id.
+    /// Example: --shared-map counters
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub(crate) shared_map: Vec<String>,
+
+    /// Optional: Name of a map to pin even though it wouldn't be by default,
+    /// such as the .data map. By default every map other than the .rodata, .bss
+    /// and .data maps is pinned. Can't name a map also given to --skip-map.
+    /// Example: --pin-map .data
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub(crate) pin_map: Vec<String>,
+
+    /// Optional: Name of a map to leave unpinned even though it would be pinned
+    /// by default. Can't name a map also given to --pin-map.
+    /// Example: --skip-map scratch
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub(crate) skip_map: Vec<String>,
+
+    /// Optional: Number of entries to create a map with in place of the
+    /// max_entries the bytecode declares. Can't be used for the maps of a map
+    /// owner or shared maps.
+    /// Format: <MAP NAME>=<MAX ENTRIES>
+    /// Example: --map-max-entries flows=1048576
+    #[clap(long, verbatim_doc_comment, value_parser=parse_map_max_entries, value_delimiter = ',')]
+    pub(crate) map_max_entries: Vec<(String, u32)>,
+
+    /// Optional: Load an XDP or TC program even though a program of the same
+    /// name from the same bytecode is already loaded on the interface. Without
+    /// it such a load fails, as the dispatcher would run the program twice.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) allow_duplicate: bool,
+
+    /// Optional: Pin namespace to keep the program's pins in, a directory of
+    /// that name in the bpffs. The program can only use the maps of a map
+    /// owner, or share maps with programs, in the same pin namespace.
+    /// Example: --pin-namespace tenant-a
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) pin_namespace: Option<String>,
+
+    /// Optional: Only check that the program passes the kernel verifier, then
+    /// unload it again without attaching it. When more than one interface is
+    /// given, the program is verified once.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) dry_run: bool,
+
+    /// The type of program to load and how to attach it. If it's left out, both
+    /// are taken from the program's section name, as long as it names where to
+    /// attach the program, as in kprobe/<function> or tracepoint/<category>/<name>.
     #[clap(subcommand)]
-    pub(crate) command: LoadCommands,
+    pub(crate) command: Option<LoadCommands>,
 }
 
 #[derive(Args, Debug)]
@@ -117,8 +209,59 @@ pub(crate) struct LoadImageArgs {
     #[clap(long, verbatim_doc_comment)]
     pub(crate) map_owner_id: Option<u32>,
 
+    /// Optional: Name of a map to share with every other eBPF program loaded with
+    /// the same name. The first program loaded creates the map and the rest reuse
+    /// it, until the last of them is unloaded. The map must be declared with
+    /// LIBBPF_PIN_BY_NAME. Can't be combined with --map-owner-id.
+    /// Example: --shared-map counters
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub(crate) shared_map: Vec<String>,
+
+    /// Optional: Name of a map to pin even though it wouldn't be by default,
+    /// such as the .data map. By default every map other than the .rodata, .bss
+    /// and .data maps is pinned. Can't name a map also given to --skip-map.
+    /// Example: --pin-map .data
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub(crate) pin_map: Vec<String>,
+
+    /// Optional: Name of a map to leave unpinned even though it would be pinned
+    /// by default. Can't name a map also given to --pin-map.
+    /// Example: --skip-map scratch
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub(crate) skip_map: Vec<String>,
+
+    /// Optional: Number of entries to create a map with in place of the
+    /// max_entries the bytecode declares. Can't be used for the maps of a map
+    /// owner or shared maps.
+    /// Format: <MAP NAME>=<MAX ENTRIES>
+    /// Example: --map-max-entries flows=1048576
+    #[clap(long, verbatim_doc_comment, value_parser=parse_map_max_entries, value_delimiter = ',')]
+    pub(crate) map_max_entries: Vec<(String, u32)>,
+
+    /// Optional: Load an XDP or TC program even though a program of the same
+    /// name from the same bytecode is already loaded on the interface. Without
+    /// it such a load fails, as the dispatcher would run the program twice.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) allow_duplicate: bool,
+
+    /// Optional: Pin namespace to keep the program's pins in, a directory of
+    /// that name in the bpffs. The program can only use the maps of a map
+    /// owner, or share maps with programs, in the same pin namespace.
+    /// Example: --pin-namespace tenant-a
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) pin_namespace: Option<String>,
+
+    /// Optional: Only check that the program passes the kernel verifier, then
+    /// unload it again without attaching it. When more than one interface is
+    /// given, the program is verified once.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) dry_run: bool,
+
+    /// The type of program to load and how to attach it. If it's left out, both
+    /// are taken from the program's section name, as long as it names where to
+    /// attach the program, as in kprobe/<function> or tracepoint/<category>/<name>.
     #[clap(subcommand)]
-    pub(crate) command: LoadCommands,
+    pub(crate) command: Option<LoadCommands>,
 }
 
 #[derive(Clone, Debug)]
@@ -133,13 +276,26 @@ pub(crate) enum LoadCommands {
     #[command(disable_version_flag = true)]
     /// Install an eBPF program on the XDP hook point for a given interface.
     Xdp {
-        /// Required: Interface to load program on.
-        #[clap(short, long)]
-        iface: String,
-
-        /// Required: Priority to run program in chain. Lower value runs first.
-        #[clap(short, long)]
-        priority: i32,
+        /// Required: Interface to load program on, by name or by index. The program
+        /// is loaded once per interface if more than one is given, and unloading
+        /// the first program unloads all of them.
+        /// Example: --iface eth0 eth1
+        /// Example: --iface 2
+        #[clap(short, long, verbatim_doc_comment, num_args(1..), required_unless_present = "all_interfaces")]
+        iface: Vec<String>,
+
+        /// Optional: Load the program on every interface that is up, other than
+        /// loopback, instead of the interfaces given with --iface.
+        #[clap(long, verbatim_doc_comment, conflicts_with = "iface")]
+        all_interfaces: bool,
+
+        /// Optional: Priority to run program in chain. Lower value runs first.
+        /// Must be between 0 and 1000, programs with the same priority run in
+        /// the order they were loaded.
+        ///
+        /// [default: priority set in the [defaults] config section, or 1000]
+        #[clap(short, long, verbatim_doc_comment)]
+        priority: Option<i32>,
 
         /// Optional: Proceed to call other programs in chain on this exit code.
         /// Multiple values supported by repeating the parameter.
@@ -150,6 +306,28 @@ pub(crate) enum LoadCommands {
         /// [default: pass, dispatcher_return]
         #[clap(long, verbatim_doc_comment, num_args(1..))]
         proceed_on: Vec<String>,
+
+        /// Optional: Network namespace to attach the program in, given as the
+        /// path to a namespace file or the PID of a process in the namespace.
+        /// Example: --netns /var/run/netns/foo
+        #[clap(long, verbatim_doc_comment)]
+        netns: Option<String>,
+
+        /// Optional: Mode to attach the interface's dispatcher in. "auto" uses
+        /// native mode where the driver supports it and generic mode otherwise.
+        /// All programs on an interface share the dispatcher's mode.
+        ///
+        /// [possible values: skb, drv, hw, auto]
+        ///
+        /// [default: mode set for the interface in the config, or skb]
+        #[clap(long, verbatim_doc_comment)]
+        xdp_mode: Option<String>,
+
+        /// Optional: Load the program disabled, so that it runs but the programs
+        /// after it run whatever it returns, until it is enabled with
+        /// "bpfman enable".
+        #[clap(long, verbatim_doc_comment)]
+        disabled: bool,
     },
     #[command(disable_version_flag = true)]
     /// Install an eBPF program on the TC hook point for a given interface.
@@ -160,13 +338,26 @@ pub(crate) enum LoadCommands {
         #[clap(short, long, verbatim_doc_comment)]
         direction: String,
 
-        /// Required: Interface to load program on.
-        #[clap(short, long)]
-        iface: String,
-
-        /// Required: Priority to run program in chain. Lower value runs first.
-        #[clap(short, long)]
-        priority: i32,
+        /// Required: Interface to load program on, by name or by index. The program
+        /// is loaded once per interface if more than one is given, and unloading
+        /// the first program unloads all of them.
+        /// Example: --iface eth0 eth1
+        /// Example: --iface 2
+        #[clap(short, long, verbatim_doc_comment, num_args(1..), required_unless_present = "all_interfaces")]
+        iface: Vec<String>,
+
+        /// Optional: Load the program on every interface that is up, other than
+        /// loopback, instead of the interfaces given with --iface.
+        #[clap(long, verbatim_doc_comment, conflicts_with = "iface")]
+        all_interfaces: bool,
+
+        /// Optional: Priority to run program in chain. Lower value runs first.
+        /// Must be between 0 and 1000, programs with the same priority run in
+        /// the order they were loaded.
+        ///
+        /// [default: priority set in the [defaults] config section, or 1000]
+        #[clap(short, long, verbatim_doc_comment)]
+        priority: Option<i32>,
 
         /// Optional: Proceed to call other programs in chain on this exit code.
         /// Multiple values supported by repeating the parameter.
@@ -178,6 +369,18 @@ pub(crate) enum LoadCommands {
         /// [default: ok, pipe, dispatcher_return]
         #[clap(long, verbatim_doc_comment, num_args(1..))]
         proceed_on: Vec<String>,
+
+        /// Optional: Network namespace to attach the program in, given as the
+        /// path to a namespace file or the PID of a process in the namespace.
+        /// Example: --netns /var/run/netns/foo
+        #[clap(long, verbatim_doc_comment)]
+        netns: Option<String>,
+
+        /// Optional: Load the program disabled, so that it runs but the programs
+        /// after it run whatever it returns, until it is enabled with
+        /// "bpfman enable".
+        #[clap(long, verbatim_doc_comment)]
+        disabled: bool,
     },
     #[command(disable_version_flag = true)]
     /// Install an eBPF program on a Tracepoint.
@@ -259,15 +462,228 @@ pub(crate) enum LoadCommands {
         #[clap(short, long)]
         fn_name: String,
     },
+    #[command(disable_version_flag = true)]
+    /// Install a cgroup_skb or cgroup_sock eBPF program on a cgroup.
+    Cgroup {
+        /// Required: Path to the cgroup v2 directory to attach to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Required: Where in the cgroup to attach the program.
+        /// cgroup_skb programs attach at "ingress" or "egress", cgroup_sock
+        /// programs at "sock_create", "sock_release", "post_bind4" or
+        /// "post_bind6", matching the program's section.
+        #[clap(short, long, verbatim_doc_comment)]
+        attach_type: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Add an eBPF program to a prog array of its map owner for tail calls.
+    TailCall {
+        /// Required: Name of the prog array map of the program given with
+        /// --map-owner-id to add the program to.
+        /// Example: --prog-array jump_table
+        #[clap(long, verbatim_doc_comment)]
+        prog_array: String,
+
+        /// Required: Index of the prog array to add the program at. Must be
+        /// less than the max entries the prog array was declared with.
+        #[clap(long, verbatim_doc_comment)]
+        index: u32,
+    },
 }
 
 #[derive(Args, Debug)]
 #[command(disable_version_flag = true)]
 pub(crate) struct UnloadArgs {
-    /// Required: Program Id to be unloaded.
+    /// Required: Program Id to be unloaded, unless --name and --iface are given.
+    #[clap(required_unless_present = "name", conflicts_with = "name")]
+    pub(crate) program_id: Option<u32>,
+
+    /// Optional: Unload the XDP or TC program loaded with this name on --iface
+    /// instead of giving its Program Id. Fails, listing the Program Ids it
+    /// matches, if more than one program matches.
+    #[clap(short, long, verbatim_doc_comment, requires = "iface")]
+    pub(crate) name: Option<String>,
+
+    /// Optional: Interface the program given by --name is attached to.
+    #[clap(short, long, requires = "name")]
+    pub(crate) iface: Option<String>,
+
+    /// Optional: Only match the program given by --name at this priority.
+    #[clap(short, long, requires = "name")]
+    pub(crate) priority: Option<i32>,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct UnloadAllArgs {
+    /// Optional: Only unload the XDP and TC programs attached to this interface.
+    #[clap(short, long)]
+    pub(crate) iface: Option<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct ReorderArgs {
+    /// Required: Program Id to be reordered.
+    pub(crate) program_id: u32,
+
+    /// Required: New priority of the program in its chain. Lower value runs first.
+    /// Must be between 0 and 1000.
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) priority: i32,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct DetachArgs {
+    /// Required: Program Id to be detached.
+    pub(crate) program_id: u32,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct AttachArgs {
+    /// Required: Program Id of a detached program to be attached.
     pub(crate) program_id: u32,
 }
 
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct EnableArgs {
+    /// Required: Program Id of the XDP or TC program.
+    pub(crate) program_id: u32,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct RelinkArgs {
+    /// Required: Program Id of the program to be attached again.
+    pub(crate) program_id: u32,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct ReloadArgs {
+    /// Required: Program Id of the program to be reloaded.
+    pub(crate) program_id: u32,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct ListMapsArgs {
+    /// Required: Program Id to list the maps of.
+    pub(crate) program_id: u32,
+}
+
+#[derive(Subcommand, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) enum ValidateSubcommand {
+    /// Check a local .o file, or one downloaded from a URL.
+    File(ValidateFileArgs),
+    /// Check the bytecode packaged in a OCI container image.
+    Image(PullBytecodeArgs),
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct ValidateFileArgs {
+    /// Required: Location of local bytecode file, or an http:// or https://
+    /// URL to download it from.
+    /// Example: --path /run/bpfman/examples/go-xdp-counter/bpf_bpfel.o
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) path: String,
+
+    /// Optional: SHA-256 checksum, in hex, that bytecode downloaded from a URL
+    /// given to --path must have.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) sha256: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) enum ReplaceSubcommand {
+    /// Replace a program with one loaded from a local .o file.
+    File(ReplaceFileArgs),
+    /// Replace a program with one packaged in a OCI container image from a given registry.
+    Image(ReplaceImageArgs),
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct ReplaceFileArgs {
+    /// Required: Program Id of the XDP or TC program to be replaced. The new
+    /// program takes over its interface, priority and proceed-on settings.
+    #[clap(verbatim_doc_comment)]
+    pub(crate) program_id: u32,
+
+    /// Required: Location of local bytecode file, or an http:// or https://
+    /// URL to download it from.
+    /// Example: --path /run/bpfman/examples/go-xdp-counter/bpf_bpfel.o
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) path: String,
+
+    /// Optional: SHA-256 checksum, in hex, that bytecode downloaded from a URL
+    /// given to --path must have.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) sha256: Option<String>,
+
+    /// Required: The name of the function that is the entry point for the BPF program.
+    #[clap(short, long)]
+    pub(crate) name: String,
+
+    /// Optional: Global variables to be set when program is loaded.
+    /// Format: <NAME>=<Hex Value>
+    #[clap(short, long, verbatim_doc_comment, num_args(1..), value_parser=parse_global_arg)]
+    pub(crate) global: Option<Vec<GlobalArg>>,
+
+    /// Optional: Specify Key/Value metadata to be attached to a program when it
+    /// is loaded by bpfman.
+    /// Format: <KEY>=<VALUE>
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_key_val, value_delimiter = ',')]
+    pub(crate) metadata: Option<Vec<(String, String)>>,
+
+    /// Optional: Program Id of loaded eBPF program this eBPF program will share a map with.
+    /// Example: --map-owner-id 63178
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) map_owner_id: Option<u32>,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct ReplaceImageArgs {
+    /// Required: Program Id of the XDP or TC program to be replaced. The new
+    /// program takes over its interface, priority and proceed-on settings.
+    #[clap(verbatim_doc_comment)]
+    pub(crate) program_id: u32,
+
+    /// Specify how the bytecode image should be pulled.
+    #[command(flatten)]
+    pub(crate) pull_args: PullBytecodeArgs,
+
+    /// Optional: The name of the function that is the entry point for the BPF program.
+    /// If not provided, the program name defined as part of the bytecode image will be used.
+    #[clap(short, long, verbatim_doc_comment, default_value = "")]
+    pub(crate) name: String,
+
+    /// Optional: Global variables to be set when program is loaded.
+    /// Format: <NAME>=<Hex Value>
+    #[clap(short, long, verbatim_doc_comment, num_args(1..), value_parser=parse_global_arg)]
+    pub(crate) global: Option<Vec<GlobalArg>>,
+
+    /// Optional: Specify Key/Value metadata to be attached to a program when it
+    /// is loaded by bpfman.
+    /// Format: <KEY>=<VALUE>
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_key_val, value_delimiter = ',')]
+    pub(crate) metadata: Option<Vec<(String, String)>>,
+
+    /// Optional: Program Id of loaded eBPF program this eBPF program will share a map with.
+    /// Example: --map-owner-id 63178
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) map_owner_id: Option<u32>,
+}
+
 #[derive(Args, Debug)]
 #[command(disable_version_flag = true)]
 pub(crate) struct ListArgs {
@@ -293,6 +709,16 @@ pub(crate) struct ListArgs {
     #[clap(short, long, verbatim_doc_comment, value_parser=parse_key_val, value_delimiter = ',')]
     pub(crate) metadata_selector: Option<Vec<(String, String)>>,
 
+    /// Optional: List programs with a specific BPF function name.
+    /// Example: --name pass
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) name: Option<String>,
+
+    /// Optional: List only the programs loaded in a pin namespace.
+    /// Example: --pin-namespace tenant-a
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) pin_namespace: Option<String>,
+
     /// Optional: List all programs.
     #[clap(short, long, verbatim_doc_comment)]
     pub(crate) all: bool,
@@ -340,6 +766,15 @@ pub(crate) fn parse_key_val(s: &str) -> Result<(String, String), std::io::Error>
     Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
 }
 
+/// Parse a map name and the max_entries to create it with
+pub(crate) fn parse_map_max_entries(s: &str) -> Result<(String, u32), std::io::Error> {
+    let (name, max_entries) = parse_key_val(s)?;
+    let max_entries = max_entries
+        .parse()
+        .map_err(|_| std::io::ErrorKind::InvalidInput)?;
+    Ok((name, max_entries))
+}
+
 pub(crate) fn parse_global_arg(global_arg: &str) -> Result<GlobalArg, std::io::Error> {
     let mut parts = global_arg.split('=');
 
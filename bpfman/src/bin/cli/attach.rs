@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::{attach_program, detach_program, relink_program, set_program_enabled};
+
+use crate::{
+    args::{AttachArgs, DetachArgs, EnableArgs, RelinkArgs},
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_detach(args: &DetachArgs) -> Result<(), anyhow::Error> {
+    let program = detach_program(args.program_id).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
+
+pub(crate) async fn execute_attach(args: &AttachArgs) -> Result<(), anyhow::Error> {
+    let program = attach_program(args.program_id).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
+
+pub(crate) async fn execute_set_enabled(
+    args: &EnableArgs,
+    enabled: bool,
+) -> Result<(), anyhow::Error> {
+    let program = set_program_enabled(args.program_id, enabled).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
+
+pub(crate) async fn execute_relink(args: &RelinkArgs) -> Result<(), anyhow::Error> {
+    let program = relink_program(args.program_id).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use bpfman::{errors::BpfmanError, get_program};
+use bpfman::{
+    errors::BpfmanError, get_program, link_status, list_maps, maps_bytes_memlock, types::Program,
+};
 use log::warn;
 
 use crate::{args::GetArgs, table::ProgTable};
@@ -10,7 +12,22 @@ pub(crate) async fn execute_get(args: &GetArgs) -> Result<(), BpfmanError> {
     match get_program(args.program_id).await {
         Ok(program) => {
             ProgTable::new_program(&program)?.print();
-            ProgTable::new_kernel_info(&program)?.print();
+            let mut kernel_info = ProgTable::new_kernel_info(&program)?;
+            if let Some(status) = link_status(std::slice::from_ref(&program)).await?.first() {
+                kernel_info.add_link_status(status);
+            }
+            kernel_info.add_maps_memlock(maps_bytes_memlock(&program)?);
+            kernel_info.print();
+            if !matches!(program, Program::Unsupported(_)) {
+                let maps = list_maps(args.program_id).await?;
+                if !maps.is_empty() {
+                    let mut table = ProgTable::new_map_list();
+                    for map in &maps {
+                        table.add_map(map);
+                    }
+                    table.print();
+                }
+            }
             Ok(())
         }
         Err(e) => {
@@ -2,9 +2,14 @@
 // Copyright Authors of bpfman
 
 use anyhow::bail;
-use bpfman::{list_programs, types::ListFilter};
+use bpfman::{
+    dump_state, list_kernel, list_maps, list_programs, program_counts, types::ListFilter,
+};
 
-use crate::{args::ListArgs, table::ProgTable};
+use crate::{
+    args::{ListArgs, ListMapsArgs},
+    table::ProgTable,
+};
 
 pub(crate) async fn execute_list(args: &ListArgs) -> anyhow::Result<()> {
     let prog_type_filter = args.program_type.map(|p| p as u32);
@@ -18,6 +23,8 @@ pub(crate) async fn execute_list(args: &ListArgs) -> anyhow::Result<()> {
             .map(|(k, v)| (k.to_owned(), v.to_owned()))
             .collect(),
         !args.all,
+        args.name.clone(),
+        args.pin_namespace.clone(),
     );
 
     let mut table = ProgTable::new_list();
@@ -28,5 +35,47 @@ pub(crate) async fn execute_list(args: &ListArgs) -> anyhow::Result<()> {
         }
     }
     table.print();
+
+    // Counts are only worth showing next to a limit.
+    let counts = program_counts().await?;
+    if let Some(max) = counts.max_programs {
+        println!("Programs loaded: {} of {max}", counts.programs);
+    }
+    if let Some(max) = counts.max_programs_per_iface {
+        for (iface, count) in &counts.per_iface {
+            println!("Programs on {iface}: {count} of {max}");
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn execute_list_maps(args: &ListMapsArgs) -> anyhow::Result<()> {
+    let mut table = ProgTable::new_map_list();
+    for map in list_maps(args.program_id).await? {
+        table.add_map(&map);
+    }
+    table.print();
+    Ok(())
+}
+
+pub(crate) async fn execute_dump_state() -> anyhow::Result<()> {
+    println!("{}", dump_state().await?);
+    Ok(())
+}
+
+pub(crate) async fn execute_list_kernel() -> anyhow::Result<()> {
+    let kernel = list_kernel().await?;
+
+    let mut table = ProgTable::new_kernel_program_list();
+    for program in &kernel.programs {
+        table.add_kernel_program(program);
+    }
+    table.print();
+
+    let mut table = ProgTable::new_kernel_link_list();
+    for link in &kernel.links {
+        table.add_kernel_link(link);
+    }
+    table.print();
     Ok(())
 }
@@ -1,15 +1,23 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::bail;
 use bpfman::{
-    add_program,
+    add_programs,
+    errors::BpfmanError,
+    infer_program,
     types::{
-        FentryProgram, FexitProgram, KprobeProgram, Location, Program, ProgramData, TcProceedOn,
-        TcProgram, TracepointProgram, UprobeProgram, XdpProceedOn, XdpProgram,
+        BytecodeUrl, CgroupProgram, FentryProgram, FexitProgram, KprobeProgram, Location, Program,
+        ProgramData, TailCallProgram, TcProceedOn, TcProgram, TracepointProgram, UprobeProgram,
+        XdpModePreference, XdpProceedOn, XdpProgram,
     },
+    utils::{get_up_interfaces, open_config_file},
+    verify_program,
 };
 
 use crate::{
@@ -26,76 +34,165 @@ impl LoadSubcommand {
     }
 }
 
+// Bytecode given by an http:// or https:// URL in place of a path is
+// downloaded when the program is loaded.
+pub(crate) fn file_location(path: &str, sha256: &Option<String>) -> anyhow::Result<Location> {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return Ok(Location::Url(BytecodeUrl {
+            url: path.to_string(),
+            sha256: sha256.clone(),
+        }));
+    }
+    if sha256.is_some() {
+        bail!("--sha256 can only be given with a URL");
+    }
+    Ok(Location::File(path.to_string()))
+}
+
 pub(crate) async fn execute_load_file(args: &LoadFileArgs) -> anyhow::Result<()> {
-    let bytecode_source = Location::File(args.path.clone());
-
-    let data = ProgramData::new(
-        bytecode_source,
-        args.name.clone(),
-        args.metadata
-            .clone()
-            .unwrap_or_default()
-            .iter()
-            .map(|(k, v)| (k.to_owned(), v.to_owned()))
-            .collect(),
-        parse_global(&args.global),
-        args.map_owner_id,
-    )?;
-
-    let program = add_program(args.command.get_program(data)?).await?;
-
-    ProgTable::new_program(&program)?.print();
-    ProgTable::new_kernel_info(&program)?.print();
-    Ok(())
+    let bytecode_source = file_location(&args.path, &args.sha256)?;
+
+    let new_data = || {
+        let mut data = ProgramData::new(
+            bytecode_source.clone(),
+            args.name.clone(),
+            args.metadata
+                .clone()
+                .unwrap_or_default()
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+            parse_global(&args.global),
+            args.map_owner_id,
+        )?;
+        data.set_shared_maps(&args.shared_map)?;
+        data.set_maps_to_pin(&args.pin_map)?;
+        data.set_maps_to_skip(&args.skip_map)?;
+        data.set_map_max_entries(&args.map_max_entries.iter().cloned().collect())?;
+        data.set_allow_duplicate(args.allow_duplicate)?;
+        if let Some(namespace) = &args.pin_namespace {
+            data.set_pin_namespace(namespace)?;
+        }
+        Ok(data)
+    };
+
+    load_programs(get_programs(&args.command, new_data).await?, args.dry_run).await
 }
 
 pub(crate) async fn execute_load_image(args: &LoadImageArgs) -> anyhow::Result<()> {
     let bytecode_source = Location::Image((&args.pull_args).try_into()?);
 
-    let data = ProgramData::new(
-        bytecode_source,
-        args.name.clone(),
-        args.metadata
-            .clone()
-            .unwrap_or_default()
-            .iter()
-            .map(|(k, v)| (k.to_owned(), v.to_owned()))
-            .collect(),
-        parse_global(&args.global),
-        args.map_owner_id,
-    )?;
-
-    let program = add_program(args.command.get_program(data)?).await?;
-
-    ProgTable::new_program(&program)?.print();
-    ProgTable::new_kernel_info(&program)?.print();
+    let new_data = || {
+        let mut data = ProgramData::new(
+            bytecode_source.clone(),
+            args.name.clone(),
+            args.metadata
+                .clone()
+                .unwrap_or_default()
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+            parse_global(&args.global),
+            args.map_owner_id,
+        )?;
+        data.set_shared_maps(&args.shared_map)?;
+        data.set_maps_to_pin(&args.pin_map)?;
+        data.set_maps_to_skip(&args.skip_map)?;
+        data.set_map_max_entries(&args.map_max_entries.iter().cloned().collect())?;
+        data.set_allow_duplicate(args.allow_duplicate)?;
+        if let Some(namespace) = &args.pin_namespace {
+            data.set_pin_namespace(namespace)?;
+        }
+        Ok(data)
+    };
+
+    load_programs(get_programs(&args.command, new_data).await?, args.dry_run).await
+}
+
+// Without a subcommand, the program is attached where its section name says.
+async fn get_programs<F>(
+    command: &Option<LoadCommands>,
+    new_data: F,
+) -> anyhow::Result<Vec<Program>>
+where
+    F: Fn() -> Result<ProgramData, BpfmanError>,
+{
+    match command {
+        Some(command) => command.get_programs(new_data),
+        None => Ok(vec![infer_program(new_data()?).await?]),
+    }
+}
+
+async fn load_programs(programs: Vec<Program>, dry_run: bool) -> anyhow::Result<()> {
+    if dry_run {
+        // Every program in a group is loaded from the same bytecode.
+        if let Some(program) = programs.into_iter().next() {
+            verify_program(program).await?;
+            println!("Program passed the verifier");
+        }
+        return Ok(());
+    }
+
+    for program in add_programs(programs).await? {
+        ProgTable::new_program(&program)?.print();
+        ProgTable::new_kernel_info(&program)?.print();
+    }
     Ok(())
 }
 
 impl LoadCommands {
-    pub(crate) fn get_program(&self, data: ProgramData) -> Result<Program, anyhow::Error> {
+    /// Returns the programs to load, one per interface for XDP and TC
+    /// programs and a single program otherwise. `new_data` is called for each.
+    pub(crate) fn get_programs<F>(&self, new_data: F) -> Result<Vec<Program>, anyhow::Error>
+    where
+        F: Fn() -> Result<ProgramData, BpfmanError>,
+    {
         match self {
             LoadCommands::Xdp {
                 iface,
+                all_interfaces,
                 priority,
                 proceed_on,
+                netns,
+                xdp_mode,
+                disabled,
             } => {
                 let proc_on = match XdpProceedOn::from_strings(proceed_on) {
                     Ok(p) => p,
                     Err(e) => bail!("error parsing proceed_on {e}"),
                 };
-                Ok(Program::Xdp(XdpProgram::new(
-                    data,
-                    *priority,
-                    iface.to_string(),
-                    XdpProceedOn::from_int32s(proc_on.as_action_vec())?,
-                )?))
+                let mode = xdp_mode
+                    .clone()
+                    .map(XdpModePreference::try_from)
+                    .transpose()?;
+                let priority = open_config_file().priority_or_default(*priority);
+                let netns = netns.as_ref().map(PathBuf::from);
+                attach_ifaces(iface, *all_interfaces, netns.as_deref())?
+                    .into_iter()
+                    .map(|iface| {
+                        let mut program = XdpProgram::new(
+                            new_data()?,
+                            priority,
+                            iface,
+                            XdpProceedOn::from_int32s(proc_on.as_action_vec())?,
+                            netns.clone(),
+                        )?;
+                        if let Some(mode) = mode {
+                            program.set_mode_preference(mode)?;
+                        }
+                        program.set_enabled(!disabled)?;
+                        Ok(Program::Xdp(program))
+                    })
+                    .collect()
             }
             LoadCommands::Tc {
                 direction,
                 iface,
+                all_interfaces,
                 priority,
                 proceed_on,
+                netns,
+                disabled,
             } => {
                 match direction.as_str() {
                     "ingress" | "egress" => (),
@@ -105,17 +202,27 @@ impl LoadCommands {
                     Ok(p) => p,
                     Err(e) => bail!("error parsing proceed_on {e}"),
                 };
-                Ok(Program::Tc(TcProgram::new(
-                    data,
-                    *priority,
-                    iface.to_string(),
-                    proc_on,
-                    direction.to_string().try_into()?,
-                )?))
+                let priority = open_config_file().priority_or_default(*priority);
+                let netns = netns.as_ref().map(PathBuf::from);
+                attach_ifaces(iface, *all_interfaces, netns.as_deref())?
+                    .into_iter()
+                    .map(|iface| {
+                        let mut program = TcProgram::new(
+                            new_data()?,
+                            priority,
+                            iface,
+                            proc_on.clone(),
+                            direction.to_string().try_into()?,
+                            netns.clone(),
+                        )?;
+                        program.set_enabled(!disabled)?;
+                        Ok(Program::Tc(program))
+                    })
+                    .collect()
             }
-            LoadCommands::Tracepoint { tracepoint } => Ok(Program::Tracepoint(
-                TracepointProgram::new(data, tracepoint.to_string())?,
-            )),
+            LoadCommands::Tracepoint { tracepoint } => Ok(vec![Program::Tracepoint(
+                TracepointProgram::new(new_data()?, tracepoint.to_string())?,
+            )]),
             LoadCommands::Kprobe {
                 fn_name,
                 offset,
@@ -126,13 +233,13 @@ impl LoadCommands {
                     bail!("kprobe container option not supported yet");
                 }
                 let offset = offset.unwrap_or(0);
-                Ok(Program::Kprobe(KprobeProgram::new(
-                    data,
+                Ok(vec![Program::Kprobe(KprobeProgram::new(
+                    new_data()?,
                     fn_name.to_string(),
                     offset,
                     *retprobe,
                     None,
-                )?))
+                )?)])
             }
             LoadCommands::Uprobe {
                 fn_name,
@@ -143,29 +250,56 @@ impl LoadCommands {
                 container_pid,
             } => {
                 let offset = offset.unwrap_or(0);
-                Ok(Program::Uprobe(UprobeProgram::new(
-                    data,
+                Ok(vec![Program::Uprobe(UprobeProgram::new(
+                    new_data()?,
                     fn_name.clone(),
                     offset,
                     target.to_string(),
                     *retprobe,
                     *pid,
                     *container_pid,
-                )?))
+                )?)])
             }
-            LoadCommands::Fentry { fn_name } => Ok(Program::Fentry(FentryProgram::new(
-                data,
+            LoadCommands::Fentry { fn_name } => Ok(vec![Program::Fentry(FentryProgram::new(
+                new_data()?,
                 fn_name.to_string(),
-            )?)),
-            LoadCommands::Fexit { fn_name } => Ok(Program::Fexit(FexitProgram::new(
-                data,
+            )?)]),
+            LoadCommands::Fexit { fn_name } => Ok(vec![Program::Fexit(FexitProgram::new(
+                new_data()?,
                 fn_name.to_string(),
-            )?)),
+            )?)]),
+            LoadCommands::Cgroup {
+                cgroup_path,
+                attach_type,
+            } => Ok(vec![Program::Cgroup(CgroupProgram::new(
+                new_data()?,
+                cgroup_path.to_string(),
+                attach_type.to_string().try_into()?,
+            )?)]),
+            LoadCommands::TailCall { prog_array, index } => Ok(vec![Program::TailCall(
+                TailCallProgram::new(new_data()?, prog_array.to_string(), *index)?,
+            )]),
+        }
+    }
+}
+
+fn attach_ifaces(
+    iface: &[String],
+    all_interfaces: bool,
+    netns: Option<&Path>,
+) -> Result<Vec<String>, anyhow::Error> {
+    if all_interfaces {
+        let ifaces = get_up_interfaces(netns)?;
+        if ifaces.is_empty() {
+            bail!("no interfaces are up");
         }
+        Ok(ifaces)
+    } else {
+        Ok(iface.to_vec())
     }
 }
 
-fn parse_global(global: &Option<Vec<GlobalArg>>) -> HashMap<String, Vec<u8>> {
+pub(crate) fn parse_global(global: &Option<Vec<GlobalArg>>) -> HashMap<String, Vec<u8>> {
     let mut global_data: HashMap<String, Vec<u8>> = HashMap::new();
 
     if let Some(global) = global {
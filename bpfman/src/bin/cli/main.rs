@@ -3,19 +3,27 @@
 
 use anyhow::anyhow;
 use args::Commands;
+use attach::{execute_attach, execute_detach, execute_relink, execute_set_enabled};
+use bpfman::utils::check_capabilities;
 use clap::Parser;
 use get::execute_get;
-use list::execute_list;
+use list::{execute_dump_state, execute_list, execute_list_kernel, execute_list_maps};
 use log::debug;
-use unload::execute_unload;
+use reorder::execute_reorder;
+use replace::execute_reload;
+use unload::{execute_unload, execute_unload_all};
 
 mod args;
+mod attach;
 mod get;
 mod image;
 mod list;
 mod load;
+mod reorder;
+mod replace;
 mod table;
 mod unload;
+mod validate;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -23,6 +31,8 @@ async fn main() -> anyhow::Result<()> {
     debug!("Log using env_logger");
 
     let cli = crate::args::Cli::parse();
+    // Fail up front rather than in the mount or load that needs them.
+    check_capabilities()?;
 
     cli.command.execute().await
 }
@@ -32,10 +42,23 @@ impl Commands {
         match self {
             Commands::Load(l) => l.execute().await,
             Commands::Unload(args) => execute_unload(args).await,
+            Commands::UnloadAll(args) => execute_unload_all(args).await,
             Commands::List(args) => execute_list(args).await,
             Commands::Get(args) => execute_get(args)
                 .await
                 .map_err(|e| anyhow!("get error: {e}")),
+            Commands::ListMaps(args) => execute_list_maps(args).await,
+            Commands::DumpState => execute_dump_state().await,
+            Commands::ListKernel => execute_list_kernel().await,
+            Commands::Reorder(args) => execute_reorder(args).await,
+            Commands::Detach(args) => execute_detach(args).await,
+            Commands::Attach(args) => execute_attach(args).await,
+            Commands::Enable(args) => execute_set_enabled(args, true).await,
+            Commands::Disable(args) => execute_set_enabled(args, false).await,
+            Commands::Relink(args) => execute_relink(args).await,
+            Commands::Replace(r) => r.execute().await,
+            Commands::Reload(args) => execute_reload(args).await,
+            Commands::Validate(v) => v.execute().await,
             Commands::Image(i) => i.execute().await,
         }?;
 
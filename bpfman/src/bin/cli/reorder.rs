@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::reorder_program;
+
+use crate::{args::ReorderArgs, table::ProgTable};
+
+pub(crate) async fn execute_reorder(args: &ReorderArgs) -> Result<(), anyhow::Error> {
+    let program = reorder_program(args.program_id, args.priority).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
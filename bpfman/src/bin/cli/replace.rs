@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::{
+    reload_program, replace_program,
+    types::{Location, ProgramData},
+};
+
+use crate::{
+    args::{ReloadArgs, ReplaceFileArgs, ReplaceImageArgs, ReplaceSubcommand},
+    load::{file_location, parse_global},
+    table::ProgTable,
+};
+
+impl ReplaceSubcommand {
+    pub(crate) async fn execute(&self) -> anyhow::Result<()> {
+        match self {
+            ReplaceSubcommand::File(r) => execute_replace_file(r).await,
+            ReplaceSubcommand::Image(r) => execute_replace_image(r).await,
+        }
+    }
+}
+
+async fn execute_replace_file(args: &ReplaceFileArgs) -> anyhow::Result<()> {
+    let data = ProgramData::new(
+        file_location(&args.path, &args.sha256)?,
+        args.name.clone(),
+        args.metadata
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        parse_global(&args.global),
+        args.map_owner_id,
+    )?;
+
+    replace(args.program_id, data).await
+}
+
+async fn execute_replace_image(args: &ReplaceImageArgs) -> anyhow::Result<()> {
+    let data = ProgramData::new(
+        Location::Image((&args.pull_args).try_into()?),
+        args.name.clone(),
+        args.metadata
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        parse_global(&args.global),
+        args.map_owner_id,
+    )?;
+
+    replace(args.program_id, data).await
+}
+
+async fn replace(id: u32, data: ProgramData) -> anyhow::Result<()> {
+    let program = replace_program(id, data).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
+
+pub(crate) async fn execute_reload(args: &ReloadArgs) -> anyhow::Result<()> {
+    let program = reload_program(args.program_id).await?;
+
+    ProgTable::new_program(&program)?.print();
+    ProgTable::new_kernel_info(&program)?.print();
+    Ok(())
+}
@@ -1,7 +1,12 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use bpfman::types::{ImagePullPolicy, Location, ProbeType::*, Program};
+use std::time::Duration;
+
+use bpfman::types::{
+    BytecodeMap, BytecodeProgram, BytecodeReport, ImagePullPolicy, KernelLink, KernelProgram,
+    LinkStatus, Location, ProbeType::*, Program, ProgramMap, ProgramType,
+};
 use comfy_table::{Cell, Color, Table};
 use hex::encode_upper;
 pub(crate) struct ProgTable(Table);
@@ -36,6 +41,12 @@ impl ProgTable {
             Location::File(p) => {
                 table.add_row(vec!["Path:", &p]);
             }
+            Location::Url(u) => {
+                table.add_row(vec!["URL:", &u.url]);
+                if let Some(sha256) = &u.sha256 {
+                    table.add_row(vec!["SHA-256:", sha256]);
+                }
+            }
         };
 
         let global_data = data.get_global_data()?;
@@ -86,6 +97,47 @@ impl ProgTable {
             None => table.add_row(vec!["Map Owner ID:", "None"]),
         };
 
+        if let Some(id) = data.get_group_id()? {
+            table.add_row(vec!["Group ID:", &id.to_string()]);
+        }
+
+        let shared_maps = data.get_shared_maps()?;
+        if !shared_maps.is_empty() {
+            table.add_row(vec!["Shared Maps:", &shared_maps.join(", ")]);
+        }
+        let maps_to_pin = data.get_maps_to_pin()?;
+        if !maps_to_pin.is_empty() {
+            table.add_row(vec!["Maps To Pin:", &maps_to_pin.join(", ")]);
+        }
+        let maps_to_skip = data.get_maps_to_skip()?;
+        if !maps_to_skip.is_empty() {
+            table.add_row(vec!["Maps To Skip:", &maps_to_skip.join(", ")]);
+        }
+        let mut max_entries: Vec<String> = data
+            .get_map_max_entries()?
+            .iter()
+            .map(|(name, n)| format!("{name}={n}"))
+            .collect();
+        if !max_entries.is_empty() {
+            max_entries.sort();
+            table.add_row(vec!["Map Max Entries:", &max_entries.join(", ")]);
+        }
+        if let Some(namespace) = data.get_pin_namespace()? {
+            table.add_row(vec!["Pin Namespace:", &namespace]);
+        }
+        if let Some(id) = data.get_duplicate_of()? {
+            table.add_row(vec!["Duplicate Of:", &id.to_string()]);
+        }
+        if let Some(loaded_at) = data.get_loaded_at()? {
+            table.add_row(vec!["Loaded At:", &loaded_at]);
+        }
+        if let Some(uptime) = data.get_uptime()? {
+            table.add_row(vec!["Uptime:", &format_uptime(uptime)]);
+        }
+        if let Some(attached_at) = data.get_attached_at()? {
+            table.add_row(vec!["Attached At:", &attached_at]);
+        }
+
         let map_used_by = data.get_maps_used_by()?;
         if map_used_by.is_empty() {
             table.add_row(vec!["Maps Used By:", "None"]);
@@ -101,10 +153,18 @@ impl ProgTable {
             }
         };
 
+        let missing = if program.interface_missing()? {
+            " (missing)"
+        } else {
+            ""
+        };
         match program {
             Program::Xdp(p) => {
                 table.add_row(vec!["Priority:", &p.get_priority()?.to_string()]);
-                table.add_row(vec!["Iface:", &p.get_iface()?]);
+                table.add_row(vec!["Iface:", &format!("{}{missing}", p.get_iface()?)]);
+                if let Some(if_index) = p.get_if_index()? {
+                    table.add_row(vec!["Iface Index:", &if_index.to_string()]);
+                }
                 table.add_row(vec![
                     "Position:",
                     &match p.get_current_position()? {
@@ -112,11 +172,42 @@ impl ProgTable {
                         None => "NONE".to_string(),
                     },
                 ]);
+                table.add_row(vec![
+                    "Revision:",
+                    &match p.get_dispatcher_revision()? {
+                        Some(rev) => rev.to_string(),
+                        None => "NONE".to_string(),
+                    },
+                ]);
+                table.add_row(vec![
+                    "Replaced In Place:",
+                    &p.get_dispatcher_replaced()?.to_string(),
+                ]);
+                table.add_row(vec![
+                    "XDP Mode:",
+                    &match (p.get_mode()?, p.get_mode_preference()?) {
+                        (Some(mode), Some(pref)) => format!("{mode} (requested {pref})"),
+                        (Some(mode), None) => mode.to_string(),
+                        (None, _) => "NONE".to_string(),
+                    },
+                ]);
+                let members = p.get_members()?;
+                if !members.is_empty() {
+                    table.add_row(vec!["Runs On:", &members.join(", ")]);
+                }
+                table.add_row(vec!["Detached:", &p.get_detached()?.to_string()]);
+                table.add_row(vec!["Enabled:", &p.get_enabled()?.to_string()]);
                 table.add_row(vec!["Proceed On:", &format!("{}", p.get_proceed_on()?)]);
+                if let Some(netns) = p.get_netns()? {
+                    table.add_row(vec!["Network Namespace:", &netns.display().to_string()]);
+                }
             }
             Program::Tc(p) => {
                 table.add_row(vec!["Priority:", &p.get_priority()?.to_string()]);
-                table.add_row(vec!["Iface:", &p.get_iface()?]);
+                table.add_row(vec!["Iface:", &format!("{}{missing}", p.get_iface()?)]);
+                if let Some(if_index) = p.get_if_index()? {
+                    table.add_row(vec!["Iface Index:", &if_index.to_string()]);
+                }
                 table.add_row(vec![
                     "Position:",
                     &match p.get_current_position()? {
@@ -124,8 +215,20 @@ impl ProgTable {
                         None => "NONE".to_string(),
                     },
                 ]);
+                table.add_row(vec![
+                    "Revision:",
+                    &match p.get_dispatcher_revision()? {
+                        Some(rev) => rev.to_string(),
+                        None => "NONE".to_string(),
+                    },
+                ]);
+                table.add_row(vec!["Detached:", &p.get_detached()?.to_string()]);
+                table.add_row(vec!["Enabled:", &p.get_enabled()?.to_string()]);
                 table.add_row(vec!["Direction:", &p.get_direction()?.to_string()]);
                 table.add_row(vec!["Proceed On:", &format!("{}", p.get_proceed_on()?)]);
+                if let Some(netns) = p.get_netns()? {
+                    table.add_row(vec!["Network Namespace:", &netns.display().to_string()]);
+                }
             }
             Program::Tracepoint(p) => {
                 table.add_row(vec!["Tracepoint:", &p.get_tracepoint()?]);
@@ -168,6 +271,14 @@ impl ProgTable {
             Program::Fexit(p) => {
                 table.add_row(vec!["Function Name:", &p.get_fn_name()?]);
             }
+            Program::Cgroup(p) => {
+                table.add_row(vec!["Cgroup:", &p.get_path()?]);
+                table.add_row(vec!["Attach Type:", &p.get_attach_type()?.to_string()]);
+            }
+            Program::TailCall(p) => {
+                table.add_row(vec!["Prog Array:", &p.get_map_name()?]);
+                table.add_row(vec!["Index:", &p.get_index()?.to_string()]);
+            }
             Program::Unsupported(_) => {
                 table.add_row(vec!["Unsupported Program Type", "None"]);
             }
@@ -228,11 +339,30 @@ impl ProgTable {
         Ok(ProgTable(table))
     }
 
+    pub(crate) fn add_link_status(&mut self, status: &LinkStatus) {
+        let link_id = match status.link_id {
+            Some(id) => id.to_string(),
+            None => "None".to_string(),
+        };
+        self.0.add_row(vec!["Link ID:".to_string(), link_id]);
+        if let Some(attached) = status.attached {
+            self.0
+                .add_row(vec!["Attached:".to_string(), attached.to_string()]);
+        }
+    }
+
+    pub(crate) fn add_maps_memlock(&mut self, bytes: u64) {
+        self.0.add_row(vec![
+            "Map Allocated Memory (bytes):".to_string(),
+            bytes.to_string(),
+        ]);
+    }
+
     pub(crate) fn new_list() -> Self {
         let mut table = Table::new();
 
         table.load_preset(comfy_table::presets::NOTHING);
-        table.set_header(vec!["Program ID", "Name", "Type", "Load Time"]);
+        table.set_header(vec!["Program ID", "Name", "Type", "Load Time", "Uptime"]);
         ProgTable(table)
     }
 
@@ -242,28 +372,194 @@ impl ProgTable {
         name: String,
         type_: String,
         load_time: String,
+        uptime: String,
     ) {
-        self.0.add_row(vec![id, name, type_, load_time]);
+        self.0.add_row(vec![id, name, type_, load_time, uptime]);
     }
 
     pub(crate) fn add_response_prog(&mut self, r: Program) -> anyhow::Result<()> {
         let data = r.get_data();
 
+        let mut type_ = r.kind().to_string();
+        if r.detached() {
+            type_.push_str(" (detached)");
+        }
+        if !r.enabled() {
+            type_.push_str(" (disabled)");
+        }
+        if r.interface_missing()? {
+            type_.push_str(" (interface missing)");
+        }
+        if let Some(id) = data.get_duplicate_of()? {
+            type_.push_str(&format!(" (duplicate of {id})"));
+        }
+
         self.add_row_list(
             data.get_id()?.to_string(),
             data.get_kernel_name()?,
-            r.kind().to_string(),
+            type_,
             data.get_kernel_loaded_at()?,
+            data.get_uptime()?.map_or("None".to_string(), format_uptime),
         );
 
         Ok(())
     }
 
+    pub(crate) fn new_map_list() -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![
+            "Name",
+            "Type",
+            "Key Size",
+            "Value Size",
+            "Max Entries",
+            "Pin Path",
+        ]);
+        ProgTable(table)
+    }
+
+    pub(crate) fn add_map(&mut self, map: &ProgramMap) {
+        self.0.add_row(vec![
+            map.name.clone(),
+            map.map_type.clone(),
+            map.key_size.to_string(),
+            map.value_size.to_string(),
+            map.max_entries.to_string(),
+            map.pin_path.to_string_lossy().to_string(),
+        ]);
+    }
+
+    pub(crate) fn new_kernel_program_list() -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec!["Program ID", "Name", "Type", "Managed By"]);
+        ProgTable(table)
+    }
+
+    pub(crate) fn add_kernel_program(&mut self, program: &KernelProgram) {
+        let program_type = ProgramType::try_from(program.program_type)
+            .map(|t| t.to_string())
+            .unwrap_or_else(|_| program.program_type.to_string());
+        let managed_by = match (program.managed, program.dispatcher) {
+            (true, true) => "bpfman (dispatcher)",
+            (true, false) => "bpfman",
+            _ => "external",
+        };
+        self.0.add_row(vec![
+            program.id.to_string(),
+            program.name.clone(),
+            program_type,
+            managed_by.to_string(),
+        ]);
+    }
+
+    pub(crate) fn new_kernel_link_list() -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![
+            "Link ID",
+            "Program ID",
+            "Type",
+            "Interface",
+            "Managed By",
+        ]);
+        ProgTable(table)
+    }
+
+    pub(crate) fn add_kernel_link(&mut self, link: &KernelLink) {
+        let iface = match (&link.iface, link.if_index) {
+            (Some(iface), _) => iface.clone(),
+            (None, Some(if_index)) => if_index.to_string(),
+            (None, None) => String::new(),
+        };
+        self.0.add_row(vec![
+            link.id.to_string(),
+            link.prog_id.to_string(),
+            link.link_type.clone(),
+            iface,
+            if link.managed { "bpfman" } else { "external" }.to_string(),
+        ]);
+    }
+
+    pub(crate) fn new_bytecode_report(report: &BytecodeReport) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Bytecode")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+        table.add_row(vec!["License:", &report.license]);
+        table.add_row(vec!["BTF:", &report.btf.to_string()]);
+        table.add_row(vec!["CO-RE:", &report.uses_core.to_string()]);
+        let issues = if report.issues.is_empty() {
+            "None".to_string()
+        } else {
+            report.issues.len().to_string()
+        };
+        table.add_row(vec!["Issues:", &issues]);
+        ProgTable(table)
+    }
+
+    pub(crate) fn new_bytecode_program_list() -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec!["Program", "Section", "Type"]);
+        ProgTable(table)
+    }
+
+    pub(crate) fn add_bytecode_program(&mut self, program: &BytecodeProgram) {
+        self.0.add_row(vec![
+            program.name.clone(),
+            program.section.clone(),
+            program.program_type.clone(),
+        ]);
+    }
+
+    pub(crate) fn new_bytecode_map_list() -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec!["Map", "Type", "Key Size", "Value Size", "Max Entries"]);
+        ProgTable(table)
+    }
+
+    pub(crate) fn add_bytecode_map(&mut self, map: &BytecodeMap) {
+        self.0.add_row(vec![
+            map.name.clone(),
+            map.map_type.clone(),
+            map.key_size.to_string(),
+            map.value_size.to_string(),
+            map.max_entries.to_string(),
+        ]);
+    }
+
     pub(crate) fn print(&self) {
         println!("{self}\n")
     }
 }
 
+// Shows a duration to the second, leaving out the units above the largest
+// one needed, for example "3d 0h 5m 12s" or "42s".
+fn format_uptime(uptime: Duration) -> String {
+    let secs = uptime.as_secs();
+    let (d, h, m, s) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if d > 0 {
+        format!("{d}d {h}h {m}m {s}s")
+    } else if h > 0 {
+        format!("{h}h {m}m {s}s")
+    } else if m > 0 {
+        format!("{m}m {s}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
 impl std::fmt::Display for ProgTable {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.0)
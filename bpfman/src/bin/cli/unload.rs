@@ -1,11 +1,43 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use bpfman::remove_program;
+use anyhow::bail;
+use bpfman::{find_program, remove_all_programs, remove_program, types::ProgramSelector};
 
-use crate::args::UnloadArgs;
+use crate::args::{UnloadAllArgs, UnloadArgs};
 
 pub(crate) async fn execute_unload(args: &UnloadArgs) -> Result<(), anyhow::Error> {
-    remove_program(args.program_id).await?;
+    let id = match (args.program_id, &args.name, &args.iface) {
+        (Some(id), _, _) => id,
+        (None, Some(name), Some(iface)) => {
+            let selector = ProgramSelector::new(name.clone(), iface.clone(), args.priority);
+            find_program(&selector).await?
+        }
+        _ => bail!("a Program Id or --name and --iface are required"),
+    };
+    remove_program(id).await?;
+    Ok(())
+}
+
+pub(crate) async fn execute_unload_all(args: &UnloadAllArgs) -> Result<(), anyhow::Error> {
+    let results = remove_all_programs(args.iface.clone()).await?;
+    if results.is_empty() {
+        println!("No programs to unload");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for (id, result) in results {
+        match result {
+            Ok(()) => println!("Unloaded program {id}"),
+            Err(e) => {
+                println!("Failed to unload program {id}: {e}");
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} programs could not be unloaded");
+    }
     Ok(())
 }
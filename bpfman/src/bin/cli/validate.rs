@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::bail;
+use bpfman::{
+    types::{BytecodeImage, Location},
+    validate_bytecode,
+};
+
+use crate::{
+    args::{ValidateFileArgs, ValidateSubcommand},
+    load::file_location,
+    table::ProgTable,
+};
+
+impl ValidateSubcommand {
+    pub(crate) async fn execute(&self) -> anyhow::Result<()> {
+        let location = match self {
+            ValidateSubcommand::File(ValidateFileArgs { path, sha256 }) => {
+                file_location(path, sha256)?
+            }
+            ValidateSubcommand::Image(args) => Location::Image(BytecodeImage::try_from(args)?),
+        };
+        let report = validate_bytecode(&location).await?;
+
+        ProgTable::new_bytecode_report(&report).print();
+        let mut table = ProgTable::new_bytecode_program_list();
+        for program in &report.programs {
+            table.add_bytecode_program(program);
+        }
+        table.print();
+        if !report.maps.is_empty() {
+            let mut table = ProgTable::new_bytecode_map_list();
+            for map in &report.maps {
+                table.add_bytecode_map(map);
+            }
+            table.print();
+        }
+
+        // Fail so that CI jobs checking bytecode stop on it.
+        if !report.issues.is_empty() {
+            for issue in &report.issues {
+                println!("{issue}");
+            }
+            bail!("{} would not load", location_name(&location));
+        }
+        Ok(())
+    }
+}
+
+fn location_name(location: &Location) -> &str {
+    match location {
+        Location::File(path) => path,
+        Location::Url(url) => &url.url,
+        Location::Image(image) => &image.image_url,
+    }
+}
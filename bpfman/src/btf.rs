@@ -0,0 +1,614 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Map values are decoded using the BTF in the program's object file. Aya
+// parses BTF itself but doesn't expose the types it holds, so the .BTF section
+// is read here, keeping only what's needed to lay out a value. See
+// Documentation/bpf/btf.rst in the kernel for the format.
+
+use object::{Endianness, Object, ObjectSection};
+use serde_json::{Map, Number, Value};
+
+const BTF_MAGIC: u16 = 0xeb9f;
+const HEADER_LEN: usize = 24;
+
+// BTF_KIND_* values from include/uapi/linux/btf.h
+const BTF_KIND_INT: u32 = 1;
+const BTF_KIND_PTR: u32 = 2;
+const BTF_KIND_ARRAY: u32 = 3;
+const BTF_KIND_STRUCT: u32 = 4;
+const BTF_KIND_UNION: u32 = 5;
+const BTF_KIND_ENUM: u32 = 6;
+const BTF_KIND_FWD: u32 = 7;
+const BTF_KIND_TYPEDEF: u32 = 8;
+const BTF_KIND_VOLATILE: u32 = 9;
+const BTF_KIND_CONST: u32 = 10;
+const BTF_KIND_RESTRICT: u32 = 11;
+const BTF_KIND_FUNC: u32 = 12;
+const BTF_KIND_FUNC_PROTO: u32 = 13;
+const BTF_KIND_VAR: u32 = 14;
+const BTF_KIND_DATASEC: u32 = 15;
+const BTF_KIND_FLOAT: u32 = 16;
+const BTF_KIND_DECL_TAG: u32 = 17;
+const BTF_KIND_TYPE_TAG: u32 = 18;
+const BTF_KIND_ENUM64: u32 = 19;
+
+// BTF_INT_* encoding bits from include/uapi/linux/btf.h
+const BTF_INT_SIGNED: u32 = 1 << 0;
+const BTF_INT_CHAR: u32 = 1 << 1;
+const BTF_INT_BOOL: u32 = 1 << 2;
+
+// Bounds how far chains of types are followed, as the kernel does.
+const MAX_DEPTH: usize = 32;
+
+struct Member {
+    name: String,
+    type_id: u32,
+    bit_offset: u32,
+    // Zero unless the member is a bitfield.
+    bit_size: u32,
+}
+
+struct SecVar {
+    type_id: u32,
+    offset: u32,
+    size: u32,
+}
+
+enum Kind {
+    Int {
+        size: u32,
+        encoding: u32,
+        bits: u32,
+    },
+    Ptr(u32),
+    Array {
+        type_id: u32,
+        len: u32,
+    },
+    Struct {
+        size: u32,
+        members: Vec<Member>,
+    },
+    Enum {
+        size: u32,
+        signed: bool,
+        values: Vec<(String, i128)>,
+    },
+    Float(u32),
+    Var(u32),
+    Datasec {
+        size: u32,
+        vars: Vec<SecVar>,
+    },
+    // Typedefs and type qualifiers, which are laid out as the type they
+    // refer to.
+    Alias(u32),
+    // Types that values can't have, such as functions.
+    Other,
+}
+
+struct Type {
+    name: String,
+    kind: Kind,
+}
+
+/// The types described by an object file's BTF.
+pub(crate) struct BtfTypes {
+    // Type IDs start at 1, with 0 standing for void.
+    types: Vec<Type>,
+    endianness: Endianness,
+}
+
+impl BtfTypes {
+    /// Reads the BTF of the object file in `bytes`. Returns None if it has
+    /// none or it can't be read.
+    pub(crate) fn from_object(bytes: &[u8]) -> Option<Self> {
+        let file = object::File::parse(bytes).ok()?;
+        let data = file.section_by_name(".BTF")?.data().ok()?;
+        Self::parse(data, file.endianness())
+    }
+
+    fn parse(data: &[u8], endianness: Endianness) -> Option<Self> {
+        let read_u32 = |offset: usize| -> Option<u32> {
+            let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+            Some(match endianness {
+                Endianness::Little => u32::from_le_bytes(bytes),
+                Endianness::Big => u32::from_be_bytes(bytes),
+            })
+        };
+        let magic: [u8; 2] = data.get(0..2)?.try_into().ok()?;
+        let magic = match endianness {
+            Endianness::Little => u16::from_le_bytes(magic),
+            Endianness::Big => u16::from_be_bytes(magic),
+        };
+        if magic != BTF_MAGIC {
+            return None;
+        }
+        let hdr_len = read_u32(4)? as usize;
+        if hdr_len < HEADER_LEN {
+            return None;
+        }
+        let type_start = hdr_len + read_u32(8)? as usize;
+        let type_end = type_start + read_u32(12)? as usize;
+        let str_start = hdr_len + read_u32(16)? as usize;
+        let strings = data.get(str_start..str_start + read_u32(20)? as usize)?;
+        let string_at = |offset: u32| -> Option<String> {
+            let s = strings.get(offset as usize..)?;
+            let end = s.iter().position(|b| *b == 0)?;
+            Some(String::from_utf8_lossy(&s[..end]).to_string())
+        };
+
+        let mut types = vec![Type {
+            name: String::new(),
+            kind: Kind::Other,
+        }];
+        let mut offset = type_start;
+        while offset < type_end {
+            let name = string_at(read_u32(offset)?)?;
+            let info = read_u32(offset + 4)?;
+            let size_or_type = read_u32(offset + 8)?;
+            offset += 12;
+
+            let kind_flag = info >> 31 == 1;
+            let vlen = (info & 0xffff) as usize;
+            let kind = match (info >> 24) & 0x1f {
+                BTF_KIND_INT => {
+                    let v = read_u32(offset)?;
+                    offset += 4;
+                    Kind::Int {
+                        size: size_or_type,
+                        encoding: (v >> 24) & 0x0f,
+                        bits: v & 0xff,
+                    }
+                }
+                BTF_KIND_PTR => Kind::Ptr(size_or_type),
+                BTF_KIND_ARRAY => {
+                    let kind = Kind::Array {
+                        type_id: read_u32(offset)?,
+                        len: read_u32(offset + 8)?,
+                    };
+                    offset += 12;
+                    kind
+                }
+                BTF_KIND_STRUCT | BTF_KIND_UNION => {
+                    let mut members = Vec::with_capacity(vlen);
+                    for _ in 0..vlen {
+                        let m = read_u32(offset + 8)?;
+                        let (bit_offset, bit_size) = if kind_flag {
+                            (m & 0xffffff, m >> 24)
+                        } else {
+                            (m, 0)
+                        };
+                        members.push(Member {
+                            name: string_at(read_u32(offset)?)?,
+                            type_id: read_u32(offset + 4)?,
+                            bit_offset,
+                            bit_size,
+                        });
+                        offset += 12;
+                    }
+                    Kind::Struct {
+                        size: size_or_type,
+                        members,
+                    }
+                }
+                BTF_KIND_ENUM => {
+                    let mut values = Vec::with_capacity(vlen);
+                    for _ in 0..vlen {
+                        let v = read_u32(offset + 4)?;
+                        let v = if kind_flag {
+                            v as i32 as i128
+                        } else {
+                            v as i128
+                        };
+                        values.push((string_at(read_u32(offset)?)?, v));
+                        offset += 8;
+                    }
+                    Kind::Enum {
+                        size: size_or_type,
+                        signed: kind_flag,
+                        values,
+                    }
+                }
+                BTF_KIND_ENUM64 => {
+                    let mut values = Vec::with_capacity(vlen);
+                    for _ in 0..vlen {
+                        let v = (read_u32(offset + 8)? as u64) << 32 | read_u32(offset + 4)? as u64;
+                        let v = if kind_flag {
+                            v as i64 as i128
+                        } else {
+                            v as i128
+                        };
+                        values.push((string_at(read_u32(offset)?)?, v));
+                        offset += 12;
+                    }
+                    Kind::Enum {
+                        size: size_or_type,
+                        signed: kind_flag,
+                        values,
+                    }
+                }
+                BTF_KIND_FLOAT => Kind::Float(size_or_type),
+                BTF_KIND_VAR => {
+                    offset += 4;
+                    Kind::Var(size_or_type)
+                }
+                BTF_KIND_DATASEC => {
+                    let mut vars = Vec::with_capacity(vlen);
+                    for _ in 0..vlen {
+                        vars.push(SecVar {
+                            type_id: read_u32(offset)?,
+                            offset: read_u32(offset + 4)?,
+                            size: read_u32(offset + 8)?,
+                        });
+                        offset += 12;
+                    }
+                    Kind::Datasec {
+                        size: size_or_type,
+                        vars,
+                    }
+                }
+                BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT
+                | BTF_KIND_TYPE_TAG => Kind::Alias(size_or_type),
+                BTF_KIND_FUNC_PROTO => {
+                    offset += 8 * vlen;
+                    Kind::Other
+                }
+                BTF_KIND_DECL_TAG => {
+                    offset += 4;
+                    Kind::Other
+                }
+                BTF_KIND_FWD | BTF_KIND_FUNC => Kind::Other,
+                // The size of an unknown kind isn't known either, so nothing
+                // after it can be read.
+                _ => return None,
+            };
+            types.push(Type { name, kind });
+        }
+
+        Some(Self { types, endianness })
+    }
+
+    /// Returns the ID of the type named `name`, other than a variable or
+    /// function.
+    pub(crate) fn type_by_name(&self, name: &str) -> Option<u32> {
+        self.types
+            .iter()
+            .position(|t| t.name == name && !matches!(t.kind, Kind::Var(_) | Kind::Other))
+            .map(|id| id as u32)
+    }
+
+    /// Returns the ID of the value type of the map named `name`. Maps defined
+    /// in the .maps section describe their value type, while the maps aya
+    /// creates for global data are laid out as the section of the same name.
+    pub(crate) fn map_value_type(&self, name: &str) -> Option<u32> {
+        let from_def = || {
+            let Kind::Datasec { vars, .. } = &self.get(self.type_by_name(".maps")?)?.kind else {
+                return None;
+            };
+            let def = vars.iter().find_map(|v| match self.get(v.type_id) {
+                Some(Type {
+                    name: var_name,
+                    kind: Kind::Var(type_id),
+                }) if var_name == name => Some(*type_id),
+                _ => None,
+            })?;
+            let Kind::Struct { members, .. } = &self.get(self.resolve(def)?)?.kind else {
+                return None;
+            };
+            let value = members.iter().find(|m| m.name == "value")?;
+            match self.get(self.resolve(value.type_id)?)?.kind {
+                Kind::Ptr(type_id) => Some(type_id),
+                _ => None,
+            }
+        };
+        from_def().or_else(|| {
+            self.type_by_name(name)
+                .filter(|id| matches!(self.types[*id as usize].kind, Kind::Datasec { .. }))
+        })
+    }
+
+    /// Returns the size of type `type_id` in bytes.
+    pub(crate) fn size(&self, type_id: u32) -> Option<usize> {
+        self.size_at(type_id, 0)
+    }
+
+    /// Decodes `bytes` as a value of type `type_id`. Returns None if the
+    /// bytes don't hold a value of the type.
+    pub(crate) fn decode(&self, type_id: u32, bytes: &[u8]) -> Option<Value> {
+        if self.size(type_id)? != bytes.len() {
+            return None;
+        }
+        self.decode_at(type_id, bytes, 0)
+    }
+
+    fn get(&self, type_id: u32) -> Option<&Type> {
+        self.types.get(type_id as usize)
+    }
+
+    fn resolve(&self, mut type_id: u32) -> Option<u32> {
+        for _ in 0..MAX_DEPTH {
+            match self.get(type_id)?.kind {
+                Kind::Alias(t) => type_id = t,
+                _ => return Some(type_id),
+            }
+        }
+        None
+    }
+
+    fn size_at(&self, type_id: u32, depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match &self.get(type_id)?.kind {
+            Kind::Int { size, .. }
+            | Kind::Struct { size, .. }
+            | Kind::Enum { size, .. }
+            | Kind::Float(size)
+            | Kind::Datasec { size, .. } => *size as usize,
+            // Pointers are 64 bits wide in BPF programs.
+            Kind::Ptr(_) => 8,
+            Kind::Array { type_id, len } => self.size_at(*type_id, depth + 1)? * *len as usize,
+            Kind::Var(t) | Kind::Alias(t) => self.size_at(*t, depth + 1)?,
+            Kind::Other => return None,
+        })
+    }
+
+    fn decode_at(&self, type_id: u32, bytes: &[u8], depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let depth = depth + 1;
+        match &self.get(type_id)?.kind {
+            Kind::Int {
+                size,
+                encoding,
+                bits,
+            } => {
+                let v = self.read_uint(bytes.get(..*size as usize)?)?;
+                Some(int_value(v, *bits, *encoding))
+            }
+            Kind::Ptr(_) => Some(Value::from(self.read_uint(bytes.get(..8)?)? as u64)),
+            Kind::Array { type_id, len } => {
+                let size = self.size_at(*type_id, depth)?;
+                let elems = bytes.get(..size * *len as usize)?;
+                if let Some(Kind::Int {
+                    size: 1, encoding, ..
+                }) = self
+                    .resolve(*type_id)
+                    .and_then(|t| self.get(t))
+                    .map(|t| &t.kind)
+                {
+                    if encoding & BTF_INT_CHAR != 0 {
+                        let end = elems.iter().position(|b| *b == 0).unwrap_or(elems.len());
+                        return Some(Value::from(String::from_utf8_lossy(&elems[..end])));
+                    }
+                }
+                if size == 0 {
+                    return Some(Value::Array(vec![]));
+                }
+                elems
+                    .chunks(size)
+                    .map(|e| self.decode_at(*type_id, e, depth))
+                    .collect::<Option<Vec<_>>>()
+                    .map(Value::Array)
+            }
+            Kind::Struct { members, .. } => {
+                let mut fields = Map::new();
+                for m in members {
+                    let value = if m.bit_size != 0 {
+                        self.decode_bitfield(m, bytes)?
+                    } else {
+                        let start = m.bit_offset as usize / 8;
+                        let end = start + self.size_at(m.type_id, depth)?;
+                        self.decode_at(m.type_id, bytes.get(start..end)?, depth)?
+                    };
+                    // The members of anonymous structs and unions are
+                    // accessed as members of the enclosing type.
+                    match value {
+                        Value::Object(inner) if m.name.is_empty() => fields.extend(inner),
+                        value => {
+                            fields.insert(m.name.clone(), value);
+                        }
+                    }
+                }
+                Some(Value::Object(fields))
+            }
+            Kind::Enum {
+                size,
+                signed,
+                values,
+            } => {
+                let bits = *size * 8;
+                let v = self.read_uint(bytes.get(..*size as usize)?)?;
+                Some(enum_value(v, bits, *signed, values))
+            }
+            Kind::Float(4) => {
+                let v = self.read_uint(bytes.get(..4)?)? as u32;
+                Some(Number::from_f64(f32::from_bits(v) as f64).map_or(Value::Null, Value::Number))
+            }
+            Kind::Float(8) => {
+                let v = self.read_uint(bytes.get(..8)?)? as u64;
+                Some(Number::from_f64(f64::from_bits(v)).map_or(Value::Null, Value::Number))
+            }
+            Kind::Datasec { vars, .. } => {
+                let mut fields = Map::new();
+                for v in vars {
+                    let start = v.offset as usize;
+                    let value = bytes.get(start..start + v.size as usize)?;
+                    let name = self.get(v.type_id)?.name.clone();
+                    fields.insert(name, self.decode_at(v.type_id, value, depth)?);
+                }
+                Some(Value::Object(fields))
+            }
+            Kind::Var(t) | Kind::Alias(t) => self.decode_at(*t, bytes, depth),
+            Kind::Float(_) | Kind::Other => None,
+        }
+    }
+
+    fn decode_bitfield(&self, m: &Member, bytes: &[u8]) -> Option<Value> {
+        // Read the bytes the bitfield spans and shift it down to bit 0. On
+        // big endian targets, bit offsets count from the most significant
+        // bit.
+        let start = m.bit_offset as usize / 8;
+        let shift = m.bit_offset % 8;
+        let len = (shift + m.bit_size).div_ceil(8) as usize;
+        let raw = self.read_uint(bytes.get(start..start + len)?)?;
+        let raw = match self.endianness {
+            Endianness::Little => raw >> shift,
+            Endianness::Big => raw >> (len as u32 * 8 - shift - m.bit_size),
+        };
+        let v = raw & mask(m.bit_size);
+        match &self.get(self.resolve(m.type_id)?)?.kind {
+            Kind::Int { encoding, .. } => Some(int_value(v, m.bit_size, *encoding)),
+            Kind::Enum { signed, values, .. } => Some(enum_value(v, m.bit_size, *signed, values)),
+            _ => None,
+        }
+    }
+
+    fn read_uint(&self, bytes: &[u8]) -> Option<u128> {
+        if bytes.len() > 16 {
+            return None;
+        }
+        let mut buf = [0u8; 16];
+        Some(match self.endianness {
+            Endianness::Little => {
+                buf[..bytes.len()].copy_from_slice(bytes);
+                u128::from_le_bytes(buf)
+            }
+            Endianness::Big => {
+                buf[16 - bytes.len()..].copy_from_slice(bytes);
+                u128::from_be_bytes(buf)
+            }
+        })
+    }
+}
+
+fn mask(bits: u32) -> u128 {
+    if bits >= 128 {
+        u128::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn sign_extend(v: u128, bits: u32) -> i128 {
+    if bits == 0 || bits >= 128 {
+        return v as i128;
+    }
+    let shift = 128 - bits;
+    ((v << shift) as i128) >> shift
+}
+
+fn int_value(v: u128, bits: u32, encoding: u32) -> Value {
+    let v = v & mask(bits);
+    if encoding & BTF_INT_BOOL != 0 {
+        return Value::Bool(v != 0);
+    }
+    if encoding & BTF_INT_SIGNED != 0 {
+        let v = sign_extend(v, bits);
+        return i64::try_from(v).map_or_else(|_| Value::from(v.to_string()), Value::from);
+    }
+    u64::try_from(v).map_or_else(|_| Value::from(v.to_string()), Value::from)
+}
+
+fn enum_value(v: u128, bits: u32, signed: bool, values: &[(String, i128)]) -> Value {
+    let v = v & mask(bits);
+    let v = if signed {
+        sign_extend(v, bits)
+    } else {
+        v as i128
+    };
+    match values.iter().find(|(_, value)| *value == v) {
+        Some((name, _)) => Value::from(name.clone()),
+        None => i64::try_from(v).map_or_else(|_| Value::from(v.to_string()), Value::from),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Builds little endian BTF from (name, info, size_or_type, extra) types.
+    fn build(strings: &[&str], types: &[(u32, u32, u32, Vec<u32>)]) -> Vec<u8> {
+        let mut type_data = vec![];
+        for (name_off, info, size_or_type, extra) in types {
+            for v in [*name_off, *info, *size_or_type].iter().chain(extra) {
+                type_data.extend(v.to_le_bytes());
+            }
+        }
+        let mut str_data = vec![0u8];
+        for s in strings {
+            str_data.extend(s.as_bytes());
+            str_data.push(0);
+        }
+
+        let mut data = vec![];
+        data.extend(BTF_MAGIC.to_le_bytes());
+        data.extend([1, 0]);
+        for v in [
+            HEADER_LEN as u32,
+            0,
+            type_data.len() as u32,
+            type_data.len() as u32,
+            str_data.len() as u32,
+        ] {
+            data.extend(v.to_le_bytes());
+        }
+        data.extend(type_data);
+        data.extend(str_data);
+        data
+    }
+
+    #[test]
+    fn test_decode_struct() {
+        // Offsets in the string section of the names below.
+        let (int, counters, packets, flags, big, name, ch) = (1, 5, 14, 22, 28, 32, 37);
+        let data = build(
+            &["int", "counters", "packets", "flags", "big", "name", "char"],
+            &[
+                // 1: unsigned int
+                (int, BTF_KIND_INT << 24, 4, vec![32]),
+                // 2: char
+                (ch, BTF_KIND_INT << 24, 1, vec![(BTF_INT_CHAR << 24) | 8]),
+                // 3: char[4]
+                (0, BTF_KIND_ARRAY << 24, 0, vec![2, 1, 4]),
+                // 4: struct counters { unsigned int packets; unsigned int
+                // flags : 3; unsigned int big : 5; char name[4]; }
+                (
+                    counters,
+                    (BTF_KIND_STRUCT << 24) | (1 << 31) | 4,
+                    12,
+                    vec![
+                        packets,
+                        1,
+                        0,
+                        flags,
+                        1,
+                        (3 << 24) | 32,
+                        big,
+                        1,
+                        (5 << 24) | 35,
+                        name,
+                        3,
+                        64,
+                    ],
+                ),
+            ],
+        );
+        let btf = BtfTypes::parse(&data, Endianness::Little).unwrap();
+        let id = btf.type_by_name("counters").unwrap();
+        assert_eq!(btf.size(id), Some(12));
+
+        let mut value = vec![];
+        value.extend(7u32.to_le_bytes());
+        value.extend((5u32 | (9 << 3)).to_le_bytes());
+        value.extend(b"ab\0\0");
+        assert_eq!(
+            btf.decode(id, &value).unwrap(),
+            serde_json::json!({"packets": 7, "flags": 5, "big": 9, "name": "ab"})
+        );
+        assert!(btf.decode(id, &value[..8]).is_none());
+    }
+}
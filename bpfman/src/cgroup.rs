@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Aya can attach cgroup programs but doesn't hand out their links in a form
+// that can be pinned, so the link is created and pinned here instead. Like
+// every other bpfman link, it stays attached for as long as the pin exists,
+// and removing the pin detaches the program from the cgroup.
+
+use std::{
+    ffi::CString,
+    fs::File,
+    mem,
+    os::{
+        fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+};
+
+use aya_obj::generated::bpf_attach_type;
+use log::debug;
+
+use crate::{
+    errors::BpfmanError,
+    sys::{bpf, BPF_LINK_CREATE, BPF_OBJ_PIN},
+    types::CgroupAttachType,
+};
+
+// From include/uapi/linux/magic.h
+const CGROUP2_SUPER_MAGIC: libc::c_long = 0x63677270;
+
+// The anonymous struct of union bpf_attr used by BPF_OBJ_PIN.
+#[repr(C)]
+struct ObjPinAttr {
+    pathname: u64,
+    bpf_fd: u32,
+    file_flags: u32,
+}
+
+// The leading fields of the link_create struct of union bpf_attr, which is
+// all that cgroup links use.
+#[repr(C)]
+struct LinkCreateAttr {
+    prog_fd: u32,
+    target_fd: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+impl From<CgroupAttachType> for bpf_attach_type {
+    fn from(attach_type: CgroupAttachType) -> Self {
+        match attach_type {
+            CgroupAttachType::Ingress => bpf_attach_type::BPF_CGROUP_INET_INGRESS,
+            CgroupAttachType::Egress => bpf_attach_type::BPF_CGROUP_INET_EGRESS,
+            CgroupAttachType::SockCreate => bpf_attach_type::BPF_CGROUP_INET_SOCK_CREATE,
+            CgroupAttachType::SockRelease => bpf_attach_type::BPF_CGROUP_INET_SOCK_RELEASE,
+            CgroupAttachType::PostBind4 => bpf_attach_type::BPF_CGROUP_INET4_POST_BIND,
+            CgroupAttachType::PostBind6 => bpf_attach_type::BPF_CGROUP_INET6_POST_BIND,
+        }
+    }
+}
+
+/// Opens the cgroup v2 directory at `path`.
+pub(crate) fn open(path: &str) -> Result<File, BpfmanError> {
+    let invalid = || BpfmanError::InvalidCgroup {
+        path: path.to_string(),
+    };
+
+    let cgroup = File::open(path).map_err(|_| invalid())?;
+    // SAFETY: statfs is a plain C struct for which all zeroes is valid.
+    let mut stat: libc::statfs = unsafe { mem::zeroed() };
+    // SAFETY: the fd is valid for the duration of the call.
+    if unsafe { libc::fstatfs(cgroup.as_raw_fd(), &mut stat) } < 0
+        || stat.f_type as libc::c_long != CGROUP2_SUPER_MAGIC
+    {
+        return Err(invalid());
+    }
+    Ok(cgroup)
+}
+
+/// Attaches the program to `cgroup` and pins the resulting link at
+/// `link_pin_path`. Other programs already attached at the same hook keep
+/// running alongside it.
+pub(crate) fn attach(
+    prog_fd: BorrowedFd<'_>,
+    cgroup: &File,
+    attach_type: CgroupAttachType,
+    link_pin_path: &Path,
+) -> Result<(), BpfmanError> {
+    let mut attr = LinkCreateAttr {
+        prog_fd: prog_fd.as_raw_fd() as u32,
+        target_fd: cgroup.as_raw_fd() as u32,
+        attach_type: bpf_attach_type::from(attach_type) as u32,
+        flags: 0,
+    };
+    let ret = bpf(BPF_LINK_CREATE, &mut attr).map_err(|e| {
+        BpfmanError::Error(format!("unable to attach to cgroup at {attach_type}: {e}"))
+    })?;
+    // SAFETY: BPF_LINK_CREATE returns a new file descriptor owned by us.
+    let link = unsafe { OwnedFd::from_raw_fd(ret as i32) };
+
+    // The link is released again when `link` is dropped unless the pin holds
+    // on to it.
+    let pathname = CString::new(link_pin_path.as_os_str().as_bytes())
+        .map_err(|e| BpfmanError::Error(format!("invalid link pin path: {e}")))?;
+    let mut attr = ObjPinAttr {
+        pathname: pathname.as_ptr() as u64,
+        bpf_fd: link.as_raw_fd() as u32,
+        file_flags: 0,
+    };
+    bpf(BPF_OBJ_PIN, &mut attr).map_err(|e| {
+        BpfmanError::Error(format!(
+            "unable to pin link at {}: {e}",
+            link_pin_path.display()
+        ))
+    })?;
+
+    debug!("Pinned cgroup link at {}", link_pin_path.display());
+    Ok(())
+}
@@ -1,37 +1,137 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf, str::FromStr, time::Duration};
 
 use aya::programs::XdpFlags;
+use log::LevelFilter;
+use nix::mount::MsFlags;
 use serde::{Deserialize, Serialize};
 
-use crate::errors::ParseError;
+use crate::errors::{BpfmanError, ParseError};
 
-#[derive(Debug, Deserialize, Default, Clone)]
-pub(crate) struct Config {
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Config {
     interfaces: Option<HashMap<String, InterfaceConfig>>,
     #[serde(default)]
     signing: Option<SigningConfig>,
     database: Option<DatabaseConfig>,
+    grpc: Option<GrpcConfig>,
+    metrics: Option<MetricsConfig>,
+    shutdown: Option<ShutdownConfig>,
+    bpffs: Option<BpffsConfig>,
+    verifier: Option<VerifierConfig>,
+    logging: Option<LoggingConfig>,
+    limits: Option<LimitsConfig>,
+    authz: Option<AuthzConfig>,
+    features: Option<FeaturesConfig>,
+    defaults: Option<DefaultsConfig>,
+    permissions: Option<PermissionsConfig>,
+    dispatchers: Option<DispatchersConfig>,
 }
 
 impl Config {
-    pub(crate) fn interfaces(&self) -> &Option<HashMap<String, InterfaceConfig>> {
+    pub fn interfaces(&self) -> &Option<HashMap<String, InterfaceConfig>> {
         &self.interfaces
     }
 
-    pub(crate) fn signing(&self) -> &Option<SigningConfig> {
+    pub fn signing(&self) -> &Option<SigningConfig> {
         &self.signing
     }
 
-    pub(crate) fn database(&self) -> &Option<DatabaseConfig> {
+    pub fn database(&self) -> &Option<DatabaseConfig> {
         &self.database
     }
+
+    pub fn grpc(&self) -> &Option<GrpcConfig> {
+        &self.grpc
+    }
+
+    pub fn metrics(&self) -> &Option<MetricsConfig> {
+        &self.metrics
+    }
+
+    pub fn shutdown(&self) -> &Option<ShutdownConfig> {
+        &self.shutdown
+    }
+
+    pub fn bpffs(&self) -> &Option<BpffsConfig> {
+        &self.bpffs
+    }
+
+    pub fn verifier(&self) -> &Option<VerifierConfig> {
+        &self.verifier
+    }
+
+    pub fn logging(&self) -> &Option<LoggingConfig> {
+        &self.logging
+    }
+
+    pub fn limits(&self) -> &Option<LimitsConfig> {
+        &self.limits
+    }
+
+    pub fn authz(&self) -> &Option<AuthzConfig> {
+        &self.authz
+    }
+
+    pub fn features(&self) -> &Option<FeaturesConfig> {
+        &self.features
+    }
+
+    pub fn defaults(&self) -> &Option<DefaultsConfig> {
+        &self.defaults
+    }
+
+    /// Returns `priority`, or the priority set in the [defaults] section for
+    /// XDP and TC programs loaded without one.
+    pub fn priority_or_default(&self, priority: Option<i32>) -> i32 {
+        priority.unwrap_or_else(|| self.defaults.clone().unwrap_or_default().priority)
+    }
+
+    pub fn permissions(&self) -> &Option<PermissionsConfig> {
+        &self.permissions
+    }
+
+    pub fn dispatchers(&self) -> &Option<DispatchersConfig> {
+        &self.dispatchers
+    }
+
+    /// Returns the config with the defaults of each section that has them
+    /// filled in, so that it shows every setting in effect. Sections that
+    /// enable something when present, such as [metrics], stay unset.
+    pub fn resolved(&self) -> Config {
+        Config {
+            interfaces: self.interfaces.clone(),
+            signing: Some(self.signing.clone().unwrap_or_default()),
+            database: Some(self.database.clone().unwrap_or_default()),
+            grpc: Some(self.grpc.clone().unwrap_or_default().resolved()),
+            metrics: self.metrics.clone(),
+            shutdown: Some(self.shutdown.clone().unwrap_or_default()),
+            bpffs: Some(self.bpffs.clone().unwrap_or_default()),
+            verifier: Some(self.verifier.clone().unwrap_or_default()),
+            logging: Some(self.logging.clone().unwrap_or_default()),
+            limits: Some(self.limits.clone().unwrap_or_default()),
+            authz: Some(self.authz.clone().unwrap_or_default()),
+            features: Some(self.features.clone().unwrap_or_default()),
+            defaults: Some(self.defaults.clone().unwrap_or_default()),
+            permissions: Some(self.permissions.clone().unwrap_or_default()),
+            dispatchers: Some(self.dispatchers.clone().unwrap_or_default()),
+        }
+    }
+
+    /// Returns the resolved config as TOML. The TLS key is left out.
+    pub fn to_toml(&self) -> Result<String, BpfmanError> {
+        toml::to_string(&self.resolved())
+            .map_err(|e| BpfmanError::Error(format!("unable to serialize config: {e}")))
+    }
 }
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct SigningConfig {
     pub allow_unsigned: bool,
+    pub require_signed_files: bool,
+    pub trusted_keys: Vec<PathBuf>,
 }
 
 impl Default for SigningConfig {
@@ -39,14 +139,20 @@ impl Default for SigningConfig {
         Self {
             // Allow unsigned programs by default
             allow_unsigned: true,
+            // Load bytecode files whether or not they are signed
+            require_signed_files: false,
+            // Public keys bytecode files may be signed with
+            trusted_keys: vec![],
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 pub struct DatabaseConfig {
     pub max_retries: u32,
     pub millisec_delay: u64,
+    pub dir: PathBuf,
 }
 
 impl Default for DatabaseConfig {
@@ -56,10 +162,486 @@ impl Default for DatabaseConfig {
             max_retries: 10,
             // Number of milli-seconds to wait between failed database attempts
             millisec_delay: 1000,
+            // Directory bpffs is mounted on, or an existing bpffs mount to reuse
+            dir: PathBuf::from("/run/bpfman/fs"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct GrpcConfig {
+    listen: Option<Vec<String>>,
+    unix_socket: Option<PathBuf>,
+    tls: Option<TlsConfig>,
+    command_queue_depth: Option<usize>,
+    // Connections open at once across all listeners.
+    max_connections: Option<usize>,
+    // How long a request may take before it is cancelled, 0 for no limit.
+    // Requests that load programs or pull bytecode are bounded by [limits]
+    // load_timeout_ms instead.
+    request_timeout_secs: Option<u64>,
+    // Idle time before TCP keepalive probes are sent, 0 to disable them.
+    tcp_keepalive_secs: Option<u64>,
+}
+
+const DEFAULT_GRPC_LISTEN: &str = "[::1]:50051";
+pub const DEFAULT_COMMAND_QUEUE_DEPTH: usize = 32;
+const DEFAULT_MAX_CONNECTIONS: usize = 128;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_TCP_KEEPALIVE_SECS: u64 = 60;
+
+impl GrpcConfig {
+    /// Parse each configured listen address into a [`SocketAddr`].
+    ///
+    /// If `listen` isn't set, `[::1]:50051` is used unless a `unix_socket`
+    /// has been configured, in which case no TCP listener is requested.
+    pub fn listen_addrs(&self) -> Result<Vec<SocketAddr>, BpfmanError> {
+        self.listen()
+            .iter()
+            .map(|address| {
+                address
+                    .parse()
+                    .map_err(|error| BpfmanError::InvalidListenAddress {
+                        address: address.to_string(),
+                        error,
+                    })
+            })
+            .collect()
+    }
+
+    fn listen(&self) -> Vec<String> {
+        match (&self.listen, &self.unix_socket) {
+            (Some(listen), _) => listen.clone(),
+            (None, Some(_)) => Vec::new(),
+            (None, None) => vec![DEFAULT_GRPC_LISTEN.to_string()],
+        }
+    }
+
+    fn resolved(self) -> GrpcConfig {
+        GrpcConfig {
+            listen: Some(self.listen()),
+            command_queue_depth: Some(
+                self.command_queue_depth
+                    .unwrap_or(DEFAULT_COMMAND_QUEUE_DEPTH),
+            ),
+            max_connections: Some(self.max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS)),
+            request_timeout_secs: Some(
+                self.request_timeout_secs
+                    .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS),
+            ),
+            tcp_keepalive_secs: Some(
+                self.tcp_keepalive_secs
+                    .unwrap_or(DEFAULT_TCP_KEEPALIVE_SECS),
+            ),
+            ..self
+        }
+    }
+
+    pub fn unix_socket(&self) -> &Option<PathBuf> {
+        &self.unix_socket
+    }
+
+    pub fn tls(&self) -> &Option<TlsConfig> {
+        &self.tls
+    }
+
+    /// Number of requests that are handled at once, 32 if not set. Requests
+    /// beyond that are rejected until one completes.
+    pub fn command_queue_depth(&self) -> Result<usize, BpfmanError> {
+        match self.command_queue_depth {
+            Some(0) => Err(BpfmanError::Error(
+                "grpc command_queue_depth must be at least 1".to_string(),
+            )),
+            Some(depth) => Ok(depth),
+            None => Ok(DEFAULT_COMMAND_QUEUE_DEPTH),
+        }
+    }
+
+    /// Number of connections, across all listeners, that can be open at
+    /// once, 128 if not set. Connections beyond that are closed as soon as
+    /// they are accepted.
+    pub fn max_connections(&self) -> Result<usize, BpfmanError> {
+        match self.max_connections {
+            Some(0) => Err(BpfmanError::Error(
+                "grpc max_connections must be at least 1".to_string(),
+            )),
+            Some(max) => Ok(max),
+            None => Ok(DEFAULT_MAX_CONNECTIONS),
+        }
+    }
+
+    /// How long a request may take before it is cancelled, 30 seconds if not
+    /// set. None when the timeout has been disabled. bpfman-rpc doesn't apply
+    /// it to the requests that load programs or pull bytecode.
+    pub fn request_timeout(&self) -> Option<Duration> {
+        match self
+            .request_timeout_secs
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS)
+        {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// How long a TCP connection may be idle before keepalive probes are sent
+    /// on it, 60 seconds if not set. None when keepalive has been disabled.
+    pub fn tcp_keepalive(&self) -> Option<Duration> {
+        match self
+            .tcp_keepalive_secs
+            .unwrap_or(DEFAULT_TCP_KEEPALIVE_SECS)
+        {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+}
+
+/// TLS settings for the gRPC TCP listeners. When `ca` is set, clients must
+/// present a certificate signed by it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    // Left out of the config returned by GetConfig.
+    #[serde(skip_serializing)]
+    pub key: PathBuf,
+    pub ca: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MetricsConfig {
+    pub listen: String,
+}
+
+impl MetricsConfig {
+    /// Parse the configured listen address into a [`SocketAddr`].
+    pub fn listen_addr(&self) -> Result<SocketAddr, BpfmanError> {
+        self.listen
+            .parse()
+            .map_err(|error| BpfmanError::InvalidListenAddress {
+                address: self.listen.to_string(),
+                error,
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    pub detach_on_exit: bool,
+    pub detach_timeout: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            // Leave programs attached and pinned when bpfman-rpc exits
+            detach_on_exit: false,
+            // Number of seconds to wait for programs to be detached on exit
+            detach_timeout: 10,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BpffsConfig {
+    pub mount_flags: Vec<String>,
+    pub per_interface_dirs: bool,
+}
+
+impl Default for BpffsConfig {
+    fn default() -> Self {
+        Self {
+            // Flags bpffs is mounted with
+            mount_flags: ["nosuid", "nodev", "noexec", "relatime"]
+                .map(String::from)
+                .to_vec(),
+            // Pin XDP and TC programs and dispatchers in a directory per interface
+            per_interface_dirs: false,
+        }
+    }
+}
+
+// The mount options a bpffs can be mounted with, named as mount(8) names them.
+const MOUNT_FLAGS: [(&str, MsFlags); 11] = [
+    ("ro", MsFlags::MS_RDONLY),
+    ("nosuid", MsFlags::MS_NOSUID),
+    ("nodev", MsFlags::MS_NODEV),
+    ("noexec", MsFlags::MS_NOEXEC),
+    ("sync", MsFlags::MS_SYNCHRONOUS),
+    ("dirsync", MsFlags::MS_DIRSYNC),
+    ("noatime", MsFlags::MS_NOATIME),
+    ("nodiratime", MsFlags::MS_NODIRATIME),
+    ("relatime", MsFlags::MS_RELATIME),
+    ("strictatime", MsFlags::MS_STRICTATIME),
+    ("lazytime", MsFlags::MS_LAZYTIME),
+];
+
+impl BpffsConfig {
+    /// Returns the flags bpffs is mounted with, failing on any flag name that
+    /// isn't recognized.
+    pub fn ms_flags(&self) -> Result<MsFlags, BpfmanError> {
+        self.mount_flags
+            .iter()
+            .try_fold(MsFlags::empty(), |flags, name| {
+                MOUNT_FLAGS
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, flag)| flags | *flag)
+                    .ok_or_else(|| BpfmanError::InvalidMountFlag {
+                        flag: name.to_string(),
+                        valid: MOUNT_FLAGS.map(|(n, _)| n).join(", "),
+                    })
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VerifierConfig {
+    pub max_log_lines: usize,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        Self {
+            // Number of lines of the verifier log returned when a load is rejected
+            max_log_lines: 100,
+        }
+    }
+}
+
+const DEFAULT_MAX_BYTECODE_BYTES: u64 = 16 * 1024 * 1024;
+const DEFAULT_VERIFIER_LOG_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub load_timeout_ms: u64,
+    pub max_programs: u32,
+    pub max_programs_per_iface: u32,
+    pub max_bytecode_bytes: u64,
+    pub load_retries: u32,
+    pub load_retry_backoff_ms: u64,
+    pub verifier_log_bytes: u64,
+    pub idempotency_ttl_secs: u64,
+    pub memlock_bytes: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            // Milliseconds a program load may take before it fails, 0 for no limit
+            load_timeout_ms: 60_000,
+            // Programs bpfman may have loaded at once, 0 for no limit
+            max_programs: 0,
+            // XDP and TC programs bpfman may have loaded on one interface, 0 for no limit
+            max_programs_per_iface: 0,
+            // Bytes of bytecode read from a file for a program, 0 for no limit
+            max_bytecode_bytes: DEFAULT_MAX_BYTECODE_BYTES,
+            // Times a load that failed with a transient kernel error is tried again
+            load_retries: 0,
+            // Milliseconds to wait before the first retry, doubled for each one after it
+            load_retry_backoff_ms: 100,
+            // Bytes of the verifier log kept for a rejected program, 0 for no limit
+            verifier_log_bytes: DEFAULT_VERIFIER_LOG_BYTES,
+            // Seconds a load's idempotency key is remembered for, 0 to keep it
+            // for as long as its programs are loaded
+            idempotency_ttl_secs: 3600,
+            // RLIMIT_MEMLOCK set at startup, 0 for no limit
+            memlock_bytes: 0,
         }
     }
 }
 
+impl LimitsConfig {
+    /// Returns how long a program load may take, if it is limited.
+    pub fn load_timeout(&self) -> Option<Duration> {
+        (self.load_timeout_ms > 0).then(|| Duration::from_millis(self.load_timeout_ms))
+    }
+
+    /// Returns how many programs bpfman may have loaded, if it is limited.
+    pub fn programs_limit(&self) -> Option<u32> {
+        (self.max_programs > 0).then_some(self.max_programs)
+    }
+
+    /// Returns how many programs bpfman may have loaded on one interface, if
+    /// it is limited.
+    pub fn programs_per_iface_limit(&self) -> Option<u32> {
+        (self.max_programs_per_iface > 0).then_some(self.max_programs_per_iface)
+    }
+
+    /// Returns how many bytes of bytecode may be read from a file, if it is
+    /// limited.
+    pub fn bytecode_size_limit(&self) -> Option<u64> {
+        (self.max_bytecode_bytes > 0).then_some(self.max_bytecode_bytes)
+    }
+
+    /// Returns how many bytes of the verifier log of a rejected program are
+    /// kept, if it is limited.
+    pub fn verifier_log_limit(&self) -> Option<usize> {
+        (self.verifier_log_bytes > 0).then_some(self.verifier_log_bytes as usize)
+    }
+
+    /// Returns how long the idempotency key of a load is remembered, if it
+    /// expires.
+    pub fn idempotency_ttl(&self) -> Option<Duration> {
+        (self.idempotency_ttl_secs > 0).then(|| Duration::from_secs(self.idempotency_ttl_secs))
+    }
+
+    /// Returns the RLIMIT_MEMLOCK bpfman sets at startup, if it is limited.
+    /// Kernels that account BPF memory to the memory cgroup ignore it.
+    pub fn memlock_limit(&self) -> Option<u64> {
+        (self.memlock_bytes > 0).then_some(self.memlock_bytes)
+    }
+
+    /// Returns how long to wait before trying a load again after the failed
+    /// attempt numbered `attempt`, counting from 0.
+    pub fn load_retry_backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(
+            self.load_retry_backoff_ms
+                .saturating_mul(1u64.checked_shl(attempt).unwrap_or(u64::MAX)),
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct AuthzConfig {
+    pub allowed_uids: Vec<u32>,
+    pub allowed_gids: Vec<u32>,
+}
+
+impl AuthzConfig {
+    /// Returns true if requests that change state are restricted to the
+    /// allowed users and groups.
+    pub fn is_enabled(&self) -> bool {
+        !(self.allowed_uids.is_empty() && self.allowed_gids.is_empty())
+    }
+
+    /// Returns true if a peer running as `uid` with primary group `gid` may
+    /// make requests that change state.
+    pub fn allows(&self, uid: u32, gid: u32) -> bool {
+        !self.is_enabled() || self.allowed_uids.contains(&uid) || self.allowed_gids.contains(&gid)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct FeaturesConfig {
+    pub auto_reattach: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DefaultsConfig {
+    pub priority: i32,
+}
+
+impl Default for DefaultsConfig {
+    fn default() -> Self {
+        Self {
+            // Priority of XDP and TC programs loaded without one, so that they
+            // run after every program given a priority
+            priority: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DispatchersConfig {
+    pub xdp_image: String,
+    pub tc_image: String,
+    pub xdp_path: Option<PathBuf>,
+    pub tc_path: Option<PathBuf>,
+}
+
+impl Default for DispatchersConfig {
+    fn default() -> Self {
+        Self {
+            // Images the dispatchers are pulled from
+            xdp_image: "quay.io/bpfman/xdp-dispatcher:v2".to_string(),
+            tc_image: "quay.io/bpfman/tc-dispatcher:v1".to_string(),
+            // Local dispatcher objects, used in place of the images when set
+            xdp_path: None,
+            tc_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PermissionsConfig {
+    socket_mode: u32,
+    pin_dir_mode: u32,
+}
+
+impl Default for PermissionsConfig {
+    fn default() -> Self {
+        Self {
+            // Mode of the bpfman-rpc Unix socket
+            socket_mode: 0o0660,
+            // Mode of the directories maps are pinned in, which are made
+            // accessible to members of the bpfman group
+            pin_dir_mode: 0o0660,
+        }
+    }
+}
+
+impl PermissionsConfig {
+    /// The mode to set on the bpfman-rpc Unix socket.
+    pub fn socket_mode(&self) -> Result<u32, BpfmanError> {
+        check_mode("socket_mode", self.socket_mode)
+    }
+
+    /// The mode to set on the directories maps are pinned in.
+    pub fn pin_dir_mode(&self) -> Result<u32, BpfmanError> {
+        check_mode("pin_dir_mode", self.pin_dir_mode)
+    }
+}
+
+// Only the permission bits may be set, and at least one of them must be.
+fn check_mode(option: &str, mode: u32) -> Result<u32, BpfmanError> {
+    if mode == 0 || mode & !0o0777 != 0 {
+        return Err(BpfmanError::InvalidMode {
+            option: option.to_string(),
+            mode,
+        });
+    }
+    Ok(mode)
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct LoggingConfig {
+    level: Option<String>,
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+/// How bpfman-rpc formats messages logged to stderr.
+#[derive(Debug, Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl LoggingConfig {
+    /// Parse the configured log level, if one is set.
+    pub fn level_filter(&self) -> Result<Option<LevelFilter>, BpfmanError> {
+        self.level
+            .as_ref()
+            .map(|level| {
+                LevelFilter::from_str(level).map_err(|_| BpfmanError::InvalidLogLevel {
+                    level: level.to_string(),
+                })
+            })
+            .transpose()
+    }
+}
+
 impl FromStr for Config {
     type Err = ParseError;
 
@@ -68,27 +650,27 @@ impl FromStr for Config {
     }
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
-pub(crate) struct InterfaceConfig {
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct InterfaceConfig {
     xdp_mode: XdpMode,
 }
 
 impl InterfaceConfig {
-    pub(crate) fn xdp_mode(&self) -> &XdpMode {
+    pub fn xdp_mode(&self) -> &XdpMode {
         &self.xdp_mode
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
-pub(crate) enum XdpMode {
+pub enum XdpMode {
     Skb,
     Drv,
     Hw,
 }
 
 impl XdpMode {
-    pub(crate) fn as_flags(&self) -> XdpFlags {
+    pub fn as_flags(&self) -> XdpFlags {
         match self {
             XdpMode::Skb => XdpFlags::SKB_MODE,
             XdpMode::Drv => XdpFlags::DRV_MODE,
@@ -173,4 +755,497 @@ mod test {
             None => panic!("expected interfaces to be present"),
         }
     }
+
+    #[test]
+    fn test_config_grpc_default_listen() {
+        let input = r#"
+        [grpc]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let addrs = config
+            .grpc
+            .expect("expected grpc to be present")
+            .listen_addrs()
+            .expect("error parsing listen addresses");
+        assert_eq!(addrs, vec!["[::1]:50051".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_config_grpc_multiple_listen() {
+        let input = r#"
+        [grpc]
+        listen = ["0.0.0.0:50051", "[fd00::1]:50052"]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let addrs = config
+            .grpc
+            .expect("expected grpc to be present")
+            .listen_addrs()
+            .expect("error parsing listen addresses");
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(addrs[0], "0.0.0.0:50051".parse::<SocketAddr>().unwrap());
+        assert_eq!(addrs[1], "[fd00::1]:50052".parse::<SocketAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_config_grpc_invalid_listen() {
+        let input = r#"
+        [grpc]
+        listen = ["localhost"]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        match config.grpc.unwrap().listen_addrs() {
+            Err(BpfmanError::InvalidListenAddress { address, .. }) => {
+                assert_eq!(address, "localhost")
+            }
+            r => panic!("expected InvalidListenAddress, got {r:?}"),
+        }
+    }
+
+    #[test]
+    fn test_config_grpc_command_queue_depth() {
+        let config: Config = toml::from_str("[grpc]").expect("error parsing toml input");
+        assert_eq!(
+            config.grpc.unwrap().command_queue_depth().unwrap(),
+            DEFAULT_COMMAND_QUEUE_DEPTH
+        );
+
+        let input = r#"
+        [grpc]
+        command_queue_depth = 128
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(config.grpc.unwrap().command_queue_depth().unwrap(), 128);
+
+        let input = r#"
+        [grpc]
+        command_queue_depth = 0
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert!(config.grpc.unwrap().command_queue_depth().is_err());
+    }
+
+    #[test]
+    fn test_config_grpc_connection_limits() {
+        let grpc = GrpcConfig::default();
+        assert_eq!(grpc.max_connections().unwrap(), DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(grpc.request_timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(grpc.tcp_keepalive(), Some(Duration::from_secs(60)));
+
+        let input = r#"
+        [grpc]
+        max_connections = 16
+        request_timeout_secs = 0
+        tcp_keepalive_secs = 15
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let grpc = config.grpc.unwrap();
+        assert_eq!(grpc.max_connections().unwrap(), 16);
+        assert_eq!(grpc.request_timeout(), None);
+        assert_eq!(grpc.tcp_keepalive(), Some(Duration::from_secs(15)));
+
+        let input = r#"
+        [grpc]
+        max_connections = 0
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert!(config.grpc.unwrap().max_connections().is_err());
+    }
+
+    #[test]
+    fn test_config_grpc_tls() {
+        let input = r#"
+        [grpc]
+        listen = ["[::]:50051"]
+          [grpc.tls]
+          cert = "/etc/bpfman/tls/server.crt"
+          key = "/etc/bpfman/tls/server.key"
+          ca = "/etc/bpfman/tls/ca.crt"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let tls = config
+            .grpc
+            .expect("expected grpc to be present")
+            .tls
+            .expect("expected tls to be present");
+        assert_eq!(tls.cert, PathBuf::from("/etc/bpfman/tls/server.crt"));
+        assert_eq!(tls.key, PathBuf::from("/etc/bpfman/tls/server.key"));
+        assert_eq!(tls.ca, Some(PathBuf::from("/etc/bpfman/tls/ca.crt")));
+    }
+
+    #[test]
+    fn test_config_logging() {
+        let input = r#"
+        [logging]
+        level = "debug"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let logging = config.logging.expect("expected logging to be present");
+        assert_eq!(logging.level_filter().unwrap(), Some(LevelFilter::Debug));
+        assert_eq!(logging.format, LogFormat::Text);
+
+        let input = r#"
+        [logging]
+        format = "json"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let logging = config.logging.expect("expected logging to be present");
+        assert_eq!(logging.level_filter().unwrap(), None);
+        assert_eq!(logging.format, LogFormat::Json);
+
+        let input = r#"
+        [logging]
+        level = "loud"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert!(config.logging.unwrap().level_filter().is_err());
+    }
+
+    #[test]
+    fn test_config_grpc_unix_socket_only() {
+        let input = r#"
+        [grpc]
+        unix_socket = "/run/bpfman.sock"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let grpc = config.grpc.expect("expected grpc to be present");
+        assert_eq!(grpc.unix_socket(), &Some(PathBuf::from("/run/bpfman.sock")));
+        assert!(grpc
+            .listen_addrs()
+            .expect("error parsing listen addresses")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_config_metrics() {
+        let input = r#"
+        [metrics]
+        listen = "127.0.0.1:9090"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(
+            config
+                .metrics
+                .expect("expected metrics to be present")
+                .listen_addr()
+                .expect("error parsing listen address"),
+            "127.0.0.1:9090".parse::<SocketAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_config_metrics_disabled_by_default() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        assert!(config.metrics.is_none());
+    }
+
+    #[test]
+    fn test_config_shutdown_defaults() {
+        let input = r#"
+        [shutdown]
+        detach_on_exit = true
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let shutdown = config.shutdown.expect("expected shutdown to be present");
+        assert!(shutdown.detach_on_exit);
+        assert_eq!(shutdown.detach_timeout, 10);
+    }
+
+    #[test]
+    fn test_config_bpffs() {
+        let config: Config = toml::from_str("[bpffs]").expect("error parsing toml input");
+        assert!(
+            !config
+                .bpffs
+                .expect("expected bpffs to be present")
+                .per_interface_dirs
+        );
+
+        let input = r#"
+        [bpffs]
+        per_interface_dirs = true
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert!(
+            config
+                .bpffs
+                .expect("expected bpffs to be present")
+                .per_interface_dirs
+        );
+    }
+
+    #[test]
+    fn test_config_database() {
+        let config: Config = toml::from_str("[database]").expect("error parsing toml input");
+        let database = config.database.expect("expected database to be present");
+        assert_eq!(database.max_retries, 10);
+        assert_eq!(database.dir, PathBuf::from("/run/bpfman/fs"));
+
+        let input = r#"
+        [database]
+        dir = "/sys/fs/bpf/bpfman"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let database = config.database.expect("expected database to be present");
+        assert_eq!(database.dir, PathBuf::from("/sys/fs/bpf/bpfman"));
+        assert_eq!(database.millisec_delay, 1000);
+    }
+
+    #[test]
+    fn test_config_bpffs_mount_flags() {
+        let bpffs = BpffsConfig::default();
+        assert_eq!(
+            bpffs
+                .ms_flags()
+                .expect("expected default flags to be valid"),
+            MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC | MsFlags::MS_RELATIME
+        );
+
+        let input = r#"
+        [bpffs]
+        mount_flags = ["nosuid", "nodev", "noatime"]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let bpffs = config.bpffs.expect("expected bpffs to be present");
+        assert_eq!(
+            bpffs.ms_flags().expect("expected flags to be valid"),
+            MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOATIME
+        );
+
+        let input = r#"
+        [bpffs]
+        mount_flags = ["nosuid", "noexce"]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let bpffs = config.bpffs.expect("expected bpffs to be present");
+        assert!(matches!(
+            bpffs.ms_flags(),
+            Err(BpfmanError::InvalidMountFlag { flag, .. }) if flag == "noexce"
+        ));
+    }
+
+    #[test]
+    fn test_config_verifier() {
+        let config: Config = toml::from_str("[verifier]").expect("error parsing toml input");
+        assert_eq!(
+            config
+                .verifier
+                .expect("expected verifier to be present")
+                .max_log_lines,
+            100
+        );
+
+        let input = r#"
+        [verifier]
+        max_log_lines = 20
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(
+            config
+                .verifier
+                .expect("expected verifier to be present")
+                .max_log_lines,
+            20
+        );
+    }
+
+    #[test]
+    fn test_config_dispatchers() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        let dispatchers = config.dispatchers().clone().unwrap_or_default();
+        assert_eq!(dispatchers.xdp_image, "quay.io/bpfman/xdp-dispatcher:v2");
+        assert_eq!(dispatchers.xdp_path, None);
+
+        let input = r#"
+        [dispatchers]
+        tc_image = "registry.example.com/tc-dispatcher:v1-el8"
+        xdp_path = "/usr/lib/bpfman/xdp_dispatcher.o"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let dispatchers = config
+            .dispatchers
+            .expect("expected dispatchers to be present");
+        assert_eq!(
+            dispatchers.tc_image,
+            "registry.example.com/tc-dispatcher:v1-el8"
+        );
+        assert_eq!(
+            dispatchers.xdp_path,
+            Some(PathBuf::from("/usr/lib/bpfman/xdp_dispatcher.o"))
+        );
+        assert_eq!(dispatchers.xdp_image, "quay.io/bpfman/xdp-dispatcher:v2");
+    }
+
+    #[test]
+    fn test_config_limits() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        let limits = config.limits().clone().unwrap_or_default();
+        assert_eq!(limits.load_timeout(), Some(Duration::from_secs(60)));
+        assert_eq!(limits.programs_limit(), None);
+        assert_eq!(limits.programs_per_iface_limit(), None);
+        assert_eq!(limits.bytecode_size_limit(), Some(16 * 1024 * 1024));
+        assert_eq!(limits.verifier_log_limit(), Some(64 * 1024));
+        assert_eq!(limits.idempotency_ttl(), Some(Duration::from_secs(3600)));
+        assert_eq!(limits.memlock_limit(), None);
+
+        let input = r#"
+        [limits]
+        load_timeout_ms = 1500
+        max_programs = 100
+        max_programs_per_iface = 4
+        max_bytecode_bytes = 1048576
+        load_retries = 3
+        load_retry_backoff_ms = 50
+        memlock_bytes = 67108864
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let limits = config.limits.expect("expected limits to be present");
+        assert_eq!(limits.load_timeout(), Some(Duration::from_millis(1500)));
+        assert_eq!(limits.programs_limit(), Some(100));
+        assert_eq!(limits.programs_per_iface_limit(), Some(4));
+        assert_eq!(limits.bytecode_size_limit(), Some(1048576));
+        assert_eq!(limits.load_retries, 3);
+        assert_eq!(limits.load_retry_backoff(0), Duration::from_millis(50));
+        assert_eq!(limits.load_retry_backoff(2), Duration::from_millis(200));
+        assert_eq!(limits.memlock_limit(), Some(64 * 1024 * 1024));
+
+        let input = r#"
+        [limits]
+        load_timeout_ms = 0
+        max_bytecode_bytes = 0
+        verifier_log_bytes = 0
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let limits = config.limits.expect("expected limits to be present");
+        assert_eq!(limits.load_timeout(), None);
+        assert_eq!(limits.bytecode_size_limit(), None);
+        assert_eq!(limits.verifier_log_limit(), None);
+    }
+
+    #[test]
+    fn test_config_features() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        assert!(!config.features().clone().unwrap_or_default().auto_reattach);
+
+        let input = r#"
+        [features]
+        auto_reattach = true
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let features = config.features.expect("expected features to be present");
+        assert!(features.auto_reattach);
+    }
+
+    #[test]
+    fn test_config_signing() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        let signing = config.signing().clone().unwrap_or_default();
+        assert!(signing.allow_unsigned);
+        assert!(!signing.require_signed_files);
+
+        let input = r#"
+        [signing]
+        require_signed_files = true
+        trusted_keys = ["/etc/bpfman/keys/release.pub"]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let signing = config.signing.expect("expected signing to be present");
+        assert!(signing.allow_unsigned);
+        assert!(signing.require_signed_files);
+        assert_eq!(
+            signing.trusted_keys,
+            vec![PathBuf::from("/etc/bpfman/keys/release.pub")]
+        );
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        assert_eq!(config.defaults().clone().unwrap_or_default().priority, 1000);
+        assert_eq!(config.priority_or_default(None), 1000);
+
+        let input = r#"
+        [defaults]
+        priority = 50
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(config.priority_or_default(None), 50);
+        assert_eq!(config.priority_or_default(Some(5)), 5);
+        let defaults = config.defaults.expect("expected defaults to be present");
+        assert_eq!(defaults.priority, 50);
+    }
+
+    #[test]
+    fn test_config_permissions() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        let permissions = config.permissions().clone().unwrap_or_default();
+        assert_eq!(permissions.socket_mode().unwrap(), 0o660);
+        assert_eq!(permissions.pin_dir_mode().unwrap(), 0o660);
+
+        let input = r#"
+        [permissions]
+        socket_mode = 0o060
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let permissions = config
+            .permissions
+            .expect("expected permissions to be present");
+        assert_eq!(permissions.socket_mode().unwrap(), 0o060);
+        assert_eq!(permissions.pin_dir_mode().unwrap(), 0o660);
+
+        for mode in ["0", "0o1000", "0o4660"] {
+            let input = format!("[permissions]\npin_dir_mode = {mode}");
+            let config: Config = toml::from_str(&input).expect("error parsing toml input");
+            assert!(matches!(
+                config.permissions.unwrap().pin_dir_mode(),
+                Err(BpfmanError::InvalidMode { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_config_authz() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        let authz = config.authz().clone().unwrap_or_default();
+        assert!(!authz.is_enabled());
+        assert!(authz.allows(1000, 1000));
+
+        let input = r#"
+        [authz]
+        allowed_uids = [0]
+        allowed_gids = [995]
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let authz = config.authz.expect("expected authz to be present");
+        assert!(authz.is_enabled());
+        assert!(authz.allows(0, 0));
+        assert!(authz.allows(1000, 995));
+        assert!(!authz.allows(1000, 1000));
+    }
+
+    #[test]
+    fn test_config_to_toml() {
+        let input = r#"
+        [grpc]
+          [grpc.tls]
+          cert = "/etc/bpfman/tls/server.crt"
+          key = "/etc/bpfman/tls/server.key"
+        [verifier]
+        max_log_lines = 20
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let output = config.to_toml().expect("error serializing config");
+        assert!(!output.contains("server.key"), "{output}");
+        assert!(!output.contains("[metrics]"), "{output}");
+        for setting in [
+            "cert = \"/etc/bpfman/tls/server.crt\"",
+            "listen = [\"[::1]:50051\"]",
+            "command_queue_depth = 32",
+            "request_timeout_secs = 30",
+            "max_log_lines = 20",
+            "detach_timeout = 10",
+        ] {
+            assert!(output.contains(setting), "{setting} missing from {output}");
+        }
+    }
 }
@@ -0,0 +1,266 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Compares a desired set of programs with the programs bpfman has loaded, so
+// that a controller can work out what to load and unload without keeping
+// state of its own. Programs are matched up by their type, their name and
+// where they are attached, and compared on the rest of what they were loaded
+// with. Nothing is changed.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    errors::BpfmanError,
+    netns::{netns_id, netns_path},
+    types::{Program, StateChange, StateDiff},
+};
+
+type Fields = BTreeMap<&'static str, Value>;
+
+/// Compares `desired` with `loaded`, which holds the IDs and programs that
+/// are in scope of the comparison.
+pub(crate) fn diff(
+    mut loaded: Vec<(u32, Program)>,
+    desired: &[Program],
+) -> Result<StateDiff, BpfmanError> {
+    loaded.sort_by_key(|(id, _)| *id);
+    let mut loaded = loaded
+        .iter()
+        .map(|(id, p)| Ok((*id, identity(p)?, settings(p)?)))
+        .collect::<Result<Vec<_>, BpfmanError>>()?;
+
+    let mut diff = StateDiff::default();
+    for (index, program) in desired.iter().enumerate() {
+        let identity = identity(program)?;
+        // Each loaded program stands in for one desired program at most, so
+        // desired duplicates beyond those loaded are added.
+        let Some(pos) = loaded.iter().position(|(_, i, _)| *i == identity) else {
+            diff.add.push(index);
+            continue;
+        };
+        let (id, _, current) = loaded.remove(pos);
+        let fields: Vec<String> = settings(program)?
+            .into_iter()
+            .filter(|(name, value)| current.get(name) != Some(value))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if fields.is_empty() {
+            diff.unchanged.push((index, id));
+        } else {
+            diff.change.push(StateChange { index, id, fields });
+        }
+    }
+    diff.remove = loaded.into_iter().map(|(id, _, _)| id).collect();
+
+    Ok(diff)
+}
+
+/// Returns a digest of what `programs` are, where they are attached and what
+/// they are loaded with, which is the same for requests that would load the
+/// same programs.
+pub(crate) fn fingerprint(programs: &[Program]) -> Result<String, BpfmanError> {
+    let fields = programs
+        .iter()
+        .map(|p| Ok(json!([identity(p)?, settings(p)?])))
+        .collect::<Result<Vec<_>, BpfmanError>>()?;
+    Ok(hex::encode(Sha256::digest(
+        Value::Array(fields).to_string(),
+    )))
+}
+
+// What a program is and where it is attached.
+fn identity(program: &Program) -> Result<Fields, BpfmanError> {
+    let data = program.get_data();
+    let mut fields = Fields::from([
+        ("kind", json!(program.kind().to_string())),
+        ("name", json!(data.get_name()?)),
+    ]);
+    match program {
+        Program::Xdp(p) => {
+            fields.insert("iface", json!(p.get_iface()?));
+            fields.insert("netns", netns(program)?);
+        }
+        Program::Tc(p) => {
+            fields.insert("iface", json!(p.get_iface()?));
+            fields.insert("direction", json!(p.get_direction()?.to_string()));
+            fields.insert("netns", netns(program)?);
+        }
+        Program::Tracepoint(p) => {
+            fields.insert("tracepoint", json!(p.get_tracepoint()?));
+        }
+        Program::Kprobe(p) => {
+            fields.insert("fn_name", json!(p.get_fn_name()?));
+            fields.insert("retprobe", json!(p.get_retprobe()?));
+            fields.insert("container_pid", json!(p.get_container_pid()?));
+        }
+        Program::Uprobe(p) => {
+            fields.insert("target", json!(p.get_target()?));
+            fields.insert("fn_name", json!(p.get_fn_name()?));
+            fields.insert("retprobe", json!(p.get_retprobe()?));
+            fields.insert("pid", json!(p.get_pid()?));
+            fields.insert("container_pid", json!(p.get_container_pid()?));
+        }
+        Program::Fentry(p) => {
+            fields.insert("fn_name", json!(p.get_fn_name()?));
+        }
+        Program::Fexit(p) => {
+            fields.insert("fn_name", json!(p.get_fn_name()?));
+        }
+        Program::Cgroup(p) => {
+            fields.insert("path", json!(p.get_path()?));
+            fields.insert("attach_type", json!(p.get_attach_type()?.to_string()));
+        }
+        Program::TailCall(p) => {
+            fields.insert("map_name", json!(p.get_map_name()?));
+            fields.insert("index", json!(p.get_index()?));
+        }
+        Program::Unsupported(_) => (),
+    }
+    Ok(fields)
+}
+
+// The rest of what a program is loaded with, which can differ between the
+// desired and the loaded program.
+fn settings(program: &Program) -> Result<Fields, BpfmanError> {
+    let data = program.get_data();
+    let global_data: BTreeMap<String, String> = data
+        .get_global_data()?
+        .into_iter()
+        .map(|(name, value)| (name, hex::encode(value)))
+        .collect();
+    let metadata: BTreeMap<String, String> = data.get_metadata()?.into_iter().collect();
+    let max_entries: BTreeMap<String, u32> = data.get_map_max_entries()?.into_iter().collect();
+    let sorted = |mut v: Vec<String>| {
+        v.sort();
+        v
+    };
+
+    let mut fields = Fields::from([
+        ("bytecode", json!(data.get_location()?.source())),
+        ("global_data", json!(global_data)),
+        ("metadata", json!(metadata)),
+        ("map_owner_id", json!(data.get_map_owner_id()?)),
+        ("pin_namespace", json!(data.get_pin_namespace()?)),
+        ("shared_maps", json!(sorted(data.get_shared_maps()?))),
+        ("maps_to_pin", json!(sorted(data.get_maps_to_pin()?))),
+        ("maps_to_skip", json!(sorted(data.get_maps_to_skip()?))),
+        ("map_max_entries", json!(max_entries)),
+    ]);
+    match program {
+        Program::Xdp(p) => {
+            fields.insert("priority", json!(p.get_priority()?));
+            fields.insert("proceed_on", json!(p.get_proceed_on()?.to_string()));
+            fields.insert(
+                "mode",
+                json!(p.get_mode_preference()?.map(|m| m.to_string())),
+            );
+            fields.insert("enabled", json!(p.get_enabled()?));
+        }
+        Program::Tc(p) => {
+            fields.insert("priority", json!(p.get_priority()?));
+            fields.insert("proceed_on", json!(p.get_proceed_on()?.to_string()));
+            fields.insert("enabled", json!(p.get_enabled()?));
+        }
+        Program::Kprobe(p) => {
+            fields.insert("offset", json!(p.get_offset()?));
+        }
+        Program::Uprobe(p) => {
+            fields.insert("offset", json!(p.get_offset()?));
+        }
+        _ => (),
+    }
+    Ok(fields)
+}
+
+// Loaded programs have the namespace they were attached in resolved to its
+// inode, while desired programs only have the path they were given.
+fn netns(program: &Program) -> Result<Value, BpfmanError> {
+    if let Some(id) = program.netns_id()? {
+        return Ok(json!(id));
+    }
+    Ok(match program.netns()? {
+        Some(path) => {
+            let path = netns_path(&path.to_string_lossy());
+            // A namespace that can't be found matches no loaded program.
+            netns_id(&path).map_or_else(|_| json!(path.to_string_lossy()), |id| json!(id))
+        }
+        None => Value::Null,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::{Location, ProgramData, XdpProceedOn, XdpProgram};
+
+    fn xdp_program(name: &str, iface: &str, priority: i32) -> Program {
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pass.o".to_string()),
+            name.to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        Program::Xdp(
+            XdpProgram::new(
+                data,
+                priority,
+                iface.to_string(),
+                XdpProceedOn::default(),
+                None,
+            )
+            .expect("unable to create xdp program"),
+        )
+    }
+
+    #[test]
+    fn test_diff() {
+        let loaded = vec![
+            (7, xdp_program("pass", "eth0", 50)),
+            (8, xdp_program("pass", "eth1", 50)),
+            (9, xdp_program("drop", "eth0", 60)),
+        ];
+        let desired = vec![
+            xdp_program("pass", "eth0", 50),
+            xdp_program("pass", "eth1", 40),
+            xdp_program("pass", "eth2", 50),
+        ];
+
+        let diff = diff(loaded, &desired).unwrap();
+        assert_eq!(diff.add, vec![2]);
+        assert_eq!(diff.remove, vec![9]);
+        assert_eq!(
+            diff.change,
+            vec![StateChange {
+                index: 1,
+                id: 8,
+                fields: vec!["priority".to_string()],
+            }]
+        );
+        assert_eq!(diff.unchanged, vec![(0, 7)]);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let programs = [xdp_program("pass", "eth0", 50)];
+        let digest = fingerprint(&programs).unwrap();
+        assert_eq!(
+            fingerprint(&[xdp_program("pass", "eth0", 50)]).unwrap(),
+            digest
+        );
+        assert_ne!(
+            fingerprint(&[xdp_program("pass", "eth0", 40)]).unwrap(),
+            digest
+        );
+        assert_ne!(
+            fingerprint(&[xdp_program("pass", "eth1", 50)]).unwrap(),
+            digest
+        );
+    }
+}
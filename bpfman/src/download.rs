@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Bytecode can be loaded from an http:// or https:// URL, such as a plain
+// artifact server. Like a file, it is read up to the [limits]
+// max_bytecode_bytes setting and kept in the database once loaded. It has to
+// be an ELF object, and has to match the SHA-256 checksum the client gave, if
+// any. With [signing] require_signed_files set, the signature is downloaded
+// from the URL with .sig appended. Each request is bounded by the [limits]
+// load_timeout_ms setting, so that a server that stops responding doesn't
+// hold the load up indefinitely.
+
+use std::time::Duration;
+
+use log::debug;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::{Config, LimitsConfig},
+    errors::BpfmanError,
+    signing,
+    types::BytecodeUrl,
+};
+
+// A signature is base64 encoded and a few hundred bytes at most.
+const MAX_SIGNATURE_BYTES: u64 = 4096;
+
+// How long connecting to the server may take, unless the load timeout is
+// shorter.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// Checks that `url` is an http or https URL, and that its checksum, if it
+/// has one, is a SHA-256 checksum in hex.
+pub(crate) fn check_url(url: &BytecodeUrl) -> Result<(), BpfmanError> {
+    let invalid = |reason: String| BpfmanError::InvalidBytecodeUrl {
+        url: url.url.clone(),
+        reason,
+    };
+    match reqwest::Url::parse(&url.url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => (),
+        Ok(_) => {
+            return Err(invalid(
+                "only http and https URLs are supported".to_string(),
+            ))
+        }
+        Err(e) => return Err(invalid(e.to_string())),
+    }
+    if let Some(sha256) = &url.sha256 {
+        if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid(
+                "the SHA-256 checksum must be 64 hex digits".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Downloads the bytecode at `url` and checks it before it is loaded.
+pub(crate) async fn download(url: &BytecodeUrl, config: &Config) -> Result<Vec<u8>, BpfmanError> {
+    check_url(url)?;
+    let limits = config.limits().clone().unwrap_or_default();
+    let client = client(&url.url, &limits)?;
+
+    let bytecode = get(&client, &url.url, limits.bytecode_size_limit()).await?;
+    if !bytecode.starts_with(ELF_MAGIC) {
+        return Err(BpfmanError::InvalidBytecode(format!(
+            "{} is not an ELF object",
+            url.url
+        )));
+    }
+    if let Some(expected) = &url.sha256 {
+        check_sha256(&url.url, expected, &bytecode)?;
+    }
+
+    let signing = config.signing().clone().unwrap_or_default();
+    if signing.require_signed_files {
+        let sig_url = signing::signature_path(&url.url);
+        let signature = get(&client, &sig_url, Some(MAX_SIGNATURE_BYTES))
+            .await
+            .map_err(|e| BpfmanError::SignatureInvalid {
+                path: url.url.clone(),
+                reason: e.to_string(),
+            })?;
+        signing::verify(&signing, &url.url, &signature, &bytecode)?;
+    }
+    debug!("Downloaded {} bytes from {}", bytecode.len(), url.url);
+    Ok(bytecode)
+}
+
+fn client(url: &str, limits: &LimitsConfig) -> Result<reqwest::Client, BpfmanError> {
+    let timeout = limits.load_timeout();
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(timeout.map_or(CONNECT_TIMEOUT, |t| t.min(CONNECT_TIMEOUT)));
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder
+        .build()
+        .map_err(|e| BpfmanError::BytecodeDownloadError {
+            url: url.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+// Fails with BpfmanError::TooLarge rather than reading more than max_bytes.
+async fn get(
+    client: &reqwest::Client,
+    url: &str,
+    max_bytes: Option<u64>,
+) -> Result<Vec<u8>, BpfmanError> {
+    let failed = |e: reqwest::Error| BpfmanError::BytecodeDownloadError {
+        url: url.to_string(),
+        reason: e.to_string(),
+    };
+    let too_large = |max| BpfmanError::TooLarge {
+        path: url.to_string(),
+        max,
+    };
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(failed)?;
+    // Fail before reading anything when the server says how large it is.
+    if let Some(max) = max_bytes.filter(|max| response.content_length() > Some(*max)) {
+        return Err(too_large(max));
+    }
+    let mut data = vec![];
+    while let Some(chunk) = response.chunk().await.map_err(failed)? {
+        data.extend_from_slice(&chunk);
+        if let Some(max) = max_bytes.filter(|max| data.len() as u64 > *max) {
+            return Err(too_large(max));
+        }
+    }
+    Ok(data)
+}
+
+fn check_sha256(url: &str, expected: &str, bytecode: &[u8]) -> Result<(), BpfmanError> {
+    let actual = hex::encode(Sha256::digest(bytecode));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(BpfmanError::ChecksumMismatch {
+            url: url.to_string(),
+            expected: expected.to_ascii_lowercase(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn url(url: &str, sha256: Option<&str>) -> BytecodeUrl {
+        BytecodeUrl {
+            url: url.to_string(),
+            sha256: sha256.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_check_url() {
+        let sha256 = "ab".repeat(32);
+        assert!(check_url(&url("https://example.com/xdp_pass.o", None)).is_ok());
+        assert!(check_url(&url("http://example.com/xdp_pass.o", Some(&sha256))).is_ok());
+        for invalid in [
+            url("file:///tmp/xdp_pass.o", None),
+            url("example.com/xdp_pass.o", None),
+            url("https://example.com/xdp_pass.o", Some("abcd")),
+            url("https://example.com/xdp_pass.o", Some(&"zz".repeat(32))),
+        ] {
+            assert!(
+                matches!(
+                    check_url(&invalid),
+                    Err(BpfmanError::InvalidBytecodeUrl { .. })
+                ),
+                "{invalid:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_sha256() {
+        // The SHA-256 checksum of an empty object.
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert!(check_sha256("https://example.com/o", empty, b"").is_ok());
+        assert!(check_sha256("https://example.com/o", &empty.to_uppercase(), b"").is_ok());
+        assert!(matches!(
+            check_sha256("https://example.com/o", empty, b"\x7fELF"),
+            Err(BpfmanError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_timeout() {
+        // The connection is accepted by the kernel, but the request is never
+        // answered.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/xdp_pass.o", listener.local_addr().unwrap());
+        let limits = LimitsConfig {
+            load_timeout_ms: 100,
+            ..Default::default()
+        };
+
+        let client = client(&url, &limits).unwrap();
+        let got = tokio::time::timeout(Duration::from_secs(5), get(&client, &url, None))
+            .await
+            .expect("download wasn't timed out");
+        assert!(matches!(
+            got,
+            Err(BpfmanError::BytecodeDownloadError { .. })
+        ));
+    }
+}
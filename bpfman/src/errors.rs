@@ -1,8 +1,14 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
+use std::io::ErrorKind;
+
 use thiserror::Error;
 use tokio::sync::oneshot;
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    Code, Status,
+};
 use url::ParseError as urlParseError;
 
 use crate::oci_utils::ImageError;
@@ -17,6 +23,20 @@ pub enum BpfmanError {
     BpfProgramError(#[from] aya::programs::ProgramError),
     #[error(transparent)]
     BpfLoadError(#[from] aya::BpfError),
+    #[error("Offset {offset} is past the end of {target}, which is {size} bytes")]
+    UprobeOffsetOutOfRange {
+        target: String,
+        offset: u64,
+        size: u64,
+    },
+    #[error("Tracepoint {name} was not found in category {category}")]
+    TracepointNotFound { category: String, name: String },
+    #[error("{path} is not a cgroup v2 directory")]
+    InvalidCgroup { path: String },
+    #[error("Kernel symbol {0} was not found in /proc/kallsyms")]
+    KernelSymbolNotFound(String),
+    #[error("Kernel function {0} was not found in the kernel's BTF")]
+    KernelFunctionNotFound(String),
     #[error("Unable to find a valid program with function name {0}")]
     BpfFunctionNameNotValid(String),
     #[error("No room to attach program. Please remove one and try again.")]
@@ -63,6 +83,303 @@ pub enum BpfmanError {
     BtfError(#[from] aya::BtfError),
     #[error("Failed to acquire database lock, please try again later")]
     DatabaseLockError,
+    #[error("Key is {actual} bytes but the map expects {expected} byte keys")]
+    InvalidMapKeySize { expected: u32, actual: usize },
+    #[error("A {key_type} key is {actual} bytes but the map expects {expected} byte keys")]
+    InvalidTypedMapKeySize {
+        key_type: String,
+        expected: u32,
+        actual: usize,
+    },
+    #[error("{key} is not a valid {key_type} map key")]
+    InvalidMapKey { key_type: String, key: String },
+    #[error("Value is {actual} bytes but the map expects {expected} byte values")]
+    InvalidMapValueSize { expected: u32, actual: usize },
+    #[error("Key not found in map")]
+    MapKeyNotFound,
+    #[error("Map {0} failed: {1}")]
+    MapOperationError(String, #[source] std::io::Error),
+    #[error("Bytecode is not a valid eBPF object: {0}")]
+    InvalidBytecode(String),
+    #[error("Unable to find program {section} in bytecode, available programs: {}", available.join(", "))]
+    SectionNotFound {
+        section: String,
+        available: Vec<String>,
+    },
+    #[error("Loading another program would exceed the {limit} limit of {max}")]
+    LimitExceeded { limit: String, max: u32 },
+    #[error(
+        "Program {name} is already loaded on {iface} from the same bytecode as program {id}, \
+         allow duplicates to load it again"
+    )]
+    DuplicateProgram {
+        name: String,
+        iface: String,
+        id: u32,
+    },
+    #[error("Idempotency key {0} was already used to load other programs")]
+    IdempotencyKeyReused(String),
+    #[error(
+        "A dispatcher has room for {capacity} programs, a capacity of {requested} was requested"
+    )]
+    DispatcherCapacityExceeded { requested: u32, capacity: u32 },
+    #[error(
+        "The dispatcher on {iface} has room for {free} more programs, {requested} were requested"
+    )]
+    DispatcherFull {
+        iface: String,
+        free: u32,
+        requested: u32,
+    },
+    #[error("{path} is larger than the limit of {max} bytes")]
+    TooLarge { path: String, max: u64 },
+    #[error("{url} isn't a valid bytecode URL: {reason}")]
+    InvalidBytecodeUrl { url: String, reason: String },
+    #[error("Unable to download bytecode from {url}: {reason}")]
+    BytecodeDownloadError { url: String, reason: String },
+    #[error("Bytecode downloaded from {url} has SHA-256 checksum {actual}, expected {expected}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Loading program {name} timed out after {timeout_ms}ms")]
+    Timeout { name: String, timeout_ms: u64 },
+    #[error("Program {name} uses CO-RE relocations, but the kernel has no BTF at {path}")]
+    KernelBtfMissing { name: String, path: String },
+    #[error("{program_type} programs are attached using the kernel's BTF, which was not found at {path}")]
+    TrampolineBtfMissing { program_type: String, path: String },
+    #[error("The kernel doesn't support BPF trampolines, which {program_type} programs are attached through")]
+    TrampolineUnsupported { program_type: String },
+    #[error(
+        "Index {index} is out of range for prog array {map_name}, which has {max_entries} entries"
+    )]
+    TailCallIndexOutOfRange {
+        map_name: String,
+        index: u32,
+        max_entries: u32,
+    },
+    #[error("Map {name} can't be shared: {reason}")]
+    InvalidSharedMap { name: String, reason: String },
+    #[error("Interface {iface} doesn't support XDP in {mode} mode: {error}")]
+    XdpModeUnsupported {
+        iface: String,
+        mode: String,
+        error: String,
+    },
+    #[error(
+        "XDP programs on interface {iface} run in {mode} mode, but {requested} mode was requested"
+    )]
+    XdpModeMismatch {
+        iface: String,
+        mode: String,
+        requested: String,
+    },
+    #[error("Map {name} can't be pinned or skipped: {reason}")]
+    InvalidMapPinOverride { name: String, reason: String },
+    #[error("The max_entries of map {name} can't be overridden: {reason}")]
+    InvalidMapMaxEntries { name: String, reason: String },
+    #[error("Program {section} has type {actual} and can't be loaded as a {expected} program")]
+    SectionTypeMismatch {
+        section: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Section {section} doesn't name where to attach the program, attach info is required")]
+    AttachInfoRequired { section: String },
+    #[error("Program {0} does not exist")]
+    ProgramNotFound(u32),
+    #[error("No {selector} was found")]
+    NoMatchingProgram { selector: String },
+    #[error("More than one {selector} was found, pick one of {candidates:?} by ID")]
+    AmbiguousProgram {
+        selector: String,
+        candidates: Vec<u32>,
+    },
+    #[error("Signature of {path} is invalid: {reason}")]
+    SignatureInvalid { path: String, reason: String },
+    #[error("A program name is required unless the bytecode has exactly one {kind} program, found: {found:?}")]
+    ProgramNameRequired { kind: String, found: Vec<String> },
+    #[error("Failed to load program on {iface}, unloaded it from {unloaded} other interface(s): {error}")]
+    GroupLoadError {
+        iface: String,
+        unloaded: usize,
+        #[source]
+        error: Box<BpfmanError>,
+    },
+    #[error("The kernel verifier rejected the program:\n{log}")]
+    VerifierRejected { log: String },
+    #[error("Priority {priority} is out of range, it must be between {min} and {max}")]
+    InvalidPriority { priority: i32, min: i32, max: i32 },
+    #[error("Unable to use network namespace {netns}: {error}")]
+    InvalidNetns { netns: String, error: String },
+    #[error("{address} is not a valid listen address: {error}")]
+    InvalidListenAddress {
+        address: String,
+        #[source]
+        error: std::net::AddrParseError,
+    },
+    #[error("{level} is not a valid log level")]
+    InvalidLogLevel { level: String },
+    #[error("{option} {mode:#o} is not a valid mode, it must be an octal value from 0o1 to 0o777")]
+    InvalidMode { option: String, mode: u32 },
+    #[error("{flag} is not a valid bpffs mount flag, valid flags are: {valid}")]
+    InvalidMountFlag { flag: String, valid: String },
+    #[error("{namespace} is not a valid pin namespace: {reason}")]
+    InvalidPinNamespace { namespace: String, reason: String },
+    #[error("Unable to load config file {path}: {error}")]
+    ConfigLoadError { path: String, error: String },
+    #[error("Not applied, operation {index} of the atomic batch failed")]
+    BatchAborted { index: usize },
+}
+
+// Errors the kernel returns while it's short of memory or busy, for example
+// while a dispatcher is being replaced, which can go away on their own.
+const TRANSIENT_ERRNOS: [i32; 4] = [libc::EAGAIN, libc::EBUSY, libc::EINTR, libc::ENOMEM];
+
+impl BpfmanError {
+    /// Returns the errno of the syscall the error came from, if it came from
+    /// one.
+    pub(crate) fn errno(&self) -> Option<i32> {
+        if let BpfmanError::BpfIOError(e) = self {
+            return e.raw_os_error();
+        }
+        let mut source = std::error::Error::source(self);
+        while let Some(e) = source {
+            if let Some(errno) = e
+                .downcast_ref::<std::io::Error>()
+                .and_then(|e| e.raw_os_error())
+            {
+                return Some(errno);
+            }
+            source = e.source();
+        }
+        None
+    }
+
+    /// Returns true if the error is one a load can succeed after when it's
+    /// tried again.
+    pub(crate) fn is_transient(&self) -> bool {
+        self.errno().is_some_and(|e| TRANSIENT_ERRNOS.contains(&e))
+    }
+
+    /// Returns the gRPC status code for the error, along with a stable name
+    /// for it that clients can match on instead of the message.
+    pub fn code(&self) -> (Code, &'static str) {
+        match self {
+            BpfmanError::InvalidInterface => (Code::InvalidArgument, "INVALID_INTERFACE"),
+            BpfmanError::InvalidAttach(_) => (Code::InvalidArgument, "INVALID_ATTACH"),
+            BpfmanError::AttachInfoRequired { .. } => {
+                (Code::InvalidArgument, "ATTACH_INFO_REQUIRED")
+            }
+            BpfmanError::InvalidCgroup { .. } => (Code::InvalidArgument, "INVALID_CGROUP"),
+            BpfmanError::InvalidNetns { .. } => (Code::InvalidArgument, "INVALID_NETNS"),
+            BpfmanError::InvalidPriority { .. } => (Code::InvalidArgument, "INVALID_PRIORITY"),
+            BpfmanError::UprobeOffsetOutOfRange { .. } => {
+                (Code::InvalidArgument, "UPROBE_OFFSET_OUT_OF_RANGE")
+            }
+            BpfmanError::TailCallIndexOutOfRange { .. } => {
+                (Code::InvalidArgument, "TAIL_CALL_INDEX_OUT_OF_RANGE")
+            }
+            BpfmanError::BpfFunctionNameNotValid(_) | BpfmanError::SectionNotFound { .. } => {
+                (Code::InvalidArgument, "SECTION_NOT_FOUND")
+            }
+            BpfmanError::SectionTypeMismatch { .. } => {
+                (Code::InvalidArgument, "SECTION_TYPE_MISMATCH")
+            }
+            BpfmanError::ProgramNameRequired { .. } => {
+                (Code::InvalidArgument, "PROGRAM_NAME_REQUIRED")
+            }
+            BpfmanError::BytecodeMetaDataMismatch { .. } => {
+                (Code::InvalidArgument, "BYTECODE_METADATA_MISMATCH")
+            }
+            BpfmanError::InvalidBytecode(_) => (Code::InvalidArgument, "INVALID_BYTECODE"),
+            BpfmanError::InvalidBytecodeUrl { .. } => {
+                (Code::InvalidArgument, "INVALID_BYTECODE_URL")
+            }
+            BpfmanError::ChecksumMismatch { .. } => (Code::InvalidArgument, "CHECKSUM_MISMATCH"),
+            BpfmanError::InvalidMapKeySize { .. }
+            | BpfmanError::InvalidTypedMapKeySize { .. }
+            | BpfmanError::InvalidMapValueSize { .. } => {
+                (Code::InvalidArgument, "INVALID_MAP_ENTRY_SIZE")
+            }
+            BpfmanError::InvalidMapKey { .. } => (Code::InvalidArgument, "INVALID_MAP_KEY"),
+            BpfmanError::InvalidSharedMap { .. } => (Code::InvalidArgument, "INVALID_SHARED_MAP"),
+            BpfmanError::InvalidMapPinOverride { .. } => {
+                (Code::InvalidArgument, "INVALID_MAP_PIN_OVERRIDE")
+            }
+            BpfmanError::InvalidMapMaxEntries { .. } => {
+                (Code::InvalidArgument, "INVALID_MAP_MAX_ENTRIES")
+            }
+            BpfmanError::InvalidPinNamespace { .. } => {
+                (Code::InvalidArgument, "INVALID_PIN_NAMESPACE")
+            }
+            BpfmanError::VerifierRejected { .. } => (Code::InvalidArgument, "VERIFIER_REJECTED"),
+            BpfmanError::ProgramNotFound(_) | BpfmanError::NoMatchingProgram { .. } => {
+                (Code::NotFound, "PROGRAM_NOT_FOUND")
+            }
+            BpfmanError::AmbiguousProgram { .. } => (Code::FailedPrecondition, "AMBIGUOUS_PROGRAM"),
+            BpfmanError::DuplicateProgram { .. } => (Code::AlreadyExists, "DUPLICATE_PROGRAM"),
+            BpfmanError::IdempotencyKeyReused(_) => {
+                (Code::FailedPrecondition, "IDEMPOTENCY_KEY_REUSED")
+            }
+            BpfmanError::MapKeyNotFound => (Code::NotFound, "MAP_KEY_NOT_FOUND"),
+            BpfmanError::TracepointNotFound { .. } => (Code::NotFound, "TRACEPOINT_NOT_FOUND"),
+            BpfmanError::KernelSymbolNotFound(_) => (Code::NotFound, "KERNEL_SYMBOL_NOT_FOUND"),
+            BpfmanError::KernelFunctionNotFound(_) => (Code::NotFound, "KERNEL_FUNCTION_NOT_FOUND"),
+            BpfmanError::DispatcherCapacityExceeded { .. } => {
+                (Code::InvalidArgument, "DISPATCHER_CAPACITY_EXCEEDED")
+            }
+            BpfmanError::TooManyPrograms
+            | BpfmanError::LimitExceeded { .. }
+            | BpfmanError::DispatcherFull { .. } => (Code::ResourceExhausted, "LIMIT_EXCEEDED"),
+            BpfmanError::TooLarge { .. } => (Code::ResourceExhausted, "TOO_LARGE"),
+            BpfmanError::Timeout { .. } => (Code::DeadlineExceeded, "TIMEOUT"),
+            BpfmanError::SignatureInvalid { .. } => (Code::PermissionDenied, "SIGNATURE_INVALID"),
+            BpfmanError::BpfIOError(e) if e.kind() == ErrorKind::PermissionDenied => {
+                (Code::PermissionDenied, "PERMISSION_DENIED")
+            }
+            BpfmanError::BpfIOError(e) if e.kind() == ErrorKind::NotFound => {
+                (Code::NotFound, "NOT_FOUND")
+            }
+            BpfmanError::KernelBtfMissing { .. } | BpfmanError::TrampolineBtfMissing { .. } => {
+                (Code::FailedPrecondition, "KERNEL_BTF_MISSING")
+            }
+            BpfmanError::TrampolineUnsupported { .. } => {
+                (Code::FailedPrecondition, "TRAMPOLINE_UNSUPPORTED")
+            }
+            BpfmanError::XdpModeUnsupported { .. } | BpfmanError::XdpModeMismatch { .. } => {
+                (Code::FailedPrecondition, "XDP_MODE_UNSUPPORTED")
+            }
+            BpfmanError::BatchAborted { .. } => (Code::Aborted, "BATCH_ABORTED"),
+            BpfmanError::DatabaseLockError => (Code::Unavailable, "DATABASE_LOCKED"),
+            BpfmanError::BpfBytecodeError(_) | BpfmanError::BytecodeDownloadError { .. } => {
+                (Code::Unavailable, "BYTECODE_UNAVAILABLE")
+            }
+            // The group is reported by the message, the code is the one of the
+            // load that failed.
+            BpfmanError::GroupLoadError { error, .. } => error.code(),
+            _ => (Code::Internal, "INTERNAL"),
+        }
+    }
+}
+
+/// The metadata key the status of a failed request carries the name of its
+/// error under, so that clients can tell errors apart without matching on the
+/// message.
+pub const ERROR_CODE_KEY: &str = "bpfman-error-code";
+
+fn status(code: Code, message: String, name: &'static str) -> Status {
+    let mut metadata = MetadataMap::new();
+    metadata.insert(ERROR_CODE_KEY, MetadataValue::from_static(name));
+    Status::with_metadata(code, message, metadata)
+}
+
+impl From<BpfmanError> for Status {
+    fn from(e: BpfmanError) -> Self {
+        let (code, name) = e.code();
+        status(code, e.to_string(), name)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -73,6 +390,8 @@ pub enum ParseError {
     InvalidProceedOn { proceedon: String },
     #[error("not a valid direction: {direction}")]
     InvalidDirection { direction: String },
+    #[error("not a valid cgroup attach type: {attach_type}")]
+    InvalidCgroupAttachType { attach_type: String },
     #[error("Failed to Parse bytecode location: {0}")]
     BytecodeLocationParseFailure(#[source] urlParseError),
     #[error("Invalid bytecode location: {location}")]
@@ -83,6 +402,75 @@ pub enum ParseError {
     InvalidProbeType { probe: String },
     #[error("Invalid XdpMode: {mode}")]
     InvalidXdpMode { mode: String },
+    #[error("Invalid map value format: {format}, expected raw, u32, u64, btf or btf:<type>")]
+    InvalidMapValueFormat { format: String },
+    #[error(
+        "Invalid map key type: {key_type}, expected ipv4, ipv6, mac, u16, u32, u64, be16 or be32"
+    )]
+    InvalidMapKeyType { key_type: String },
     #[error("Error parsing config file: {0}")]
     ConfigParseError(#[from] toml::de::Error),
 }
+
+impl From<ParseError> for Status {
+    fn from(e: ParseError) -> Self {
+        status(Code::InvalidArgument, e.to_string(), "INVALID_ARGUMENT")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error_code(status: &Status) -> Option<&str> {
+        status
+            .metadata()
+            .get(ERROR_CODE_KEY)
+            .map(|v| v.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_status_from_error() {
+        let status = Status::from(BpfmanError::ProgramNotFound(7));
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "Program 7 does not exist");
+        assert_eq!(error_code(&status), Some("PROGRAM_NOT_FOUND"));
+
+        // A failed group load is reported with the code of the load that
+        // failed.
+        let status = Status::from(BpfmanError::GroupLoadError {
+            iface: "eth0".to_string(),
+            unloaded: 1,
+            error: Box::new(BpfmanError::InvalidInterface),
+        });
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(error_code(&status), Some("INVALID_INTERFACE"));
+
+        let status = Status::from(BpfmanError::Error("unexpected".to_string()));
+        assert_eq!(status.code(), Code::Internal);
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let syscall = |errno| {
+            BpfmanError::BpfProgramError(aya::programs::ProgramError::LoadError {
+                io_error: std::io::Error::from_raw_os_error(errno),
+                verifier_log: aya_obj::VerifierLog::new(String::new()),
+            })
+        };
+        assert!(syscall(libc::ENOMEM).is_transient());
+        assert!(
+            BpfmanError::BpfIOError(std::io::Error::from_raw_os_error(libc::EBUSY)).is_transient()
+        );
+
+        // The verifier rejects programs with EACCES or EINVAL, which won't
+        // change by trying again.
+        assert!(!syscall(libc::EACCES).is_transient());
+        assert!(!syscall(libc::EINVAL).is_transient());
+        assert!(!BpfmanError::SectionNotFound {
+            section: "xdp".to_string(),
+            available: vec![],
+        }
+        .is_transient());
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Changes to programs are announced on a broadcast channel, so that any
+// number of subscribers in the process can follow them as they happen, which
+// makes them only useful to long running users of the library such as
+// bpfman-rpc. Announcing a change never waits on a subscriber: one that falls
+// more than EVENT_QUEUE_DEPTH events behind is told so on its next receive,
+// and has missed the events in between.
+
+use std::{sync::OnceLock, time::SystemTime};
+
+use tokio::sync::broadcast;
+
+use crate::types::{Program, ProgramEvent, ProgramEventKind, ProgramType};
+
+const EVENT_QUEUE_DEPTH: usize = 256;
+
+static EVENTS: OnceLock<broadcast::Sender<ProgramEvent>> = OnceLock::new();
+
+fn sender() -> &'static broadcast::Sender<ProgramEvent> {
+    EVENTS.get_or_init(|| broadcast::channel(EVENT_QUEUE_DEPTH).0)
+}
+
+pub(crate) fn subscribe() -> broadcast::Receiver<ProgramEvent> {
+    sender().subscribe()
+}
+
+/// Returns the event for `program`, which for an unload has to be taken
+/// before the program's state is deleted.
+pub(crate) fn event(kind: ProgramEventKind, program: &Program) -> ProgramEvent {
+    let data = program.get_data();
+    ProgramEvent {
+        kind,
+        id: data.get_id().unwrap_or_default(),
+        name: data.get_name().unwrap_or_default(),
+        program_type: match program {
+            Program::Unsupported(_) => ProgramType::Unspec,
+            _ => program.kind(),
+        },
+        iface: match program {
+            Program::Xdp(_) | Program::Tc(_) => program.if_name().ok(),
+            _ => None,
+        },
+        timestamp: SystemTime::now(),
+    }
+}
+
+pub(crate) fn publish(event: ProgramEvent) {
+    // Sending only fails when nobody is subscribed.
+    let _ = sender().send(event);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::{Location, ProgramData, XdpProceedOn, XdpProgram};
+
+    #[test]
+    fn test_publish() {
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pass.o".to_string()),
+            "pass".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        let program = Program::Xdp(
+            XdpProgram::new(data, 50, "eth0".to_string(), XdpProceedOn::default(), None)
+                .expect("unable to create xdp program"),
+        );
+
+        let mut rx = subscribe();
+        publish(event(ProgramEventKind::Loaded, &program));
+        let received = rx.try_recv().unwrap();
+        assert_eq!(received.kind, ProgramEventKind::Loaded);
+        assert_eq!(received.name, "pass");
+        assert_eq!(received.program_type, ProgramType::Xdp);
+        assert_eq!(received.iface.as_deref(), Some("eth0"));
+
+        // Subscribers that fall behind miss the oldest events.
+        for _ in 0..=EVENT_QUEUE_DEPTH {
+            publish(event(ProgramEventKind::Detached, &program));
+        }
+        assert!(matches!(
+            rx.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(1))
+        ));
+    }
+}
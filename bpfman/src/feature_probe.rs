@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// The kernel is probed the first time a feature is asked about and the result
+// is kept for the life of the process, since none of them change without a
+// reboot.
+
+use std::{path::Path, sync::OnceLock};
+
+use aya::util::KernelVersion;
+use log::{info, warn};
+use object::{Endianness, Object, ObjectSection};
+
+use crate::types::KernelFeatures;
+
+/// Where the kernel exposes its BTF, which aya relocates CO-RE programs
+/// against.
+pub(crate) const KERNEL_BTF_PATH: &str = "/sys/kernel/btf/vmlinux";
+
+// The XDP dispatcher is attached through an XDP link, and the programs behind
+// it are attached as extensions through links of their own. Both are
+// available from 5.9.
+const XDP_DISPATCHER_MIN_KERNEL: (u8, u8) = (5, 9);
+
+// fentry and fexit programs are attached through BPF trampolines, which each
+// architecture gained separately. Other architectures are left to the kernel
+// to refuse.
+const TRAMPOLINE_MIN_KERNEL: Option<(u8, u8)> = if cfg!(target_arch = "x86_64") {
+    Some((5, 5))
+} else if cfg!(any(target_arch = "aarch64", target_arch = "s390x")) {
+    Some((6, 0))
+} else {
+    None
+};
+
+static FEATURES: OnceLock<KernelFeatures> = OnceLock::new();
+
+pub(crate) fn features() -> &'static KernelFeatures {
+    FEATURES.get_or_init(|| {
+        let features = probe();
+        info!("Kernel features: {features:?}");
+        if !features.btf {
+            warn!("{KERNEL_BTF_PATH} was not found, programs using CO-RE can't be loaded");
+        }
+        features
+    })
+}
+
+fn probe() -> KernelFeatures {
+    let version = KernelVersion::current()
+        .map_err(|e| warn!("Unable to read the kernel version: {e}"))
+        .ok();
+    let at_least = |(major, minor): (u8, u8)| {
+        version.is_some_and(|v| v >= KernelVersion::new(major, minor, 0))
+    };
+
+    KernelFeatures {
+        btf: Path::new(KERNEL_BTF_PATH).exists(),
+        bpf_link: aya::features().bpf_perf_link(),
+        xdp_dispatcher: at_least(XDP_DISPATCHER_MIN_KERNEL),
+        trampoline: TRAMPOLINE_MIN_KERNEL.is_none_or(at_least),
+    }
+}
+
+/// Returns true if the object in `bytes` has CO-RE relocations, which need
+/// the kernel's BTF to be resolved.
+pub(crate) fn uses_core(bytes: &[u8]) -> bool {
+    let Ok(file) = object::File::parse(bytes) else {
+        return false;
+    };
+    let Some(data) = file.section_by_name(".BTF.ext").and_then(|s| s.data().ok()) else {
+        return false;
+    };
+
+    // The CO-RE relocations are described at the end of the .BTF.ext header,
+    // which older objects don't have room for. The section they point to
+    // starts with the size of a record, so anything past that is a record.
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match file.endianness() {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    };
+    const HDR_LEN_OFFSET: usize = 4;
+    const CORE_RELO_LEN_OFFSET: usize = 28;
+    match (read_u32(HDR_LEN_OFFSET), read_u32(CORE_RELO_LEN_OFFSET)) {
+        (Some(hdr_len), Some(core_relo_len)) => {
+            hdr_len as usize > CORE_RELO_LEN_OFFSET && core_relo_len > 4
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_uses_core_not_an_object() {
+        assert!(!uses_core(b""));
+        assert!(!uses_core(b"not an elf file"));
+    }
+}
@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Loads with an idempotency key are made one at a time per key, from looking
+// the key up until the programs are loaded, so that a retry that arrives
+// while the first load is still running waits for it and then finds its
+// programs, rather than loading them a second time. Loads with other keys,
+// or without one, aren't held up.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::types::IdempotencyKey;
+
+type KeyLocks = Mutex<HashMap<IdempotencyKey, Weak<AsyncMutex<()>>>>;
+
+static KEY_LOCKS: OnceLock<KeyLocks> = OnceLock::new();
+
+/// Waits until no other load with `key` is in flight, and holds the key until
+/// the returned guard is dropped.
+pub(crate) async fn lock(key: &IdempotencyKey) -> OwnedMutexGuard<()> {
+    let key_lock = {
+        let mut locks = KEY_LOCKS
+            .get_or_init(Default::default)
+            .lock()
+            .expect("idempotency key locks poisoned");
+        // Forget the keys that no load holds or waits for any more.
+        locks.retain(|_, l| l.strong_count() > 0);
+        match locks.get(key).and_then(Weak::upgrade) {
+            Some(l) => l,
+            None => {
+                let l = Arc::new(AsyncMutex::new(()));
+                locks.insert(key.clone(), Arc::downgrade(&l));
+                l
+            }
+        }
+    };
+    key_lock.lock_owned().await
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn key(key: &str) -> IdempotencyKey {
+        IdempotencyKey {
+            owner: "uid 0".to_string(),
+            key: key.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lock() {
+        let held = lock(&key("a")).await;
+        // Other keys aren't held up.
+        drop(lock(&key("b")).await);
+
+        let waiter = tokio::spawn(async { drop(lock(&key("a")).await) });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+        drop(held);
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("load with the same key still waiting")
+            .unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// With [bpffs] per_interface_dirs set, XDP and TC programs are pinned in a
+// directory named after their interface under iface/ in the bpffs, along with
+// the dispatchers on the interface:
+//
+//   iface/<name>/prog_<id>
+//   iface/<name>/xdp/dispatcher_<key>_<revision>/link_<id>
+//   iface/<name>/tc-ingress/dispatcher_<key>_<revision>/link_<id>
+//
+// Maps stay in the maps directory, as they can be shared with programs on
+// other interfaces. The directory a program or dispatcher is pinned in is
+// recorded with it, so that changing the setting doesn't lose track of pins
+// that are already there.
+
+use std::{
+    fs::{self, create_dir_all, read_dir},
+    path::Path,
+};
+
+use log::debug;
+
+use crate::{directories::RTDIR_FS, errors::BpfmanError, utils::set_dir_permissions};
+
+// The same as a pin namespace, as the directory holds the same kind of pins.
+const IFACE_DIR_MODE: u32 = 0o0770;
+
+// Dispatcher directories, named as at the top of the bpffs.
+const DISPATCHER_DIRS: [&str; 3] = ["xdp", "tc-ingress", "tc-egress"];
+
+fn base() -> String {
+    format!("{}/iface", *RTDIR_FS)
+}
+
+/// Returns the directory the programs on `if_name` are pinned in. Interface
+/// names are only unique within a network namespace, so interfaces in other
+/// namespaces have the namespace's ID added.
+pub(crate) fn dir(if_name: &str, netns_id: Option<u64>) -> String {
+    match netns_id {
+        Some(id) => format!("{}/{if_name}@ns{id}", base()),
+        None => format!("{}/{if_name}", base()),
+    }
+}
+
+/// Creates `dir` if it doesn't exist yet.
+pub(crate) fn create(dir: &str) -> Result<(), BpfmanError> {
+    create_dir_all(dir)
+        .map_err(|e| BpfmanError::Error(format!("can't create interface pin dir {dir}: {e}")))?;
+    debug!("bpf set dir permissions for {dir}");
+    set_dir_permissions(dir, IFACE_DIR_MODE)
+}
+
+/// Removes `dir` once the last program and dispatcher pinned in it are gone.
+/// Directories that still have pins in them are left alone.
+pub(crate) fn remove_if_empty(dir: &str) {
+    for sub in DISPATCHER_DIRS {
+        let _ = fs::remove_dir(format!("{dir}/{sub}"));
+    }
+    if fs::remove_dir(dir).is_ok() {
+        debug!("Removed interface pin dir {dir}");
+    }
+}
+
+/// Returns the interface directories in the bpffs.
+pub(crate) fn dirs() -> Vec<String> {
+    match read_dir(Path::new(&base())) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.path().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Returns the dispatcher directories in the interface directories.
+pub(crate) fn dispatcher_dirs() -> Vec<String> {
+    dirs()
+        .iter()
+        .flat_map(|dir| DISPATCHER_DIRS.map(|sub| format!("{dir}/{sub}")))
+        .collect()
+}
@@ -2,56 +2,110 @@
 // Copyright Authors of bpfman
 
 use std::{
-    collections::HashMap,
-    fs::{create_dir_all, remove_dir_all},
+    collections::{HashMap, HashSet},
+    fs::{self, create_dir_all, remove_dir_all},
+    future::Future,
+    os::fd::{AsFd, AsRawFd},
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
 };
 
 use aya::{
     programs::{
         fentry::FEntryLink, fexit::FExitLink, kprobe::KProbeLink, links::FdLink, loaded_programs,
-        trace_point::TracePointLink, uprobe::UProbeLink, FEntry, FExit, KProbe, TracePoint, UProbe,
+        trace_point::TracePointLink, uprobe::UProbeLink, CgroupSkb, CgroupSock, FEntry, FExit,
+        KProbe, ProbeKind, ProgramError, SchedClassifier, TracePoint, UProbe, Xdp,
     },
-    BpfLoader, Btf,
+    BpfError, BpfLoader, Btf,
+};
+use aya_obj::{
+    btf::BtfKind, generated::bpf_link_type, programs::CgroupSockAttachType, ProgramSection,
 };
 use log::{debug, info, warn};
+use object::{Object, ObjectSection, ObjectSymbol, SymbolKind};
+use sha2::{Digest, Sha256};
 use sled::{Config as SledConfig, Db};
-use tokio::time::{sleep, Duration};
+use tokio::{
+    runtime::Handle,
+    sync::broadcast,
+    task::JoinHandle,
+    time::{sleep, Duration},
+};
 use utils::initialize_bpfman;
 
 use crate::{
-    config::Config,
+    btf::BtfTypes,
+    config::{Config, LimitsConfig},
     directories::*,
     errors::BpfmanError,
+    map_watch::EventReader,
+    maps::{sum_counters, BpfMap, ValueDecoder},
     multiprog::{
-        Dispatcher, DispatcherId, DispatcherInfo, TC_DISPATCHER_PREFIX, XDP_DISPATCHER_PREFIX,
+        dispatcher_key, Dispatcher, DispatcherId, DispatcherInfo, TC_DISPATCHER_PREFIX,
+        XDP_DISPATCHER_PREFIX,
     },
+    netns::{in_netns, netns_id, netns_path},
     oci_utils::image_manager::ImageManager,
+    shared_maps::SharedMaps,
     types::{
-        BytecodeImage, Direction, ListFilter,
+        BatchOp, BatchResult, BytecodeImage, BytecodeReport, CgroupAttachType, CgroupProgram,
+        Direction, DispatcherSlots, FentryProgram, FexitProgram, Health, IdempotencyKey,
+        ImagePullPolicy, KernelFeatures, KernelLink, KernelObjects, KernelProgram, KprobeProgram,
+        LinkStatus, ListFilter, Location, MapDump, MapEntry, MapKey, MapValueFormat, MapWatch,
         ProbeType::{self, *},
-        Program, ProgramData, ProgramType, PROGRAM_PREFIX,
+        Program, ProgramCounts, ProgramData, ProgramEvent, ProgramEventKind, ProgramMap,
+        ProgramSelector, ProgramStats, ProgramType, StateDiff, TcProgram, TracepointProgram,
+        XdpProgram, MAX_PRIORITY, MIN_PRIORITY, PROGRAM_PREFIX,
     },
     utils::{
-        bytes_to_string, bytes_to_u32, get_error_msg_from_stderr, get_ifindex, open_config_file,
-        set_dir_permissions, should_map_be_pinned, sled_insert,
+        bytes_to_string, bytes_to_u32, fdinfo_memlock, get_error_msg_from_stderr, get_ifindex,
+        if_indextoname, is_bpffs_mounted, kernel_symbol_exists, log_tail, open_config_file,
+        set_dir_permissions, set_file_permissions, sled_insert, tracepoint_exists,
     },
 };
 
-mod config;
+mod btf;
+mod cgroup;
+pub mod config;
+mod diff;
 mod dispatcher_config;
+mod download;
 pub mod errors;
+mod events;
+mod feature_probe;
+mod idempotency;
+mod iface_dir;
+mod map_watch;
+mod maps;
+mod metrics;
 mod multiprog;
+mod netdev;
+mod netns;
 mod oci_utils;
+mod orphaned_pins;
+mod pin_namespace;
+mod shared_maps;
+mod signing;
+mod state;
 mod static_program;
+mod stats;
+mod sys;
 pub mod types;
 pub mod utils;
+mod validate;
 
-const MAPS_MODE: u32 = 0o0660;
 const MAP_PREFIX: &str = "map_";
 const MAPS_USED_BY_PREFIX: &str = "map_used_by_";
+const DEFAULT_MAP_DUMP_BATCH_SIZE: usize = 256;
+const INLINE_BYTECODE_PREFIX: &str = "inline_";
+const INLINE_BYTECODE_MODE: u32 = 0o0640;
 
 pub(crate) mod directories {
+    use lazy_static::lazy_static;
+
+    use crate::utils::open_config_file;
+
     // The following directories are used by bpfman. They should be created by bpfman service
     // via the bpfman.service settings. They will be manually created in the case where bpfman
     // is not being run as a service.
@@ -68,11 +122,23 @@ pub(crate) mod directories {
     pub(crate) const RTDIR_XDP_DISPATCHER: &str = "/run/bpfman/dispatchers/xdp";
     pub(crate) const RTDIR_TC_INGRESS_DISPATCHER: &str = "/run/bpfman/dispatchers/tc-ingress";
     pub(crate) const RTDIR_TC_EGRESS_DISPATCHER: &str = "/run/bpfman/dispatchers/tc-egress";
-    pub(crate) const RTDIR_FS: &str = "/run/bpfman/fs";
-    pub(crate) const RTDIR_FS_TC_INGRESS: &str = "/run/bpfman/fs/tc-ingress";
-    pub(crate) const RTDIR_FS_TC_EGRESS: &str = "/run/bpfman/fs/tc-egress";
-    pub(crate) const RTDIR_FS_XDP: &str = "/run/bpfman/fs/xdp";
-    pub(crate) const RTDIR_FS_MAPS: &str = "/run/bpfman/fs/maps";
+    // The bpffs mount point defaults to /run/bpfman/fs but can be set with
+    // the [database] dir option, for example to use a mount inherited from
+    // the host when running in a container.
+    lazy_static! {
+        pub(crate) static ref RTDIR_FS: String = open_config_file()
+            .database()
+            .to_owned()
+            .unwrap_or_default()
+            .dir
+            .to_string_lossy()
+            .to_string();
+        pub(crate) static ref RTDIR_FS_TC_INGRESS: String = format!("{}/tc-ingress", *RTDIR_FS);
+        pub(crate) static ref RTDIR_FS_TC_EGRESS: String = format!("{}/tc-egress", *RTDIR_FS);
+        pub(crate) static ref RTDIR_FS_XDP: String = format!("{}/xdp", *RTDIR_FS);
+        pub(crate) static ref RTDIR_FS_MAPS: String = format!("{}/maps", *RTDIR_FS);
+        pub(crate) static ref RTDIR_FS_SHARED_MAPS: String = format!("{}/shared-maps", *RTDIR_FS);
+    }
     pub(crate) const RTDIR_PROGRAMS: &str = "/run/bpfman/programs";
     // The TUF repository is used to store Rekor and Fulcio public keys.
     pub(crate) const RTDIR_TUF: &str = "/run/bpfman/tuf";
@@ -93,54 +159,262 @@ pub(crate) fn get_db_config() -> SledConfig {
     SledConfig::default().temporary(true)
 }
 
-/// Loads an ebpf program.
-pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
-    let (config, root_db) = &setup().await?;
+/// Loads an ebpf program. XDP and TC programs are returned once the dispatcher
+/// they are added to has been rebuilt and attached, with its revision set. If
+/// the load takes longer than the [limits]
+/// load_timeout_ms config option allows, this fails with
+/// [`BpfmanError::Timeout`], and the program is unloaded again as soon as the
+/// load finishes. The load keeps the database open until then, so other calls
+/// wait for it, and fail with [`BpfmanError::DatabaseLockError`] if it takes
+/// longer than the [database] retries allow. Loading more programs than the max_programs or
+/// max_programs_per_iface options allow fails with
+/// [`BpfmanError::LimitExceeded`]. A load that fails with a transient kernel
+/// error, such as ENOMEM or EBUSY, is tried again as many times as the
+/// load_retries option allows, all within the load timeout. An XDP or TC
+/// program that is already loaded on the interface from the same bytecode
+/// fails with [`BpfmanError::DuplicateProgram`], unless
+/// [`ProgramData::set_allow_duplicate`] allows it.
+pub async fn add_program(program: Program) -> Result<Program, BpfmanError> {
+    let (config, root_db) = setup().await?;
+    let name = LoadName::new(program.get_data().get_name()?);
+    let location = program.get_data().get_location()?;
+    let shared = program.get_data().get_shared_maps()?;
+    let namespace = program.get_data().get_pin_namespace()?;
+    let limits = config.limits().clone().unwrap_or_default();
+
+    let retries = limits.clone();
+    let load_name = name.clone();
+    let load = spawn_load(async move {
+        // Every attempt starts from the program as it was passed in, as a
+        // failed attempt removes everything it added.
+        let mut attempt = 0;
+        let result = loop {
+            match add_program_internal(&config, &root_db, program.clone(), &load_name).await {
+                Err(e) if e.is_transient() && attempt < retries.load_retries => {
+                    let backoff = retries.load_retry_backoff(attempt);
+                    warn!(
+                        "Loading program {} failed, retrying in {}ms: {e}",
+                        load_name.get(),
+                        backoff.as_millis()
+                    );
+                    sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+        if result.is_err() {
+            release_inline_bytecode(&root_db, &location);
+            shared_maps::release(&root_db, namespace.as_deref(), &shared);
+        }
+        result
+    });
+    let result = match with_load_timeout(&name, &limits, load).await {
+        Ok(result) => result,
+        Err((e, load)) => {
+            // The load itself can't be interrupted, so clean up after it. It
+            // holds root_db until it finishes, which keeps other calls from
+            // opening the database meanwhile.
+            tokio::spawn(async move {
+                if let Ok(Ok(program)) = load.await {
+                    let id = program.get_data().get_id();
+                    drop(program);
+                    if let Ok(id) = id {
+                        warn!("Removing program {id}, its load finished after timing out");
+                        if let Err(e) = remove_program(id).await {
+                            warn!("Unable to remove program {id}: {e}");
+                        }
+                    }
+                }
+            });
+            Err(e)
+        }
+    };
+    metrics::record_load(&result);
+    result
+}
+
+// Loads run on a thread of their own, since the kernel can spend a long time
+// in the bpf syscall and the thread can't be used for anything else meanwhile.
+fn spawn_load<T: Send + 'static>(load: impl Future<Output = T> + Send + 'static) -> JoinHandle<T> {
+    let handle = Handle::current();
+    tokio::task::spawn_blocking(move || handle.block_on(load))
+}
+
+// The name of a program being loaded. A program loaded from a file without a
+// name only gets one once the load has read its bytecode.
+#[derive(Clone)]
+struct LoadName(Arc<Mutex<String>>);
+
+impl LoadName {
+    fn new(name: String) -> Self {
+        Self(Arc::new(Mutex::new(name)))
+    }
+
+    fn set(&self, name: String) {
+        *self.0.lock().expect("load name lock poisoned") = name;
+    }
+
+    fn get(&self) -> String {
+        self.0.lock().expect("load name lock poisoned").clone()
+    }
+}
+
+// Waits for a load running in load to finish, for as long as the [limits]
+// config allows. On timeout the load is handed back along with the error, so
+// that the caller can clean up once it does finish.
+async fn with_load_timeout<T>(
+    name: &LoadName,
+    limits: &LimitsConfig,
+    mut load: JoinHandle<Result<T, BpfmanError>>,
+) -> Result<Result<T, BpfmanError>, (BpfmanError, JoinHandle<Result<T, BpfmanError>>)> {
+    let joined = match limits.load_timeout() {
+        Some(timeout) => match tokio::time::timeout(timeout, &mut load).await {
+            Ok(joined) => joined,
+            Err(_) => {
+                let name = name.get();
+                warn!(
+                    "Loading program {name} timed out after {}ms",
+                    limits.load_timeout_ms
+                );
+                let e = BpfmanError::Timeout {
+                    name,
+                    timeout_ms: limits.load_timeout_ms,
+                };
+                return Err((e, load));
+            }
+        },
+        None => load.await,
+    };
+    Ok(joined.unwrap_or_else(|e| Err(BpfmanError::Error(format!("program load failed: {e}")))))
+}
+
+async fn add_program_internal(
+    config: &Config,
+    root_db: &Db,
+    mut program: Program,
+    name: &LoadName,
+) -> Result<Program, BpfmanError> {
     let mut image_manager = init_image_manager().await;
+    prepare_program(config, root_db, &mut program, &mut image_manager).await?;
+    let map_owner_id = program.get_data().get_map_owner_id()?;
+
+    let validated = validate_program(&mut program);
+    // Validating the program resolves its name if it was loaded without one.
+    if let Ok(resolved) = program.get_data().get_name() {
+        name.set(resolved);
+    }
+
+    let result = match validated {
+        Err(e) => Err(e),
+        Ok(()) => match program {
+            Program::Xdp(_) | Program::Tc(_) => {
+                validate_priority(program.priority()?)?;
+                add_multi_attach_program(root_db, &mut program, &mut image_manager, config).await
+            }
+            Program::Tracepoint(_)
+            | Program::Kprobe(_)
+            | Program::Uprobe(_)
+            | Program::Fentry(_)
+            | Program::Fexit(_)
+            | Program::Cgroup(_)
+            | Program::TailCall(_) => add_single_attach_program(root_db, &mut program),
+            Program::Unsupported(_) => panic!("Cannot add unsupported program"),
+        },
+    };
+
+    finish_load(config, root_db, program, map_owner_id, result)
+}
+
+// Gets a program ready to be loaded: its interface is resolved, the [limits]
+// are checked, and its bytecode is fetched.
+async fn prepare_program(
+    config: &Config,
+    root_db: &Db,
+    program: &mut Program,
+    image_manager: &mut ImageManager,
+) -> Result<(), BpfmanError> {
+    if let Program::Xdp(_) | Program::Tc(_) = program {
+        resolve_interface(program)?;
+    }
+    check_program_limits(
+        root_db,
+        &config.limits().clone().unwrap_or_default(),
+        program,
+    )?;
+    if let Program::Xdp(_) | Program::Tc(_) = program {
+        check_duplicate(root_db, program)?;
+    }
+
     // This is only required in the add_program api
     program.get_data_mut().load(root_db)?;
 
-    let map_owner_id = program.get_data().get_map_owner_id()?;
+    let namespace = program.get_data().get_pin_namespace()?;
+    if let Some(ref namespace) = namespace {
+        pin_namespace::create(namespace)?;
+    }
+
+    // Programs in a pin namespace are pinned there rather than with their
+    // interface.
+    let per_interface_dirs = config
+        .bpffs()
+        .clone()
+        .unwrap_or_default()
+        .per_interface_dirs;
+    if let (Program::Xdp(_) | Program::Tc(_), None, true) =
+        (&program, &namespace, per_interface_dirs)
+    {
+        let dir = iface_dir::dir(&program.if_name()?, program.netns_id()?);
+        iface_dir::create(&dir)?;
+        program.get_data_mut().set_iface_pin_dir(&dir)?;
+    }
+
     // Set map_pin_path if we're using another program's maps
-    if let Some(map_owner_id) = map_owner_id {
-        let map_pin_path = is_map_owner_id_valid(root_db, map_owner_id)?;
+    if let Some(map_owner_id) = program.get_data().get_map_owner_id()? {
+        let map_pin_path = is_map_owner_id_valid(root_db, map_owner_id, namespace.as_deref())?;
         program.get_data_mut().set_map_pin_path(&map_pin_path)?;
     }
 
     program
         .get_data_mut()
-        .set_program_bytes(root_db, &mut image_manager)
-        .await?;
-
-    let result = match program {
-        Program::Xdp(_) | Program::Tc(_) => {
-            program.set_if_index(get_ifindex(&program.if_name().unwrap())?)?;
-
-            add_multi_attach_program(root_db, &mut program, &mut image_manager, config).await
-        }
-        Program::Tracepoint(_)
-        | Program::Kprobe(_)
-        | Program::Uprobe(_)
-        | Program::Fentry(_)
-        | Program::Fexit(_) => add_single_attach_program(root_db, &mut program),
-        Program::Unsupported(_) => panic!("Cannot add unsupported program"),
-    };
+        .set_program_bytes(root_db, image_manager)
+        .await
+}
 
-    match result {
+// Records a program that was loaded with the ID the kernel gave it, or cleans
+// up after one that failed to load.
+fn finish_load(
+    config: &Config,
+    root_db: &Db,
+    mut program: Program,
+    map_owner_id: Option<u32>,
+    result: Result<u32, BpfmanError>,
+) -> Result<Program, BpfmanError> {
+    match result.map_err(|e| with_verifier_log(e, config)) {
         Ok(id) => {
+            let name = program.get_data().get_name()?;
+            let iface = match program {
+                Program::Xdp(_) | Program::Tc(_) => Some(program.if_name()?),
+                _ => None,
+            };
             info!(
-                "Added {} program with name: {} and id: {id}",
+                program_id = id,
+                program_type:% = program.kind(),
+                name = name.as_str(),
+                iface = iface.as_deref();
+                "Added {} program with name: {name} and id: {id}",
                 program.kind(),
-                program.get_data().get_name()?
             );
 
             // Now that program is successfully loaded, update the id, maps hash table,
             // and allow access to all maps by bpfman group members.
-            save_map(root_db, &mut program, id, map_owner_id)?;
+            save_map(root_db, config, &mut program, id, map_owner_id)?;
+            set_loaded_now(&mut program)?;
 
             // Swap the db tree to be persisted with the unique program ID generated
             // by the kernel.
             program.get_data_mut().swap_tree(root_db, id)?;
+            events::publish(events::event(ProgramEventKind::Loaded, &program));
 
             Ok(program)
         }
@@ -153,156 +427,2817 @@ pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
             }
 
             // Cleanup any program that failed to create. Ignore any delete errors.
+            let iface_pin_dir = program.get_data().get_iface_pin_dir().unwrap_or(None);
             let _ = program.delete(root_db);
+            if let Some(dir) = iface_pin_dir {
+                iface_dir::remove_if_empty(&dir);
+            }
 
             Err(e)
         }
     }
 }
 
-/// Unloads and ebpf program.
-pub async fn remove_program(id: u32) -> Result<(), BpfmanError> {
-    let (config, root_db) = &setup().await?;
+// Interfaces are resolved in the namespace the program is attached in, which
+// is remembered by its inode so the program can still be removed once the
+// namespace is gone. Both the name and the index are stored, whichever of the
+// two the interface was given by.
+fn resolve_interface(program: &mut Program) -> Result<(), BpfmanError> {
+    let netns = program.netns()?.map(|n| netns_path(&n.to_string_lossy()));
+    if let Some(ref n) = netns {
+        program.set_netns(n)?;
+        program.set_netns_id(netns_id(n)?)?;
+    }
+    let iface = program.if_name()?;
+    let (if_name, if_index) = in_netns(netns.as_deref(), || get_ifindex(&iface))?;
+    program.set_if_name(if_name)?;
+    program.set_if_index(if_index)
+}
 
-    info!("Removing program with id: {id}");
-    let prog = match get(root_db, &id) {
-        Some(p) => p,
-        None => {
-            return Err(BpfmanError::Error(format!(
-                "Program {0} does not exist or was not created by bpfman",
-                id,
-            )));
+// Refuses to load another program once the [limits] config caps are reached,
+// so that a misbehaving client can't load programs without bound.
+fn check_program_limits(
+    root_db: &Db,
+    limits: &LimitsConfig,
+    program: &Program,
+) -> Result<(), BpfmanError> {
+    let counts = count_programs(root_db, limits);
+    if let Some(max) = counts.max_programs {
+        if counts.programs >= max {
+            return Err(BpfmanError::LimitExceeded {
+                limit: "max_programs".to_string(),
+                max,
+            });
+        }
+    }
+    if let (Some(max), Ok(iface)) = (counts.max_programs_per_iface, program.if_name()) {
+        if counts.per_iface.get(&iface).is_some_and(|n| *n >= max) {
+            return Err(BpfmanError::LimitExceeded {
+                limit: format!("max_programs_per_iface on {iface}"),
+                max,
+            });
         }
+    }
+    Ok(())
+}
+
+// Refuses to load an XDP or TC program that is already loaded at the same
+// hook, which the dispatcher would run twice, unless duplicates are allowed.
+// An allowed duplicate records the program it duplicates, so that it stands
+// out when programs are listed.
+fn check_duplicate(root_db: &Db, program: &mut Program) -> Result<(), BpfmanError> {
+    let Some(id) = get_programs_iter(root_db)
+        .filter(|(_, p)| program.is_duplicate_of(p).unwrap_or(false))
+        .map(|(id, _)| id)
+        .min()
+    else {
+        return Ok(());
     };
+    if !program.get_data().get_allow_duplicate()? {
+        return Err(BpfmanError::DuplicateProgram {
+            name: program.get_data().get_name()?,
+            iface: program.if_name()?,
+            id,
+        });
+    }
+    program.get_data_mut().set_duplicate_of(id)
+}
 
-    let map_owner_id = prog.get_data().get_map_owner_id()?;
+fn count_programs(root_db: &Db, limits: &LimitsConfig) -> ProgramCounts {
+    let mut counts = ProgramCounts {
+        max_programs: limits.programs_limit(),
+        max_programs_per_iface: limits.programs_per_iface_limit(),
+        ..Default::default()
+    };
+    for (_, p) in get_programs_iter(root_db) {
+        counts.programs += 1;
+        if let Ok(iface) = p.if_name() {
+            *counts.per_iface.entry(iface).or_default() += 1;
+        }
+    }
+    counts
+}
 
-    match prog {
-        Program::Xdp(_) | Program::Tc(_) => {
-            let did = prog
-                .dispatcher_id()?
-                .ok_or(BpfmanError::DispatcherNotRequired)?;
-            let program_type = prog.kind();
-            let if_index = prog.if_index()?;
-            let if_name = prog.if_name().unwrap();
-            let direction = prog.direction()?;
+/// Loads a program into the kernel so that it is checked by the verifier, then
+/// unloads it again. Nothing is attached, pinned or recorded, so this can be
+/// used to validate bytecode before loading it for real. A program rejected by
+/// the verifier fails with [`BpfmanError::VerifierRejected`].
+///
+/// XDP and TC programs are verified as standalone programs rather than as
+/// extensions of a dispatcher.
+pub async fn verify_program(program: Program) -> Result<(), BpfmanError> {
+    let (config, root_db) = setup().await?;
+    let name = LoadName::new(program.get_data().get_name()?);
+    let location = program.get_data().get_location()?;
+    let timeout = config.limits().clone().unwrap_or_default();
+
+    // Nothing is left behind by a dry run that finishes after timing out, since
+    // the program is never pinned and is unloaded when the loader is dropped.
+    let load_name = name.clone();
+    let load = spawn_load(async move {
+        let result = verify_program_internal(&config, &root_db, program, &load_name).await;
+        release_inline_bytecode(&root_db, &location);
+        result
+    });
+    with_load_timeout(&name, &timeout, load)
+        .await
+        .unwrap_or_else(|(e, _)| Err(e))
+}
 
-            prog.delete(root_db)
-                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+async fn verify_program_internal(
+    config: &Config,
+    root_db: &Db,
+    mut program: Program,
+    name: &LoadName,
+) -> Result<(), BpfmanError> {
+    let mut image_manager = init_image_manager().await;
 
-            remove_multi_attach_program(
-                root_db,
-                config,
-                did,
-                program_type,
-                if_index,
-                if_name,
-                direction,
-            )
-            .await?
-        }
-        Program::Tracepoint(_)
-        | Program::Kprobe(_)
-        | Program::Uprobe(_)
-        | Program::Fentry(_)
-        | Program::Fexit(_)
-        | Program::Unsupported(_) => {
-            prog.delete(root_db)
-                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
-        }
+    let namespace = program.get_data().get_pin_namespace()?;
+    let map_pin_path = match program.get_data().get_map_owner_id()? {
+        Some(map_owner_id) => Some(is_map_owner_id_valid(
+            root_db,
+            map_owner_id,
+            namespace.as_deref(),
+        )?),
+        None => None,
+    };
+
+    program
+        .get_data_mut()
+        .set_program_bytes(root_db, &mut image_manager)
+        .await?;
+    validate_program(&mut program)?;
+    name.set(program.get_data().get_name()?);
+    if let Program::Xdp(_) | Program::Tc(_) = program {
+        validate_priority(program.priority()?)?;
     }
 
-    delete_map(root_db, id, map_owner_id)?;
+    load_unattached(&program, map_pin_path.as_deref()).map_err(|e| with_verifier_log(e, config))?;
 
+    info!(
+        "{} program with name: {} passed the verifier",
+        program.kind(),
+        program.get_data().get_name()?
+    );
     Ok(())
 }
 
-/// Lists the currently loaded ebpf programs.
-pub async fn list_programs(filter: ListFilter) -> Result<Vec<Program>, BpfmanError> {
-    let (_, root_db) = &setup().await?;
+// Loads the program without attaching it. Dropping the loader on return
+// unloads the program again, along with any maps it created.
+fn load_unattached(p: &Program, map_pin_path: Option<&Path>) -> Result<(), BpfmanError> {
+    let name = &p.get_data().get_name()?;
+    let mut bpf = BpfLoader::new();
 
-    debug!("BpfManager::list_programs()");
+    let data = &p.get_data().get_global_data()?;
+    for (key, value) in data {
+        bpf.set_global(key, value.as_slice(), true);
+    }
+    let max_entries = &p.get_data().get_map_max_entries()?;
+    for (name, n) in max_entries {
+        bpf.set_max_entries(name, *n);
+    }
+    // Share the map owner's maps, as a real load would.
+    if let Some(map_pin_path) = map_pin_path {
+        bpf.map_pin_path(map_pin_path);
+    }
+    let shared_maps = SharedMaps::prepare(p.get_data())?;
+    if let Some(ref shared) = shared_maps {
+        bpf.map_pin_path(shared.pin_path());
+    }
 
-    // Get an iterator for the bpfman load programs, a hash map indexed by program id.
-    let mut bpfman_progs: HashMap<u32, Program> = get_programs_iter(root_db).collect();
+    let mut loader = bpf
+        .allow_unsupported_maps()
+        .load(&p.get_data().get_program_bytes()?)?;
+    let raw_program = loader
+        .program_mut(name)
+        .ok_or(BpfmanError::BpfFunctionNameNotValid(name.to_owned()))?;
 
-    // Call Aya to get ALL the loaded eBPF programs, and loop through each one.
-    Ok(loaded_programs()
-        .filter_map(|p| p.ok())
-        .map(|prog| {
-            let prog_id = prog.id();
+    match p {
+        Program::Xdp(_) => {
+            let xdp: &mut Xdp = raw_program.try_into()?;
+            xdp.load()?
+        }
+        Program::Tc(_) => {
+            let tc: &mut SchedClassifier = raw_program.try_into()?;
+            tc.load()?
+        }
+        Program::Tracepoint(_) => {
+            let tracepoint: &mut TracePoint = raw_program.try_into()?;
+            tracepoint.load()?
+        }
+        Program::Kprobe(_) => {
+            let kprobe: &mut KProbe = raw_program.try_into()?;
+            kprobe.load()?
+        }
+        Program::Uprobe(_) => {
+            let uprobe: &mut UProbe = raw_program.try_into()?;
+            uprobe.load()?
+        }
+        Program::Fentry(program) => {
+            let btf = trampoline_btf(&program.get_fn_name()?, "fentry")?;
+            let fentry: &mut FEntry = raw_program.try_into()?;
+            fentry.load(&program.get_fn_name()?, &btf)?
+        }
+        Program::Fexit(program) => {
+            let btf = trampoline_btf(&program.get_fn_name()?, "fexit")?;
+            let fexit: &mut FExit = raw_program.try_into()?;
+            fexit.load(&program.get_fn_name()?, &btf)?
+        }
+        Program::Cgroup(program) => match program.get_attach_type()?.program_type() {
+            ProgramType::CgroupSkb => {
+                let cgroup_skb: &mut CgroupSkb = raw_program.try_into()?;
+                cgroup_skb.load()?
+            }
+            _ => {
+                let cgroup_sock: &mut CgroupSock = raw_program.try_into()?;
+                cgroup_sock.load()?
+            }
+        },
+        Program::TailCall(_) => load_tail_call(raw_program)?,
+        Program::Unsupported(_) => panic!("Cannot verify unsupported program"),
+    };
+    Ok(())
+}
 
-            // If the program was loaded by bpfman (check the hash map), then use it.
-            // Otherwise, convert the data returned from Aya into an Unsupported Program Object.
-            match bpfman_progs.remove(&prog_id) {
-                Some(p) => p.to_owned(),
-                None => {
-                    let db_tree = root_db
-                        .open_tree(prog_id.to_string())
-                        .expect("Unable to open program database tree for listing programs");
+// Tail calls only reach programs of the caller's type, and programs of these
+// types can be loaded without knowing where their caller is attached.
+fn load_tail_call(raw_program: &mut aya::programs::Program) -> Result<(), BpfmanError> {
+    match raw_program {
+        aya::programs::Program::Xdp(p) => p.load()?,
+        aya::programs::Program::SchedClassifier(p) => p.load()?,
+        aya::programs::Program::TracePoint(p) => p.load()?,
+        aya::programs::Program::KProbe(p) => p.load()?,
+        aya::programs::Program::UProbe(p) => p.load()?,
+        aya::programs::Program::CgroupSkb(p) => p.load()?,
+        _ => {
+            return Err(BpfmanError::Error(
+                "programs of this type can't be tail called".to_string(),
+            ))
+        }
+    };
+    Ok(())
+}
 
-                    let mut data = ProgramData::new_empty(db_tree);
-                    if let Err(e) = data.set_kernel_info(&prog) {
-                        warn!("Unable to set kernal info for prog {prog_id}, error: {e}");
-                    };
+fn pin_tail_call(raw_program: &mut aya::programs::Program, path: &str) -> Result<(), BpfmanError> {
+    match raw_program {
+        aya::programs::Program::Xdp(p) => p.pin(path),
+        aya::programs::Program::SchedClassifier(p) => p.pin(path),
+        aya::programs::Program::TracePoint(p) => p.pin(path),
+        aya::programs::Program::KProbe(p) => p.pin(path),
+        aya::programs::Program::UProbe(p) => p.pin(path),
+        aya::programs::Program::CgroupSkb(p) => p.pin(path),
+        _ => unreachable!("only programs loaded by load_tail_call are pinned"),
+    }
+    .map_err(BpfmanError::UnableToPinProgram)
+}
 
-                    Program::Unsupported(data)
-                }
-            }
-        })
-        .filter(|p| filter.matches(p))
-        .collect())
+// Turns a failed BPF_PROG_LOAD into a VerifierRejected error carrying the end
+// of the verifier log, which is where the kernel explains the rejection. Only
+// a failed load is given a log buffer, aya loads a program without one first.
+fn with_verifier_log(e: BpfmanError, config: &Config) -> BpfmanError {
+    // The verifier didn't reject a load that failed for lack of memory or
+    // because the kernel was busy, and the load may be tried again.
+    if e.is_transient() {
+        return e;
+    }
+    let log = match &e {
+        BpfmanError::BpfProgramError(ProgramError::LoadError { verifier_log, .. })
+        | BpfmanError::BpfLoadError(BpfError::ProgramError(ProgramError::LoadError {
+            verifier_log,
+            ..
+        })) => verifier_log.to_string(),
+        _ => return e,
+    };
+
+    let max_lines = config
+        .verifier()
+        .to_owned()
+        .unwrap_or_default()
+        .max_log_lines;
+    let max_bytes = config
+        .limits()
+        .clone()
+        .unwrap_or_default()
+        .verifier_log_limit();
+    BpfmanError::VerifierRejected {
+        log: log_tail(&log, max_lines, max_bytes),
+    }
 }
 
-/// Fetches more information regarding a currently loaded ebpf program.
-pub async fn get_program(id: u32) -> Result<Program, BpfmanError> {
-    let (_, root_db) = &setup().await?;
+// Checks that the bytecode is a valid eBPF object containing the requested
+// program, and that the program's section matches the program type. This
+// catches mistakes before the kernel is involved, where they would otherwise
+// surface as an opaque load or attach error. A program loaded without a name
+// is given the name of the only program of its type in the bytecode.
+fn validate_program(program: &mut Program) -> Result<(), BpfmanError> {
+    let bytes = program.get_data().get_program_bytes()?;
+
+    let obj =
+        aya_obj::Object::parse(&bytes).map_err(|e| BpfmanError::InvalidBytecode(e.to_string()))?;
+    if program.get_data().get_name()?.is_empty() {
+        let sections = obj.programs.iter().map(|(name, p)| (name, &p.section));
+        let name = find_program_name(program, sections)?;
+        program.get_data_mut().set_name(&name)?;
+    }
+    let data = program.get_data();
+    let name = data.get_name()?;
+    shared_maps::validate(data, &obj)?;
+    data.check_map_pin_overrides(|name| obj.maps.contains_key(name))?;
+    data.check_map_max_entries(|name| obj.maps.get(name).map(|m| m.map_type()))?;
+    if !feature_probe::features().btf && feature_probe::uses_core(&bytes) {
+        return Err(BpfmanError::KernelBtfMissing {
+            name,
+            path: feature_probe::KERNEL_BTF_PATH.to_string(),
+        });
+    }
 
-    debug!("Getting program with id: {id}");
-    // If the program was loaded by bpfman, then use it.
-    // Otherwise, call Aya to get ALL the loaded eBPF programs, and convert the data
-    // returned from Aya into an Unsupported Program Object.
-    match get(root_db, &id) {
-        Some(p) => Ok(p.to_owned()),
-        None => loaded_programs()
-            .find_map(|p| {
-                let prog = p.ok()?;
-                if prog.id() == id {
-                    let db_tree = root_db
-                        .open_tree(prog.id().to_string())
-                        .expect("Unable to open program database tree for listing programs");
+    if let Program::TailCall(_) = program {
+        if data.get_map_owner_id()?.is_none() {
+            return Err(BpfmanError::Error(
+                "a tail called program must be loaded with the map owner whose prog array it is added to".to_string(),
+            ));
+        }
+    }
 
-                    let mut data = ProgramData::new_empty(db_tree);
-                    data.set_kernel_info(&prog)
-                        .expect("unable to set kernel info");
+    let section = match obj.programs.get(&name) {
+        Some(p) => &p.section,
+        None => {
+            let mut available: Vec<String> = obj.programs.keys().cloned().collect();
+            available.sort();
+            return Err(BpfmanError::SectionNotFound {
+                section: name,
+                available,
+            });
+        }
+    };
 
-                    Some(Program::Unsupported(data))
-                } else {
-                    None
-                }
-            })
-            .ok_or(BpfmanError::Error(format!(
-                "Program {0} does not exist",
-                id
-            ))),
+    let expected = match program {
+        Program::Cgroup(p) => return validate_cgroup_section(&name, section, p),
+        Program::Unsupported(_) => return Ok(()),
+        _ if section_matches(program, section) => return Ok(()),
+        Program::Xdp(_) => "xdp",
+        Program::Tc(_) => "tc",
+        Program::Tracepoint(_) => "tracepoint",
+        Program::Kprobe(_) => "kprobe",
+        Program::Uprobe(_) => "uprobe",
+        Program::Fentry(_) => "fentry",
+        Program::Fexit(_) => "fexit",
+        Program::TailCall(_) => "xdp, classifier, tracepoint, kprobe, uprobe or cgroup_skb",
+    };
+
+    Err(BpfmanError::SectionTypeMismatch {
+        section: name,
+        expected: expected.to_string(),
+        actual: section_type(section),
+    })
+}
+
+// Returns the name of the only program, out of the programs of an object and
+// their sections, whose section can be loaded as program.
+fn find_program_name<'a>(
+    program: &Program,
+    sections: impl IntoIterator<Item = (&'a String, &'a ProgramSection)>,
+) -> Result<String, BpfmanError> {
+    let mut found: Vec<String> = sections
+        .into_iter()
+        .filter(|(_, section)| section_matches(program, section))
+        .map(|(name, _)| name.clone())
+        .collect();
+    if found.len() == 1 {
+        return Ok(found.remove(0));
+    }
+    found.sort();
+    Err(BpfmanError::ProgramNameRequired {
+        kind: program.kind().to_string(),
+        found,
+    })
+}
+
+fn section_matches(program: &Program, section: &ProgramSection) -> bool {
+    match program {
+        Program::Xdp(_) => matches!(section, ProgramSection::Xdp { .. }),
+        Program::Tc(_) => matches!(section, ProgramSection::SchedClassifier),
+        Program::Tracepoint(_) => matches!(section, ProgramSection::TracePoint),
+        Program::Kprobe(_) => matches!(section, ProgramSection::KProbe | ProgramSection::KRetProbe),
+        Program::Uprobe(_) => matches!(
+            section,
+            ProgramSection::UProbe { .. } | ProgramSection::URetProbe { .. }
+        ),
+        Program::Fentry(_) => matches!(section, ProgramSection::FEntry { .. }),
+        Program::Fexit(_) => matches!(section, ProgramSection::FExit { .. }),
+        Program::Cgroup(p) => validate_cgroup_section("", section, p).is_ok(),
+        Program::TailCall(_) => matches!(
+            section,
+            ProgramSection::Xdp { .. }
+                | ProgramSection::SchedClassifier
+                | ProgramSection::TracePoint
+                | ProgramSection::KProbe
+                | ProgramSection::KRetProbe
+                | ProgramSection::UProbe { .. }
+                | ProgramSection::URetProbe { .. }
+                | ProgramSection::CgroupSkb
+                | ProgramSection::CgroupSkbIngress
+                | ProgramSection::CgroupSkbEgress
+        ),
+        Program::Unsupported(_) => true,
     }
 }
 
-/// Pulls an ebpf bytecode image from a remote OCI container registry.
-pub async fn pull_bytecode(image: BytecodeImage) -> anyhow::Result<()> {
+// cgroup_skb programs can be attached at either hook, but the hook of a
+// cgroup_sock program is fixed by its section when it is loaded.
+fn validate_cgroup_section(
+    name: &str,
+    section: &ProgramSection,
+    program: &CgroupProgram,
+) -> Result<(), BpfmanError> {
+    let attach_type = program.get_attach_type()?;
+    let expected = match attach_type {
+        CgroupAttachType::Ingress | CgroupAttachType::Egress => {
+            if matches!(
+                section,
+                ProgramSection::CgroupSkb
+                    | ProgramSection::CgroupSkbIngress
+                    | ProgramSection::CgroupSkbEgress
+            ) {
+                return Ok(());
+            }
+            "cgroup_skb".to_string()
+        }
+        _ => {
+            if let ProgramSection::CgroupSock {
+                attach_type: section_attach_type,
+            } = section
+            {
+                let section_attach_type = match section_attach_type {
+                    CgroupSockAttachType::SockCreate => CgroupAttachType::SockCreate,
+                    CgroupSockAttachType::SockRelease => CgroupAttachType::SockRelease,
+                    CgroupSockAttachType::PostBind4 => CgroupAttachType::PostBind4,
+                    CgroupSockAttachType::PostBind6 => CgroupAttachType::PostBind6,
+                };
+                if section_attach_type == attach_type {
+                    return Ok(());
+                }
+                return Err(BpfmanError::SectionTypeMismatch {
+                    section: name.to_string(),
+                    expected: format!("cgroup/{attach_type}"),
+                    actual: format!("cgroup/{section_attach_type}"),
+                });
+            }
+            format!("cgroup/{attach_type}")
+        }
+    };
+
+    Err(BpfmanError::SectionTypeMismatch {
+        section: name.to_string(),
+        expected,
+        actual: section_type(section),
+    })
+}
+
+// Returns the ELF section prefix used for the common program sections, or the
+// section variant's name for the rest.
+fn section_type(section: &ProgramSection) -> String {
+    match section {
+        ProgramSection::KProbe => "kprobe".to_string(),
+        ProgramSection::KRetProbe => "kretprobe".to_string(),
+        ProgramSection::UProbe { .. } => "uprobe".to_string(),
+        ProgramSection::URetProbe { .. } => "uretprobe".to_string(),
+        ProgramSection::TracePoint => "tracepoint".to_string(),
+        ProgramSection::Xdp { .. } => "xdp".to_string(),
+        ProgramSection::SchedClassifier => "classifier".to_string(),
+        ProgramSection::FEntry { .. } => "fentry".to_string(),
+        ProgramSection::FExit { .. } => "fexit".to_string(),
+        ProgramSection::CgroupSkb
+        | ProgramSection::CgroupSkbIngress
+        | ProgramSection::CgroupSkbEgress => "cgroup_skb".to_string(),
+        ProgramSection::CgroupSock { .. } => "cgroup".to_string(),
+        s => format!("{s:?}")
+            .split(|c: char| !c.is_alphanumeric())
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Checks the bytecode at `location` without loading it: its programs and
+/// maps are listed, along with anything in it bpfman can't load. Unlike a dry
+/// run, the kernel isn't involved, so files and URLs can be checked without
+/// privileges. Images are pulled into bpfman's image store first, which does
+/// need them.
+pub async fn validate_bytecode(location: &Location) -> Result<BytecodeReport, BpfmanError> {
+    let config = open_config_file();
+    let bytes = match location {
+        Location::File(path) => utils::read(
+            path,
+            config
+                .limits()
+                .clone()
+                .unwrap_or_default()
+                .bytecode_size_limit(),
+        )?,
+        Location::Url(url) => download::download(url, &config).await?,
+        Location::Image(_) => {
+            let (_, root_db) = &setup().await?;
+            let mut image_manager = init_image_manager().await;
+            location
+                .get_program_bytes(root_db, &mut image_manager)
+                .await?
+                .0
+        }
+    };
+    validate_bytes(&bytes)
+}
+
+/// Checks bytecode that has already been read, as [`validate_bytecode`] does.
+pub fn validate_bytes(bytes: &[u8]) -> Result<BytecodeReport, BpfmanError> {
+    validate::report(bytes)
+}
+
+/// Works out how to attach the program `data` names from its ELF section name,
+/// following the libbpf conventions, for programs loaded without attach info.
+/// Only sections that name their attach point can be attached this way:
+/// `kprobe/<function>`, `kretprobe/<function>`, `tracepoint/<category>/<name>`
+/// (or `tp/`), `fentry/<function>` and `fexit/<function>`. A program loaded
+/// without a name is given the name of the only program in the bytecode.
+pub async fn infer_program(mut data: ProgramData) -> Result<Program, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    let location = data.get_location()?;
+
+    let result = async {
+        let mut image_manager = init_image_manager().await;
+        data.set_program_bytes(root_db, &mut image_manager).await?;
+        let bytes = data.get_program_bytes()?;
+
+        let obj = aya_obj::Object::parse(&bytes)
+            .map_err(|e| BpfmanError::InvalidBytecode(e.to_string()))?;
+        if data.get_name()?.is_empty() {
+            let mut found: Vec<String> = obj.programs.keys().cloned().collect();
+            if found.len() != 1 {
+                found.sort();
+                return Err(BpfmanError::ProgramNameRequired {
+                    kind: "any type of".to_string(),
+                    found,
+                });
+            }
+            data.set_name(&found.remove(0))?;
+        }
+        let name = data.get_name()?;
+        let section = elf_section_name(&bytes, &name).ok_or_else(|| {
+            let mut available: Vec<String> = obj.programs.keys().cloned().collect();
+            available.sort();
+            BpfmanError::SectionNotFound {
+                section: name.clone(),
+                available,
+            }
+        })?;
+        program_from_section(data, &section)
+    }
+    .await;
+
+    // The bytecode is only read again if the program is loaded.
+    if result.is_err() {
+        release_inline_bytecode(root_db, &location);
+    }
+    result
+}
+
+// Returns the name of the ELF section the function `name` is in.
+fn elf_section_name(bytes: &[u8], name: &str) -> Option<String> {
+    let file = object::File::parse(bytes).ok()?;
+    let symbol = file
+        .symbols()
+        .find(|s| s.kind() == SymbolKind::Text && s.name() == Ok(name))?;
+    let section = file.section_by_index(symbol.section_index()?).ok()?;
+    section.name().ok().map(|n| n.to_string())
+}
+
+fn program_from_section(data: ProgramData, section: &str) -> Result<Program, BpfmanError> {
+    let (prefix, target) = section.split_once('/').unwrap_or((section, ""));
+    let program = match prefix {
+        "kprobe" | "kretprobe" if !target.is_empty() => Program::Kprobe(KprobeProgram::new(
+            data,
+            target.to_string(),
+            0,
+            prefix == "kretprobe",
+            None,
+        )?),
+        "tracepoint" | "tp" if target.contains('/') => {
+            Program::Tracepoint(TracepointProgram::new(data, target.to_string())?)
+        }
+        "fentry" if !target.is_empty() => {
+            Program::Fentry(FentryProgram::new(data, target.to_string())?)
+        }
+        "fexit" if !target.is_empty() => {
+            Program::Fexit(FexitProgram::new(data, target.to_string())?)
+        }
+        _ => {
+            return Err(BpfmanError::AttachInfoRequired {
+                section: section.to_string(),
+            })
+        }
+    };
+    Ok(program)
+}
+
+/// Loads a group of programs, typically the same program on several
+/// interfaces. The ID of the first program identifies the group, and removing
+/// it removes every program in the group. If a program fails to load, the
+/// programs loaded before it are removed again.
+///
+/// If the programs have an idempotency key, and programs loaded with the
+/// same key are still loaded and their key hasn't expired, those are returned
+/// instead, so a load can be retried without loading the programs twice. A
+/// retry made while the first load is still running waits for it. Reusing a
+/// key with other programs fails with [`BpfmanError::IdempotencyKeyReused`].
+pub async fn add_programs(programs: Vec<Program>) -> Result<Vec<Program>, BpfmanError> {
+    add_group(programs, false).await
+}
+
+/// Loads several programs from the same bytecode object as a group, in the
+/// way [`add_programs`] does. Programs after the first are loaded with the
+/// first program as their map owner, so the object's maps are only created
+/// once, unless they already have a map owner of their own.
+pub async fn add_object_programs(programs: Vec<Program>) -> Result<Vec<Program>, BpfmanError> {
+    add_group(programs, true).await
+}
+
+async fn add_group(
+    mut programs: Vec<Program>,
+    share_maps: bool,
+) -> Result<Vec<Program>, BpfmanError> {
+    let key = programs
+        .first()
+        .map(|p| p.get_data().get_idempotency_key())
+        .transpose()?
+        .flatten();
+    // Held until the programs are loaded, see idempotency.rs.
+    let _in_flight = match &key {
+        Some(key) => Some(idempotency::lock(key).await),
+        None => None,
+    };
+    if let Some(key) = &key {
+        let digest = diff::fingerprint(&programs)?;
+        for program in &mut programs {
+            program.get_data_mut().set_idempotency_digest(&digest)?;
+        }
+        if let Some(loaded) = previous_load(key, &digest, &programs).await? {
+            return Ok(loaded);
+        }
+    }
+
+    if programs.len() <= 1 {
+        let mut loaded = vec![];
+        for program in programs {
+            loaded.push(add_program(program).await?);
+        }
+        return Ok(loaded);
+    }
+
+    // Only the IDs are kept between loads, since a Program holds the
+    // database open.
+    let mut ids: Vec<u32> = vec![];
+    for mut program in programs {
+        let iface = program.if_name().unwrap_or_default();
+        if let Some(&owner) = ids.first().filter(|_| share_maps) {
+            if program.get_data().get_map_owner_id()?.is_none() {
+                program.get_data_mut().set_map_owner_id(owner)?;
+            }
+        }
+        match add_program(program).await {
+            Ok(p) => ids.push(p.get_data().get_id()?),
+            Err(error) => {
+                for id in ids.iter().rev() {
+                    if let Err(e) = remove_program(*id).await {
+                        warn!("Unable to remove program {id} after failed group load: {e}");
+                    }
+                }
+                return Err(BpfmanError::GroupLoadError {
+                    iface,
+                    unloaded: ids.len(),
+                    error: Box::new(error),
+                });
+            }
+        }
+    }
+
+    let (_, root_db) = &setup().await?;
+    let group_id = ids[0];
+    ids.iter()
+        .map(|id| {
+            let mut p = get(root_db, id).ok_or_else(|| {
+                BpfmanError::Error(format!("Program {id} was removed while loading its group"))
+            })?;
+            p.get_data_mut().set_group_id(group_id)?;
+            Ok(p)
+        })
+        .collect()
+}
+
+/// Applies a batch of loads and unloads, rebuilding each dispatcher they touch
+/// once rather than once per program. Every load is prepared and every
+/// unload looked up before any program is attached or removed, and a result
+/// is returned for each operation in the order they were given. Programs
+/// loaded by the batch with the same priority on the same dispatcher run in
+/// no particular order relative to each other.
+///
+/// With `atomic`, the batch is only applied if every operation succeeds:
+/// otherwise the changes it made are undone, and the operations that didn't
+/// fail report [`BpfmanError::BatchAborted`]. Loads in a batch aren't retried
+/// and aren't bound by the [limits] load_timeout_ms config option.
+pub async fn apply_batch(
+    ops: Vec<BatchOp>,
+    atomic: bool,
+) -> Result<Vec<Result<BatchResult, BpfmanError>>, BpfmanError> {
+    let is_load: Vec<bool> = ops
+        .iter()
+        .map(|op| matches!(op, BatchOp::Load(_)))
+        .collect();
+    let (config, root_db) = setup().await?;
+    let results = spawn_load(async move { Batch::new(&config, &root_db, atomic).apply(ops).await })
+        .await
+        .map_err(|e| BpfmanError::Error(format!("batch failed: {e}")))?;
+    for (result, is_load) in results.iter().zip(is_load) {
+        if is_load {
+            metrics::record_load(result);
+        } else {
+            metrics::record_unload(result);
+        }
+    }
+    Ok(results)
+}
+
+// A load in a batch, from when it is prepared until it is recorded as loaded.
+struct BatchLoad {
+    index: usize,
+    program: Program,
+    map_owner_id: Option<u32>,
+    location: Location,
+    shared: Vec<String>,
+    namespace: Option<String>,
+    // The dispatcher XDP and TC programs are added to.
+    did: Option<DispatcherId>,
+}
+
+impl BatchLoad {
+    fn new(index: usize, program: Program) -> Result<Self, BpfmanError> {
+        let data = program.get_data();
+        Ok(Self {
+            index,
+            map_owner_id: None,
+            location: data.get_location()?,
+            shared: data.get_shared_maps()?,
+            namespace: data.get_pin_namespace()?,
+            program,
+            did: None,
+        })
+    }
+
+    // Gives back the inline bytecode and shared maps the load holds on to.
+    fn release(&self, root_db: &Db) {
+        release_inline_bytecode(root_db, &self.location);
+        shared_maps::release(root_db, self.namespace.as_deref(), &self.shared);
+    }
+
+    // Removes everything the load added. Errors are ignored, as the load may
+    // have stopped anywhere between being prepared and being attached.
+    fn discard(self, root_db: &Db) {
+        if let Ok(Some(pin_path)) = self.program.get_data().get_map_pin_path() {
+            let _ = cleanup_map_pin_path(&pin_path, self.map_owner_id);
+        }
+        let _ = self.program.delete(root_db);
+        self.release(root_db);
+    }
+}
+
+// An unload in a batch. Its XDP and TC programs are detached while their
+// dispatchers are rebuilt, and only removed once that succeeds.
+struct BatchUnload {
+    index: usize,
+    ids: Vec<u32>,
+    detached: Vec<Program>,
+}
+
+impl BatchUnload {
+    fn restore(&mut self) {
+        for program in self.detached.iter_mut() {
+            if let Err(e) = set_detached(program, false) {
+                warn!("Unable to reattach program after failed batch: {e}");
+            }
+        }
+    }
+}
+
+struct Batch<'a> {
+    config: &'a Config,
+    root_db: &'a Db,
+    atomic: bool,
+    results: Vec<Option<Result<BatchResult, BpfmanError>>>,
+    // Loads that are still to be attached.
+    loads: Vec<BatchLoad>,
+    unloads: Vec<BatchUnload>,
+    // Programs loaded by the batch that needed no dispatcher.
+    loaded: Vec<u32>,
+}
+
+impl<'a> Batch<'a> {
+    fn new(config: &'a Config, root_db: &'a Db, atomic: bool) -> Self {
+        Self {
+            config,
+            root_db,
+            atomic,
+            results: vec![],
+            loads: vec![],
+            unloads: vec![],
+            loaded: vec![],
+        }
+    }
+
+    async fn apply(mut self, ops: Vec<BatchOp>) -> Vec<Result<BatchResult, BpfmanError>> {
+        self.results = ops.iter().map(|_| None).collect();
+
+        let mut image_manager = init_image_manager().await;
+        for (index, op) in ops.into_iter().enumerate() {
+            match op {
+                BatchOp::Load(program) => self.prepare(index, program, &mut image_manager).await,
+                BatchOp::Unload(id) => self.detach(index, id),
+            }
+        }
+        // Staging once the unloads are detached leaves room on the dispatchers
+        // for the programs that replace them.
+        self.stage();
+
+        if self.atomic && self.failed().is_some() {
+            for load in std::mem::take(&mut self.loads) {
+                load.discard(self.root_db);
+            }
+            for unload in self.unloads.iter_mut() {
+                unload.restore();
+            }
+        } else {
+            self.attach_single();
+            let rebuilt = self.rebuild(&mut image_manager).await;
+            if self.atomic && self.failed().is_some() {
+                self.roll_back(rebuilt, &mut image_manager).await;
+            } else {
+                self.finish().await;
+            }
+        }
+
+        let failed = self.failed();
+        self.results
+            .into_iter()
+            .map(|result| match (result, failed) {
+                (Some(Err(e)), _) => Err(e),
+                (Some(Ok(_)) | None, Some(index)) if self.atomic => {
+                    Err(BpfmanError::BatchAborted { index })
+                }
+                (Some(Ok(result)), _) => Ok(result),
+                (None, _) => Err(BpfmanError::Error("operation wasn't applied".to_string())),
+            })
+            .collect()
+    }
+
+    // Returns the index of the first operation that failed.
+    fn failed(&self) -> Option<usize> {
+        self.results.iter().position(|r| matches!(r, Some(Err(_))))
+    }
+
+    async fn prepare(&mut self, index: usize, program: Program, image_manager: &mut ImageManager) {
+        let mut load = match BatchLoad::new(index, program) {
+            Ok(load) => load,
+            Err(e) => {
+                self.results[index] = Some(Err(e));
+                return;
+            }
+        };
+        match self.prepare_load(&mut load, image_manager).await {
+            Ok(()) => self.loads.push(load),
+            Err(e) => {
+                load.discard(self.root_db);
+                self.results[index] = Some(Err(e));
+            }
+        }
+    }
+
+    async fn prepare_load(
+        &self,
+        load: &mut BatchLoad,
+        image_manager: &mut ImageManager,
+    ) -> Result<(), BpfmanError> {
+        prepare_program(self.config, self.root_db, &mut load.program, image_manager).await?;
+        load.map_owner_id = load.program.get_data().get_map_owner_id()?;
+        validate_program(&mut load.program)?;
+        if let Program::Xdp(_) | Program::Tc(_) = load.program {
+            validate_priority(load.program.priority()?)?;
+        }
+        Ok(())
+    }
+
+    fn detach(&mut self, index: usize, id: u32) {
+        if get(self.root_db, &id).is_none() {
+            self.results[index] = Some(Err(BpfmanError::ProgramNotFound(id)));
+            return;
+        }
+        let mut ids = group_members(self.root_db, id);
+        ids.push(id);
+
+        let mut unload = BatchUnload {
+            index,
+            ids,
+            detached: vec![],
+        };
+        for id in &unload.ids {
+            let Some(mut program) = get(self.root_db, id) else {
+                continue;
+            };
+            if !matches!(program, Program::Xdp(_) | Program::Tc(_)) || program.detached() {
+                continue;
+            }
+            if let Err(e) = set_detached(&mut program, true) {
+                unload.restore();
+                self.results[index] = Some(Err(e));
+                return;
+            }
+            unload.detached.push(program);
+        }
+        self.unloads.push(unload);
+    }
+
+    fn stage(&mut self) {
+        for mut load in std::mem::take(&mut self.loads) {
+            if let Program::Xdp(_) | Program::Tc(_) = load.program {
+                match stage_multi_attach_program(self.root_db, &load.program) {
+                    Ok(did) => load.did = Some(did),
+                    Err(e) => {
+                        self.results[load.index] = Some(Err(e));
+                        load.discard(self.root_db);
+                        continue;
+                    }
+                }
+            }
+            self.loads.push(load);
+        }
+    }
+
+    // Attaches the programs that need no dispatcher.
+    fn attach_single(&mut self) {
+        let (single, staged) = std::mem::take(&mut self.loads)
+            .into_iter()
+            .partition(|l| l.did.is_none());
+        self.loads = staged;
+        for mut load in single {
+            let result = add_single_attach_program(self.root_db, &mut load.program);
+            self.finish_load(load, result);
+        }
+    }
+
+    fn finish_load(&mut self, load: BatchLoad, result: Result<u32, BpfmanError>) {
+        let result = finish_load(
+            self.config,
+            self.root_db,
+            load.program.clone(),
+            load.map_owner_id,
+            result,
+        );
+        match result {
+            Ok(program) => {
+                if load.did.is_none() {
+                    self.loaded.extend(program.get_data().get_id().ok());
+                }
+                self.results[load.index] = Some(Ok(BatchResult::Loaded(program)));
+            }
+            Err(e) => {
+                load.release(self.root_db);
+                self.results[load.index] = Some(Err(e));
+            }
+        }
+    }
+
+    // Rebuilds each dispatcher with a program being added or removed,
+    // returning the ones that were rebuilt.
+    async fn rebuild(&mut self, image_manager: &mut ImageManager) -> Vec<(DispatcherId, String)> {
+        let mut dids: Vec<(DispatcherId, String)> = vec![];
+        let programs = self
+            .loads
+            .iter()
+            .map(|l| &l.program)
+            .chain(self.unloads.iter().flat_map(|u| u.detached.iter()));
+        for program in programs {
+            if let (Ok(Some(did)), Ok(if_name)) = (program.dispatcher_id(), program.if_name()) {
+                if !dids.iter().any(|(d, _)| *d == did) {
+                    dids.push((did, if_name));
+                }
+            }
+        }
+
+        let mut rebuilt = vec![];
+        for (did, if_name) in dids {
+            match refresh_dispatcher(self.root_db, self.config, &did, &if_name, image_manager).await
+            {
+                Ok(()) => rebuilt.push((did, if_name)),
+                Err(e) => self.fail_dispatcher(&did, e),
+            }
+        }
+        rebuilt
+    }
+
+    // Fails the operations on a dispatcher that couldn't be rebuilt. The
+    // first of them is given the error, the rest are told where it came from.
+    fn fail_dispatcher(&mut self, did: &DispatcherId, e: BpfmanError) {
+        let on_did = |p: &Program| p.dispatcher_id().ok().flatten().as_ref() == Some(did);
+        let mut error = Some(with_verifier_log(e, self.config));
+        let message = error.as_ref().map(|e| e.to_string()).unwrap_or_default();
+
+        let mut failed = vec![];
+        for load in std::mem::take(&mut self.loads) {
+            if on_did(&load.program) {
+                failed.push(load.index);
+                load.discard(self.root_db);
+            } else {
+                self.loads.push(load);
+            }
+        }
+        for unload in self.unloads.iter_mut() {
+            if unload.detached.iter().any(on_did) {
+                failed.push(unload.index);
+                unload.restore();
+            }
+        }
+        failed.sort();
+        for index in failed {
+            self.results[index] = Some(Err(error.take().unwrap_or_else(|| {
+                BpfmanError::Error(format!("Not applied, the dispatcher failed: {message}"))
+            })));
+        }
+    }
+
+    // Undoes what the batch applied and rebuilds the dispatchers without it.
+    async fn roll_back(
+        &mut self,
+        rebuilt: Vec<(DispatcherId, String)>,
+        image_manager: &mut ImageManager,
+    ) {
+        for load in std::mem::take(&mut self.loads) {
+            load.discard(self.root_db);
+        }
+        for id in std::mem::take(&mut self.loaded) {
+            if let Err(e) = unload_program(self.config, self.root_db, id).await {
+                warn!("Unable to remove program {id} after failed batch: {e}");
+            }
+        }
+        for unload in self
+            .unloads
+            .iter_mut()
+            .filter(|u| self.results[u.index].is_none())
+        {
+            unload.restore();
+        }
+        for (did, if_name) in rebuilt {
+            if let Err(e) =
+                refresh_dispatcher(self.root_db, self.config, &did, &if_name, image_manager).await
+            {
+                warn!("Unable to restore dispatcher on {if_name} after failed batch: {e}");
+            }
+        }
+    }
+
+    async fn finish(&mut self) {
+        for mut load in std::mem::take(&mut self.loads) {
+            load.program.set_attached();
+            let result = load.program.get_data().get_id();
+            self.finish_load(load, result);
+        }
+        for unload in std::mem::take(&mut self.unloads) {
+            if self.results[unload.index].is_some() {
+                continue;
+            }
+            let mut result = Ok(BatchResult::Unloaded);
+            for id in &unload.ids {
+                if let Err(e) = unload_program(self.config, self.root_db, *id).await {
+                    warn!("Unable to remove program {id}: {e}");
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+            }
+            self.results[unload.index] = Some(result);
+        }
+    }
+}
+
+// Rebuilds the dispatcher with the programs recorded for it, removing it if
+// there are none.
+async fn refresh_dispatcher(
+    root_db: &Db,
+    config: &Config,
+    did: &DispatcherId,
+    if_name: &str,
+    image_manager: &mut ImageManager,
+) -> Result<(), BpfmanError> {
+    let (program_type, DispatcherInfo(if_index, direction, netns_id)) = match did.clone() {
+        DispatcherId::Xdp(info) => (ProgramType::Xdp, info),
+        DispatcherId::Tc(info) => (ProgramType::Tc, info),
+    };
+    let old_dispatcher = get_dispatcher(did, root_db);
+    if filter(root_db, program_type, Some(if_index), direction, netns_id)
+        .next()
+        .is_none()
+    {
+        return match old_dispatcher {
+            Some(mut old) => old.delete(root_db, true),
+            None => Ok(()),
+        };
+    }
+    rebuild_dispatcher(
+        root_db,
+        config,
+        did.clone(),
+        old_dispatcher,
+        if_name.to_string(),
+        image_manager,
+    )
+    .await
+}
+
+/// Unloads and ebpf program. If the program is the first in a group loaded by
+/// [`add_programs`], the rest of the group is unloaded too. XDP and TC
+/// programs whose interface has been removed were detached by the kernel along
+/// with it, so only their state is dropped.
+pub async fn remove_program(id: u32) -> Result<(), BpfmanError> {
+    let members = {
+        let (_, root_db) = &setup().await?;
+        group_members(root_db, id)
+    };
+    for member in members {
+        let result = remove_program_internal(member).await;
+        metrics::record_unload(&result);
+        result?;
+    }
+
+    let result = remove_program_internal(id).await;
+    metrics::record_unload(&result);
+    result
+}
+
+/// Returns the ID of the one XDP or TC program that `selector` matches. It's an
+/// error for it to match none or more than one, with the IDs of the programs
+/// it matches listed in the error.
+pub async fn find_program(selector: &ProgramSelector) -> Result<u32, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let mut ids: Vec<u32> = get_programs_iter(root_db)
+        .filter(|(_, p)| selector.matches(p))
+        .map(|(id, _)| id)
+        .collect();
+    ids.sort();
+    match ids[..] {
+        [id] => Ok(id),
+        [] => Err(BpfmanError::NoMatchingProgram {
+            selector: selector.to_string(),
+        }),
+        _ => Err(BpfmanError::AmbiguousProgram {
+            selector: selector.to_string(),
+            candidates: ids,
+        }),
+    }
+}
+
+/// Unloads every program loaded by bpfman, or only the XDP and TC programs
+/// attached to `iface` when it is set. Each program is unloaded on its own,
+/// so one that fails doesn't stop the rest, and its result is returned
+/// alongside its ID. Nothing is returned once there is nothing left to unload.
+pub async fn remove_all_programs(
+    iface: Option<String>,
+) -> Result<Vec<(u32, Result<(), BpfmanError>)>, BpfmanError> {
+    let ids: Vec<u32> = {
+        let (_, root_db) = &setup().await?;
+        get_programs_iter(root_db)
+            .filter(|(_, p)| match &iface {
+                Some(iface) => p.if_name().is_ok_and(|i| i == *iface),
+                None => true,
+            })
+            .map(|(id, _)| id)
+            .collect()
+    };
+
+    let mut results = vec![];
+    for id in ids {
+        let result = remove_program_internal(id).await;
+        metrics::record_unload(&result);
+        if let Err(ref e) = result {
+            warn!("Unable to remove program {id}: {e}");
+        }
+        results.push((id, result));
+    }
+    Ok(results)
+}
+
+// Returns the programs an earlier load with idempotency key `key` loaded, in
+// the order they were loaded, unless the key has expired. Fails if they were
+// loaded by a request with another digest.
+async fn previous_load(
+    key: &IdempotencyKey,
+    digest: &str,
+    programs: &[Program],
+) -> Result<Option<Vec<Program>>, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+    let ttl = config
+        .limits()
+        .clone()
+        .unwrap_or_default()
+        .idempotency_ttl();
+
+    let mut loaded = vec![];
+    for (id, p) in get_programs_iter(root_db) {
+        let data = p.get_data();
+        if data.get_idempotency_key()?.as_ref() == Some(key)
+            && !data.idempotency_key_expired(ttl)?
+        {
+            loaded.push((id, p));
+        }
+    }
+    if loaded.is_empty() {
+        return Ok(None);
+    }
+    // Programs loaded before digests were recorded have none.
+    for (_, p) in &loaded {
+        if p.get_data()
+            .get_idempotency_digest()?
+            .is_some_and(|d| d != digest)
+        {
+            return Err(BpfmanError::IdempotencyKeyReused(key.key.clone()));
+        }
+    }
+    // IDs are handed out in increasing order, so the first is the group's.
+    loaded.sort_by_key(|(id, _)| *id);
+    info!(
+        "Programs {:?} were already loaded with idempotency key {}",
+        loaded.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        key.key
+    );
+    for p in programs {
+        release_inline_bytecode(root_db, &p.get_data().get_location()?);
+    }
+    Ok(Some(loaded.into_iter().map(|(_, p)| p).collect()))
+}
+
+// Returns the IDs of the other programs in the group identified by id.
+fn group_members(root_db: &Db, id: u32) -> Vec<u32> {
+    get_programs_iter(root_db)
+        .filter(|(member, p)| {
+            *member != id && p.get_data().get_group_id().unwrap_or(None) == Some(id)
+        })
+        .map(|(member, _)| member)
+        .collect()
+}
+
+async fn remove_program_internal(id: u32) -> Result<(), BpfmanError> {
+    let (config, root_db) = &setup().await?;
+    unload_program(config, root_db, id).await
+}
+
+async fn unload_program(config: &Config, root_db: &Db, id: u32) -> Result<(), BpfmanError> {
+    info!(program_id = id; "Removing program with id: {id}");
+    let prog = match get(root_db, &id) {
+        Some(p) => p,
+        None => {
+            return Err(BpfmanError::Error(format!(
+                "Program {0} does not exist or was not created by bpfman",
+                id,
+            )));
+        }
+    };
+
+    let map_owner_id = prog.get_data().get_map_owner_id()?;
+    let location = prog.get_data().get_location()?;
+    let shared = prog.get_data().get_shared_maps()?;
+    let namespace = prog.get_data().get_pin_namespace()?;
+    let iface_pin_dir = prog.get_data().get_iface_pin_dir()?;
+    let event = events::event(ProgramEventKind::Unloaded, &prog);
+
+    match prog {
+        Program::Xdp(_) | Program::Tc(_) => {
+            let did = prog
+                .dispatcher_id()?
+                .ok_or(BpfmanError::DispatcherNotRequired)?;
+            let if_name = prog.if_name().unwrap();
+            let detached = prog.detached();
+            let interface_missing = prog.interface_missing()?;
+
+            prog.delete(root_db)
+                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+
+            // A detached program is no longer part of the dispatcher.
+            if interface_missing {
+                info!("Interface {if_name} no longer exists, dropping program {id}");
+                remove_missing_interface_program(root_db, did)?
+            } else if !detached {
+                remove_multi_attach_program(root_db, config, did, if_name).await?
+            }
+        }
+        Program::Tracepoint(_)
+        | Program::Kprobe(_)
+        | Program::Uprobe(_)
+        | Program::Fentry(_)
+        | Program::Fexit(_)
+        | Program::Cgroup(_)
+        | Program::Unsupported(_) => {
+            prog.delete(root_db)
+                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+        }
+        Program::TailCall(ref p) => {
+            // The program stays loaded for as long as the prog array refers
+            // to it, so take it out of the prog array as well as unpinning it.
+            let map_name = p.get_map_name()?;
+            if let Err(e) = open_program_map(root_db, id, &map_name)
+                .and_then(|prog_array| prog_array.clear_tail_call(p.get_index()?, id))
+            {
+                warn!("Unable to remove program {id} from prog array {map_name}: {e}");
+            }
+
+            prog.delete(root_db)
+                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+        }
+    }
+
+    delete_map(root_db, id, map_owner_id, namespace.as_deref())?;
+    release_inline_bytecode(root_db, &location);
+    shared_maps::release(root_db, namespace.as_deref(), &shared);
+    if let Some(namespace) = namespace {
+        pin_namespace::remove_if_empty(&namespace);
+    }
+    // The dispatcher has been rebuilt or removed by now.
+    if let Some(dir) = iface_pin_dir {
+        iface_dir::remove_if_empty(&dir);
+    }
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Returns the path [`write_inline_bytecode`] writes `bytecode` to, for use as
+/// the program's [`Location::File`]. Files are named by a hash of their
+/// contents, so sending the same bytecode again reuses the file.
+pub fn inline_bytecode_path(bytecode: &[u8]) -> String {
+    format!(
+        "{RTDIR_PROGRAMS}/{INLINE_BYTECODE_PREFIX}{}.o",
+        hex::encode(Sha256::digest(bytecode))
+    )
+}
+
+/// Writes bytecode sent with a load request, rather than read from a path or
+/// an image, to [`inline_bytecode_path`]. The file is removed once no program
+/// loaded from it is left.
+pub fn write_inline_bytecode(bytecode: &[u8]) -> Result<(), BpfmanError> {
+    if bytecode.is_empty() {
+        return Err(BpfmanError::InvalidBytecode(
+            "inline bytecode is empty".to_string(),
+        ));
+    }
+    create_dir_all(RTDIR_PROGRAMS)
+        .map_err(|e| BpfmanError::Error(format!("unable to create {RTDIR_PROGRAMS}: {e}")))?;
+
+    let path = inline_bytecode_path(bytecode);
+    // Write to a temporary file first so a concurrent load of the same
+    // bytecode never reads a partially written file.
+    let tmp_path = format!("{path}.{}", rand::random::<u32>());
+    fs::write(&tmp_path, bytecode)
+        .and_then(|_| {
+            set_file_permissions(Path::new(&tmp_path), INLINE_BYTECODE_MODE);
+            fs::rename(&tmp_path, &path)
+        })
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            BpfmanError::Error(format!("unable to write inline bytecode to {path}: {e}"))
+        })?;
+
+    debug!(
+        "Wrote {} bytes of inline bytecode to {path}",
+        bytecode.len()
+    );
+    Ok(())
+}
+
+// Removes a file written by write_inline_bytecode once no program that was
+// loaded from it is left.
+fn release_inline_bytecode(root_db: &Db, location: &Location) {
+    let path = match location {
+        Location::File(path)
+            if path.starts_with(&format!("{RTDIR_PROGRAMS}/{INLINE_BYTECODE_PREFIX}")) =>
+        {
+            path
+        }
+        _ => return,
+    };
+    let in_use = get_programs_iter(root_db).any(
+        |(_, p)| matches!(p.get_data().get_location(), Ok(Location::File(ref f)) if f == path),
+    );
+    if !in_use {
+        if let Err(e) = fs::remove_file(path) {
+            warn!("Unable to remove inline bytecode {path}: {e}");
+        }
+    }
+}
+
+/// Removes the inline bytecode written for `programs` that no loaded program
+/// uses, for programs that were read from a request but won't be loaded
+/// because another part of the request was refused.
+pub async fn discard_inline_bytecode(programs: &[Program]) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    let mut locations = vec![];
+    for program in programs {
+        if let Location::File(path) = program.get_data().get_location()? {
+            if !locations.contains(&path) {
+                locations.push(path);
+            }
+        }
+    }
+    for path in locations {
+        release_inline_bytecode(root_db, &Location::File(path));
+    }
+    Ok(())
+}
+
+/// Removes pins that bpfman created under its pin directory but no longer
+/// tracks, for example because bpfman was stopped part way through loading a
+/// program, and returns their paths. Pins bpfman didn't create are left alone.
+pub async fn prune_orphaned_pins() -> Result<Vec<PathBuf>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    orphaned_pins::prune(root_db)
+}
+
+/// Waits for the database the way every other request does and returns,
+/// which shows whether requests are being handled.
+pub async fn ping() -> Result<(), BpfmanError> {
+    setup().await.map(|_| ())
+}
+
+/// Returns everything bpfman tracks, its programs, their maps and pins and
+/// the dispatchers on each interface, as a pretty printed JSON document. Only
+/// bpfman's own state is read, so this is safe to call at any time. The
+/// credentials of images are left out.
+pub async fn dump_state() -> Result<String, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    serde_json::to_string_pretty(&state::dump(root_db)?)
+        .map_err(|e| BpfmanError::Error(format!("unable to serialize state: {e}")))
+}
+
+/// Compares `desired` with the programs bpfman has loaded and returns which
+/// of them need to be loaded, unloaded, or unloaded and loaded again with
+/// other settings, see [`StateDiff`]. Only the loaded programs with all of
+/// the metadata in `selector` are compared, so that programs loaded by others
+/// are left out. Nothing is loaded or unloaded.
+pub async fn diff_state(
+    desired: Vec<Program>,
+    selector: HashMap<String, String>,
+) -> Result<StateDiff, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let filter = ListFilter::new(None, selector, true, None, None);
+    let loaded = get_programs_iter(root_db)
+        .filter(|(_, p)| filter.matches(p))
+        .collect();
+    let result = diff::diff(loaded, &desired);
+
+    for program in &desired {
+        if let Ok(location) = program.get_data().get_location() {
+            release_inline_bytecode(root_db, &location);
+        }
+    }
+    result
+}
+
+/// Returns a receiver for the programs loaded, unloaded, attached and detached
+/// from now on by this process. Changes are never held up by a subscriber, so
+/// one that doesn't keep up misses events, and finds out from
+/// [`broadcast::error::RecvError::Lagged`].
+pub fn subscribe_events() -> broadcast::Receiver<ProgramEvent> {
+    events::subscribe()
+}
+
+/// Counts the programs loaded by bpfman, in total and on each interface, and
+/// reports them along with the limits [`add_program`] enforces.
+pub async fn program_counts() -> Result<ProgramCounts, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    Ok(count_programs(
+        root_db,
+        &config.limits().clone().unwrap_or_default(),
+    ))
+}
+
+/// Changes the priority of an XDP or TC program without unloading it. The
+/// dispatcher it is attached to is rebuilt so its programs run in the new
+/// order.
+pub async fn reorder_program(id: u32, priority: i32) -> Result<Program, BpfmanError> {
+    validate_priority(priority)?;
+
+    let (config, root_db) = &setup().await?;
+
+    info!("Changing priority of program {id} to {priority}");
+    let mut prog = get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {id} does not exist or was not created by bpfman"
+    )))?;
+
+    let old_priority = match prog {
+        Program::Xdp(ref p) => p.get_priority()?,
+        Program::Tc(ref p) => p.get_priority()?,
+        _ => {
+            return Err(BpfmanError::Error(format!(
+                "Program {id} is not attached through a dispatcher and has no priority"
+            )))
+        }
+    };
+    set_priority(&mut prog, priority)?;
+
+    // The new priority takes effect when a detached program is attached again.
+    if prog.detached() {
+        return Ok(prog);
+    }
+
+    let did = prog
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    let mut image_manager = init_image_manager().await;
+
+    if let Err(e) = rebuild_dispatcher(
+        root_db,
+        config,
+        did,
+        old_dispatcher,
+        prog.if_name()?,
+        &mut image_manager,
+    )
+    .await
+    {
+        // The old dispatcher still runs the program at its old priority.
+        set_priority(&mut prog, old_priority)?;
+        return Err(e);
+    }
+
+    get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {id} was removed while it was being reordered"
+    )))
+}
+
+/// Enables or disables an XDP or TC program without unloading it or taking
+/// it off its dispatcher. A disabled program still runs, but its dispatcher
+/// carries on to the next program whatever it returns, so a program can be
+/// staged before it acts on packets. The dispatcher is rebuilt to apply the
+/// change.
+pub async fn set_program_enabled(id: u32, enabled: bool) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    let mut prog = dispatcher_program(root_db, id)?;
+    if prog.enabled() == enabled {
+        return Ok(prog);
+    }
+    info!(
+        "{} program {id}",
+        if enabled { "Enabling" } else { "Disabling" }
+    );
+    set_enabled(&mut prog, enabled)?;
+
+    // The change takes effect when a detached program is attached again.
+    if prog.detached() {
+        return Ok(prog);
+    }
+
+    let did = prog
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    let mut image_manager = init_image_manager().await;
+
+    if let Err(e) = rebuild_dispatcher(
+        root_db,
+        config,
+        did,
+        old_dispatcher,
+        prog.if_name()?,
+        &mut image_manager,
+    )
+    .await
+    {
+        // The old dispatcher is still running the program as it was.
+        set_enabled(&mut prog, !enabled)?;
+        return Err(e);
+    }
+
+    get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {id} was removed while it was being {}",
+        if enabled { "enabled" } else { "disabled" }
+    )))
+}
+
+/// Detaches an XDP or TC program from its dispatcher without unloading it.
+/// The program stays loaded and pinned, so [`attach_program`] can put it back
+/// without reading the bytecode or running the verifier again.
+pub async fn detach_program(id: u32) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    info!("Detaching program {id}");
+    let mut prog = dispatcher_program(root_db, id)?;
+    if prog.detached() {
+        return Err(BpfmanError::Error(format!(
+            "Program {id} is already detached"
+        )));
+    }
+
+    let did = prog
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    let if_name = prog.if_name()?;
+
+    set_detached(&mut prog, true)?;
+
+    if let Err(e) = remove_multi_attach_program(root_db, config, did, if_name).await {
+        // Leave the program recorded as attached to the dispatcher that still
+        // runs it.
+        set_detached(&mut prog, false)?;
+        return Err(e);
+    }
+    prog.get_data_mut().set_attached_at(None)?;
+    events::publish(events::event(ProgramEventKind::Detached, &prog));
+
+    Ok(prog)
+}
+
+/// Attaches a program detached by [`detach_program`] to its dispatcher
+/// again, at the position its priority gives it.
+pub async fn attach_program(id: u32) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    info!("Attaching program {id}");
+    let mut prog = dispatcher_program(root_db, id)?;
+    if !prog.detached() {
+        return Err(BpfmanError::Error(format!(
+            "Program {id} is already attached"
+        )));
+    }
+
+    let did = prog
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    if num_attached_programs(&did, root_db) >= did.capacity() {
+        return Err(BpfmanError::TooManyPrograms);
+    }
+
+    set_detached(&mut prog, false)?;
+
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    let mut image_manager = init_image_manager().await;
+
+    if let Err(e) = rebuild_dispatcher(
+        root_db,
+        config,
+        did,
+        old_dispatcher,
+        prog.if_name()?,
+        &mut image_manager,
+    )
+    .await
+    {
+        // Leave the program detached rather than recorded as attached to a
+        // dispatcher that doesn't run it.
+        set_detached(&mut prog, true)?;
+        return Err(e);
+    }
+    prog.get_data_mut()
+        .set_attached_at(Some(SystemTime::now()))?;
+    events::publish(events::event(ProgramEventKind::Attached, &prog));
+
+    get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {id} was removed while it was being attached"
+    )))
+}
+
+// Returns the XDP or TC program with the given id.
+fn dispatcher_program(root_db: &Db, id: u32) -> Result<Program, BpfmanError> {
+    let prog = get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {id} does not exist or was not created by bpfman"
+    )))?;
+
+    match prog {
+        Program::Xdp(_) | Program::Tc(_) => Ok(prog),
+        _ => Err(BpfmanError::Error(format!(
+            "Program {id} is not an XDP or TC program attached through a dispatcher"
+        ))),
+    }
+}
+
+/// Returns the kernel link each of `programs` is attached by, in the same
+/// order, so that programs detached by other tools, such as bpftool, can be
+/// told apart from attached ones.
+pub async fn link_status(programs: &[Program]) -> Result<Vec<LinkStatus>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    let links = kernel_links();
+    let mut dispatchers = HashMap::new();
+
+    let mut statuses = vec![];
+    for program in programs {
+        statuses.push(program_link_status(root_db, program, &links, &mut dispatchers).await?);
+    }
+    Ok(statuses)
+}
+
+// Returns the IDs of the kernel's links by the ID of the program they attach.
+fn kernel_links() -> HashMap<u32, u32> {
+    aya::programs::loaded_links()
+        .filter_map(|l| l.ok())
+        .map(|l| (l.prog_id, l.id))
+        .collect()
+}
+
+/// Lists every BPF program and link in the kernel, including those bpfman
+/// didn't load, marking which ones bpfman manages. This helps find conflicts
+/// with other tools, such as another agent attaching XDP to an interface
+/// bpfman has a dispatcher on. Nothing is changed. TC programs and XDP
+/// programs attached through netlink rather than a link only show up as
+/// programs.
+pub async fn list_kernel() -> Result<KernelObjects, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let managed: HashSet<u32> = get_programs_iter(root_db).map(|(id, _)| id).collect();
+    let dispatchers = dispatcher_program_ids(root_db);
+
+    let programs: Vec<KernelProgram> = loaded_programs()
+        .filter_map(|p| p.ok())
+        .map(|p| {
+            let dispatcher = dispatchers.contains(&p.id());
+            KernelProgram {
+                id: p.id(),
+                name: p.name_as_str().unwrap_or_default().to_string(),
+                program_type: p.program_type(),
+                managed: dispatcher || managed.contains(&p.id()),
+                dispatcher,
+            }
+        })
+        .collect();
+
+    let links = aya::programs::loaded_links()
+        .filter_map(|l| l.ok())
+        .map(|l| {
+            let if_index = if l.type_ == bpf_link_type::BPF_LINK_TYPE_XDP as u32 {
+                // SAFETY: the kernel fills in the xdp member for XDP links.
+                Some(unsafe { l.__bindgen_anon_1.xdp.ifindex })
+            } else {
+                None
+            };
+            KernelLink {
+                id: l.id,
+                prog_id: l.prog_id,
+                link_type: link_type_name(l.type_).to_string(),
+                if_index,
+                iface: if_index.and_then(if_indextoname),
+                managed: managed.contains(&l.prog_id) || dispatchers.contains(&l.prog_id),
+            }
+        })
+        .collect();
+
+    Ok(KernelObjects { programs, links })
+}
+
+// The IDs of the dispatcher programs bpfman has loaded, read from their pins.
+fn dispatcher_program_ids(root_db: &Db) -> HashSet<u32> {
+    let mut ids = HashSet::new();
+    for name in root_db.tree_names() {
+        let name = bytes_to_string(&name);
+        if !name.starts_with(XDP_DISPATCHER_PREFIX) && !name.starts_with(TC_DISPATCHER_PREFIX) {
+            continue;
+        }
+        let Ok(tree) = root_db.open_tree(&name) else {
+            continue;
+        };
+        let dispatcher = Dispatcher::new_from_db(tree);
+        if let Some(path) = dispatcher
+            .pin_paths()
+            .ok()
+            .and_then(|p| p.into_iter().next())
+        {
+            if let Ok(info) = aya::programs::ProgramInfo::from_pin(path) {
+                ids.insert(info.id());
+            }
+        }
+    }
+    ids
+}
+
+fn link_type_name(link_type: u32) -> &'static str {
+    use bpf_link_type::*;
+    match link_type {
+        t if t == BPF_LINK_TYPE_RAW_TRACEPOINT as u32 => "raw_tracepoint",
+        t if t == BPF_LINK_TYPE_TRACING as u32 => "tracing",
+        t if t == BPF_LINK_TYPE_CGROUP as u32 => "cgroup",
+        t if t == BPF_LINK_TYPE_ITER as u32 => "iter",
+        t if t == BPF_LINK_TYPE_NETNS as u32 => "netns",
+        t if t == BPF_LINK_TYPE_XDP as u32 => "xdp",
+        t if t == BPF_LINK_TYPE_PERF_EVENT as u32 => "perf_event",
+        t if t == BPF_LINK_TYPE_KPROBE_MULTI as u32 => "kprobe_multi",
+        t if t == BPF_LINK_TYPE_STRUCT_OPS as u32 => "struct_ops",
+        t if t == BPF_LINK_TYPE_NETFILTER as u32 => "netfilter",
+        _ => "unknown",
+    }
+}
+
+// XDP and TC programs run only as long as their dispatcher does, so whether
+// a dispatcher is attached is looked up once and kept in `dispatchers`.
+async fn program_link_status(
+    root_db: &Db,
+    program: &Program,
+    links: &HashMap<u32, u32>,
+    dispatchers: &mut HashMap<DispatcherId, bool>,
+) -> Result<LinkStatus, BpfmanError> {
+    let link_id = links.get(&program.get_data().get_id()?).copied();
+    if let Program::TailCall(_) = program {
+        return Ok(LinkStatus {
+            link_id,
+            attached: None,
+        });
+    }
+    let mut attached = link_id.is_some();
+    if attached && !matches!(program, Program::Unsupported(_)) {
+        if let Some(did) = program.dispatcher_id()? {
+            attached = match dispatchers.get(&did) {
+                Some(attached) => *attached,
+                None => {
+                    let dispatcher_attached = match get_dispatcher(&did, root_db) {
+                        Some(d) => d.is_attached().await?,
+                        None => false,
+                    };
+                    dispatchers.insert(did, dispatcher_attached);
+                    dispatcher_attached
+                }
+            };
+        }
+    }
+    Ok(LinkStatus {
+        link_id,
+        attached: Some(attached),
+    })
+}
+
+/// Attaches the program `id` again after another tool, such as bpftool,
+/// detached it, using the program that is still loaded. XDP and TC programs
+/// are attached again by rebuilding their dispatcher, which is attached to its
+/// interface again if the kernel no longer runs it. Programs that are still
+/// attached, or that were detached with [`detach_program`], are left alone.
+pub async fn relink_program(id: u32) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    let mut program = get(root_db, &id).ok_or(BpfmanError::ProgramNotFound(id))?;
+    if program.detached() {
+        return Err(BpfmanError::Error(format!(
+            "Program {id} was detached by bpfman, attach it instead"
+        )));
+    }
+    let status =
+        program_link_status(root_db, &program, &kernel_links(), &mut HashMap::new()).await?;
+    match status.attached {
+        Some(false) => (),
+        Some(true) => {
+            return Err(BpfmanError::Error(format!(
+                "Program {id} is still attached"
+            )))
+        }
+        None => {
+            return Err(BpfmanError::Error(format!(
+                "Program {id} isn't attached by a link"
+            )))
+        }
+    }
+
+    info!("Relinking program {id}");
+    match program.dispatcher_id()? {
+        Some(did) => {
+            let dispatcher = get_dispatcher(&did, root_db)
+                .ok_or_else(|| BpfmanError::Error(format!("Program {id} has no dispatcher")))?;
+            if let Dispatcher::Xdp(ref d) = dispatcher {
+                if !dispatcher.is_attached().await? {
+                    d.unpin_link()?;
+                }
+            }
+            let if_name = dispatcher.if_name()?;
+            let mut image_manager = init_image_manager().await;
+            rebuild_dispatcher(
+                root_db,
+                config,
+                did,
+                Some(dispatcher),
+                if_name,
+                &mut image_manager,
+            )
+            .await?;
+        }
+        None => relink_single_attach_program(&program)?,
+    }
+    program
+        .get_data_mut()
+        .set_attached_at(Some(SystemTime::now()))?;
+    events::publish(events::event(ProgramEventKind::Attached, &program));
+    get(root_db, &id).ok_or(BpfmanError::ProgramNotFound(id))
+}
+
+// Attaches a program that doesn't use a dispatcher the way
+// add_single_attach_program does, from its pin instead of its bytecode.
+fn relink_single_attach_program(program: &Program) -> Result<(), BpfmanError> {
+    let data = program.get_data();
+    let program_pin_path = data.program_pin_path()?;
+    let link_pin_path = data.link_pin_path()?;
+    // A pin of a link that was detached holds on to the defunct link.
+    if let Err(e) = fs::remove_file(&link_pin_path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            return Err(e.into());
+        }
+    }
+
+    let fd_link: FdLink = match program {
+        Program::Tracepoint(p) => {
+            let tracepoint = p.get_tracepoint()?;
+            let (category, name) = tracepoint
+                .split_once('/')
+                .ok_or_else(|| BpfmanError::InvalidAttach(tracepoint.clone()))?;
+            let mut tracepoint = TracePoint::from_pin(&program_pin_path)?;
+            let link_id = tracepoint.attach(category, name)?;
+            let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
+            owned_link
+                .try_into()
+                .expect("unable to get owned tracepoint attach link")
+        }
+        Program::Kprobe(p) => {
+            let kind = match p.get_retprobe()? {
+                true => ProbeKind::KRetProbe,
+                false => ProbeKind::KProbe,
+            };
+            let mut kprobe = KProbe::from_pin(&program_pin_path, kind)?;
+            let link_id = kprobe.attach(p.get_fn_name()?, p.get_offset()?)?;
+            let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+            owned_link
+                .try_into()
+                .expect("unable to get owned kprobe attach link")
+        }
+        Program::Uprobe(p) => {
+            if p.get_container_pid()?.is_some() {
+                return Err(BpfmanError::Error(
+                    "uprobes attached in another container can't be relinked".to_string(),
+                ));
+            }
+            let kind = match p.get_retprobe()? {
+                true => ProbeKind::URetProbe,
+                false => ProbeKind::UProbe,
+            };
+            let mut uprobe = UProbe::from_pin(&program_pin_path, kind)?;
+            let link_id = uprobe.attach(
+                p.get_fn_name()?.as_deref(),
+                p.get_offset()?,
+                p.get_target()?,
+                p.get_pid()?,
+            )?;
+            let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+            owned_link
+                .try_into()
+                .expect("unable to get owned uprobe attach link")
+        }
+        Program::Fentry(_) => {
+            let mut fentry = FEntry::from_pin(&program_pin_path)?;
+            let link_id = fentry.attach()?;
+            let owned_link: FEntryLink = fentry.take_link(link_id)?;
+            owned_link.into()
+        }
+        Program::Fexit(_) => {
+            let mut fexit = FExit::from_pin(&program_pin_path)?;
+            let link_id = fexit.attach()?;
+            let owned_link: FExitLink = fexit.take_link(link_id)?;
+            owned_link.into()
+        }
+        Program::Cgroup(p) => {
+            let fd = aya::programs::ProgramInfo::from_pin(&program_pin_path)?.fd()?;
+            return cgroup::attach(
+                fd.as_fd(),
+                &cgroup::open(&p.get_path()?)?,
+                p.get_attach_type()?,
+                Path::new(&link_pin_path),
+            );
+        }
+        _ => {
+            return Err(BpfmanError::Error(format!(
+                "{} programs aren't attached by a link",
+                program.kind()
+            )))
+        }
+    };
+    fd_link
+        .pin(link_pin_path)
+        .map_err(BpfmanError::UnableToPinLink)?;
+    Ok(())
+}
+
+/// Attaches the dispatchers on the interface with index `if_index` in bpfman's
+/// own network namespace again if the kernel no longer runs them, for example
+/// after the interface was reset or left a bond. Dispatchers the kernel kept
+/// are left as they are, so programs are never attached twice. Returns the IDs
+/// of the programs that were attached again.
+pub async fn reattach_programs(if_index: u32) -> Result<Vec<u32>, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+    let mut image_manager = init_image_manager().await;
+
+    let mut reattached = vec![];
+    for did in dispatcher_ids(if_index) {
+        let Some(dispatcher) = get_dispatcher(&did, root_db) else {
+            continue;
+        };
+        if dispatcher.is_attached().await? {
+            debug!("Dispatcher {did:?} is still attached");
+            continue;
+        }
+
+        let if_name = dispatcher.if_name()?;
+        info!("Attaching the dispatcher on {if_name} again, the kernel no longer runs it");
+        if let Dispatcher::Xdp(ref d) = dispatcher {
+            d.unpin_link()?;
+        }
+        let (program_type, direction) = match did {
+            DispatcherId::Xdp(_) => (ProgramType::Xdp, None),
+            DispatcherId::Tc(DispatcherInfo(_, direction, _)) => (ProgramType::Tc, direction),
+        };
+        let ids: Vec<u32> = filter(root_db, program_type, Some(if_index), direction, None)
+            .filter_map(|p| p.get_data().get_id().ok())
+            .collect();
+
+        rebuild_dispatcher(
+            root_db,
+            config,
+            did,
+            Some(dispatcher),
+            if_name,
+            &mut image_manager,
+        )
+        .await?;
+        reattached.extend(ids);
+    }
+    Ok(reattached)
+}
+
+// The dispatchers an interface in bpfman's own network namespace can have.
+fn dispatcher_ids(if_index: u32) -> [DispatcherId; 3] {
+    [
+        DispatcherId::Xdp(DispatcherInfo(if_index, None, None)),
+        DispatcherId::Tc(DispatcherInfo(if_index, Some(Direction::Ingress), None)),
+        DispatcherId::Tc(DispatcherInfo(if_index, Some(Direction::Egress), None)),
+    ]
+}
+
+/// Returns the capacity, the number of used slots and the revision of each
+/// dispatcher on `iface` in bpfman's own network namespace, given by name or
+/// by index. Interfaces without XDP or TC programs have no dispatchers.
+pub async fn dispatcher_info(iface: &str) -> Result<Vec<DispatcherSlots>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    let (_, if_index) = get_ifindex(iface)?;
+
+    let mut slots = vec![];
+    for did in dispatcher_ids(if_index) {
+        let Some(dispatcher) = get_dispatcher(&did, root_db) else {
+            continue;
+        };
+        let (kind, direction) = match did {
+            DispatcherId::Xdp(_) => (ProgramType::Xdp, None),
+            DispatcherId::Tc(DispatcherInfo(_, direction, _)) => (ProgramType::Tc, direction),
+        };
+        slots.push(DispatcherSlots {
+            kind,
+            direction,
+            capacity: did.capacity() as u32,
+            used: dispatcher.num_extensions() as u32,
+            revision: dispatcher.revision(),
+        });
+    }
+    Ok(slots)
+}
+
+/// Checks that the dispatchers `programs` are to be attached to can be given
+/// room for `capacity` programs, and that they have enough free slots for the
+/// XDP and TC programs among `programs`, so that a large group of programs
+/// fails before any of them are loaded. Dispatchers are built with a fixed
+/// number of slots, so a capacity larger than that can't be provided.
+pub async fn check_dispatcher_capacity(
+    programs: &[Program],
+    capacity: u32,
+) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let mut needed: HashMap<DispatcherId, (String, u32)> = HashMap::new();
+    for program in programs {
+        if !matches!(program.kind(), ProgramType::Xdp | ProgramType::Tc) {
+            continue;
+        }
+        let mut program = program.clone();
+        resolve_interface(&mut program)?;
+        let Some(did) = program.dispatcher_id()? else {
+            continue;
+        };
+        if capacity as usize > did.capacity() {
+            return Err(BpfmanError::DispatcherCapacityExceeded {
+                requested: capacity,
+                capacity: did.capacity() as u32,
+            });
+        }
+        needed.entry(did).or_insert((program.if_name()?, 0)).1 += 1;
+    }
+
+    for (did, (iface, requested)) in needed {
+        check_free_slots(&did, num_attached_programs(&did, root_db), iface, requested)?;
+    }
+    Ok(())
+}
+
+// A dispatcher can hold more than its capacity when it was built by a bpfman
+// with larger dispatchers, in which case it has no free slots.
+fn check_free_slots(
+    did: &DispatcherId,
+    attached: usize,
+    iface: String,
+    requested: u32,
+) -> Result<(), BpfmanError> {
+    let free = did.capacity().saturating_sub(attached);
+    if requested as usize > free {
+        return Err(BpfmanError::DispatcherFull {
+            iface,
+            free: free as u32,
+            requested,
+        });
+    }
+    Ok(())
+}
+
+/// Replaces the XDP or TC program `id` with a program loaded from `data`,
+/// which takes over the old program's interface, priority and other attach
+/// settings. The dispatcher is rebuilt with the new program in place of the
+/// old one and swapped in atomically, so there is no moment when neither of
+/// them runs. The old program is only unloaded once the new one is attached,
+/// and is left untouched if the new one fails to load or verify.
+pub async fn replace_program(id: u32, data: ProgramData) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    info!("Replacing program {id}");
+    let mut old = dispatcher_program(root_db, id)?;
+    if old.detached() {
+        return Err(BpfmanError::Error(format!(
+            "Program {id} is detached, attach it before replacing it"
+        )));
+    }
+
+    let result = replace_program_internal(root_db, config, &mut old, data).await;
+    metrics::record_load(&result);
+    let program = result?;
+
+    let map_owner_id = old.get_data().get_map_owner_id()?;
+    let location = old.get_data().get_location()?;
+    let shared = old.get_data().get_shared_maps()?;
+    let namespace = old.get_data().get_pin_namespace()?;
+    let event = events::event(ProgramEventKind::Unloaded, &old);
+    let result = old
+        .delete(root_db)
+        .map_err(BpfmanError::BpfmanProgramDeleteError)
+        .and_then(|_| delete_map(root_db, id, map_owner_id, namespace.as_deref()));
+    metrics::record_unload(&result);
+    match result {
+        Ok(_) => events::publish(event),
+        Err(e) => warn!("Unable to remove program {id} after replacing it: {e}"),
+    }
+    release_inline_bytecode(root_db, &location);
+    shared_maps::release(root_db, namespace.as_deref(), &shared);
+
+    Ok(program)
+}
+
+/// Reloads the XDP or TC program `id` from the file, image or URL it was
+/// loaded from, and replaces it with the new program the way [`replace_program`]
+/// does. The name, metadata, global data and map settings it was loaded with
+/// are kept. Images are pulled again, so that a newer image pushed under the
+/// same tag is used.
+pub async fn reload_program(id: u32) -> Result<Program, BpfmanError> {
+    let data = {
+        let (_, root_db) = &setup().await?;
+        let old = dispatcher_program(root_db, id)?;
+        reload_data(old.get_data())?
+    };
+
+    info!("Reloading program {id} from {}", data.get_location()?);
+    replace_program(id, data).await
+}
+
+// Returns the data to load the program in `old` from its source again with.
+fn reload_data(old: &ProgramData) -> Result<ProgramData, BpfmanError> {
+    let id = old.get_id()?;
+    let location = match old.get_location()? {
+        Location::File(path)
+            if path.starts_with(&format!("{RTDIR_PROGRAMS}/{INLINE_BYTECODE_PREFIX}")) =>
+        {
+            return Err(BpfmanError::Error(format!(
+                "Program {id} was loaded from inline bytecode, which can't have changed"
+            )));
+        }
+        Location::Image(image) => Location::Image(BytecodeImage {
+            image_pull_policy: ImagePullPolicy::Always,
+            ..image
+        }),
+        location => location,
+    };
+
+    let mut data = ProgramData::new(
+        location,
+        old.get_name()?,
+        old.get_metadata()?,
+        old.get_global_data()?,
+        old.get_map_owner_id()?,
+    )?;
+    data.set_shared_maps(&old.get_shared_maps()?)?;
+    data.set_maps_to_pin(&old.get_maps_to_pin()?)?;
+    data.set_maps_to_skip(&old.get_maps_to_skip()?)?;
+    data.set_map_max_entries(&old.get_map_max_entries()?)?;
+    data.set_allow_duplicate(old.get_allow_duplicate()?)?;
+    if let Some(duplicate_of) = old.get_duplicate_of()? {
+        data.set_duplicate_of(duplicate_of)?;
+    }
+    Ok(data)
+}
+
+// Returns the program to replace the XDP or TC program `old` with, loaded from
+// `data` and attached with the old program's settings, including whether it
+// is enabled.
+fn replacement(old: &Program, data: ProgramData) -> Result<Program, BpfmanError> {
+    let mut program = match old {
+        Program::Xdp(p) => {
+            let mut new = XdpProgram::new(
+                data,
+                p.get_priority()?,
+                p.get_iface()?,
+                p.get_proceed_on()?,
+                p.get_netns()?,
+            )?;
+            if let Some(mode) = p.get_mode_preference()? {
+                new.set_mode_preference(mode)?;
+            }
+            new.set_enabled(p.get_enabled()?)?;
+            Program::Xdp(new)
+        }
+        Program::Tc(p) => {
+            let mut new = TcProgram::new(
+                data,
+                p.get_priority()?,
+                p.get_iface()?,
+                p.get_proceed_on()?,
+                p.get_direction()?,
+                p.get_netns()?,
+            )?;
+            new.set_enabled(p.get_enabled()?)?;
+            Program::Tc(new)
+        }
+        _ => unreachable!("dispatcher_program only returns XDP and TC programs"),
+    };
+    // The new program goes on the same dispatcher, so the interface and
+    // namespace the old one was resolved to are kept as they are.
+    if let Some(netns_id) = old.netns_id()? {
+        program.set_netns_id(netns_id)?;
+    }
+    if let Some(if_index) = old.if_index()? {
+        program.set_if_index(if_index)?;
+    }
+    Ok(program)
+}
+
+async fn replace_program_internal(
+    root_db: &Db,
+    config: &Config,
+    old: &mut Program,
+    data: ProgramData,
+) -> Result<Program, BpfmanError> {
+    let mut program = replacement(old, data)?;
+
+    let mut image_manager = init_image_manager().await;
+    program.get_data_mut().load(root_db)?;
+
+    // The new program is pinned alongside the old one.
+    let namespace = old.get_data().get_pin_namespace()?;
+    if let Some(ref namespace) = namespace {
+        program.get_data_mut().set_pin_namespace(namespace)?;
+    }
+
+    let map_owner_id = program.get_data().get_map_owner_id()?;
+    if let Some(map_owner_id) = map_owner_id {
+        let map_pin_path = is_map_owner_id_valid(root_db, map_owner_id, namespace.as_deref())?;
+        program.get_data_mut().set_map_pin_path(&map_pin_path)?;
+    }
+
+    program
+        .get_data_mut()
+        .set_program_bytes(root_db, &mut image_manager)
+        .await?;
+
+    // The old program is left out of the rebuilt dispatcher the same way a
+    // detached one is, and put back if the new dispatcher can't be built.
+    set_detached(old, true)?;
+    let did = old
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    let result = match validate_program(&mut program).and_then(|_| check_extension_name(&program)) {
+        Err(e) => Err(e),
+        Ok(()) => {
+            let old_dispatcher = get_dispatcher(&did, root_db);
+            rebuild_dispatcher(
+                root_db,
+                config,
+                did.clone(),
+                old_dispatcher,
+                program.if_name()?,
+                &mut image_manager,
+            )
+            .await
+        }
+    };
+
+    match result.map_err(|e| with_verifier_log(e, config)) {
+        Ok(()) => {
+            let name = program.get_data().get_name()?;
+            let new_id = program.get_data().get_id()?;
+            program.set_attached();
+            info!(
+                program_id = new_id,
+                program_type:% = program.kind(),
+                name = name.as_str();
+                "Replaced program {} with {} program with name: {name} and id: {new_id}",
+                old.get_data().get_id()?,
+                program.kind(),
+            );
+            save_map(root_db, config, &mut program, new_id, map_owner_id)?;
+            set_loaded_now(&mut program)?;
+            program.get_data_mut().swap_tree(root_db, new_id)?;
+            events::publish(events::event(ProgramEventKind::Loaded, &program));
+            Ok(program)
+        }
+        Err(e) => {
+            set_detached(old, false)?;
+            if let Some(pin_path) = program.get_data().get_map_pin_path()? {
+                let _ = cleanup_map_pin_path(&pin_path, map_owner_id);
+            }
+            let _ = program.delete(root_db);
+            // Back to the positions the running dispatcher has.
+            let (program_type, DispatcherInfo(if_index, direction, netns_id)) = match did {
+                DispatcherId::Xdp(info) => (ProgramType::Xdp, info),
+                DispatcherId::Tc(info) => (ProgramType::Tc, info),
+            };
+            set_program_positions(root_db, program_type, if_index, direction, netns_id);
+            Err(e)
+        }
+    }
+}
+
+// Records that a program bpfman has just loaded was attached at the same
+// time.
+fn set_loaded_now(program: &mut Program) -> Result<(), BpfmanError> {
+    let now = SystemTime::now();
+    program.get_data_mut().set_loaded_at(now)?;
+    program.get_data_mut().set_attached_at(Some(now))
+}
+
+// Marks an XDP or TC program as detached, which leaves it out of its
+// dispatcher the next time the dispatcher is rebuilt.
+fn set_detached(prog: &mut Program, detached: bool) -> Result<(), BpfmanError> {
+    match prog {
+        Program::Xdp(ref mut p) => p.set_detached(detached),
+        Program::Tc(ref mut p) => p.set_detached(detached),
+        _ => Ok(()),
+    }
+}
+
+// Sets the priority of an XDP or TC program, which takes effect the next time
+// its dispatcher is rebuilt.
+fn set_priority(prog: &mut Program, priority: i32) -> Result<(), BpfmanError> {
+    match prog {
+        Program::Xdp(ref mut p) => p.set_priority(priority),
+        Program::Tc(ref mut p) => p.set_priority(priority),
+        _ => Ok(()),
+    }
+}
+
+// Marks an XDP or TC program as enabled or disabled, which takes effect the
+// next time its dispatcher is rebuilt.
+fn set_enabled(prog: &mut Program, enabled: bool) -> Result<(), BpfmanError> {
+    match prog {
+        Program::Xdp(ref mut p) => p.set_enabled(enabled),
+        Program::Tc(ref mut p) => p.set_enabled(enabled),
+        _ => Ok(()),
+    }
+}
+
+/// Lists the currently loaded ebpf programs. XDP and TC programs whose
+/// interface has been removed are listed too, see
+/// [`Program::interface_missing`].
+pub async fn list_programs(filter: ListFilter) -> Result<Vec<Program>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("BpfManager::list_programs()");
+
+    // Get an iterator for the bpfman load programs, a hash map indexed by program id.
+    let mut bpfman_progs: HashMap<u32, Program> = get_programs_iter(root_db).collect();
+
+    // Call Aya to get ALL the loaded eBPF programs, and loop through each one.
+    Ok(loaded_programs()
+        .filter_map(|p| p.ok())
+        .map(|prog| {
+            let prog_id = prog.id();
+
+            // If the program was loaded by bpfman (check the hash map), then use it.
+            // Otherwise, convert the data returned from Aya into an Unsupported Program Object.
+            match bpfman_progs.remove(&prog_id) {
+                Some(p) => p.to_owned(),
+                None => {
+                    let db_tree = root_db
+                        .open_tree(prog_id.to_string())
+                        .expect("Unable to open program database tree for listing programs");
+
+                    let mut data = ProgramData::new_empty(db_tree);
+                    if let Err(e) = data.set_kernel_info(&prog) {
+                        warn!("Unable to set kernal info for prog {prog_id}, error: {e}");
+                    };
+
+                    Program::Unsupported(data)
+                }
+            }
+        })
+        .filter(|p| filter.matches(p))
+        .collect())
+}
+
+/// Fetches more information regarding a currently loaded ebpf program.
+pub async fn get_program(id: u32) -> Result<Program, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Getting program with id: {id}");
+    // If the program was loaded by bpfman, then use it.
+    // Otherwise, call Aya to get ALL the loaded eBPF programs, and convert the data
+    // returned from Aya into an Unsupported Program Object.
+    match get(root_db, &id) {
+        Some(p) => Ok(p.to_owned()),
+        None => loaded_programs()
+            .find_map(|p| {
+                let prog = p.ok()?;
+                if prog.id() == id {
+                    let db_tree = root_db
+                        .open_tree(prog.id().to_string())
+                        .expect("Unable to open program database tree for listing programs");
+
+                    let mut data = ProgramData::new_empty(db_tree);
+                    data.set_kernel_info(&prog)
+                        .expect("unable to set kernel info");
+
+                    Some(Program::Unsupported(data))
+                } else {
+                    None
+                }
+            })
+            .ok_or(BpfmanError::ProgramNotFound(id)),
+    }
+}
+
+/// Returns the run statistics of the program with kernel ID `id`. BPF
+/// statistics are enabled if they aren't already, and stay enabled until
+/// [`disable_stats`] is called or the process exits, so the counters of a
+/// program only start advancing after the first call.
+pub async fn program_stats(id: u32) -> Result<ProgramStats, BpfmanError> {
+    let (loaded_at, attach_type) = {
+        let program = get_program(id).await?;
+        (
+            program.get_data().get_kernel_loaded_at()?,
+            program.attach_type()?,
+        )
+    };
+
+    stats::enable()?;
+    let (run_count, run_time_ns) = stats::run_stats(id)?;
+
+    Ok(ProgramStats {
+        id,
+        run_count,
+        run_time_ns,
+        stats_enabled: stats::enabled(),
+        loaded_at,
+        attach_type,
+    })
+}
+
+/// Disables BPF statistics if they were enabled by [`program_stats`].
+pub fn disable_stats() {
+    stats::disable()
+}
+
+/// Returns metrics about the programs and dispatchers managed by bpfman in the
+/// Prometheus text exposition format.
+pub async fn metrics() -> Result<String, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let programs: Vec<Program> = get_programs_iter(root_db).map(|(_, p)| p).collect();
+    Ok(metrics::encode(&programs, root_db))
+}
+
+/// Reports the health of bpfman. The database isn't used, so this answers even
+/// while another request holds the database lock. Programs are counted from
+/// their bpffs pins instead.
+pub fn health() -> Health {
+    let count_pins = |dir: &str| {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| {
+                        e.file_name()
+                            .to_str()
+                            .and_then(|n| n.strip_prefix("prog_"))
+                            .is_some_and(|id| id.parse::<u32>().is_ok())
+                    })
+                    .count() as u32
+            })
+            .unwrap_or(0)
+    };
+    let programs_loaded = count_pins(&RTDIR_FS)
+        + pin_namespace::dirs()
+            .iter()
+            .map(|n| count_pins(&pin_namespace::pin_dir(Some(n))))
+            .sum::<u32>()
+        + iface_dir::dirs().iter().map(|d| count_pins(d)).sum::<u32>();
+
+    Health {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        programs_loaded,
+        bpffs_mounted: is_bpffs_mounted(&RTDIR_FS).unwrap_or(false),
+        features: kernel_features(),
+    }
+}
+
+/// Reports the kernel features bpfman relies on. The kernel is only probed
+/// the first time this is called.
+pub fn kernel_features() -> KernelFeatures {
+    feature_probe::features().clone()
+}
+
+/// Pulls an ebpf bytecode image from a remote OCI container registry.
+pub async fn pull_bytecode(image: BytecodeImage) -> anyhow::Result<()> {
+    let (_, root_db) = &setup().await?;
+    let image_manager = &mut init_image_manager().await;
+
+    image_manager
+        .get_image(
+            root_db,
+            &image.image_url,
+            image.image_pull_policy.clone(),
+            image.username.clone(),
+            image.password.clone(),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Looks up `key` in the map named `map_name` that belongs to the bpfman
+/// program with kernel ID `id`. A typed key is rejected if its encoding isn't
+/// the map's key size. Per-CPU maps return one value per possible CPU.
+/// The values are also decoded as `format` asks. If `sum_width` is given, the
+/// values of a per-CPU map are also summed across CPUs as arrays of unsigned
+/// counters of that many bytes.
+pub async fn map_lookup(
+    id: u32,
+    map_name: &str,
+    key: &MapKey,
+    format: &MapValueFormat,
+    sum_width: Option<usize>,
+) -> Result<MapEntry, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Looking up entry in map {map_name} of program {id}");
+    let map = open_program_map(root_db, id, map_name)?;
+    let sum_width = check_sum_width(map_name, &map, sum_width)?;
+    let key = match key {
+        MapKey::Bytes(key) => key.clone(),
+        MapKey::Typed(key_type, text) => {
+            let key = key_type.encode(text)?;
+            if key.len() != map.key_size() {
+                return Err(BpfmanError::InvalidTypedMapKeySize {
+                    key_type: key_type.to_string(),
+                    expected: map.key_size() as u32,
+                    actual: key.len(),
+                });
+            }
+            key
+        }
+    };
+    let values = map.lookup(&key)?;
+    let decoded = match map_value_decoder(root_db, id, map_name, &map, format)? {
+        Some(d) => d.decode(&values),
+        None => vec![],
+    };
+    let sums = match sum_width {
+        Some(width) => sum_counters(&values, width),
+        None => vec![],
+    };
+    Ok(MapEntry {
+        key,
+        values,
+        decoded,
+        sums,
+    })
+}
+
+// Only per-CPU maps have values to sum, so the width is dropped for others.
+fn check_sum_width(
+    map_name: &str,
+    map: &BpfMap,
+    sum_width: Option<usize>,
+) -> Result<Option<usize>, BpfmanError> {
+    let Some(width) = sum_width.filter(|_| map.is_per_cpu()) else {
+        return Ok(None);
+    };
+    if ![1, 2, 4, 8].contains(&width) || !map.value_size().is_multiple_of(width) {
+        return Err(BpfmanError::Error(format!(
+            "values of map {map_name} are {} bytes and can't be summed as {width} byte counters",
+            map.value_size()
+        )));
+    }
+    Ok(Some(width))
+}
+
+/// Returns the memory the kernel has locked for the maps `program` uses, as
+/// reported in each map's fdinfo. Kernels before 5.11 charge it against
+/// RLIMIT_MEMLOCK, later ones account it to the memory cgroup, so there it
+/// is only informational. Maps that can't be opened are left out.
+pub fn maps_bytes_memlock(program: &Program) -> Result<u64, BpfmanError> {
+    let memlock = |id| {
+        let fd = aya::maps::MapInfo::from_id(id).ok()?.fd().ok()?;
+        let fdinfo =
+            fs::read_to_string(format!("/proc/self/fdinfo/{}", fd.as_fd().as_raw_fd())).ok()?;
+        fdinfo_memlock(&fdinfo)
+    };
+    Ok(program
+        .get_data()
+        .get_kernel_map_ids()?
+        .into_iter()
+        .filter_map(memlock)
+        .sum())
+}
+
+/// Lists the maps pinned for the bpfman program with kernel ID `id`, sorted by
+/// name. For a program loaded with a map owner, these are the owner's maps.
+/// Maps that aren't pinned, such as the .rodata map by default, aren't listed.
+pub async fn list_maps(id: u32) -> Result<Vec<ProgramMap>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let prog = get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {id} does not exist or was not created by bpfman"
+    )))?;
+    match prog.get_data().get_map_pin_path()? {
+        Some(map_pin_path) => pinned_maps(&map_pin_path),
+        None => Ok(vec![]),
+    }
+}
+
+// Returns the maps pinned in map_pin_path, sorted by name.
+fn pinned_maps(map_pin_path: &Path) -> Result<Vec<ProgramMap>, BpfmanError> {
+    let entries = fs::read_dir(map_pin_path).map_err(|e| {
+        BpfmanError::Error(format!("unable to read {}: {e}", map_pin_path.display()))
+    })?;
+    let mut maps = vec![];
+    for entry in entries {
+        let path = entry
+            .map_err(|e| BpfmanError::Error(format!("unable to read map pin path: {e}")))?
+            .path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match BpfMap::from_pin(&path) {
+            Ok(map) => maps.push(map.describe(name, path)),
+            Err(e) => debug!("Skipping {}: {e}", path.display()),
+        }
+    }
+    maps.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(maps)
+}
+
+/// Creates or updates the entry for `key` in the map named `map_name` that
+/// belongs to the bpfman program with kernel ID `id`. For per-CPU maps, either
+/// one value per possible CPU or a single value applied to all CPUs is accepted.
+pub async fn map_update(
+    id: u32,
+    map_name: &str,
+    key: &[u8],
+    values: &[Vec<u8>],
+) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Updating entry in map {map_name} of program {id}");
+    open_program_map(root_db, id, map_name)?.update(key, values)
+}
+
+/// Deletes the entry for `key` in the map named `map_name` that belongs to
+/// the bpfman program with kernel ID `id`.
+pub async fn map_delete(id: u32, map_name: &str, key: &[u8]) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Deleting entry in map {map_name} of program {id}");
+    open_program_map(root_db, id, map_name)?.delete(key)
+}
+
+/// Freezes the map named `map_name` that belongs to the bpfman program with
+/// kernel ID `id`, so that it can no longer be changed from user space. Returns
+/// false if it was frozen already. The .rodata map is frozen when the program
+/// is loaded.
+pub async fn map_freeze(id: u32, map_name: &str) -> Result<bool, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Freezing map {map_name} of program {id}");
+    open_program_map(root_db, id, map_name)?.freeze()
+}
+
+/// Starts a dump of the map named `map_name` that belongs to the bpfman program
+/// with kernel ID `id`, reading up to `batch_size` entries at a time. A
+/// `batch_size` of 0 uses the default of 256. The values are also decoded as
+/// `format` asks, and summed across CPUs as [`map_lookup`] sums them.
+pub async fn map_dump(
+    id: u32,
+    map_name: &str,
+    batch_size: usize,
+    format: &MapValueFormat,
+    sum_width: Option<usize>,
+) -> Result<MapDump, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Dumping map {map_name} of program {id}");
+    let batch_size = if batch_size == 0 {
+        DEFAULT_MAP_DUMP_BATCH_SIZE
+    } else {
+        batch_size
+    };
+    let map = open_program_map(root_db, id, map_name)?;
+    let sum_width = check_sum_width(map_name, &map, sum_width)?;
+    let decoder = map_value_decoder(root_db, id, map_name, &map, format)?;
+    Ok(MapDump::new(map, batch_size, decoder, sum_width))
+}
+
+/// Starts reading the records written to the ringbuf or perf event array named
+/// `map_name` that belongs to the bpfman program with kernel ID `id`. Records
+/// are taken from the map as they're read, so other readers of the map don't
+/// see them.
+pub async fn map_watch(id: u32, map_name: &str) -> Result<MapWatch, BpfmanError> {
     let (_, root_db) = &setup().await?;
-    let image_manager = &mut init_image_manager().await;
 
-    image_manager
-        .get_image(
-            root_db,
-            &image.image_url,
-            image.image_pull_policy.clone(),
-            image.username.clone(),
-            image.password.clone(),
-        )
-        .await?;
-    Ok(())
+    debug!("Watching map {map_name} of program {id}");
+    let map = open_program_map(root_db, id, map_name)?.into_event_map(map_name)?;
+    Ok(MapWatch::new(EventReader::new(map)?))
+}
+
+// Returns None if the values are only returned as bytes, either because that
+// was asked for or because the program has no BTF to decode them with.
+fn map_value_decoder(
+    root_db: &Db,
+    id: u32,
+    map_name: &str,
+    map: &BpfMap,
+    format: &MapValueFormat,
+) -> Result<Option<ValueDecoder>, BpfmanError> {
+    let type_name = match format {
+        MapValueFormat::Raw => return Ok(None),
+        MapValueFormat::U32 => return Ok(Some(ValueDecoder::Uint(4))),
+        MapValueFormat::U64 => return Ok(Some(ValueDecoder::Uint(8))),
+        MapValueFormat::Btf(name) => name,
+    };
+
+    let bytes = match get(root_db, &id) {
+        Some(p) => p.get_data().get_program_bytes()?,
+        None => return Ok(None),
+    };
+    let Some(btf) = BtfTypes::from_object(&bytes) else {
+        debug!("Program {id} has no BTF, not decoding values of map {map_name}");
+        return Ok(None);
+    };
+    let type_id = match type_name {
+        Some(name) => btf.type_by_name(name).ok_or_else(|| {
+            BpfmanError::Error(format!("no type named {name} in the BTF of program {id}"))
+        })?,
+        None => match btf.map_value_type(map_name) {
+            Some(type_id) => type_id,
+            None => {
+                debug!("No BTF value type for map {map_name}, not decoding its values");
+                return Ok(None);
+            }
+        },
+    };
+    let size = btf.size(type_id).unwrap_or(0);
+    if size != map.value_size() {
+        return Err(BpfmanError::Error(format!(
+            "values of map {map_name} are {} bytes, but the type to decode them as is {size}",
+            map.value_size()
+        )));
+    }
+    Ok(Some(ValueDecoder::Btf(btf, type_id)))
 }
 
 pub(crate) async fn init_database(sled_config: SledConfig) -> Result<Db, BpfmanError> {
@@ -328,18 +3263,55 @@ pub(crate) async fn init_database(sled_config: SledConfig) -> Result<Db, BpfmanE
 // cosign tuf registries and container registries.
 pub(crate) async fn init_image_manager() -> ImageManager {
     let config = open_config_file();
-    ImageManager::new(config.signing().as_ref().map_or(true, |s| s.allow_unsigned))
+    ImageManager::new(config.signing().as_ref().is_none_or(|s| s.allow_unsigned))
         .await
         .expect("failed to initialize image manager")
 }
 
+fn open_program_map(root_db: &Db, id: u32, map_name: &str) -> Result<BpfMap, BpfmanError> {
+    // The map name is used as a path component, so don't allow it to point
+    // anywhere other than the program's map pin directory.
+    if map_name.is_empty() || map_name.contains('/') || map_name == "." || map_name == ".." {
+        return Err(BpfmanError::Error(format!("invalid map name {map_name}")));
+    }
+
+    let prog = get(root_db, &id).ok_or(BpfmanError::Error(format!(
+        "Program {0} does not exist or was not created by bpfman",
+        id,
+    )))?;
+    let map_pin_path = prog
+        .get_data()
+        .get_map_pin_path()?
+        .ok_or(BpfmanError::Error(format!(
+            "map_pin_path should be set for {}",
+            id
+        )))?;
+
+    let path = map_pin_path.join(map_name);
+    if !path.exists() {
+        return Err(BpfmanError::Error(format!(
+            "Map {map_name} not found for program {id}"
+        )));
+    }
+    BpfMap::from_pin(path)
+}
+
 fn get_dispatcher(id: &DispatcherId, root_db: &Db) -> Option<Dispatcher> {
     let tree_name_prefix = match id {
-        DispatcherId::Xdp(DispatcherInfo(if_index, _)) => {
-            format!("{}_{}", XDP_DISPATCHER_PREFIX, if_index)
+        DispatcherId::Xdp(DispatcherInfo(if_index, _, netns_id)) => {
+            format!(
+                "{}_{}",
+                XDP_DISPATCHER_PREFIX,
+                dispatcher_key(*if_index, *netns_id)
+            )
         }
-        DispatcherId::Tc(DispatcherInfo(if_index, Some(direction))) => {
-            format!("{}_{}_{}", TC_DISPATCHER_PREFIX, if_index, direction)
+        DispatcherId::Tc(DispatcherInfo(if_index, Some(direction), netns_id)) => {
+            format!(
+                "{}_{}_{}",
+                TC_DISPATCHER_PREFIX,
+                dispatcher_key(*if_index, *netns_id),
+                direction
+            )
         }
         _ => {
             return None;
@@ -385,6 +3357,7 @@ fn filter(
     program_type: ProgramType,
     if_index: Option<u32>,
     direction: Option<Direction>,
+    netns_id: Option<u64>,
 ) -> impl Iterator<Item = Program> + '_ {
     root_db
         .tree_names()
@@ -393,70 +3366,65 @@ fn filter(
         .map(|p| {
             let id = bytes_to_string(&p)
                 .split('_')
-                .last()
+                .next_back()
                 .unwrap()
                 .parse::<u32>()
                 .unwrap();
             let tree = root_db.open_tree(p).expect("unable to open database tree");
             Program::new_from_db(id, tree).expect("Failed to build program from database")
         })
+        // Detached programs stay loaded but aren't part of the dispatcher.
         .filter(move |p| {
             p.kind() == program_type
+                && !p.detached()
                 && p.if_index().unwrap() == if_index
                 && p.direction().unwrap() == direction
+                && p.netns_id().unwrap() == netns_id
         })
 }
 
-// Adds a new program and sets the positions of programs that are to be attached via a dispatcher.
-// Positions are set based on order of priority. Ties are broken based on:
-// - Already attached programs are preferred
-// - Program name. Lowest lexical order wins.
-fn add_and_set_program_positions(root_db: &Db, program: Program) {
-    let program_type = program.kind();
-    let if_index = program.if_index().unwrap();
-    let direction = program.direction().unwrap();
-
-    let mut extensions =
-        filter(root_db, program_type, if_index, direction).collect::<Vec<Program>>();
-
-    extensions.sort_by_key(|b| {
-        (
-            b.priority().unwrap(),
-            b.attached(),
-            b.get_data().get_name().unwrap().to_owned(),
-        )
-    });
-    for (i, v) in extensions.iter_mut().enumerate() {
-        v.set_position(i).expect("unable to set program position");
-    }
-}
-
 // Sets the positions of programs that are to be attached via a dispatcher.
-// Positions are set based on order of priority. Ties are broken based on:
-// - Already attached programs are preferred
-// - Program name. Lowest lexical order wins.
+// Positions are set based on order of priority, see position_key for how ties are broken.
 fn set_program_positions(
     root_db: &Db,
     program_type: ProgramType,
     if_index: u32,
     direction: Option<Direction>,
+    netns_id: Option<u64>,
 ) {
-    let mut extensions =
-        filter(root_db, program_type, Some(if_index), direction).collect::<Vec<Program>>();
+    let mut extensions = filter(root_db, program_type, Some(if_index), direction, netns_id)
+        .collect::<Vec<Program>>();
 
-    extensions.sort_by_key(|b| {
-        (
-            b.priority().unwrap(),
-            b.attached(),
-            b.get_data().get_name().unwrap().to_owned(),
-        )
-    });
+    extensions.sort_by_key(position_key);
     for (i, v) in extensions.iter_mut().enumerate() {
         v.set_position(i).expect("unable to set program position");
     }
 }
 
-fn get_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_ {
+// Programs with the same priority run in the order they were loaded. Already
+// attached programs were loaded before the one being added, and among
+// themselves kernel program IDs are allocated in increasing order.
+fn position_key(p: &Program) -> (i32, bool, Option<u32>) {
+    (
+        p.priority().unwrap(),
+        !p.attached(),
+        p.get_data().get_id().ok(),
+    )
+}
+
+// Checks that an XDP or TC program priority is within the supported range.
+fn validate_priority(priority: i32) -> Result<(), BpfmanError> {
+    if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&priority) {
+        return Err(BpfmanError::InvalidPriority {
+            priority,
+            min: MIN_PRIORITY,
+            max: MAX_PRIORITY,
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn get_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_ {
     root_db
         .tree_names()
         .into_iter()
@@ -464,7 +3432,7 @@ fn get_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_
         .map(|p| {
             let id = bytes_to_string(&p)
                 .split('_')
-                .last()
+                .next_back()
                 .unwrap()
                 .parse::<u32>()
                 .unwrap();
@@ -476,15 +3444,57 @@ fn get_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_
         })
 }
 
+/// Drops any program from the database that is no longer loaded in the kernel,
+/// for example because the system rebooted or the pins were removed while
+/// bpfman wasn't running, and returns their IDs. Meant to be called once when
+/// bpfman-rpc starts. Nothing is dropped if the kernel's programs can't all be
+/// listed, since every program would then look stale.
+pub async fn prune_stale_programs() -> Result<Vec<u32>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    let loaded_ids = loaded_programs()
+        .map(|p| p.map(|p| p.id()))
+        .collect::<Result<HashSet<u32>, _>>()?;
+    Ok(prune_programs_not_in(root_db, &loaded_ids))
+}
+
+// A stale entry that can't be fully removed is logged rather than treated as
+// an error, so that it doesn't prevent the rest from being removed.
+fn prune_programs_not_in(root_db: &Db, loaded_ids: &HashSet<u32>) -> Vec<u32> {
+    let mut pruned = vec![];
+    for (id, prog) in get_programs_iter(root_db) {
+        if loaded_ids.contains(&id) {
+            continue;
+        }
+
+        warn!("Program {id} no longer exists in the kernel, removing it from bpfman state");
+        let map_owner_id = prog.get_data().get_map_owner_id().unwrap_or(None);
+        let namespace = prog.get_data().get_pin_namespace().unwrap_or(None);
+        let iface_pin_dir = prog.get_data().get_iface_pin_dir().unwrap_or(None);
+        if let Err(e) = prog.delete(root_db) {
+            warn!("Unable to remove stale program {id}: {e}");
+        }
+        if let Err(e) = delete_map(root_db, id, map_owner_id, namespace.as_deref()) {
+            warn!("Unable to remove maps of stale program {id}: {e}");
+        }
+        if let Some(namespace) = namespace {
+            pin_namespace::remove_if_empty(&namespace);
+        }
+        if let Some(dir) = iface_pin_dir {
+            iface_dir::remove_if_empty(&dir);
+        }
+        pruned.push(id);
+    }
+    pruned
+}
+
 async fn setup() -> Result<(Config, Db), BpfmanError> {
     initialize_bpfman()?;
 
-    Ok((
-        open_config_file(),
-        init_database(get_db_config())
-            .await
-            .expect("Unable to open root database"),
-    ))
+    let root_db = init_database(get_db_config())
+        .await
+        .expect("Unable to open root database");
+
+    Ok((open_config_file(), root_db))
 }
 
 async fn add_multi_attach_program(
@@ -494,78 +3504,92 @@ async fn add_multi_attach_program(
     config: &Config,
 ) -> Result<u32, BpfmanError> {
     debug!("BpfManager::add_multi_attach_program()");
-    let name = &program.get_data().get_name()?;
+    let did = stage_multi_attach_program(root_db, program)?;
+    let if_name = program.if_name()?;
+    let old_dispatcher = get_dispatcher(&did, root_db);
 
-    // This load is just to verify the BPF Function Name is valid.
-    // The actual load is performed in the XDP or TC logic.
-    // don't pin maps here.
-    let mut ext_loader = BpfLoader::new()
-        .allow_unsupported_maps()
-        .extension(name)
-        .load(&program.get_data().get_program_bytes()?)?;
+    rebuild_dispatcher(root_db, config, did, old_dispatcher, if_name, image_manager)
+        .await
+        .or_else(|e| {
+            // If kernel ID was never set there's no pins to cleanup here so just continue
+            if program.get_data().get_id().is_ok() {
+                program
+                    .delete(root_db)
+                    .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+            }
+            Err(e)
+        })?;
 
-    match ext_loader.program_mut(name) {
-        Some(_) => Ok(()),
-        None => Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
-    }?;
+    let id = program.get_data().get_id()?;
+    program.set_attached();
+
+    Ok(id)
+}
+
+// Checks that an XDP or TC program in the database can be added to its
+// dispatcher, returning the dispatcher to rebuild with it. Programs waiting
+// to be added count towards the dispatcher's capacity.
+fn stage_multi_attach_program(
+    root_db: &Db,
+    program: &Program,
+) -> Result<DispatcherId, BpfmanError> {
+    check_extension_name(program)?;
 
     let did = program
         .dispatcher_id()?
         .ok_or(BpfmanError::DispatcherNotRequired)?;
-
-    let next_available_id = num_attached_programs(&did, root_db);
-    if next_available_id >= 10 {
+    let programs = filter(
+        root_db,
+        program.kind(),
+        program.if_index()?,
+        program.direction()?,
+        program.netns_id()?,
+    )
+    .count();
+    if programs > did.capacity() {
         return Err(BpfmanError::TooManyPrograms);
     }
+    debug!("{programs} programs on dispatcher {did:?}");
+    Ok(did)
+}
 
-    debug!("next_available_id={next_available_id}");
-
-    let program_type = program.kind();
-    let if_index = program.if_index()?;
-    let if_name = program.if_name().unwrap().to_string();
-    let direction = program.direction()?;
-
-    add_and_set_program_positions(root_db, program.clone());
-
-    let mut programs: Vec<Program> =
-        filter(root_db, program_type, if_index, direction).collect::<Vec<Program>>();
-
-    let old_dispatcher = get_dispatcher(&did, root_db);
-
-    let if_config = if let Some(ref i) = config.interfaces() {
-        i.get(&if_name)
-    } else {
-        None
-    };
-    let next_revision = if let Some(ref old) = old_dispatcher {
-        old.next_revision()
-    } else {
-        1
-    };
-
-    Dispatcher::new(
-        root_db,
-        if_config,
-        &mut programs,
-        next_revision,
-        old_dispatcher,
-        image_manager,
-    )
-    .await
-    .or_else(|e| {
-        // If kernel ID was never set there's no pins to cleanup here so just continue
-        if program.get_data().get_id().is_ok() {
-            program
-                .delete(root_db)
-                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
-        }
-        Err(e)
-    })?;
+// This load is just to verify the BPF Function Name is valid.
+// The actual load is performed in the XDP or TC logic.
+// don't pin maps here.
+fn check_extension_name(program: &Program) -> Result<(), BpfmanError> {
+    let name = &program.get_data().get_name()?;
+    let mut ext_loader = BpfLoader::new()
+        .allow_unsupported_maps()
+        .extension(name)
+        .load(&program.get_data().get_program_bytes()?)?;
 
-    let id = program.get_data().get_id()?;
-    program.set_attached();
+    match ext_loader.program_mut(name) {
+        Some(_) => Ok(()),
+        None => Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
+    }
+}
 
-    Ok(id)
+// fentry and fexit programs are attached through a BPF trampoline to a kernel
+// function, which aya looks up in the kernel's BTF. Checking for both first
+// gives a clearer error than the load would.
+fn trampoline_btf(fn_name: &str, program_type: &str) -> Result<Btf, BpfmanError> {
+    let features = feature_probe::features();
+    if !features.btf {
+        return Err(BpfmanError::TrampolineBtfMissing {
+            program_type: program_type.to_string(),
+            path: feature_probe::KERNEL_BTF_PATH.to_string(),
+        });
+    }
+    if !features.trampoline {
+        return Err(BpfmanError::TrampolineUnsupported {
+            program_type: program_type.to_string(),
+        });
+    }
+    let btf = Btf::from_sys_fs()?;
+    if btf.id_by_type_name_kind(fn_name, BtfKind::Func).is_err() {
+        return Err(BpfmanError::KernelFunctionNotFound(fn_name.to_string()));
+    }
+    Ok(btf)
 }
 
 pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result<u32, BpfmanError> {
@@ -577,6 +3601,10 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
     for (key, value) in data {
         bpf.set_global(key, value.as_slice(), true);
     }
+    let max_entries = &p.get_data().get_map_max_entries()?;
+    for (name, n) in max_entries {
+        bpf.set_max_entries(name, *n);
+    }
 
     // If map_pin_path is set already it means we need to use a pin
     // path which should already exist on the system.
@@ -587,6 +3615,10 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
         );
         bpf.map_pin_path(map_pin_path);
     }
+    let shared_maps = SharedMaps::prepare(p.get_data())?;
+    if let Some(ref shared) = shared_maps {
+        bpf.map_pin_path(shared.pin_path());
+    }
 
     let mut loader = bpf
         .allow_unsupported_maps()
@@ -607,6 +3639,9 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
             }
             let category = parts[0].to_owned();
             let name = parts[1].to_owned();
+            if !tracepoint_exists(&category, &name) {
+                return Err(BpfmanError::TracepointNotFound { category, name });
+            }
 
             let tracepoint: &mut TracePoint = raw_program.try_into()?;
 
@@ -625,11 +3660,11 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                 .expect("unable to get owned tracepoint attach link");
 
             fd_link
-                .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                .pin(program.data.link_pin_path()?)
                 .map_err(BpfmanError::UnableToPinLink)?;
 
             tracepoint
-                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .pin(program.data.program_pin_path()?)
                 .map_err(BpfmanError::UnableToPinProgram)?;
 
             Ok(id)
@@ -646,6 +3681,11 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                 )));
             }
 
+            let fn_name = program.get_fn_name()?;
+            if !kernel_symbol_exists(&fn_name) {
+                return Err(BpfmanError::KernelSymbolNotFound(fn_name));
+            }
+
             let kprobe: &mut KProbe = raw_program.try_into()?;
             kprobe.load()?;
 
@@ -662,7 +3702,7 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
 
             let id = program.data.get_id()?;
 
-            let link_id = kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
+            let link_id = kprobe.attach(&fn_name, program.get_offset()?)?;
 
             let owned_link: KProbeLink = kprobe.take_link(link_id)?;
             let fd_link: FdLink = owned_link
@@ -670,11 +3710,11 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                 .expect("unable to get owned kprobe attach link");
 
             fd_link
-                .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                .pin(program.data.link_pin_path()?)
                 .map_err(BpfmanError::UnableToPinLink)?;
 
             kprobe
-                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .pin(program.data.program_pin_path()?)
                 .map_err(BpfmanError::UnableToPinProgram)?;
 
             Ok(id)
@@ -685,6 +3725,23 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                 false => Uprobe,
             };
 
+            // Without a function name the offset is relative to the start of
+            // the file, so it must fall within it. Targets in other containers
+            // aren't visible from here and are left to bpfman-ns.
+            if program.get_fn_name()?.is_none() && program.get_container_pid()?.is_none() {
+                let target = program.get_target()?;
+                let offset = program.get_offset()?;
+                if let Ok(metadata) = fs::metadata(&target) {
+                    if offset >= metadata.len() {
+                        return Err(BpfmanError::UprobeOffsetOutOfRange {
+                            target,
+                            offset,
+                            size: metadata.len(),
+                        });
+                    }
+                }
+            }
+
             let uprobe: &mut UProbe = raw_program.try_into()?;
             uprobe.load()?;
 
@@ -701,7 +3758,7 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
 
             let id = program.data.get_id()?;
 
-            let program_pin_path = format!("{RTDIR_FS}/prog_{}", id);
+            let program_pin_path = program.data.program_pin_path()?;
             let fn_name = program.get_fn_name()?;
 
             uprobe
@@ -715,7 +3772,7 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                         fn_name.as_deref(),
                         program.get_offset()?,
                         program.get_target()?,
-                        None,
+                        program.get_pid()?,
                     )?;
 
                     let owned_link: UProbeLink = uprobe.take_link(link_id)?;
@@ -724,7 +3781,7 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                         .expect("unable to get owned uprobe attach link");
 
                     fd_link
-                        .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                        .pin(program.data.link_pin_path()?)
                         .map_err(BpfmanError::UnableToPinLink)?;
                 }
                 Some(p) => {
@@ -804,7 +3861,7 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
         }
         Program::Fentry(ref mut program) => {
             let fn_name = program.get_fn_name()?;
-            let btf = Btf::from_sys_fs()?;
+            let btf = trampoline_btf(&fn_name, "fentry")?;
             let fentry: &mut FEntry = raw_program.try_into()?;
             fentry
                 .load(&fn_name, &btf)
@@ -816,18 +3873,18 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
             let owned_link: FEntryLink = fentry.take_link(link_id)?;
             let fd_link: FdLink = owned_link.into();
             fd_link
-                .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                .pin(program.data.link_pin_path()?)
                 .map_err(BpfmanError::UnableToPinLink)?;
 
             fentry
-                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .pin(program.data.program_pin_path()?)
                 .map_err(BpfmanError::UnableToPinProgram)?;
 
             Ok(id)
         }
         Program::Fexit(ref mut program) => {
             let fn_name = program.get_fn_name()?;
-            let btf = Btf::from_sys_fs()?;
+            let btf = trampoline_btf(&fn_name, "fexit")?;
             let fexit: &mut FExit = raw_program.try_into()?;
             fexit
                 .load(&fn_name, &btf)
@@ -839,15 +3896,93 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
             let owned_link: FExitLink = fexit.take_link(link_id)?;
             let fd_link: FdLink = owned_link.into();
             fd_link
-                .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                .pin(program.data.link_pin_path()?)
                 .map_err(BpfmanError::UnableToPinLink)?;
 
             fexit
-                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .pin(program.data.program_pin_path()?)
                 .map_err(BpfmanError::UnableToPinProgram)?;
 
             Ok(id)
         }
+        Program::Cgroup(ref mut program) => {
+            let attach_type = program.get_attach_type()?;
+            let cgroup = cgroup::open(&program.get_path()?)?;
+
+            match attach_type.program_type() {
+                ProgramType::CgroupSkb => {
+                    let cgroup_skb: &mut CgroupSkb = raw_program.try_into()?;
+                    cgroup_skb.load()?;
+                    program
+                        .get_data_mut()
+                        .set_kernel_info(&cgroup_skb.info()?)?;
+
+                    let id = program.data.get_id()?;
+                    cgroup::attach(
+                        cgroup_skb.fd()?.as_fd(),
+                        &cgroup,
+                        attach_type,
+                        Path::new(&program.data.link_pin_path()?),
+                    )?;
+
+                    cgroup_skb
+                        .pin(program.data.program_pin_path()?)
+                        .map_err(BpfmanError::UnableToPinProgram)?;
+
+                    Ok(id)
+                }
+                _ => {
+                    let cgroup_sock: &mut CgroupSock = raw_program.try_into()?;
+                    cgroup_sock.load()?;
+                    program
+                        .get_data_mut()
+                        .set_kernel_info(&cgroup_sock.info()?)?;
+
+                    let id = program.data.get_id()?;
+                    cgroup::attach(
+                        cgroup_sock.fd()?.as_fd(),
+                        &cgroup,
+                        attach_type,
+                        Path::new(&program.data.link_pin_path()?),
+                    )?;
+
+                    cgroup_sock
+                        .pin(program.data.program_pin_path()?)
+                        .map_err(BpfmanError::UnableToPinProgram)?;
+
+                    Ok(id)
+                }
+            }
+        }
+        Program::TailCall(ref mut program) => {
+            let map_name = program.get_map_name()?;
+            let index = program.get_index()?;
+            let map_owner_id = program
+                .get_data()
+                .get_map_owner_id()?
+                .expect("map owner should be checked in validate_program");
+
+            // Check the index before loading, rather than once there is a
+            // program to clean up.
+            let prog_array = open_program_map(root_db, map_owner_id, &map_name)?;
+            prog_array.check_tail_call_index(&map_name, index)?;
+
+            load_tail_call(raw_program)?;
+            let info = raw_program.info()?;
+            program.set_program_type(
+                info.program_type()
+                    .try_into()
+                    .map_err(|e| BpfmanError::Error(format!("{e}")))?,
+            )?;
+            program.get_data_mut().set_kernel_info(&info)?;
+
+            let id = program.data.get_id()?;
+
+            pin_tail_call(raw_program, &program.data.program_pin_path()?)?;
+            prog_array.set_tail_call(&map_name, index, raw_program.fd()?.as_fd())?;
+
+            Ok(id)
+        }
         _ => panic!("not a supported single attach program"),
     };
 
@@ -855,12 +3990,13 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
         Ok(id) => {
             // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
             if p.get_data().get_map_pin_path()?.is_none() {
-                let map_pin_path = calc_map_pin_path(id);
+                let map_pin_path =
+                    calc_map_pin_path(p.get_data().get_pin_namespace()?.as_deref(), id);
                 p.get_data_mut().set_map_pin_path(&map_pin_path)?;
                 create_map_pin_path(&map_pin_path)?;
 
                 for (name, map) in loader.maps_mut() {
-                    if !should_map_be_pinned(name) {
+                    if !p.get_data().should_pin_map(name)? {
                         continue;
                     }
                     debug!(
@@ -871,6 +4007,9 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                         .map_err(BpfmanError::UnableToPinMap)?;
                 }
             }
+            if let Some(shared) = shared_maps {
+                shared.publish(&loader)?;
+            }
         }
         Err(_) => {
             // If kernel ID was never set there's no pins to cleanup here so just continue
@@ -888,10 +4027,7 @@ async fn remove_multi_attach_program(
     root_db: &Db,
     config: &Config,
     did: DispatcherId,
-    program_type: ProgramType,
-    if_index: Option<u32>,
     if_name: String,
-    direction: Option<Direction>,
 ) -> Result<(), BpfmanError> {
     debug!("BpfManager::remove_multi_attach_program()");
     let mut image_manager = init_image_manager().await;
@@ -908,10 +4044,55 @@ async fn remove_multi_attach_program(
         }
     }
 
-    set_program_positions(root_db, program_type, if_index.unwrap(), direction);
+    rebuild_dispatcher(
+        root_db,
+        config,
+        did,
+        old_dispatcher,
+        if_name,
+        &mut image_manager,
+    )
+    .await
+}
+
+// The kernel detached the dispatcher along with its interface, so there is
+// nothing to rebuild. The dispatcher's pins are removed with its last program.
+fn remove_missing_interface_program(root_db: &Db, did: DispatcherId) -> Result<(), BpfmanError> {
+    let (program_type, DispatcherInfo(if_index, direction, netns_id)) = match did.clone() {
+        DispatcherId::Xdp(info) => (ProgramType::Xdp, info),
+        DispatcherId::Tc(info) => (ProgramType::Tc, info),
+    };
+    if filter(root_db, program_type, Some(if_index), direction, netns_id)
+        .next()
+        .is_some()
+    {
+        return Ok(());
+    }
+    match get_dispatcher(&did, root_db) {
+        Some(mut dispatcher) => dispatcher.delete(root_db, true),
+        None => Ok(()),
+    }
+}
+
+// Recalculates the positions of the programs on a dispatcher and replaces it
+// with a new revision that runs them in that order.
+async fn rebuild_dispatcher(
+    root_db: &Db,
+    config: &Config,
+    did: DispatcherId,
+    old_dispatcher: Option<Dispatcher>,
+    if_name: String,
+    image_manager: &mut ImageManager,
+) -> Result<(), BpfmanError> {
+    let (program_type, DispatcherInfo(if_index, direction, netns_id)) = match did {
+        DispatcherId::Xdp(info) => (ProgramType::Xdp, info),
+        DispatcherId::Tc(info) => (ProgramType::Tc, info),
+    };
+    set_program_positions(root_db, program_type, if_index, direction, netns_id);
 
     // Intentionally don't add filter program here
-    let mut programs: Vec<Program> = filter(root_db, program_type, if_index, direction).collect();
+    let mut programs: Vec<Program> =
+        filter(root_db, program_type, Some(if_index), direction, netns_id).collect();
 
     let if_config = if let Some(ref i) = config.interfaces() {
         i.get(&if_name)
@@ -928,10 +4109,11 @@ async fn remove_multi_attach_program(
     Dispatcher::new(
         root_db,
         if_config,
+        &config.dispatchers().clone().unwrap_or_default(),
         &mut programs,
         next_revision,
         old_dispatcher,
-        &mut image_manager,
+        image_manager,
     )
     .await?;
 
@@ -939,11 +4121,17 @@ async fn remove_multi_attach_program(
 }
 
 // This function checks to see if the user provided map_owner_id is valid.
-fn is_map_owner_id_valid(root_db: &Db, map_owner_id: u32) -> Result<PathBuf, BpfmanError> {
-    let map_pin_path = calc_map_pin_path(map_owner_id);
+// The map owner must be in the same pin namespace, which is where its maps are
+// pinned.
+fn is_map_owner_id_valid(
+    root_db: &Db,
+    map_owner_id: u32,
+    namespace: Option<&str>,
+) -> Result<PathBuf, BpfmanError> {
+    let map_pin_path = calc_map_pin_path(namespace, map_owner_id);
     let name: &sled::IVec = &format!("{}{}", MAP_PREFIX, map_owner_id).as_bytes().into();
 
-    if root_db.tree_names().contains(name) {
+    if root_db.tree_names().contains(name) && map_pin_path.exists() {
         // Return the map_pin_path
         return Ok(map_pin_path);
     }
@@ -977,6 +4165,7 @@ fn cleanup_map_pin_path(map_pin_path: &Path, map_owner_id: Option<u32>) -> Resul
 // the Used-By array.
 fn save_map(
     root_db: &Db,
+    config: &Config,
     program: &mut Program,
     id: u32,
     map_owner_id: Option<u32>,
@@ -1019,7 +4208,12 @@ fn save_map(
             if let Some(map_pin_path) = data.get_map_pin_path()? {
                 if let Some(path) = map_pin_path.to_str() {
                     debug!("bpf set dir permissions for {}", path);
-                    set_dir_permissions(path, MAPS_MODE);
+                    let mode = config
+                        .permissions()
+                        .clone()
+                        .unwrap_or_default()
+                        .pin_dir_mode()?;
+                    set_dir_permissions(path, mode)?;
                 } else {
                     return Err(BpfmanError::Error(format!(
                         "invalid map_pin_path {} for {}",
@@ -1045,7 +4239,12 @@ fn save_map(
 // directory is removed. If this eBPF program is referencing a
 // map from another eBPF program, then this eBPF programs ID
 // is removed from the UsedBy array.
-fn delete_map(root_db: &Db, id: u32, map_owner_id: Option<u32>) -> Result<(), BpfmanError> {
+fn delete_map(
+    root_db: &Db,
+    id: u32,
+    map_owner_id: Option<u32>,
+    namespace: Option<&str>,
+) -> Result<(), BpfmanError> {
     let index = match map_owner_id {
         Some(i) => i,
         None => id,
@@ -1062,7 +4261,7 @@ fn delete_map(root_db: &Db, id: u32, map_owner_id: Option<u32>) -> Result<(), Bp
         set_maps_used_by(map.clone(), used_by.clone())?;
 
         if used_by.is_empty() {
-            let path: PathBuf = calc_map_pin_path(index);
+            let path: PathBuf = calc_map_pin_path(namespace, index);
             // No more programs using this map, so remove the entry from the map list.
             root_db
                 .drop_tree(MAP_PREFIX.to_string() + &index.to_string())
@@ -1089,9 +4288,10 @@ fn delete_map(root_db: &Db, id: u32, map_owner_id: Option<u32>) -> Result<(), Bp
 // map_pin_path is a the directory the maps are located. Currently, it
 // is a fixed bpfman location containing the map_index, which is a ID.
 // The ID is either the programs ID, or the ID of another program
-// that map_owner_id references.
-pub(crate) fn calc_map_pin_path(id: u32) -> PathBuf {
-    PathBuf::from(format!("{RTDIR_FS_MAPS}/{}", id))
+// that map_owner_id references. Programs in a pin namespace keep their maps
+// in the namespace's maps directory.
+pub(crate) fn calc_map_pin_path(namespace: Option<&str>, id: u32) -> PathBuf {
+    PathBuf::from(format!("{}/maps/{}", pin_namespace::pin_dir(namespace), id))
 }
 
 // Create the map_pin_path for a given program.
@@ -1148,3 +4348,273 @@ fn get_map(id: u32, root_db: &Db) -> Option<sled::Tree> {
         .find(|n| bytes_to_string(n) == format!("{}{}", MAP_PREFIX, id))
         .map(|n| root_db.open_tree(n).expect("unable to open map tree"))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{Location, TailCallProgram, TcProceedOn, TracepointProgram, XdpProceedOn};
+
+    fn program_data(name: &str) -> ProgramData {
+        ProgramData::new(
+            Location::File("/tmp/bytecode.o".to_string()),
+            name.to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data")
+    }
+
+    fn store_tracepoint(root_db: &Db, id: u32) {
+        let data = program_data("enter_openat");
+        let mut program = Program::Tracepoint(
+            TracepointProgram::new(data, "syscalls/sys_enter_openat".to_string())
+                .expect("unable to create tracepoint program"),
+        );
+        program
+            .get_data_mut()
+            .swap_tree(root_db, id)
+            .expect("unable to store program");
+    }
+
+    #[test]
+    fn test_program_from_section() {
+        let tests = [
+            ("kprobe/do_unlinkat", Some(ProgramType::Probe)),
+            ("kretprobe/do_unlinkat", Some(ProgramType::Probe)),
+            (
+                "tracepoint/syscalls/sys_enter_openat",
+                Some(ProgramType::Tracepoint),
+            ),
+            (
+                "tp/syscalls/sys_enter_openat",
+                Some(ProgramType::Tracepoint),
+            ),
+            ("fentry/do_unlinkat", Some(ProgramType::Tracing)),
+            ("fexit/do_unlinkat", Some(ProgramType::Tracing)),
+            ("kprobe", None),
+            ("tracepoint/sys_enter_openat", None),
+            ("fentry", None),
+            ("xdp", None),
+            ("classifier/tc", None),
+        ];
+        for (section, kind) in tests {
+            let program = program_from_section(program_data("prog"), section);
+            match kind {
+                Some(kind) => assert_eq!(program.unwrap().kind(), kind, "{section}"),
+                None => assert!(
+                    matches!(program, Err(BpfmanError::AttachInfoRequired { .. })),
+                    "{section}"
+                ),
+            }
+        }
+
+        let Program::Kprobe(kretprobe) =
+            program_from_section(program_data("prog"), "kretprobe/do_unlinkat").unwrap()
+        else {
+            panic!("not a kprobe");
+        };
+        assert!(kretprobe.get_retprobe().unwrap());
+        assert_eq!(kretprobe.get_fn_name().unwrap(), "do_unlinkat");
+
+        let Program::Tracepoint(tp) =
+            program_from_section(program_data("prog"), "tp/syscalls/sys_enter_openat").unwrap()
+        else {
+            panic!("not a tracepoint");
+        };
+        assert_eq!(tp.get_tracepoint().unwrap(), "syscalls/sys_enter_openat");
+    }
+
+    #[test]
+    fn test_release_inline_bytecode() {
+        let root_db = get_db_config().open().unwrap();
+        let bytecode = b"test_release_inline_bytecode".to_vec();
+        write_inline_bytecode(&bytecode).unwrap();
+        let path = inline_bytecode_path(&bytecode);
+
+        // Kept while a program loaded from it is left.
+        let data = ProgramData::new(
+            Location::File(path.clone()),
+            "enter_openat".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let mut program = Program::Tracepoint(
+            TracepointProgram::new(data, "syscalls/sys_enter_openat".to_string()).unwrap(),
+        );
+        program.get_data_mut().swap_tree(&root_db, 7).unwrap();
+        release_inline_bytecode(&root_db, &Location::File(path.clone()));
+        assert!(Path::new(&path).exists());
+
+        root_db.drop_tree(PROGRAM_PREFIX.to_string() + "7").unwrap();
+        release_inline_bytecode(&root_db, &Location::File(path.clone()));
+        assert!(!Path::new(&path).exists());
+
+        // Other files are never removed.
+        let dir = tempfile::TempDir::new().unwrap();
+        let other = dir.path().join("prog.o");
+        fs::write(&other, &bytecode).unwrap();
+        release_inline_bytecode(&root_db, &Location::File(other.display().to_string()));
+        assert!(other.exists());
+    }
+
+    #[tokio::test]
+    async fn test_program_link_status() {
+        let root_db = get_db_config().open().unwrap();
+        let links = HashMap::from([(7, 70)]);
+
+        store_tracepoint(&root_db, 7);
+        let tracepoint = get(&root_db, &7).unwrap();
+        let status = program_link_status(&root_db, &tracepoint, &links, &mut HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!((status.link_id, status.attached), (Some(70), Some(true)));
+
+        store_tracepoint(&root_db, 8);
+        let tracepoint = get(&root_db, &8).unwrap();
+        let status = program_link_status(&root_db, &tracepoint, &links, &mut HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!((status.link_id, status.attached), (None, Some(false)));
+
+        // Tail call programs have no link.
+        let mut tail_call = Program::TailCall(
+            TailCallProgram::new(program_data("tail_call"), "jump_table".to_string(), 0).unwrap(),
+        );
+        tail_call.get_data_mut().swap_tree(&root_db, 9).unwrap();
+        let status = program_link_status(&root_db, &tail_call, &links, &mut HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!((status.link_id, status.attached), (None, None));
+    }
+
+    #[test]
+    fn test_check_free_slots() {
+        let did = DispatcherId::Xdp(DispatcherInfo(1, None, None));
+        let capacity = did.capacity();
+        assert!(check_free_slots(&did, capacity - 2, "eth0".to_string(), 2).is_ok());
+        assert!(matches!(
+            check_free_slots(&did, capacity - 1, "eth0".to_string(), 2),
+            Err(BpfmanError::DispatcherFull { free: 1, .. })
+        ));
+        assert!(matches!(
+            check_free_slots(&did, capacity + 1, "eth0".to_string(), 1),
+            Err(BpfmanError::DispatcherFull { free: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_prune_programs_not_in() {
+        let root_db = get_db_config().open().unwrap();
+        store_tracepoint(&root_db, 7);
+        store_tracepoint(&root_db, 8);
+
+        assert_eq!(
+            prune_programs_not_in(&root_db, &HashSet::from([7])),
+            vec![8]
+        );
+        let ids: Vec<u32> = get_programs_iter(&root_db).map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![7]);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut xdp = XdpProgram::new(
+            program_data("pass"),
+            50,
+            "lo".to_string(),
+            XdpProceedOn::default(),
+            None,
+        )
+        .unwrap();
+        xdp.set_enabled(false).unwrap();
+        xdp.set_if_index(1).unwrap();
+        let Program::Xdp(new) = replacement(&Program::Xdp(xdp), program_data("pass")).unwrap()
+        else {
+            panic!("not an XDP program");
+        };
+        assert!(!new.get_enabled().unwrap());
+        assert_eq!(new.get_priority().unwrap(), 50);
+        assert_eq!(new.get_if_index().unwrap(), Some(1));
+
+        let mut tc = TcProgram::new(
+            program_data("pass"),
+            50,
+            "lo".to_string(),
+            TcProceedOn::default(),
+            Direction::Ingress,
+            None,
+        )
+        .unwrap();
+        let Program::Tc(new) = replacement(&Program::Tc(tc.clone()), program_data("pass")).unwrap()
+        else {
+            panic!("not a TC program");
+        };
+        assert!(new.get_enabled().unwrap());
+        tc.set_enabled(false).unwrap();
+        let Program::Tc(new) = replacement(&Program::Tc(tc), program_data("pass")).unwrap() else {
+            panic!("not a TC program");
+        };
+        assert!(!new.get_enabled().unwrap());
+    }
+
+    #[test]
+    fn test_section_matches() {
+        let xdp = Program::Xdp(
+            XdpProgram::new(
+                program_data(""),
+                50,
+                "lo".to_string(),
+                XdpProceedOn::default(),
+                None,
+            )
+            .unwrap(),
+        );
+        let kprobe = Program::Kprobe(
+            KprobeProgram::new(program_data(""), "do_sys_open".to_string(), 0, false, None)
+                .unwrap(),
+        );
+        let section = |s: &str| s.parse::<ProgramSection>().unwrap();
+
+        assert!(section_matches(&xdp, &section("xdp")));
+        assert!(section_matches(&xdp, &section("xdp.frags")));
+        assert!(!section_matches(&xdp, &section("classifier")));
+        assert!(section_matches(&kprobe, &section("kprobe")));
+        assert!(section_matches(&kprobe, &section("kretprobe")));
+        assert!(!section_matches(&kprobe, &section("tracepoint")));
+    }
+
+    #[test]
+    fn test_find_program_name() {
+        let xdp = Program::Xdp(
+            XdpProgram::new(
+                program_data(""),
+                50,
+                "lo".to_string(),
+                XdpProceedOn::default(),
+                None,
+            )
+            .unwrap(),
+        );
+        let names = ["pass", "drop", "count_opens"].map(String::from);
+        let sections = ["xdp", "xdp", "tracepoint"].map(|s| s.parse::<ProgramSection>().unwrap());
+
+        // The only XDP program in the object is picked.
+        let found = find_program_name(&xdp, [(&names[0], &sections[0]), (&names[2], &sections[2])]);
+        assert_eq!(found.unwrap(), "pass");
+
+        // Either none or more than one fails, listing those there are.
+        match find_program_name(&xdp, [(&names[2], &sections[2])]) {
+            Err(BpfmanError::ProgramNameRequired { found, .. }) => assert!(found.is_empty()),
+            other => panic!("expected ProgramNameRequired, got {other:?}"),
+        }
+        match find_program_name(&xdp, names.iter().zip(sections.iter())) {
+            Err(BpfmanError::ProgramNameRequired { found, .. }) => {
+                assert_eq!(found, vec!["drop".to_string(), "pass".to_string()])
+            }
+            other => panic!("expected ProgramNameRequired, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Events are read from ringbufs and perf event arrays the way a program's own
+// userspace would read them, so a watch competes with any other reader of the
+// map: records a watch takes from a ringbuf aren't seen by other readers, and
+// opening a perf event array points its entries at the watch's buffers until
+// the watch ends.
+
+use std::{
+    io,
+    os::fd::{AsRawFd, RawFd},
+    time::Duration,
+};
+
+use aya::{
+    maps::{perf::PerfEventArrayBuffer, Map, MapData, PerfEventArray, RingBuf},
+    util::online_cpus,
+};
+use bytes::BytesMut;
+use log::debug;
+
+use crate::errors::BpfmanError;
+
+// Upper bound on the records returned by a single read, so that a busy map
+// doesn't keep the reader from handing over what it has.
+const MAX_EVENTS_PER_READ: usize = 256;
+
+// Size of each per-CPU perf buffer in pages, which must be a power of two.
+const PERF_BUFFER_PAGES: usize = 16;
+
+/// Records read from a watched map.
+pub(crate) struct Events {
+    pub(crate) records: Vec<Vec<u8>>,
+    pub(crate) lost: u64,
+}
+
+pub(crate) enum EventReader {
+    RingBuf(RingBuf<MapData>),
+    PerfEventArray {
+        buffers: Vec<PerfEventArrayBuffer<MapData>>,
+        out: Vec<BytesMut>,
+    },
+}
+
+impl EventReader {
+    /// Starts reading events from `map`, which must be a ringbuf or a perf
+    /// event array.
+    pub(crate) fn new(map: Map) -> Result<Self, BpfmanError> {
+        match map {
+            Map::RingBuf(_) => {
+                Ok(Self::RingBuf(RingBuf::try_from(map).map_err(|e| {
+                    BpfmanError::Error(format!("unable to open ringbuf: {e}"))
+                })?))
+            }
+            Map::PerfEventArray(_) => {
+                let mut array = PerfEventArray::try_from(map).map_err(|e| {
+                    BpfmanError::Error(format!("unable to open perf event array: {e}"))
+                })?;
+                let mut buffers = vec![];
+                for cpu in online_cpus()? {
+                    buffers.push(array.open(cpu, Some(PERF_BUFFER_PAGES)).map_err(|e| {
+                        BpfmanError::Error(format!("unable to open perf buffer for CPU {cpu}: {e}"))
+                    })?);
+                }
+                debug!("Opened {} perf buffers", buffers.len());
+                Ok(Self::PerfEventArray {
+                    buffers,
+                    out: vec![BytesMut::new(); MAX_EVENTS_PER_READ],
+                })
+            }
+            _ => Err(BpfmanError::Error(
+                "only ringbufs and perf event arrays can be watched".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the records that are waiting, waiting up to `timeout` for one
+    /// to arrive if there are none.
+    pub(crate) fn read(&mut self, timeout: Duration) -> Result<Events, BpfmanError> {
+        let events = self.drain()?;
+        if !events.records.is_empty() || events.lost > 0 {
+            return Ok(events);
+        }
+        if !poll(&self.fds(), timeout)? {
+            return Ok(events);
+        }
+        self.drain()
+    }
+
+    fn fds(&self) -> Vec<RawFd> {
+        match self {
+            Self::RingBuf(ring) => vec![ring.as_raw_fd()],
+            Self::PerfEventArray { buffers, .. } => buffers.iter().map(|b| b.as_raw_fd()).collect(),
+        }
+    }
+
+    fn drain(&mut self) -> Result<Events, BpfmanError> {
+        let mut events = Events {
+            records: vec![],
+            lost: 0,
+        };
+        match self {
+            Self::RingBuf(ring) => {
+                while events.records.len() < MAX_EVENTS_PER_READ {
+                    match ring.next() {
+                        Some(item) => events.records.push(item.to_vec()),
+                        None => break,
+                    }
+                }
+            }
+            Self::PerfEventArray { buffers, out } => {
+                for buffer in buffers.iter_mut().filter(|b| b.readable()) {
+                    let room = MAX_EVENTS_PER_READ - events.records.len();
+                    if room == 0 {
+                        break;
+                    }
+                    let read = buffer.read_events(&mut out[..room]).map_err(|e| {
+                        BpfmanError::Error(format!("unable to read perf buffer: {e}"))
+                    })?;
+                    events
+                        .records
+                        .extend(out[..read.read].iter().map(|b| b.to_vec()));
+                    events.lost += read.lost as u64;
+                }
+            }
+        }
+        Ok(events)
+    }
+}
+
+// Returns whether any of `fds` became readable within `timeout`.
+fn poll(fds: &[RawFd], timeout: Duration) -> Result<bool, BpfmanError> {
+    let mut pollfds: Vec<libc::pollfd> = fds
+        .iter()
+        .map(|fd| libc::pollfd {
+            fd: *fd,
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+    let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+    let nfds = pollfds.len() as libc::nfds_t;
+    // SAFETY: pollfds is a valid array of nfds pollfd structs that outlives
+    // the call, and poll only writes to their revents fields.
+    let ret = unsafe { libc::poll(pollfds.as_mut_ptr(), nfds, timeout) };
+    if ret < 0 {
+        let e = io::Error::last_os_error();
+        if e.kind() == io::ErrorKind::Interrupted {
+            return Ok(false);
+        }
+        return Err(e.into());
+    }
+    Ok(ret > 0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Write, os::unix::net::UnixStream};
+
+    use super::*;
+
+    #[test]
+    fn test_poll() {
+        let (mut writer, reader) = UnixStream::pair().unwrap();
+        let fds = [reader.as_raw_fd()];
+        assert!(!poll(&fds, Duration::from_millis(10)).unwrap());
+
+        writer.write_all(b"record").unwrap();
+        assert!(poll(&fds, Duration::from_millis(10)).unwrap());
+        assert!(poll(&[], Duration::ZERO).is_ok_and(|ready| !ready));
+    }
+}
@@ -0,0 +1,624 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Aya only exposes typed map wrappers, which require the key and value types
+// to be known at compile time. bpfman operates on maps belonging to arbitrary
+// user programs, so entries are handled as byte slices sized from the map's
+// kernel info and the map element commands are issued directly.
+
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd, BorrowedFd},
+    path::{Path, PathBuf},
+};
+
+use aya::{
+    maps::{Map, MapData, MapInfo},
+    util::nr_cpus,
+};
+
+use serde_json::Value;
+
+use crate::{
+    btf::BtfTypes,
+    errors::BpfmanError,
+    sys::{
+        bpf, BPF_MAP_DELETE_ELEM, BPF_MAP_FREEZE, BPF_MAP_GET_NEXT_KEY, BPF_MAP_LOOKUP_BATCH,
+        BPF_MAP_LOOKUP_ELEM, BPF_MAP_UPDATE_ELEM,
+    },
+    types::ProgramMap,
+};
+
+// Returned by kernels without batch support for a map type, see
+// include/linux/errno.h.
+const ENOTSUPP: i32 = 524;
+
+// Upper bound when growing a batch to fit a hash bucket.
+const MAX_BATCH_SIZE: usize = 65536;
+
+// bpf_map_type values from include/uapi/linux/bpf.h
+const BPF_MAP_TYPE_PROG_ARRAY: u32 = 3;
+const BPF_MAP_TYPE_PERF_EVENT_ARRAY: u32 = 4;
+const BPF_MAP_TYPE_PERCPU_HASH: u32 = 5;
+const BPF_MAP_TYPE_PERCPU_ARRAY: u32 = 6;
+const BPF_MAP_TYPE_LRU_PERCPU_HASH: u32 = 10;
+const BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE: u32 = 21;
+const BPF_MAP_TYPE_RINGBUF: u32 = 27;
+
+// Local storage maps hold an entry per object, such as a socket or cgroup,
+// rather than a fixed number of entries.
+const LOCAL_STORAGE_MAP_TYPES: [u32; 6] = [19, 21, 24, 28, 29, 32];
+
+// Names of the bpf_map_type values, as bpftool shows them.
+const MAP_TYPE_NAMES: [&str; 33] = [
+    "unspec",
+    "hash",
+    "array",
+    "prog_array",
+    "perf_event_array",
+    "percpu_hash",
+    "percpu_array",
+    "stack_trace",
+    "cgroup_array",
+    "lru_hash",
+    "lru_percpu_hash",
+    "lpm_trie",
+    "array_of_maps",
+    "hash_of_maps",
+    "devmap",
+    "sockmap",
+    "cpumap",
+    "xskmap",
+    "sockhash",
+    "cgroup_storage",
+    "reuseport_sockarray",
+    "percpu_cgroup_storage",
+    "queue",
+    "stack",
+    "sk_storage",
+    "devmap_hash",
+    "struct_ops",
+    "ringbuf",
+    "inode_storage",
+    "task_storage",
+    "bloom_filter",
+    "user_ringbuf",
+    "cgrp_storage",
+];
+
+/// Returns the name bpftool gives the bpf_map_type `map_type`, or the number
+/// for types it doesn't know.
+pub(crate) fn map_type_name(map_type: u32) -> String {
+    MAP_TYPE_NAMES
+        .get(map_type as usize)
+        .map_or_else(|| map_type.to_string(), |n| n.to_string())
+}
+
+// Create a new element or update an existing one.
+const BPF_ANY: u64 = 0;
+
+// The anonymous struct of union bpf_attr used by the BPF_MAP_*_ELEM commands.
+#[repr(C)]
+#[derive(Default)]
+struct MapElemAttr {
+    map_fd: u32,
+    _pad: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+// The anonymous struct of union bpf_attr used by the BPF_MAP_*_BATCH commands.
+#[repr(C)]
+#[derive(Default)]
+struct MapBatchAttr {
+    in_batch: u64,
+    out_batch: u64,
+    keys: u64,
+    values: u64,
+    count: u32,
+    map_fd: u32,
+    elem_flags: u64,
+    flags: u64,
+}
+
+// The anonymous struct of union bpf_attr used by BPF_MAP_FREEZE.
+#[repr(C)]
+struct MapFdAttr {
+    map_fd: u32,
+}
+
+/// Entries read by [`BpfMap::lookup_batch`], as (key, values) pairs.
+pub(crate) struct Batch {
+    pub(crate) entries: Vec<(Vec<u8>, Vec<Vec<u8>>)>,
+    /// The position to continue from, or None once the end of the map was
+    /// reached.
+    pub(crate) next: Option<Vec<u8>>,
+}
+
+/// Decodes map values for the [`crate::types::MapValueFormat`]s other than
+/// raw.
+pub(crate) enum ValueDecoder {
+    // Arrays of native endian unsigned integers of the given size in bytes.
+    Uint(usize),
+    // Values of the given type.
+    Btf(BtfTypes, u32),
+}
+
+impl ValueDecoder {
+    /// Decodes each of `values` as JSON. Returns nothing if any of them
+    /// can't be decoded.
+    pub(crate) fn decode(&self, values: &[Vec<u8>]) -> Vec<String> {
+        values
+            .iter()
+            .map(|v| self.decode_value(v).map(|v| v.to_string()))
+            .collect::<Option<_>>()
+            .unwrap_or_default()
+    }
+
+    fn decode_value(&self, value: &[u8]) -> Option<Value> {
+        match self {
+            ValueDecoder::Uint(size) => {
+                if !value.len().is_multiple_of(*size) {
+                    return None;
+                }
+                value
+                    .chunks(*size)
+                    .map(|c| match size {
+                        4 => Some(Value::from(u32::from_ne_bytes(c.try_into().ok()?))),
+                        8 => Some(Value::from(u64::from_ne_bytes(c.try_into().ok()?))),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()
+                    .map(Value::Array)
+            }
+            ValueDecoder::Btf(btf, type_id) => btf.decode(*type_id, value),
+        }
+    }
+}
+
+/// Sums each of the native endian unsigned counters of `width` bytes in
+/// `values` across the values, such as the per-CPU values of an entry.
+pub(crate) fn sum_counters(values: &[Vec<u8>], width: usize) -> Vec<u64> {
+    let mut sums = vec![0u64; values.first().map_or(0, |v| v.len() / width)];
+    for value in values {
+        for (sum, c) in sums.iter_mut().zip(value.chunks_exact(width)) {
+            let n = match width {
+                1 => c[0] as u64,
+                2 => u16::from_ne_bytes([c[0], c[1]]) as u64,
+                4 => u32::from_ne_bytes([c[0], c[1], c[2], c[3]]) as u64,
+                _ => u64::from_ne_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]),
+            };
+            *sum = sum.wrapping_add(n);
+        }
+    }
+    sums
+}
+
+/// Checks that a map of `map_type` can be created with `max_entries` entries,
+/// returning the reason it can't otherwise.
+pub(crate) fn check_max_entries(map_type: u32, max_entries: u32) -> Result<(), String> {
+    if LOCAL_STORAGE_MAP_TYPES.contains(&map_type) {
+        let name = MAP_TYPE_NAMES.get(map_type as usize).unwrap_or(&"storage");
+        return Err(format!("{name} maps don't have a max_entries"));
+    }
+    if max_entries == 0 {
+        return Err("max_entries must be greater than 0".to_string());
+    }
+    if map_type == BPF_MAP_TYPE_RINGBUF && !max_entries.is_power_of_two() {
+        return Err("the size of a ringbuf must be a power of 2".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) struct BpfMap {
+    data: MapData,
+    info: MapInfo,
+}
+
+impl BpfMap {
+    pub(crate) fn from_pin<P: AsRef<Path>>(path: P) -> Result<Self, BpfmanError> {
+        let path = path.as_ref();
+        let data = MapData::from_pin(path).map_err(|e| {
+            BpfmanError::Error(format!("unable to open map {}: {e}", path.display()))
+        })?;
+        let info = data.info().map_err(|e| {
+            BpfmanError::Error(format!(
+                "unable to get map info for {}: {e}",
+                path.display()
+            ))
+        })?;
+
+        Ok(Self { data, info })
+    }
+
+    pub(crate) fn key_size(&self) -> usize {
+        self.info.key_size() as usize
+    }
+
+    pub(crate) fn value_size(&self) -> usize {
+        self.info.value_size() as usize
+    }
+
+    /// Describes the map, which is pinned at `pin_path` under `name`.
+    pub(crate) fn describe(&self, name: String, pin_path: PathBuf) -> ProgramMap {
+        let map_type = self.info.map_type();
+        ProgramMap {
+            name,
+            map_type: map_type_name(map_type),
+            key_size: self.info.key_size(),
+            value_size: self.info.value_size(),
+            max_entries: self.info.max_entries(),
+            pin_path,
+        }
+    }
+
+    pub(crate) fn is_per_cpu(&self) -> bool {
+        matches!(
+            self.info.map_type(),
+            BPF_MAP_TYPE_PERCPU_HASH
+                | BPF_MAP_TYPE_PERCPU_ARRAY
+                | BPF_MAP_TYPE_LRU_PERCPU_HASH
+                | BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE
+        )
+    }
+
+    /// Converts the map into the aya map events are read from, if it's a
+    /// ringbuf or a perf event array.
+    pub(crate) fn into_event_map(self, map_name: &str) -> Result<Map, BpfmanError> {
+        match self.info.map_type() {
+            BPF_MAP_TYPE_RINGBUF => Ok(Map::RingBuf(self.data)),
+            BPF_MAP_TYPE_PERF_EVENT_ARRAY => Ok(Map::PerfEventArray(self.data)),
+            _ => Err(BpfmanError::Error(format!(
+                "map {map_name} is not a ringbuf or perf event array"
+            ))),
+        }
+    }
+
+    /// Returns the value of `key`, as one value per possible CPU for per-CPU
+    /// maps and a single value otherwise.
+    pub(crate) fn lookup(&self, key: &[u8]) -> Result<Vec<Vec<u8>>, BpfmanError> {
+        self.check_key_size(key)?;
+
+        let (slots, slot_size) = self.value_layout()?;
+        let mut value = vec![0u8; slots * slot_size];
+        self.elem_op(
+            BPF_MAP_LOOKUP_ELEM,
+            key,
+            value.as_mut_ptr() as u64,
+            0,
+            "lookup",
+        )?;
+
+        let value_size = self.info.value_size() as usize;
+        Ok(value
+            .chunks(slot_size)
+            .map(|v| v[..value_size].to_vec())
+            .collect())
+    }
+
+    /// Sets the value of `key`. For per-CPU maps `values` holds either one
+    /// value per possible CPU, or a single value that is applied to every CPU.
+    pub(crate) fn update(&self, key: &[u8], values: &[Vec<u8>]) -> Result<(), BpfmanError> {
+        self.check_key_size(key)?;
+
+        let value_size = self.info.value_size() as usize;
+        if let Some(v) = values.iter().find(|v| v.len() != value_size) {
+            return Err(BpfmanError::InvalidMapValueSize {
+                expected: self.info.value_size(),
+                actual: v.len(),
+            });
+        }
+
+        let (slots, slot_size) = self.value_layout()?;
+        let values: Vec<&Vec<u8>> = if values.len() == slots {
+            values.iter().collect()
+        } else if values.len() == 1 {
+            std::iter::repeat_n(&values[0], slots).collect()
+        } else {
+            return Err(BpfmanError::Error(format!(
+                "expected {slots} map value(s) but got {}",
+                values.len()
+            )));
+        };
+
+        let mut value = vec![0u8; slots * slot_size];
+        for (slot, v) in value.chunks_mut(slot_size).zip(values) {
+            slot[..value_size].copy_from_slice(v);
+        }
+        self.elem_op(
+            BPF_MAP_UPDATE_ELEM,
+            key,
+            value.as_mut_ptr() as u64,
+            BPF_ANY,
+            "update",
+        )
+    }
+
+    pub(crate) fn delete(&self, key: &[u8]) -> Result<(), BpfmanError> {
+        self.check_key_size(key)?;
+        self.elem_op(BPF_MAP_DELETE_ELEM, key, 0, 0, "delete")
+    }
+
+    /// Makes the map read-only from user space. Returns false if the map was
+    /// frozen already, which the kernel reports the same way as a map that is
+    /// memory mapped for writing.
+    pub(crate) fn freeze(&self) -> Result<bool, BpfmanError> {
+        let mut attr = MapFdAttr {
+            map_fd: self.data.fd().as_fd().as_raw_fd() as u32,
+        };
+        match bpf(BPF_MAP_FREEZE, &mut attr) {
+            Ok(_) => Ok(true),
+            Err(e) if e.raw_os_error() == Some(libc::EBUSY) => Ok(false),
+            Err(e) if e.raw_os_error() == Some(ENOTSUPP) => Err(BpfmanError::Error(
+                "maps of this type, or with spin locks or timers in their values, can't be frozen"
+                    .to_string(),
+            )),
+            Err(e) => Err(BpfmanError::MapOperationError("freeze".to_string(), e)),
+        }
+    }
+
+    /// Returns the key following `key`, or the first key if `key` is None.
+    /// Returns None once there are no more keys.
+    pub(crate) fn next_key(&self, key: Option<&[u8]>) -> Result<Option<Vec<u8>>, BpfmanError> {
+        if let Some(key) = key {
+            self.check_key_size(key)?;
+        }
+
+        let mut next = vec![0u8; self.info.key_size() as usize];
+        let mut attr = MapElemAttr {
+            map_fd: self.data.fd().as_fd().as_raw_fd() as u32,
+            key: key.map_or(0, |k| k.as_ptr() as u64),
+            value: next.as_mut_ptr() as u64,
+            ..Default::default()
+        };
+        match bpf(BPF_MAP_GET_NEXT_KEY, &mut attr) {
+            Ok(_) => Ok(Some(next)),
+            Err(e) if e.raw_os_error() == Some(libc::ENOENT) => Ok(None),
+            Err(e) => Err(BpfmanError::MapOperationError(
+                "get next key".to_string(),
+                e,
+            )),
+        }
+    }
+
+    /// Reads up to `count` entries starting from `in_batch`, which is the
+    /// `next` of the previous batch or None to start from the beginning.
+    /// Returns None if the kernel doesn't support batch lookups for this map.
+    pub(crate) fn lookup_batch(
+        &self,
+        in_batch: Option<&[u8]>,
+        count: usize,
+    ) -> Result<Option<Batch>, BpfmanError> {
+        let key_size = self.info.key_size() as usize;
+        let value_size = self.info.value_size() as usize;
+        let (slots, slot_size) = self.value_layout()?;
+        // The batch position is a bucket index for hash maps and a key
+        // otherwise.
+        let batch_size = key_size.max(mem::size_of::<u32>());
+
+        let mut count = count.max(1);
+        loop {
+            let mut out_batch = vec![0u8; batch_size];
+            let mut keys = vec![0u8; count * key_size];
+            let mut values = vec![0u8; count * slots * slot_size];
+            let mut attr = MapBatchAttr {
+                in_batch: in_batch.map_or(0, |b| b.as_ptr() as u64),
+                out_batch: out_batch.as_mut_ptr() as u64,
+                keys: keys.as_mut_ptr() as u64,
+                values: values.as_mut_ptr() as u64,
+                count: count as u32,
+                map_fd: self.data.fd().as_fd().as_raw_fd() as u32,
+                ..Default::default()
+            };
+
+            let done = match bpf(BPF_MAP_LOOKUP_BATCH, &mut attr) {
+                Ok(_) => false,
+                // The end of the map was reached, possibly after some entries.
+                Err(e) if e.raw_os_error() == Some(libc::ENOENT) => true,
+                // A hash bucket holds more entries than fit in the batch.
+                Err(e) if e.raw_os_error() == Some(libc::ENOSPC) && count < MAX_BATCH_SIZE => {
+                    count *= 2;
+                    continue;
+                }
+                Err(e)
+                    if matches!(
+                        e.raw_os_error(),
+                        Some(libc::EINVAL | libc::EOPNOTSUPP | ENOTSUPP)
+                    ) && in_batch.is_none() =>
+                {
+                    return Ok(None)
+                }
+                Err(e) => {
+                    return Err(BpfmanError::MapOperationError(
+                        "batch lookup".to_string(),
+                        e,
+                    ))
+                }
+            };
+
+            let entries = keys
+                .chunks(key_size)
+                .zip(values.chunks(slots * slot_size))
+                .take(attr.count as usize)
+                .map(|(k, v)| {
+                    (
+                        k.to_vec(),
+                        v.chunks(slot_size)
+                            .map(|v| v[..value_size].to_vec())
+                            .collect(),
+                    )
+                })
+                .collect();
+            return Ok(Some(Batch {
+                entries,
+                next: (!done).then_some(out_batch),
+            }));
+        }
+    }
+
+    /// Points `index` of this prog array at the program `fd`, so that tail
+    /// calls to the index run it.
+    pub(crate) fn set_tail_call(
+        &self,
+        map_name: &str,
+        index: u32,
+        fd: BorrowedFd<'_>,
+    ) -> Result<(), BpfmanError> {
+        self.check_tail_call_index(map_name, index)?;
+        let fd = fd.as_raw_fd() as u32;
+        self.update(&index.to_ne_bytes(), &[fd.to_ne_bytes().to_vec()])
+    }
+
+    /// Clears `index` of this prog array if the program with kernel ID `id`
+    /// is still the one registered there.
+    pub(crate) fn clear_tail_call(&self, index: u32, id: u32) -> Result<(), BpfmanError> {
+        // Looking up a prog array entry returns the ID of the program in it.
+        let key = index.to_ne_bytes();
+        match self.lookup(&key) {
+            Ok(values) if values.first() == Some(&id.to_ne_bytes().to_vec()) => self.delete(&key),
+            Ok(_) | Err(BpfmanError::MapKeyNotFound) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks that this is a prog array that `index` fits in.
+    pub(crate) fn check_tail_call_index(
+        &self,
+        map_name: &str,
+        index: u32,
+    ) -> Result<(), BpfmanError> {
+        if self.info.map_type() != BPF_MAP_TYPE_PROG_ARRAY {
+            return Err(BpfmanError::Error(format!(
+                "map {map_name} is not a prog array"
+            )));
+        }
+        let max_entries = self.info.max_entries();
+        if index >= max_entries {
+            return Err(BpfmanError::TailCallIndexOutOfRange {
+                map_name: map_name.to_string(),
+                index,
+                max_entries,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_key_size(&self, key: &[u8]) -> Result<(), BpfmanError> {
+        check_key_size(self.info.key_size(), key)
+    }
+
+    fn value_layout(&self) -> Result<(usize, usize), BpfmanError> {
+        let nr_cpus = if self.is_per_cpu() {
+            Some(nr_cpus()?)
+        } else {
+            None
+        };
+        Ok(value_layout(self.info.value_size(), nr_cpus))
+    }
+
+    fn elem_op(
+        &self,
+        cmd: libc::c_long,
+        key: &[u8],
+        value: u64,
+        flags: u64,
+        op: &str,
+    ) -> Result<(), BpfmanError> {
+        let mut attr = MapElemAttr {
+            map_fd: self.data.fd().as_fd().as_raw_fd() as u32,
+            key: key.as_ptr() as u64,
+            value,
+            flags,
+            ..Default::default()
+        };
+
+        bpf(cmd, &mut attr)
+            .map(|_| ())
+            .map_err(|e| match e.raw_os_error() {
+                Some(libc::ENOENT) => BpfmanError::MapKeyNotFound,
+                _ => BpfmanError::MapOperationError(op.to_string(), e),
+            })
+    }
+}
+
+fn check_key_size(key_size: u32, key: &[u8]) -> Result<(), BpfmanError> {
+    if key.len() != key_size as usize {
+        return Err(BpfmanError::InvalidMapKeySize {
+            expected: key_size,
+            actual: key.len(),
+        });
+    }
+    Ok(())
+}
+
+// Returns the number of slots a map value is copied in and the size of each.
+// The kernel copies per-CPU values in slots rounded up to 8 bytes, one per
+// possible CPU, so nr_cpus is only given for per-CPU maps.
+fn value_layout(value_size: u32, nr_cpus: Option<usize>) -> (usize, usize) {
+    let value_size = value_size as usize;
+    match nr_cpus {
+        Some(nr_cpus) => (nr_cpus, value_size.next_multiple_of(8)),
+        None => (1, value_size),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_key_size() {
+        assert!(check_key_size(4, &[0; 4]).is_ok());
+        assert!(matches!(
+            check_key_size(4, &[0; 8]),
+            Err(BpfmanError::InvalidMapKeySize {
+                expected: 4,
+                actual: 8
+            })
+        ));
+        assert!(matches!(
+            check_key_size(4, &[]),
+            Err(BpfmanError::InvalidMapKeySize {
+                expected: 4,
+                actual: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_value_layout() {
+        assert_eq!(value_layout(12, None), (1, 12));
+        assert_eq!(value_layout(12, Some(4)), (4, 16));
+        assert_eq!(value_layout(8, Some(4)), (4, 8));
+        assert_eq!(value_layout(1, Some(2)), (2, 8));
+    }
+
+    #[test]
+    fn test_sum_counters() {
+        let values: Vec<Vec<u8>> = [[1u64, 10], [2, 20], [3, 30]]
+            .iter()
+            .map(|v| v.iter().flat_map(|n| n.to_ne_bytes()).collect())
+            .collect();
+        assert_eq!(sum_counters(&values, 8), vec![6, 60]);
+
+        let values: Vec<Vec<u8>> = [u32::MAX, 1]
+            .iter()
+            .map(|n| n.to_ne_bytes().to_vec())
+            .collect();
+        assert_eq!(sum_counters(&values, 4), vec![u32::MAX as u64 + 1]);
+        assert!(sum_counters(&[], 8).is_empty());
+    }
+
+    #[test]
+    fn test_check_max_entries() {
+        assert!(check_max_entries(1, 65536).is_ok());
+        assert!(check_max_entries(1, 0).is_err());
+        assert!(check_max_entries(BPF_MAP_TYPE_RINGBUF, 1 << 20).is_ok());
+        assert!(check_max_entries(BPF_MAP_TYPE_RINGBUF, 100000).is_err());
+        assert_eq!(
+            check_max_entries(24, 1024),
+            Err("sk_storage maps don't have a max_entries".to_string())
+        );
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use log::warn;
+use sled::Db;
+
+use crate::{
+    multiprog::{Dispatcher, TC_DISPATCHER_PREFIX, XDP_DISPATCHER_PREFIX},
+    types::Program,
+    utils::bytes_to_string,
+};
+
+// Counters are kept for the lifetime of the process, so they are only
+// meaningful for long running users of the library such as bpfman-rpc.
+static LOADS_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+static LOADS_FAILED: AtomicU64 = AtomicU64::new(0);
+static UNLOADS_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+static UNLOADS_FAILED: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn record_load<T, E>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => LOADS_SUCCEEDED.fetch_add(1, Ordering::Relaxed),
+        Err(_) => LOADS_FAILED.fetch_add(1, Ordering::Relaxed),
+    };
+}
+
+pub(crate) fn record_unload<T, E>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => UNLOADS_SUCCEEDED.fetch_add(1, Ordering::Relaxed),
+        Err(_) => UNLOADS_FAILED.fetch_add(1, Ordering::Relaxed),
+    };
+}
+
+/// Renders the current metrics in the Prometheus text exposition format.
+pub(crate) fn encode(programs: &[Program], root_db: &Db) -> String {
+    let mut out = String::new();
+
+    // Programs loaded by bpfman, keyed by (iface, type). The interface is
+    // empty for program types that aren't attached to one.
+    let mut loaded: BTreeMap<(String, String), u64> = BTreeMap::new();
+    for p in programs {
+        let iface = match p {
+            Program::Xdp(_) | Program::Tc(_) => p.if_name().unwrap_or_default(),
+            _ => String::new(),
+        };
+        *loaded.entry((iface, p.kind().to_string())).or_default() += 1;
+    }
+    writeln!(
+        out,
+        "# HELP bpfman_programs_loaded Number of eBPF programs loaded by bpfman."
+    )
+    .unwrap();
+    writeln!(out, "# TYPE bpfman_programs_loaded gauge").unwrap();
+    for ((iface, kind), count) in loaded {
+        writeln!(
+            out,
+            "bpfman_programs_loaded{{iface=\"{iface}\",type=\"{kind}\"}} {count}"
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        out,
+        "# HELP bpfman_dispatcher_revision Current revision of the dispatcher on an interface."
+    )
+    .unwrap();
+    writeln!(out, "# TYPE bpfman_dispatcher_revision gauge").unwrap();
+    for ((iface, kind), revision) in dispatcher_revisions(root_db) {
+        writeln!(
+            out,
+            "bpfman_dispatcher_revision{{iface=\"{iface}\",type=\"{kind}\"}} {revision}"
+        )
+        .unwrap();
+    }
+
+    for (name, help, succeeded, failed) in [
+        (
+            "bpfman_program_loads_total",
+            "Number of program load requests.",
+            &LOADS_SUCCEEDED,
+            &LOADS_FAILED,
+        ),
+        (
+            "bpfman_program_unloads_total",
+            "Number of program unload requests.",
+            &UNLOADS_SUCCEEDED,
+            &UNLOADS_FAILED,
+        ),
+    ] {
+        writeln!(out, "# HELP {name} {help}").unwrap();
+        writeln!(out, "# TYPE {name} counter").unwrap();
+        writeln!(
+            out,
+            "{name}{{result=\"success\"}} {}",
+            succeeded.load(Ordering::Relaxed)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{name}{{result=\"failure\"}} {}",
+            failed.load(Ordering::Relaxed)
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+// Returns the highest dispatcher revision keyed by (iface, type), where type
+// is "xdp" or "tc_<direction>".
+fn dispatcher_revisions(root_db: &Db) -> BTreeMap<(String, String), u32> {
+    let mut revisions = BTreeMap::new();
+    for name in root_db.tree_names() {
+        let name = bytes_to_string(&name);
+        if !name.contains(XDP_DISPATCHER_PREFIX) && !name.contains(TC_DISPATCHER_PREFIX) {
+            continue;
+        }
+        let tree = match root_db.open_tree(&name) {
+            Ok(t) => t,
+            Err(e) => {
+                warn!("Unable to open dispatcher tree {name}: {e}");
+                continue;
+            }
+        };
+        let (iface, kind, revision) = match Dispatcher::new_from_db(tree) {
+            Dispatcher::Xdp(d) => (d.get_ifname(), Ok("xdp".to_string()), d.get_revision()),
+            Dispatcher::Tc(d) => (
+                d.get_ifname(),
+                d.get_direction().map(|dir| format!("tc_{dir}")),
+                d.get_revision(),
+            ),
+        };
+        match (iface, kind, revision) {
+            (Ok(iface), Ok(kind), Ok(revision)) => {
+                let r = revisions.entry((iface, kind)).or_insert(revision);
+                *r = (*r).max(revision);
+            }
+            _ => warn!("Unable to read dispatcher state from {name}"),
+        }
+    }
+    revisions
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        get_db_config,
+        types::{Location, ProgramData, XdpProceedOn, XdpProgram},
+    };
+
+    #[test]
+    fn test_encode_programs_loaded() {
+        let root_db = get_db_config().open().expect("unable to open database");
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pass.o".to_string()),
+            "pass".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        let prog = Program::Xdp(
+            XdpProgram::new(data, 50, "eth0".to_string(), XdpProceedOn::default(), None)
+                .expect("unable to create xdp program"),
+        );
+
+        let out = encode(&[prog.clone(), prog], &root_db);
+        assert!(out.contains("bpfman_programs_loaded{iface=\"eth0\",type=\"xdp\"} 2\n"));
+        assert!(out.contains("# TYPE bpfman_program_loads_total counter\n"));
+        assert!(out.contains("bpfman_program_unloads_total{result=\"failure\"} "));
+    }
+}
@@ -4,22 +4,35 @@
 mod tc;
 mod xdp;
 
+use std::path::{Path, PathBuf};
+
 use log::debug;
 use sled::Db;
 pub use tc::TcDispatcher;
 pub use xdp::XdpDispatcher;
 
 use crate::{
-    config::{InterfaceConfig, XdpMode},
+    config::{DispatchersConfig, InterfaceConfig, XdpMode},
+    dispatcher_config::{MAX_DISPATCHER_ACTIONS, TC_MAX_DISPATCHER_ACTIONS},
     errors::BpfmanError,
+    netdev::{check_xdp_mode, upper_device, UpperDevice, UpperKind},
     oci_utils::image_manager::ImageManager,
-    types::{Direction, Program, ProgramType},
-    utils::bytes_to_string,
+    types::{Direction, ImagePullPolicy, Program, ProgramType, XdpModePreference},
+    utils::{bytes_to_string, read},
 };
 
 pub(crate) const TC_DISPATCHER_PREFIX: &str = "tc_dispatcher_";
 pub(crate) const XDP_DISPATCHER_PREFIX: &str = "xdp_dispatcher_";
 
+// The chain call actions of a disabled program, which carry on to the next
+// program whatever it returns.
+pub(crate) const PROCEED_ON_ANY: u32 = u32::MAX;
+
+// Names of the programs in the dispatcher objects, for objects read from a
+// path. The labels of a dispatcher image name its program.
+const XDP_DISPATCHER_PROGRAM: &str = "xdp_dispatcher";
+const TC_DISPATCHER_PROGRAM: &str = "tc_dispatcher";
+
 #[derive(Debug)]
 pub(crate) enum Dispatcher {
     Xdp(XdpDispatcher),
@@ -30,6 +43,7 @@ impl Dispatcher {
     pub async fn new(
         root_db: &Db,
         config: Option<&InterfaceConfig>,
+        dispatchers: &DispatchersConfig,
         programs: &mut [Program],
         revision: u32,
         old_dispatcher: Option<Dispatcher>,
@@ -44,18 +58,53 @@ impl Dispatcher {
             .ok_or_else(|| BpfmanError::Error("missing ifindex".to_string()))?;
         let if_name = p.if_name()?;
         let direction = p.direction()?;
-        let xdp_mode = if let Some(c) = config {
-            c.xdp_mode()
-        } else {
-            &XdpMode::Skb
-        };
+        let netns = p.netns()?;
+        let netns_id = p.netns_id()?;
+        let pin_dir = pin_dir(programs, old_dispatcher.as_ref())?;
         let d = match p.kind() {
             ProgramType::Xdp => {
-                let mut x =
-                    XdpDispatcher::new(root_db, xdp_mode, if_index, if_name.to_string(), revision)?;
+                let (xdp_mode, fallback) =
+                    xdp_mode(config, programs, old_dispatcher.as_ref(), &if_name)?;
+                let upper = upper_device(if_index, netns.as_deref()).await?;
+                let (xdp_mode, fallback) =
+                    check_xdp_mode(&if_name, upper.as_ref(), xdp_mode, fallback)?;
+                let mut x = XdpDispatcher::new(
+                    root_db,
+                    &xdp_mode,
+                    if_index,
+                    if_name.to_string(),
+                    netns,
+                    netns_id,
+                    revision,
+                )?;
+                if let Some(ref dir) = pin_dir {
+                    x.set_pin_dir(dir)?;
+                }
 
-                x.load(root_db, programs, old_dispatcher, image_manager)
-                    .await?;
+                // The bonding driver runs native programs on each member.
+                let members = match upper {
+                    Some(UpperDevice {
+                        kind: UpperKind::Bond,
+                        lower,
+                    }) => lower,
+                    _ => vec![],
+                };
+                let bytecode = dispatcher_bytecode(
+                    root_db,
+                    image_manager,
+                    &dispatchers.xdp_image,
+                    dispatchers.xdp_path.as_deref(),
+                    XDP_DISPATCHER_PROGRAM,
+                )
+                .await?;
+                x.load(
+                    root_db,
+                    programs,
+                    old_dispatcher,
+                    &bytecode,
+                    fallback,
+                    &members,
+                )?;
                 Dispatcher::Xdp(x)
             }
             ProgramType::Tc => {
@@ -64,11 +113,23 @@ impl Dispatcher {
                     direction.expect("missing direction"),
                     if_index,
                     if_name.to_string(),
+                    netns,
+                    netns_id,
                     revision,
                 )?;
+                if let Some(ref dir) = pin_dir {
+                    t.set_pin_dir(dir)?;
+                }
 
-                t.load(root_db, programs, old_dispatcher, image_manager)
-                    .await?;
+                let bytecode = dispatcher_bytecode(
+                    root_db,
+                    image_manager,
+                    &dispatchers.tc_image,
+                    dispatchers.tc_path.as_deref(),
+                    TC_DISPATCHER_PROGRAM,
+                )
+                .await?;
+                t.load(root_db, programs, old_dispatcher, &bytecode).await?;
                 Dispatcher::Tc(t)
             }
             _ => return Err(BpfmanError::DispatcherNotRequired),
@@ -92,16 +153,49 @@ impl Dispatcher {
         }
     }
 
-    pub(crate) fn next_revision(&self) -> u32 {
-        let current = match self {
+    pub(crate) fn pin_dir(&self) -> Result<String, BpfmanError> {
+        match self {
+            Dispatcher::Xdp(d) => d.get_pin_dir(),
+            Dispatcher::Tc(d) => d.get_pin_dir(),
+        }
+    }
+
+    /// Returns the paths the dispatcher is pinned at.
+    pub(crate) fn pin_paths(&self) -> Result<Vec<PathBuf>, BpfmanError> {
+        match self {
+            Dispatcher::Xdp(d) => d.pin_paths(),
+            Dispatcher::Tc(d) => d.pin_paths(),
+        }
+    }
+
+    /// Returns whether the kernel still runs the dispatcher on its interface.
+    pub(crate) async fn is_attached(&self) -> Result<bool, BpfmanError> {
+        match self {
+            Dispatcher::Xdp(d) => d.is_attached().await,
+            Dispatcher::Tc(d) => d.is_attached().await,
+        }
+    }
+
+    pub(crate) fn if_name(&self) -> Result<String, BpfmanError> {
+        match self {
+            Dispatcher::Xdp(d) => d.get_ifname(),
+            Dispatcher::Tc(d) => d.get_ifname(),
+        }
+    }
+
+    pub(crate) fn revision(&self) -> u32 {
+        match self {
             Dispatcher::Xdp(d) => d
                 .get_revision()
                 .expect("failed to get xdp_dispatcher revision"),
             Dispatcher::Tc(d) => d
                 .get_revision()
                 .expect("failed to get tc_dispatcher revision"),
-        };
-        current.wrapping_add(1)
+        }
+    }
+
+    pub(crate) fn next_revision(&self) -> u32 {
+        self.revision().wrapping_add(1)
     }
 
     pub(crate) fn num_extensions(&self) -> usize {
@@ -116,11 +210,135 @@ impl Dispatcher {
     }
 }
 
+// Picks the mode to attach an XDP dispatcher in, and whether generic mode may
+// be used instead if the driver doesn't support it. A dispatcher that replaces
+// another takes over its link and so keeps its mode. Otherwise the mode asked
+// for by the programs is used, falling back to the one set for the interface.
+fn xdp_mode(
+    config: Option<&InterfaceConfig>,
+    programs: &[Program],
+    old_dispatcher: Option<&Dispatcher>,
+    if_name: &str,
+) -> Result<(XdpMode, bool), BpfmanError> {
+    let mut preferences = vec![];
+    for p in programs {
+        if let Program::Xdp(p) = p {
+            if let Some(preference) = p.get_mode_preference()? {
+                preferences.push(preference);
+            }
+        }
+    }
+
+    let (mode, fallback) = match (old_dispatcher, preferences.first()) {
+        (Some(Dispatcher::Xdp(old)), _) => (old.get_mode()?, false),
+        (_, Some(XdpModePreference::Skb)) => (XdpMode::Skb, false),
+        (_, Some(XdpModePreference::Drv)) => (XdpMode::Drv, false),
+        (_, Some(XdpModePreference::Hw)) => (XdpMode::Hw, false),
+        (_, Some(XdpModePreference::Auto)) => (
+            XdpMode::Drv,
+            preferences.iter().all(|p| p.accepts(XdpMode::Skb)),
+        ),
+        (_, None) => (config.map_or(XdpMode::Skb, |c| *c.xdp_mode()), false),
+    };
+
+    if let Some(requested) = preferences.iter().find(|p| !p.accepts(mode)) {
+        return Err(BpfmanError::XdpModeMismatch {
+            iface: if_name.to_string(),
+            mode: mode.to_string(),
+            requested: requested.to_string(),
+        });
+    }
+    Ok((mode, fallback))
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub(crate) enum DispatcherId {
     Xdp(DispatcherInfo),
     Tc(DispatcherInfo),
 }
 
+impl DispatcherId {
+    /// Returns the number of programs the dispatcher has slots for, which is
+    /// fixed when the dispatcher's bytecode is compiled.
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            DispatcherId::Xdp(_) => MAX_DISPATCHER_ACTIONS,
+            DispatcherId::Tc(_) => TC_MAX_DISPATCHER_ACTIONS,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
-pub(crate) struct DispatcherInfo(pub u32, pub Option<Direction>);
+pub(crate) struct DispatcherInfo(pub u32, pub Option<Direction>, pub Option<u64>);
+
+/// Returns the key used to name a dispatcher's database tree and bpffs pins.
+/// Interface indexes are only unique within a network namespace, so the
+/// namespace is part of the key for dispatchers outside of bpfman's own.
+// Returns the directory a new dispatcher for `programs` is pinned in, or None
+// for the default. A new revision is pinned where the one it replaces is, so
+// that it is attached through the same link, and the first dispatcher on an
+// interface goes in the interface's directory if its programs are pinned in
+// one.
+fn pin_dir(
+    programs: &[Program],
+    old_dispatcher: Option<&Dispatcher>,
+) -> Result<Option<String>, BpfmanError> {
+    if let Some(old) = old_dispatcher {
+        return old.pin_dir().map(Some);
+    }
+    for p in programs {
+        if let Some(dir) = p.get_data().get_iface_pin_dir()? {
+            let sub = match p.direction()? {
+                None => "xdp",
+                Some(Direction::Ingress) => "tc-ingress",
+                Some(Direction::Egress) => "tc-egress",
+            };
+            return Ok(Some(format!("{dir}/{sub}")));
+        }
+    }
+    Ok(None)
+}
+
+/// The object a dispatcher is loaded from, with the name of the dispatcher
+/// program in it.
+pub(crate) struct DispatcherBytecode {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) program_name: String,
+}
+
+// Returns the object a dispatcher is loaded from. With a path configured, the
+// object is read from it, so that a dispatcher built for the kernel at hand
+// can be used without publishing an image for it.
+async fn dispatcher_bytecode(
+    root_db: &Db,
+    image_manager: &mut ImageManager,
+    image: &str,
+    path: Option<&Path>,
+    program_name: &str,
+) -> Result<DispatcherBytecode, BpfmanError> {
+    if let Some(path) = path {
+        debug!("Reading dispatcher from {}", path.display());
+        let bytes = read(path, None).map_err(|e| {
+            BpfmanError::Error(format!("unable to read dispatcher {}: {e}", path.display()))
+        })?;
+        return Ok(DispatcherBytecode {
+            bytes,
+            program_name: program_name.to_string(),
+        });
+    }
+
+    let (path, program_name) = image_manager
+        .get_image(root_db, image, ImagePullPolicy::IfNotPresent, None, None)
+        .await?;
+    Ok(DispatcherBytecode {
+        bytes: image_manager.get_bytecode_from_image_store(root_db, path)?,
+        program_name,
+    })
+}
+
+pub(crate) fn dispatcher_key(if_index: u32, netns_id: Option<u64>) -> String {
+    match netns_id {
+        Some(id) => format!("ns{id}_{if_index}"),
+        None => if_index.to_string(),
+    }
+}
@@ -1,7 +1,10 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{fs, mem};
+use std::{
+    fs, mem,
+    path::{Path, PathBuf},
+};
 
 use aya::{
     programs::{
@@ -21,16 +24,15 @@ use crate::{
     directories::*,
     dispatcher_config::TcDispatcherConfig,
     errors::BpfmanError,
-    multiprog::{Dispatcher, TC_DISPATCHER_PREFIX},
-    oci_utils::image_manager::ImageManager,
-    types::{
-        BytecodeImage, Direction,
-        Direction::{Egress, Ingress},
-        ImagePullPolicy, Program, TcProgram,
+    multiprog::{
+        dispatcher_key, Dispatcher, DispatcherBytecode, PROCEED_ON_ANY, TC_DISPATCHER_PREFIX,
     },
+    netns::in_netns,
+    shared_maps::SharedMaps,
+    types::{Direction, Program, TcProgram},
     utils::{
-        bytes_to_string, bytes_to_u16, bytes_to_u32, bytes_to_usize, should_map_be_pinned,
-        sled_get, sled_get_option, sled_insert,
+        bytes_to_string, bytes_to_u16, bytes_to_u32, bytes_to_u64, bytes_to_usize, sled_get,
+        sled_get_option, sled_insert,
     },
 };
 
@@ -46,6 +48,9 @@ const DIRECTION: &str = "direction";
 const NUM_EXTENSIONS: &str = "num_extension";
 const PROGRAM_NAME: &str = "program_name";
 const HANDLE: &str = "handle";
+const NETNS: &str = "netns";
+const NETNS_ID: &str = "netns_id";
+const PIN_DIR: &str = "pin_dir";
 
 #[derive(Debug)]
 pub struct TcDispatcher {
@@ -59,12 +64,17 @@ impl TcDispatcher {
         direction: Direction,
         if_index: u32,
         if_name: String,
+        netns: Option<PathBuf>,
+        netns_id: Option<u64>,
         revision: u32,
     ) -> Result<Self, BpfmanError> {
         let db_tree = root_db
             .open_tree(format!(
                 "{}_{}_{}_{}",
-                TC_DISPATCHER_PREFIX, if_index, direction, revision
+                TC_DISPATCHER_PREFIX,
+                dispatcher_key(if_index, netns_id),
+                direction,
+                revision
             ))
             .expect("Unable to open tc dispatcher database tree");
 
@@ -78,6 +88,12 @@ impl TcDispatcher {
         dp.set_direction(direction)?;
         dp.set_revision(revision)?;
         dp.set_priority(TC_DISPATCHER_PRIORITY)?;
+        if let Some(n) = netns {
+            dp.set_netns(&n)?;
+        }
+        if let Some(id) = netns_id {
+            dp.set_netns_id(id)?;
+        }
         Ok(dp)
     }
 
@@ -94,11 +110,10 @@ impl TcDispatcher {
         root_db: &Db,
         programs: &mut [Program],
         old_dispatcher: Option<Dispatcher>,
-        image_manager: &mut ImageManager,
+        bytecode: &DispatcherBytecode,
     ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
-        let direction = self.get_direction()?;
 
         debug!("TcDispatcher::new() for if_index {if_index}, revision {revision}");
         let mut extensions: Vec<&mut TcProgram> = programs
@@ -108,56 +123,34 @@ impl TcDispatcher {
                 _ => panic!("All programs should be of type TC"),
             })
             .collect();
-        let mut chain_call_actions = [0; 10];
-        for v in extensions.iter() {
-            chain_call_actions[v.get_current_position()?.unwrap()] = v.get_proceed_on()?.mask()
-        }
-
         let config = TcDispatcherConfig {
             num_progs_enabled: extensions.len() as u8,
-            chain_call_actions,
+            chain_call_actions: chain_call_actions(&extensions)?,
             run_prios: [DEFAULT_PRIORITY; 10],
         };
 
         debug!("tc dispatcher config: {:?}", config);
-        let image = BytecodeImage::new(
-            "quay.io/bpfman/tc-dispatcher:v1".to_string(),
-            ImagePullPolicy::IfNotPresent as i32,
-            None,
-            None,
-        );
-
-        let (path, bpf_function_name) = image_manager
-            .get_image(
-                root_db,
-                &image.image_url,
-                image.image_pull_policy.clone(),
-                image.username.clone(),
-                image.password.clone(),
-            )
-            .await?;
-
-        let program_bytes = image_manager.get_bytecode_from_image_store(root_db, path)?;
-
         let mut loader = BpfLoader::new()
             .set_global("CONFIG", &config, true)
-            .load(&program_bytes)?;
+            .load(&bytecode.bytes)?;
 
-        let dispatcher: &mut SchedClassifier =
-            loader.program_mut(&bpf_function_name).unwrap().try_into()?;
+        let dispatcher: &mut SchedClassifier = loader
+            .program_mut(&bytecode.program_name)
+            .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(bytecode.program_name.clone()))?
+            .try_into()?;
 
         dispatcher.load()?;
 
-        let base = match direction {
-            Ingress => RTDIR_FS_TC_INGRESS,
-            Egress => RTDIR_FS_TC_EGRESS,
-        };
-        let path = format!("{base}/dispatcher_{if_index}_{revision}");
+        let path = format!(
+            "{}/dispatcher_{}_{revision}",
+            self.get_pin_dir()?,
+            self.get_key()?
+        );
         fs::create_dir_all(path).unwrap();
 
         self.loader = Some(loader);
         self.set_num_extensions(extensions.len())?;
-        self.set_program_name(&bpf_function_name)?;
+        self.set_program_name(&bytecode.program_name)?;
 
         self.attach_extensions(&mut extensions)?;
         self.attach(root_db, old_dispatcher).await?;
@@ -165,8 +158,14 @@ impl TcDispatcher {
     }
 
     /// has_qdisc returns true if the qdisc_name is found on the if_index.
-    async fn has_qdisc(qdisc_name: String, if_index: i32) -> Result<bool, anyhow::Error> {
-        let (connection, handle, _) = rtnetlink::new_connection().unwrap();
+    async fn has_qdisc(
+        qdisc_name: String,
+        if_index: i32,
+        netns: Option<&Path>,
+    ) -> Result<bool, anyhow::Error> {
+        // The netlink socket belongs to the namespace it was created in, so
+        // only its creation needs to happen inside the target namespace.
+        let (connection, handle, _) = in_netns(netns, || Ok(rtnetlink::new_connection()?))?;
         tokio::spawn(connection);
 
         let mut qdiscs = handle.qdisc().get().execute();
@@ -194,6 +193,7 @@ impl TcDispatcher {
         let revision = self.get_revision()?;
         let direction = self.get_direction()?;
         let program_name = self.get_program_name()?;
+        let netns = self.get_netns()?;
 
         debug!(
             "TcDispatcher::attach() for if_index {}, revision {}",
@@ -205,14 +205,16 @@ impl TcDispatcher {
         // qdisc, we return an error. If the qdisc is a clsact qdisc, we do nothing. Otherwise, we add a clsact qdisc.
 
         // no need to add a new clsact qdisc if one already exists.
-        if TcDispatcher::has_qdisc("clsact".to_string(), if_index as i32).await? {
+        if TcDispatcher::has_qdisc("clsact".to_string(), if_index as i32, netns.as_deref()).await? {
             debug!(
                 "clsact qdisc found for if_index {}, no need to add a new clsact qdisc",
                 if_index
             );
 
         // if ingress qdisc exists, return error.
-        } else if TcDispatcher::has_qdisc("ingress".to_string(), if_index as i32).await? {
+        } else if TcDispatcher::has_qdisc("ingress".to_string(), if_index as i32, netns.as_deref())
+            .await?
+        {
             debug!("ingress qdisc found for if_index {}", if_index);
             return Err(BpfmanError::InvalidAttach(format!(
                 "Ingress qdisc found for if_index {}",
@@ -222,7 +224,10 @@ impl TcDispatcher {
         // otherwise, add a new clsact qdisc.
         } else {
             debug!("No qdisc found for if_index {}, adding clsact", if_index);
-            let _ = tc::qdisc_add_clsact(&iface);
+            in_netns(netns.as_deref(), || {
+                let _ = tc::qdisc_add_clsact(&iface);
+                Ok(())
+            })?;
         }
 
         let new_dispatcher: &mut SchedClassifier = self
@@ -238,14 +243,16 @@ impl TcDispatcher {
             Direction::Egress => TcAttachType::Egress,
         };
 
-        let link_id = new_dispatcher.attach_with_options(
-            &iface,
-            attach_type,
-            TcOptions {
-                priority,
-                ..Default::default()
-            },
-        )?;
+        let link_id = in_netns(netns.as_deref(), || {
+            Ok(new_dispatcher.attach_with_options(
+                &iface,
+                attach_type,
+                TcOptions {
+                    priority,
+                    ..Default::default()
+                },
+            )?)
+        })?;
 
         let link = new_dispatcher.take_link(link_id)?;
         self.set_handle(link.handle())?;
@@ -269,8 +276,9 @@ impl TcDispatcher {
     fn attach_extensions(&mut self, extensions: &mut [&mut TcProgram]) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
-        let direction = self.get_direction()?;
         let program_name = self.get_program_name()?;
+        let key = self.get_key()?;
+        let dir = self.get_pin_dir()?;
 
         debug!(
             "TcDispatcher::attach_extensions() for if_index {}, revision {}",
@@ -294,21 +302,18 @@ impl TcDispatcher {
             if v.get_attached()? {
                 let id = v.data.get_id()?;
                 debug!("program {id} was already attached loading from pin");
-                let mut ext = Extension::from_pin(format!("{RTDIR_FS}/prog_{id}"))?;
+                let mut ext = Extension::from_pin(v.get_data().program_pin_path()?)?;
                 let target_fn = format!("prog{i}");
                 let new_link_id = ext
                     .attach_to_program(dispatcher.fd().unwrap(), &target_fn)
                     .unwrap();
                 let new_link: FdLink = ext.take_link(new_link_id)?.into();
-                let base = match direction {
-                    Direction::Ingress => RTDIR_FS_TC_INGRESS,
-                    Direction::Egress => RTDIR_FS_TC_EGRESS,
-                };
-                let path = format!("{base}/dispatcher_{if_index}_{}/link_{id}", revision);
+                let path = format!("{dir}/dispatcher_{key}_{revision}/link_{id}");
                 new_link.pin(path).map_err(BpfmanError::UnableToPinLink)?;
             } else {
                 let name = &v.data.get_name()?;
                 let global_data = &v.data.get_global_data()?;
+                let max_entries = &v.data.get_map_max_entries()?;
 
                 let mut bpf = BpfLoader::new();
 
@@ -317,6 +322,9 @@ impl TcDispatcher {
                 for (name, value) in global_data {
                     bpf.set_global(name, value.as_slice(), true);
                 }
+                for (name, n) in max_entries {
+                    bpf.set_max_entries(name, *n);
+                }
 
                 // If map_pin_path is set already it means we need to use a pin
                 // path which should already exist on the system.
@@ -324,6 +332,10 @@ impl TcDispatcher {
                     debug!("tc program {name} is using maps from {:?}", map_pin_path);
                     bpf.map_pin_path(map_pin_path);
                 }
+                let shared_maps = SharedMaps::prepare(v.get_data())?;
+                if let Some(ref shared) = shared_maps {
+                    bpf.map_pin_path(shared.pin_path());
+                }
 
                 let mut loader = bpf
                     .load(&v.get_data().get_program_bytes()?)
@@ -341,30 +353,24 @@ impl TcDispatcher {
 
                 let id = v.get_data().get_id()?;
 
-                ext.pin(format!("{RTDIR_FS}/prog_{id}"))
+                ext.pin(v.get_data().program_pin_path()?)
                     .map_err(BpfmanError::UnableToPinProgram)?;
                 let new_link_id = ext.attach()?;
                 let new_link = ext.take_link(new_link_id)?;
                 let fd_link: FdLink = new_link.into();
-                let base = match direction {
-                    Direction::Ingress => RTDIR_FS_TC_INGRESS,
-                    Direction::Egress => RTDIR_FS_TC_EGRESS,
-                };
                 fd_link
-                    .pin(format!(
-                        "{base}/dispatcher_{if_index}_{}/link_{id}",
-                        revision,
-                    ))
+                    .pin(format!("{dir}/dispatcher_{key}_{revision}/link_{id}"))
                     .map_err(BpfmanError::UnableToPinLink)?;
 
                 // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
                 if v.data.get_map_pin_path()?.is_none() {
-                    let map_pin_path = calc_map_pin_path(id);
+                    let map_pin_path =
+                        calc_map_pin_path(v.get_data().get_pin_namespace()?.as_deref(), id);
                     v.data.set_map_pin_path(&map_pin_path.clone())?;
                     create_map_pin_path(&map_pin_path)?;
 
                     for (name, map) in loader.maps_mut() {
-                        if !should_map_be_pinned(name) {
+                        if !v.data.should_pin_map(name)? {
                             continue;
                         }
                         debug!(
@@ -375,11 +381,43 @@ impl TcDispatcher {
                             .map_err(BpfmanError::UnableToPinMap)?;
                     }
                 }
+
+                if let Some(shared) = shared_maps {
+                    shared.publish(&loader)?;
+                }
             }
         }
+        // Only recorded once every extension is attached, so a failed reload
+        // leaves them pointing at the dispatcher that is still in use.
+        for v in extensions.iter_mut() {
+            v.set_dispatcher_revision(revision)?;
+            v.set_dispatcher_pin_dir(&dir)?;
+        }
         Ok(())
     }
 
+    /// Returns whether the clsact qdisc the dispatcher is attached through is
+    /// still on its interface. The dispatcher's filter goes with the qdisc,
+    /// for example when the interface is reset.
+    pub(crate) async fn is_attached(&self) -> Result<bool, BpfmanError> {
+        let if_index = self.get_ifindex()?;
+        let netns = self.get_netns()?;
+        TcDispatcher::has_qdisc("clsact".to_string(), if_index as i32, netns.as_deref())
+            .await
+            .map_err(|e| {
+                BpfmanError::Error(format!("unable to get qdiscs on if_index {if_index}: {e}"))
+            })
+    }
+
+    pub(crate) fn pin_paths(&self) -> Result<Vec<PathBuf>, BpfmanError> {
+        Ok(vec![PathBuf::from(format!(
+            "{}/dispatcher_{}_{}",
+            self.get_pin_dir()?,
+            self.get_key()?,
+            self.get_revision()?
+        ))])
+    }
+
     pub(crate) fn delete(&mut self, root_db: &Db, full: bool) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let if_name = self.get_ifname()?;
@@ -387,6 +425,8 @@ impl TcDispatcher {
         let direction = self.get_direction()?;
         let handle = self.get_handle()?;
         let priority = self.get_priority()?;
+        let netns = self.get_netns()?;
+        let key = self.get_key()?;
 
         debug!(
             "TcDispatcher::delete() for if_index {}, revision {}",
@@ -403,11 +443,7 @@ impl TcDispatcher {
             )
         })?;
 
-        let base = match direction {
-            Direction::Ingress => RTDIR_FS_TC_INGRESS,
-            Direction::Egress => RTDIR_FS_TC_EGRESS,
-        };
-        let path = format!("{base}/dispatcher_{}_{}", if_index, revision);
+        let path = format!("{}/dispatcher_{key}_{revision}", self.get_pin_dir()?);
         fs::remove_dir_all(path)
             .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
 
@@ -418,20 +454,28 @@ impl TcDispatcher {
                     Direction::Ingress => TcAttachType::Ingress,
                     Direction::Egress => TcAttachType::Egress,
                 };
-                if let Ok(old_link) =
-                    SchedClassifierLink::attached(&if_name, attach_type, priority, old_handle)
-                {
-                    let detach_result = old_link.detach();
-                    match detach_result {
-                        Ok(_) => debug!(
-                            "TC dispatcher {}, {}, {}, {} successfully detached",
-                            if_name, direction, priority, old_handle
-                        ),
-                        Err(_) => debug!(
-                            "TC dispatcher {}, {}, {}, {} not attached when detach attempted",
-                            if_name, direction, priority, old_handle
-                        ),
+                // If the namespace is gone, so is the interface the
+                // dispatcher was attached to.
+                let result = in_netns(netns.as_deref(), || {
+                    if let Ok(old_link) =
+                        SchedClassifierLink::attached(&if_name, attach_type, priority, old_handle)
+                    {
+                        let detach_result = old_link.detach();
+                        match detach_result {
+                            Ok(_) => debug!(
+                                "TC dispatcher {}, {}, {}, {} successfully detached",
+                                if_name, direction, priority, old_handle
+                            ),
+                            Err(_) => debug!(
+                                "TC dispatcher {}, {}, {}, {} not attached when detach attempted",
+                                if_name, direction, priority, old_handle
+                            ),
+                        }
                     }
+                    Ok(())
+                });
+                if let Err(e) = result {
+                    debug!("TC dispatcher {if_name} not detached: {e}");
                 }
             };
         }
@@ -503,4 +547,94 @@ impl TcDispatcher {
     pub(crate) fn get_handle(&self) -> Result<Option<u32>, BpfmanError> {
         sled_get_option(&self.db_tree, HANDLE).map(|v| v.map(bytes_to_u32))
     }
+    pub(crate) fn set_netns(&mut self, netns: &Path) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NETNS, netns.to_str().unwrap().as_bytes())
+    }
+
+    pub(crate) fn get_netns(&self) -> Result<Option<PathBuf>, BpfmanError> {
+        sled_get_option(&self.db_tree, NETNS).map(|v| v.map(|f| PathBuf::from(bytes_to_string(&f))))
+    }
+
+    pub(crate) fn set_netns_id(&mut self, netns_id: u64) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NETNS_ID, &netns_id.to_ne_bytes())
+    }
+
+    pub(crate) fn get_netns_id(&self) -> Result<Option<u64>, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, NETNS_ID)?.map(bytes_to_u64))
+    }
+
+    pub(crate) fn set_pin_dir(&mut self, dir: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, PIN_DIR, dir.as_bytes())
+    }
+
+    /// Returns the directory the dispatcher is pinned in, see
+    /// [`crate::iface_dir`].
+    pub(crate) fn get_pin_dir(&self) -> Result<String, BpfmanError> {
+        match sled_get_option(&self.db_tree, PIN_DIR)? {
+            Some(dir) => Ok(bytes_to_string(&dir)),
+            None => Ok(match self.get_direction()? {
+                Direction::Ingress => RTDIR_FS_TC_INGRESS.to_string(),
+                Direction::Egress => RTDIR_FS_TC_EGRESS.to_string(),
+            }),
+        }
+    }
+
+    fn get_key(&self) -> Result<String, BpfmanError> {
+        Ok(dispatcher_key(self.get_ifindex()?, self.get_netns_id()?))
+    }
+}
+
+// Returns the actions after which the dispatcher calls the next program, by
+// the position of each program.
+fn chain_call_actions(extensions: &[&mut TcProgram]) -> Result<[u32; 10], BpfmanError> {
+    let mut chain_call_actions = [0; 10];
+    for v in extensions.iter() {
+        chain_call_actions[v.get_current_position()?.unwrap()] = match v.get_enabled()? {
+            true => v.get_proceed_on()?.mask(),
+            false => PROCEED_ON_ANY,
+        }
+    }
+    Ok(chain_call_actions)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::{Location, ProgramData, TcProceedOn};
+
+    fn tc_program(position: usize, enabled: bool) -> TcProgram {
+        let data = ProgramData::new(
+            Location::File("/tmp/tc_pass.o".to_string()),
+            "pass".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        let mut program = TcProgram::new(
+            data,
+            50,
+            "lo".to_string(),
+            TcProceedOn::default(),
+            Direction::Ingress,
+            None,
+        )
+        .expect("unable to create tc program");
+        program.set_current_position(position).unwrap();
+        program.set_enabled(enabled).unwrap();
+        program
+    }
+
+    #[test]
+    fn test_chain_call_actions() {
+        let mut disabled = tc_program(0, false);
+        let mut enabled = tc_program(1, true);
+
+        let actions = chain_call_actions(&[&mut disabled, &mut enabled]).unwrap();
+        assert_eq!(actions[0], PROCEED_ON_ANY);
+        assert_eq!(actions[1], TcProceedOn::default().mask());
+        assert_eq!(actions[2..], [0; 8]);
+    }
 }
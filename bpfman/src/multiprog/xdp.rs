@@ -1,16 +1,24 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{fs, path::PathBuf};
+use std::{
+    ffi::CString,
+    fs, io, mem,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+};
 
 use aya::{
-    programs::{
-        links::{FdLink, PinnedLink},
-        Extension, Xdp,
-    },
+    programs::{links::FdLink, Extension, Xdp},
     Bpf, BpfLoader,
 };
-use log::debug;
+use aya_obj::generated::{bpf_link_info, BPF_F_REPLACE};
+use futures::stream::TryStreamExt;
+use log::{debug, info, warn};
+use netlink_packet_route::link::{LinkAttribute, LinkXdp, XdpAttached};
 use sled::Db;
 
 use crate::{
@@ -20,11 +28,16 @@ use crate::{
     directories::*,
     dispatcher_config::XdpDispatcherConfig,
     errors::BpfmanError,
-    multiprog::{Dispatcher, XDP_DISPATCHER_PREFIX},
-    oci_utils::image_manager::ImageManager,
-    types::{BytecodeImage, ImagePullPolicy, Program, XdpProgram},
+    multiprog::{
+        dispatcher_key, Dispatcher, DispatcherBytecode, PROCEED_ON_ANY, XDP_DISPATCHER_PREFIX,
+    },
+    netns::in_netns,
+    shared_maps::SharedMaps,
+    sys::{bpf, obj_get_info_by_fd, BPF_LINK_UPDATE, BPF_OBJ_GET, BPF_PROG_GET_FD_BY_ID},
+    types::{Program, XdpProgram},
     utils::{
-        bytes_to_string, bytes_to_u32, bytes_to_usize, should_map_be_pinned, sled_get, sled_insert,
+        bytes_to_string, bytes_to_u32, bytes_to_u64, bytes_to_usize, sled_get, sled_get_option,
+        sled_insert,
     },
 };
 
@@ -37,6 +50,34 @@ const IF_NAME: &str = "if_name";
 const MODE: &str = "mode";
 const NUM_EXTENSIONS: &str = "num_extension";
 const PROGRAM_NAME: &str = "program_name";
+const NETNS: &str = "netns";
+const NETNS_ID: &str = "netns_id";
+const PIN_DIR: &str = "pin_dir";
+
+// The anonymous struct of union bpf_attr used by BPF_OBJ_GET.
+#[repr(C)]
+struct ObjGetAttr {
+    pathname: u64,
+    bpf_fd: u32,
+    file_flags: u32,
+}
+
+// The anonymous struct of union bpf_attr used by BPF_PROG_GET_FD_BY_ID.
+#[repr(C)]
+struct GetFdByIdAttr {
+    id: u32,
+    next_id: u32,
+    open_flags: u32,
+}
+
+// The link_update struct of union bpf_attr.
+#[repr(C)]
+struct LinkUpdateAttr {
+    link_fd: u32,
+    new_prog_fd: u32,
+    flags: u32,
+    old_prog_fd: u32,
+}
 
 #[derive(Debug)]
 pub struct XdpDispatcher {
@@ -50,12 +91,16 @@ impl XdpDispatcher {
         mode: &XdpMode,
         if_index: u32,
         if_name: String,
+        netns: Option<PathBuf>,
+        netns_id: Option<u64>,
         revision: u32,
     ) -> Result<Self, BpfmanError> {
         let db_tree = root_db
             .open_tree(format!(
                 "{}_{}_{}",
-                XDP_DISPATCHER_PREFIX, if_index, revision
+                XDP_DISPATCHER_PREFIX,
+                dispatcher_key(if_index, netns_id),
+                revision
             ))
             .expect("Unable to open xdp dispatcher database tree");
 
@@ -68,6 +113,12 @@ impl XdpDispatcher {
         dp.set_ifname(&if_name)?;
         dp.set_mode(mode)?;
         dp.set_revision(revision)?;
+        if let Some(n) = netns {
+            dp.set_netns(&n)?;
+        }
+        if let Some(id) = netns_id {
+            dp.set_netns_id(id)?;
+        }
         Ok(dp)
     }
 
@@ -79,12 +130,14 @@ impl XdpDispatcher {
         }
     }
 
-    pub(crate) async fn load(
+    pub(crate) fn load(
         &mut self,
         root_db: &Db,
         programs: &mut [Program],
         old_dispatcher: Option<Dispatcher>,
-        image_manager: &mut ImageManager,
+        bytecode: &DispatcherBytecode,
+        fallback: bool,
+        members: &[String],
     ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
@@ -97,72 +150,76 @@ impl XdpDispatcher {
             })
             .collect();
 
-        let mut chain_call_actions = [0; 10];
         extensions.sort_by(|a, b| {
             a.get_current_position()
                 .unwrap()
                 .cmp(&b.get_current_position().unwrap())
         });
-        for p in extensions.iter() {
-            chain_call_actions[p.get_current_position()?.unwrap()] = p.get_proceed_on()?.mask();
-        }
 
         let config = XdpDispatcherConfig::new(
             extensions.len() as u8,
             0x0,
-            chain_call_actions,
+            chain_call_actions(&extensions)?,
             [DEFAULT_PRIORITY; 10],
             [0; 10],
         );
 
         debug!("xdp dispatcher config: {:?}", config);
-        let image = BytecodeImage::new(
-            "quay.io/bpfman/xdp-dispatcher:v2".to_string(),
-            ImagePullPolicy::IfNotPresent as i32,
-            None,
-            None,
-        );
-
-        let (path, bpf_function_name) = image_manager
-            .get_image(
-                root_db,
-                &image.image_url.clone(),
-                image.image_pull_policy.clone(),
-                image.username.clone(),
-                image.password.clone(),
-            )
-            .await?;
-
-        let program_bytes = image_manager.get_bytecode_from_image_store(root_db, path)?;
-
         let mut loader = BpfLoader::new()
             .set_global("conf", &config, true)
-            .load(&program_bytes)?;
+            .load(&bytecode.bytes)?;
 
-        let dispatcher: &mut Xdp = loader.program_mut(&bpf_function_name).unwrap().try_into()?;
+        let dispatcher: &mut Xdp = loader
+            .program_mut(&bytecode.program_name)
+            .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(bytecode.program_name.clone()))?
+            .try_into()?;
 
         dispatcher.load()?;
 
-        let path = format!("{RTDIR_FS_XDP}/dispatcher_{if_index}_{revision}");
+        let path = format!(
+            "{}/dispatcher_{}_{revision}",
+            self.get_pin_dir()?,
+            self.get_key()?
+        );
         fs::create_dir_all(path).unwrap();
 
         self.loader = Some(loader);
         self.set_num_extensions(extensions.len())?;
-        self.set_program_name(&bpf_function_name)?;
+        self.set_program_name(&bytecode.program_name)?;
 
         self.attach_extensions(&mut extensions)?;
-        self.attach()?;
+        let replaced = self.attach(fallback)?;
+        let mode = self.get_mode()?;
+        // Programs in generic mode run on the interface itself.
+        let members = match mode {
+            XdpMode::Drv => members,
+            _ => &[],
+        };
+        for v in extensions.iter_mut() {
+            v.set_mode(mode)?;
+            v.set_members(members)?;
+            v.set_dispatcher_replaced(replaced)?;
+        }
         if let Some(mut old) = old_dispatcher {
             old.delete(root_db, false)?;
         }
         Ok(())
     }
 
-    pub(crate) fn attach(&mut self) -> Result<(), BpfmanError> {
+    /// Attaches the dispatcher to its interface. With `fallback`, a dispatcher
+    /// that can't be attached in native mode is attached in generic mode.
+    ///
+    /// When an older revision is already attached, its link is updated to run
+    /// this dispatcher instead, so that there's no moment with no program on
+    /// the interface. Returns true if that is what happened.
+    pub(crate) fn attach(&mut self, fallback: bool) -> Result<bool, BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
-        let mode = self.get_mode()?;
+        let mut mode = self.get_mode()?;
         let program_name = self.get_program_name()?;
+        let netns = self.get_netns()?;
+        let key = self.get_key()?;
+        let dir = self.get_pin_dir()?;
 
         debug!(
             "XdpDispatcher::attach() for if_index {}, revision {}",
@@ -177,21 +234,52 @@ impl XdpDispatcher {
             .unwrap()
             .try_into()?;
 
-        let path = PathBuf::from(format!("{RTDIR_FS_XDP}/dispatcher_{if_index}_link"));
+        let path = PathBuf::from(format!("{dir}/dispatcher_{key}_link"));
+        let mut replaced = false;
         if path.exists() {
-            let pinned_link: FdLink = PinnedLink::from_pin(path).unwrap().into();
-            dispatcher
-                .attach_to_link(pinned_link.try_into().unwrap())
-                .unwrap();
-        } else {
-            let flags = mode.as_flags();
-            let link = dispatcher.attach(&iface, flags).map_err(|e| {
-                BpfmanError::Error(format!(
-                    "dispatcher attach failed on interface {iface}: {e}"
-                ))
+            match replace_link(&path, dispatcher.fd()?.as_fd()) {
+                Ok(()) => replaced = true,
+                Err(e) => {
+                    // Dropping the pin releases the link, which detaches the
+                    // old dispatcher before the new one is attached below.
+                    warn!(
+                        "Unable to replace the dispatcher on {iface} in place, \
+                        detaching it first, so packets briefly go unprocessed: {e}"
+                    );
+                    fs::remove_file(&path).map_err(|e| {
+                        BpfmanError::Error(format!(
+                            "unable to remove dispatcher link {}: {e}",
+                            path.display()
+                        ))
+                    })?;
+                }
+            }
+        }
+        if !replaced {
+            let link = in_netns(netns.as_deref(), || {
+                match dispatcher.attach(&iface, mode.as_flags()) {
+                    Err(e) if fallback && mode == XdpMode::Drv => {
+                        info!(
+                            "Interface {iface} doesn't support XDP in native mode, using generic mode: {e}"
+                        );
+                        mode = XdpMode::Skb;
+                        dispatcher.attach(&iface, mode.as_flags())
+                    }
+                    result => result,
+                }
+                .map_err(|e| match mode {
+                    XdpMode::Skb => BpfmanError::Error(format!(
+                        "dispatcher attach failed on interface {iface}: {e}"
+                    )),
+                    _ => BpfmanError::XdpModeUnsupported {
+                        iface: iface.clone(),
+                        mode: mode.to_string(),
+                        error: e.to_string(),
+                    },
+                })
             })?;
             let owned_link = dispatcher.take_link(link)?;
-            let path = format!("{RTDIR_FS_XDP}/dispatcher_{if_index}_link");
+            let path = format!("{dir}/dispatcher_{key}_link");
             let _ = TryInto::<FdLink>::try_into(owned_link)
                 .map_err(|e| {
                     BpfmanError::Error(format!(
@@ -200,14 +288,17 @@ impl XdpDispatcher {
                 })?
                 .pin(path)
                 .map_err(BpfmanError::UnableToPinLink)?;
+            self.set_mode(&mode)?;
         }
-        Ok(())
+        Ok(replaced)
     }
 
     fn attach_extensions(&mut self, extensions: &mut [&mut XdpProgram]) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
         let program_name = self.get_program_name()?;
+        let key = self.get_key()?;
+        let dir = self.get_pin_dir()?;
         debug!(
             "XdpDispatcher::attach_extensions() for if_index {}, revision {}",
             if_index, revision
@@ -227,20 +318,18 @@ impl XdpDispatcher {
         for (i, v) in extensions.iter_mut().enumerate() {
             if v.get_attached()? {
                 let id = v.get_data().get_id()?;
-                let mut ext = Extension::from_pin(format!("{RTDIR_FS}/prog_{id}"))?;
+                let mut ext = Extension::from_pin(v.get_data().program_pin_path()?)?;
                 let target_fn = format!("prog{i}");
                 let new_link_id = ext
                     .attach_to_program(dispatcher.fd().unwrap(), &target_fn)
                     .unwrap();
                 let new_link: FdLink = ext.take_link(new_link_id)?.into();
-                let path = format!(
-                    "{RTDIR_FS_XDP}/dispatcher_{if_index}_{}/link_{id}",
-                    revision
-                );
+                let path = format!("{dir}/dispatcher_{key}_{revision}/link_{id}");
                 new_link.pin(path).map_err(BpfmanError::UnableToPinLink)?;
             } else {
                 let name = &v.get_data().get_name()?;
                 let global_data = &v.get_data().get_global_data()?;
+                let max_entries = &v.get_data().get_map_max_entries()?;
 
                 let mut bpf = BpfLoader::new();
 
@@ -249,6 +338,9 @@ impl XdpDispatcher {
                 for (name, value) in global_data {
                     bpf.set_global(name, value.as_slice(), true);
                 }
+                for (name, n) in max_entries {
+                    bpf.set_max_entries(name, *n);
+                }
 
                 // If map_pin_path is set already it means we need to use a pin
                 // path which should already exist on the system.
@@ -256,6 +348,10 @@ impl XdpDispatcher {
                     debug!("xdp program {name} is using maps from {:?}", map_pin_path);
                     bpf.map_pin_path(map_pin_path);
                 }
+                let shared_maps = SharedMaps::prepare(v.get_data())?;
+                if let Some(ref shared) = shared_maps {
+                    bpf.map_pin_path(shared.pin_path());
+                }
 
                 let mut loader = bpf
                     .load(&v.get_data().get_program_bytes()?)
@@ -273,26 +369,24 @@ impl XdpDispatcher {
 
                 let id = v.get_data().get_id()?;
 
-                ext.pin(format!("{RTDIR_FS}/prog_{id}"))
+                ext.pin(v.get_data().program_pin_path()?)
                     .map_err(BpfmanError::UnableToPinProgram)?;
                 let new_link_id = ext.attach()?;
                 let new_link = ext.take_link(new_link_id)?;
                 let fd_link: FdLink = new_link.into();
                 fd_link
-                    .pin(format!(
-                        "{RTDIR_FS_XDP}/dispatcher_{if_index}_{}/link_{id}",
-                        revision,
-                    ))
+                    .pin(format!("{dir}/dispatcher_{key}_{revision}/link_{id}"))
                     .map_err(BpfmanError::UnableToPinLink)?;
 
                 // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
                 if v.get_data().get_map_pin_path()?.is_none() {
-                    let map_pin_path = calc_map_pin_path(id);
+                    let map_pin_path =
+                        calc_map_pin_path(v.get_data().get_pin_namespace()?.as_deref(), id);
                     v.get_data_mut().set_map_pin_path(&map_pin_path)?;
                     create_map_pin_path(&map_pin_path)?;
 
                     for (name, map) in loader.maps_mut() {
-                        if !should_map_be_pinned(name) {
+                        if !v.get_data().should_pin_map(name)? {
                             continue;
                         }
                         debug!(
@@ -303,8 +397,18 @@ impl XdpDispatcher {
                             .map_err(BpfmanError::UnableToPinMap)?;
                     }
                 }
+
+                if let Some(shared) = shared_maps {
+                    shared.publish(&loader)?;
+                }
             }
         }
+        // Only recorded once every extension is attached, so a failed reload
+        // leaves them pointing at the dispatcher that is still in use.
+        for v in extensions.iter_mut() {
+            v.set_dispatcher_revision(revision)?;
+            v.set_dispatcher_pin_dir(&dir)?;
+        }
         Ok(())
     }
 
@@ -325,17 +429,72 @@ impl XdpDispatcher {
             )
         })?;
 
-        let path = format!("{RTDIR_FS_XDP}/dispatcher_{}_{}", if_index, revision);
+        let key = self.get_key()?;
+        let dir = self.get_pin_dir()?;
+        let path = format!("{dir}/dispatcher_{key}_{revision}");
         fs::remove_dir_all(path)
             .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
         if full {
-            let path_link = format!("{RTDIR_FS_XDP}/dispatcher_{}_link", if_index);
+            let path_link = format!("{dir}/dispatcher_{key}_link");
             fs::remove_file(path_link)
                 .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
         }
         Ok(())
     }
 
+    /// Returns whether an XDP program is still attached to the dispatcher's
+    /// interface. The kernel only runs one at a time, so this is taken to be
+    /// the dispatcher.
+    pub(crate) async fn is_attached(&self) -> Result<bool, BpfmanError> {
+        let if_index = self.get_ifindex()?;
+        let netns = self.get_netns()?;
+        let (connection, handle, _) =
+            in_netns(netns.as_deref(), || Ok(rtnetlink::new_connection()?))?;
+        tokio::spawn(connection);
+
+        let link = handle
+            .link()
+            .get()
+            .match_index(if_index)
+            .execute()
+            .try_next()
+            .await
+            .map_err(|e| BpfmanError::Error(format!("unable to get if_index {if_index}: {e}")))?;
+        Ok(link.is_some_and(|link| {
+            link.attributes.iter().any(|a| match a {
+                LinkAttribute::Xdp(xdp) => xdp
+                    .iter()
+                    .any(|x| matches!(x, LinkXdp::Attached(a) if *a != XdpAttached::None)),
+                _ => false,
+            })
+        }))
+    }
+
+    /// Removes the pin of the dispatcher's link once the kernel has detached
+    /// it, so that the next dispatcher is attached with a new link rather than
+    /// through the old one.
+    pub(crate) fn unpin_link(&self) -> Result<(), BpfmanError> {
+        let path = PathBuf::from(format!(
+            "{}/dispatcher_{}_link",
+            self.get_pin_dir()?,
+            self.get_key()?
+        ));
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pin_paths(&self) -> Result<Vec<PathBuf>, BpfmanError> {
+        let key = self.get_key()?;
+        let dir = self.get_pin_dir()?;
+        Ok(vec![
+            PathBuf::from(format!("{dir}/dispatcher_{key}_{}", self.get_revision()?)),
+            PathBuf::from(format!("{dir}/dispatcher_{key}_link")),
+        ])
+    }
+
     pub(crate) fn set_revision(&mut self, revision: u32) -> Result<(), BpfmanError> {
         sled_insert(&self.db_tree, REVISION, &revision.to_ne_bytes())
     }
@@ -385,4 +544,127 @@ impl XdpDispatcher {
     pub(crate) fn get_program_name(&self) -> Result<String, BpfmanError> {
         sled_get(&self.db_tree, PROGRAM_NAME).map(|v| bytes_to_string(&v))
     }
+    pub(crate) fn set_netns(&mut self, netns: &Path) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NETNS, netns.to_str().unwrap().as_bytes())
+    }
+
+    pub(crate) fn get_netns(&self) -> Result<Option<PathBuf>, BpfmanError> {
+        sled_get_option(&self.db_tree, NETNS).map(|v| v.map(|f| PathBuf::from(bytes_to_string(&f))))
+    }
+
+    pub(crate) fn set_netns_id(&mut self, netns_id: u64) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NETNS_ID, &netns_id.to_ne_bytes())
+    }
+
+    pub(crate) fn get_netns_id(&self) -> Result<Option<u64>, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, NETNS_ID)?.map(bytes_to_u64))
+    }
+
+    pub(crate) fn set_pin_dir(&mut self, dir: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, PIN_DIR, dir.as_bytes())
+    }
+
+    /// Returns the directory the dispatcher is pinned in, see
+    /// [`crate::iface_dir`].
+    pub(crate) fn get_pin_dir(&self) -> Result<String, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, PIN_DIR)?
+            .map(|v| bytes_to_string(&v))
+            .unwrap_or_else(|| RTDIR_FS_XDP.to_string()))
+    }
+
+    fn get_key(&self) -> Result<String, BpfmanError> {
+        Ok(dispatcher_key(self.get_ifindex()?, self.get_netns_id()?))
+    }
+}
+
+// Points the pinned link at `path` to `new_prog`. BPF_F_REPLACE makes the
+// kernel check that the link still runs the program it was found to run, so a
+// dispatcher attached by someone else in the meantime isn't replaced.
+fn replace_link(path: &Path, new_prog: BorrowedFd<'_>) -> Result<(), io::Error> {
+    let pathname = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut attr = ObjGetAttr {
+        pathname: pathname.as_ptr() as u64,
+        bpf_fd: 0,
+        file_flags: 0,
+    };
+    let ret = bpf(BPF_OBJ_GET, &mut attr)?;
+    // SAFETY: BPF_OBJ_GET returns a new file descriptor owned by us.
+    let link = unsafe { OwnedFd::from_raw_fd(ret as i32) };
+
+    // SAFETY: bpf_link_info is a plain C struct for which all zeroes is valid.
+    let mut info: bpf_link_info = unsafe { mem::zeroed() };
+    obj_get_info_by_fd(link.as_fd(), &mut info)?;
+    let mut attr = GetFdByIdAttr {
+        id: info.prog_id,
+        next_id: 0,
+        open_flags: 0,
+    };
+    let ret = bpf(BPF_PROG_GET_FD_BY_ID, &mut attr)?;
+    // SAFETY: BPF_PROG_GET_FD_BY_ID returns a new file descriptor owned by us.
+    let old_prog = unsafe { OwnedFd::from_raw_fd(ret as i32) };
+
+    let mut attr = LinkUpdateAttr {
+        link_fd: link.as_raw_fd() as u32,
+        new_prog_fd: new_prog.as_raw_fd() as u32,
+        flags: BPF_F_REPLACE,
+        old_prog_fd: old_prog.as_raw_fd() as u32,
+    };
+    bpf(BPF_LINK_UPDATE, &mut attr)?;
+    debug!(
+        "Replaced program {} on link {} at {}",
+        info.prog_id,
+        info.id,
+        path.display()
+    );
+    Ok(())
+}
+
+// Returns the actions after which the dispatcher calls the next program, by
+// the position of each program.
+fn chain_call_actions(extensions: &[&mut XdpProgram]) -> Result<[u32; 10], BpfmanError> {
+    let mut chain_call_actions = [0; 10];
+    for p in extensions.iter() {
+        chain_call_actions[p.get_current_position()?.unwrap()] = match p.get_enabled()? {
+            true => p.get_proceed_on()?.mask(),
+            false => PROCEED_ON_ANY,
+        };
+    }
+    Ok(chain_call_actions)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::{Location, ProgramData, XdpProceedOn};
+
+    fn xdp_program(position: usize, enabled: bool) -> XdpProgram {
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pass.o".to_string()),
+            "pass".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        let mut program =
+            XdpProgram::new(data, 50, "lo".to_string(), XdpProceedOn::default(), None)
+                .expect("unable to create xdp program");
+        program.set_current_position(position).unwrap();
+        program.set_enabled(enabled).unwrap();
+        program
+    }
+
+    #[test]
+    fn test_chain_call_actions() {
+        let mut enabled = xdp_program(0, true);
+        let mut disabled = xdp_program(1, false);
+
+        let actions = chain_call_actions(&[&mut enabled, &mut disabled]).unwrap();
+        assert_eq!(actions[0], XdpProceedOn::default().mask());
+        assert_eq!(actions[1], PROCEED_ON_ANY);
+        assert_eq!(actions[2..], [0; 8]);
+    }
 }
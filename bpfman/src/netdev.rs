@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Bonds, teams and VLANs sit on top of other interfaces and see packets only
+// once the devices underneath have received them. The bonding driver installs
+// a native XDP program on each of its members and runs it there, while teams
+// and VLANs have no native XDP support and only run programs in generic mode.
+
+use std::{fmt, path::Path};
+
+use futures::stream::TryStreamExt;
+use log::info;
+use netlink_packet_route::link::{InfoKind, LinkAttribute, LinkInfo, LinkMessage};
+use rtnetlink::Handle;
+
+use crate::{config::XdpMode, errors::BpfmanError, netns::in_netns};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UpperKind {
+    Bond,
+    Team,
+    Vlan,
+}
+
+impl fmt::Display for UpperKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpperKind::Bond => write!(f, "bond"),
+            UpperKind::Team => write!(f, "team"),
+            UpperKind::Vlan => write!(f, "VLAN"),
+        }
+    }
+}
+
+/// An interface on top of other interfaces, with the names of the interfaces
+/// underneath it: the members of a bond or team, or the device of a VLAN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UpperDevice {
+    pub(crate) kind: UpperKind,
+    pub(crate) lower: Vec<String>,
+}
+
+/// Returns the interface at `if_index` as an upper device, or None if it is
+/// an ordinary interface.
+pub(crate) async fn upper_device(
+    if_index: u32,
+    netns: Option<&Path>,
+) -> Result<Option<UpperDevice>, BpfmanError> {
+    let (connection, handle, _) = in_netns(netns, || Ok(rtnetlink::new_connection()?))?;
+    tokio::spawn(connection);
+
+    let link = get_link(&handle, if_index)
+        .await?
+        .ok_or(BpfmanError::InvalidInterface)?;
+    let kind = link.attributes.iter().find_map(|a| match a {
+        LinkAttribute::LinkInfo(info) => info.iter().find_map(|i| match i {
+            LinkInfo::Kind(InfoKind::Bond) => Some(UpperKind::Bond),
+            LinkInfo::Kind(InfoKind::Vlan) => Some(UpperKind::Vlan),
+            LinkInfo::Kind(InfoKind::Other(kind)) if kind == "team" => Some(UpperKind::Team),
+            _ => None,
+        }),
+        _ => None,
+    });
+    let Some(kind) = kind else {
+        return Ok(None);
+    };
+
+    let mut lower = vec![];
+    match kind {
+        UpperKind::Bond | UpperKind::Team => {
+            let mut links = handle.link().get().execute();
+            while let Some(link) = links
+                .try_next()
+                .await
+                .map_err(|e| BpfmanError::Error(format!("unable to list interfaces: {e}")))?
+            {
+                if link
+                    .attributes
+                    .iter()
+                    .any(|a| matches!(a, LinkAttribute::Controller(c) if *c == if_index))
+                {
+                    lower.extend(if_name(&link));
+                }
+            }
+            lower.sort();
+        }
+        UpperKind::Vlan => {
+            let parent = link.attributes.iter().find_map(|a| match a {
+                LinkAttribute::Link(index) => Some(*index),
+                _ => None,
+            });
+            if let Some(parent) = parent {
+                // The device may be in another namespace, where its index
+                // doesn't resolve to a name here.
+                let name = get_link(&handle, parent).await?.and_then(|l| if_name(&l));
+                lower.push(name.unwrap_or_else(|| format!("if_index {parent}")));
+            }
+        }
+    }
+    Ok(Some(UpperDevice { kind, lower }))
+}
+
+async fn get_link(handle: &Handle, if_index: u32) -> Result<Option<LinkMessage>, BpfmanError> {
+    match handle
+        .link()
+        .get()
+        .match_index(if_index)
+        .execute()
+        .try_next()
+        .await
+    {
+        Ok(link) => Ok(link),
+        // The kernel reports an index that doesn't exist as an error.
+        Err(rtnetlink::Error::NetlinkError(e)) if e.raw_code() == -libc::ENODEV => Ok(None),
+        Err(e) => Err(BpfmanError::Error(format!(
+            "unable to get if_index {if_index}: {e}"
+        ))),
+    }
+}
+
+fn if_name(link: &LinkMessage) -> Option<String> {
+    link.attributes.iter().find_map(|a| match a {
+        LinkAttribute::IfName(name) => Some(name.clone()),
+        _ => None,
+    })
+}
+
+/// Checks that a dispatcher can be attached to `iface` in `mode`, returning
+/// the mode and fallback to attach it with. Teams and VLANs only run XDP
+/// programs in generic mode, so a dispatcher that may fall back is attached
+/// in generic mode directly, and one that may not is refused with the
+/// interfaces it could go on instead.
+pub(crate) fn check_xdp_mode(
+    iface: &str,
+    upper: Option<&UpperDevice>,
+    mode: XdpMode,
+    fallback: bool,
+) -> Result<(XdpMode, bool), BpfmanError> {
+    let Some(upper) = upper else {
+        return Ok((mode, fallback));
+    };
+    let lower = upper.lower.join(", ");
+    let reason = match (upper.kind, mode) {
+        (_, XdpMode::Skb) | (UpperKind::Bond, XdpMode::Drv) => return Ok((mode, fallback)),
+        (UpperKind::Team | UpperKind::Vlan, _) if fallback => {
+            info!(
+                "Interface {iface} is a {} device, using generic mode",
+                upper.kind
+            );
+            return Ok((XdpMode::Skb, false));
+        }
+        (UpperKind::Bond, _) => format!(
+            "bonds can't offload XDP programs, load the program on its members ({lower}) \
+             or in drv mode, which runs it on each member"
+        ),
+        (UpperKind::Team, _) if upper.lower.is_empty() => {
+            "teams only run XDP programs in skb mode, load the program in skb mode".to_string()
+        }
+        (UpperKind::Team, _) => format!(
+            "teams only run XDP programs in skb mode, load the program on its members \
+             ({lower}) or in skb mode"
+        ),
+        (UpperKind::Vlan, _) => format!(
+            "VLANs only run XDP programs in skb mode, load the program on {lower} and match \
+             the VLAN tag in the program, or load it in skb mode"
+        ),
+    };
+    Err(BpfmanError::XdpModeUnsupported {
+        iface: iface.to_string(),
+        mode: mode.to_string(),
+        error: reason,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_xdp_mode() {
+        let device = |kind| UpperDevice {
+            kind,
+            lower: vec!["eth0".to_string(), "eth1".to_string()],
+        };
+        let bond = device(UpperKind::Bond);
+        let vlan = device(UpperKind::Vlan);
+
+        assert_eq!(
+            check_xdp_mode("eth0", None, XdpMode::Hw, false).unwrap(),
+            (XdpMode::Hw, false)
+        );
+        assert_eq!(
+            check_xdp_mode("bond0", Some(&bond), XdpMode::Drv, true).unwrap(),
+            (XdpMode::Drv, true)
+        );
+        assert!(matches!(
+            check_xdp_mode("bond0", Some(&bond), XdpMode::Hw, false),
+            Err(BpfmanError::XdpModeUnsupported { .. })
+        ));
+
+        // Teams and VLANs fall back to generic mode up front where allowed.
+        assert_eq!(
+            check_xdp_mode("eth0.10", Some(&vlan), XdpMode::Drv, true).unwrap(),
+            (XdpMode::Skb, false)
+        );
+        assert_eq!(
+            check_xdp_mode("eth0.10", Some(&vlan), XdpMode::Skb, false).unwrap(),
+            (XdpMode::Skb, false)
+        );
+        match check_xdp_mode("team0", Some(&device(UpperKind::Team)), XdpMode::Drv, false) {
+            Err(BpfmanError::XdpModeUnsupported { error, .. }) => {
+                assert!(error.contains("eth0, eth1"), "{error}")
+            }
+            r => panic!("unexpected result {r:?}"),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Network namespaces are a per-thread attribute, so entering one only affects
+// the calling thread. NetnsGuard is deliberately !Send so that it can't be
+// held across an await point, where the task could resume on another thread
+// and leave a tokio worker stuck in the wrong namespace.
+
+use std::{
+    fs::File,
+    marker::PhantomData,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, error};
+use nix::sched::{setns, CloneFlags};
+
+use crate::errors::BpfmanError;
+
+const SELF_NETNS: &str = "/proc/self/ns/net";
+
+/// Resolves a user provided network namespace to the path of its namespace
+/// file. A PID is resolved to the network namespace of that process, anything
+/// else is treated as a path such as `/var/run/netns/foo`.
+pub(crate) fn netns_path(netns: &str) -> PathBuf {
+    match netns.parse::<u32>() {
+        Ok(pid) => PathBuf::from(format!("/proc/{pid}/ns/net")),
+        Err(_) => PathBuf::from(netns),
+    }
+}
+
+/// Returns the inode number of the network namespace file, which uniquely
+/// identifies the namespace for as long as it exists.
+pub(crate) fn netns_id(path: &Path) -> Result<u64, BpfmanError> {
+    path.metadata()
+        .map(|m| m.ino())
+        .map_err(|e| BpfmanError::InvalidNetns {
+            netns: path.display().to_string(),
+            error: e.to_string(),
+        })
+}
+
+/// Runs `f` inside the network namespace at `netns`, or in the current one if
+/// `netns` is None.
+pub(crate) fn in_netns<T, F>(netns: Option<&Path>, f: F) -> Result<T, BpfmanError>
+where
+    F: FnOnce() -> Result<T, BpfmanError>,
+{
+    match netns {
+        Some(path) => {
+            let _guard = NetnsGuard::enter(path)?;
+            f()
+        }
+        None => f(),
+    }
+}
+
+pub(crate) struct NetnsGuard {
+    host: File,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl NetnsGuard {
+    pub(crate) fn enter(path: &Path) -> Result<Self, BpfmanError> {
+        let invalid = |error: String| BpfmanError::InvalidNetns {
+            netns: path.display().to_string(),
+            error,
+        };
+
+        let host = File::open(SELF_NETNS).map_err(|e| invalid(e.to_string()))?;
+        let target = File::open(path).map_err(|e| invalid(e.to_string()))?;
+        setns(target, CloneFlags::CLONE_NEWNET).map_err(|e| invalid(e.to_string()))?;
+        debug!("Entered network namespace {}", path.display());
+
+        Ok(Self {
+            host,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+impl Drop for NetnsGuard {
+    fn drop(&mut self) {
+        if let Err(e) = setns(&self.host, CloneFlags::CLONE_NEWNET) {
+            // Carrying on would leave this thread operating on the wrong
+            // interfaces, so treat it as fatal.
+            error!("Unable to return to the bpfman network namespace: {e}");
+            panic!("unable to return to the bpfman network namespace");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_netns_path() {
+        assert_eq!(netns_path("1234"), PathBuf::from("/proc/1234/ns/net"));
+        assert_eq!(
+            netns_path("/var/run/netns/foo"),
+            PathBuf::from("/var/run/netns/foo")
+        );
+    }
+}
@@ -278,7 +278,7 @@ impl ImageManager {
         // decode and unpack to access bytecode
         let unzipped_tarball = GzDecoder::new(f.as_ref());
 
-        return Ok(Archive::new(unzipped_tarball)
+        let bytecode = Archive::new(unzipped_tarball)
             .entries()
             .expect("unable to parse tarball entries")
             .filter_map(|e| e.ok())
@@ -292,7 +292,15 @@ impl ImageManager {
             .collect::<Vec<Vec<u8>>>()
             .first()
             .expect("unable to get bytecode file bytes")
-            .to_owned());
+            .to_owned();
+
+        // Catch images that don't package an eBPF object before handing the
+        // bytes to the kernel.
+        if !is_elf(&bytecode) {
+            return Err(ImageError::BytecodeNotElf);
+        }
+
+        Ok(bytecode)
     }
 
     fn load_image_meta(
@@ -342,15 +350,18 @@ impl ImageManager {
     }
 }
 
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+fn is_elf(bytecode: &[u8]) -> bool {
+    bytecode.starts_with(ELF_MAGIC)
+}
+
 fn get_image_content_key(image: &Reference) -> String {
     // Try to get the tag, if it doesn't exist, get the digest
     // if neither exist, return "latest" as the tag
     let tag = match image.tag() {
         Some(t) => t,
-        _ => match image.digest() {
-            Some(d) => d,
-            _ => "latest",
-        },
+        _ => image.digest().unwrap_or("latest"),
     };
 
     format!(
@@ -368,6 +379,13 @@ mod tests {
     use super::*;
     use crate::{get_db_config, init_database};
 
+    #[test]
+    fn bytecode_elf_check() {
+        assert!(is_elf(b"\x7fELF\x02\x01\x01"));
+        assert!(!is_elf(b"#!/bin/sh"));
+        assert!(!is_elf(b""));
+    }
+
     #[tokio::test]
     async fn image_pull_and_bytecode_verify() {
         let root_db = init_database(get_db_config())
@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Pins are left behind on the bpffs if bpfman stops part way through a
+// request, or if its database is removed. They are found by comparing the
+// entries bpfman creates under its pin directory against the programs and
+// dispatchers in the database. Only entries named the way bpfman names its own
+// pins are ever removed, since the pin directory can be a bpffs mount shared
+// with other users. Pin namespaces are laid out the same way, so each is
+// checked as the pin directory is, and interface directories hold program and
+// dispatcher pins that are checked the same way.
+
+use std::{
+    collections::HashSet,
+    fs::{self, read_dir},
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use sled::Db;
+
+use crate::{
+    calc_map_pin_path,
+    directories::{RTDIR_FS_TC_EGRESS, RTDIR_FS_TC_INGRESS, RTDIR_FS_XDP},
+    errors::BpfmanError,
+    get_programs_iter, iface_dir,
+    multiprog::{Dispatcher, TC_DISPATCHER_PREFIX, XDP_DISPATCHER_PREFIX},
+    pin_namespace::{self, pin_dir},
+    utils::bytes_to_string,
+};
+
+// What bpfman tracks, which the entries under the pin directories are checked
+// against.
+#[derive(Default)]
+struct Tracked {
+    program_ids: HashSet<u32>,
+    map_dirs: HashSet<PathBuf>,
+    shared_maps: HashSet<(Option<String>, String)>,
+    dispatcher_pins: HashSet<PathBuf>,
+}
+
+/// Removes the pins under the pin directory that don't belong to a program or
+/// dispatcher bpfman tracks, and returns their paths.
+pub(crate) fn prune(root_db: &Db) -> Result<Vec<PathBuf>, BpfmanError> {
+    let mut tracked = Tracked::default();
+    for (id, p) in get_programs_iter(root_db) {
+        tracked.program_ids.insert(id);
+        let data = p.get_data();
+        let namespace = data.get_pin_namespace()?;
+        tracked.map_dirs.insert(
+            data.get_map_pin_path()?
+                .unwrap_or(calc_map_pin_path(namespace.as_deref(), id)),
+        );
+        for name in data.get_shared_maps()? {
+            tracked.shared_maps.insert((namespace.clone(), name));
+        }
+    }
+
+    for name in root_db.tree_names() {
+        let name_str = bytes_to_string(&name);
+        if !name_str.starts_with(XDP_DISPATCHER_PREFIX)
+            && !name_str.starts_with(TC_DISPATCHER_PREFIX)
+        {
+            continue;
+        }
+        let tree = root_db
+            .open_tree(name)
+            .map_err(|e| BpfmanError::DatabaseError(name_str.clone(), e.to_string()))?;
+        tracked
+            .dispatcher_pins
+            .extend(Dispatcher::new_from_db(tree).pin_paths()?);
+    }
+
+    let mut removed = vec![];
+
+    let namespaces = pin_namespace::dirs().into_iter().map(Some);
+    for namespace in [None].into_iter().chain(namespaces) {
+        let dir = pin_dir(namespace.as_deref());
+        prune_pin_dir(&dir, namespace, &tracked, &mut removed);
+    }
+
+    for dir in iface_dir::dirs() {
+        remove_program_pins(&dir, &tracked.program_ids, &mut removed);
+    }
+
+    let dispatcher_dirs = [&*RTDIR_FS_XDP, &*RTDIR_FS_TC_INGRESS, &*RTDIR_FS_TC_EGRESS]
+        .map(|d| d.to_string())
+        .into_iter()
+        .chain(iface_dir::dispatcher_dirs());
+    for dir in dispatcher_dirs {
+        prune_dispatcher_dir(&dir, &tracked, &mut removed);
+    }
+
+    Ok(removed)
+}
+
+// Prunes the pin directory `dir` of the pin namespace `namespace`.
+fn prune_pin_dir(
+    dir: &str,
+    namespace: Option<String>,
+    tracked: &Tracked,
+    removed: &mut Vec<PathBuf>,
+) {
+    remove_program_pins(dir, &tracked.program_ids, removed);
+
+    for path in entries(Path::new(&format!("{dir}/maps"))) {
+        if file_name(&path).parse::<u32>().is_ok() && !tracked.map_dirs.contains(&path) {
+            remove(path, removed);
+        }
+    }
+
+    for path in entries(Path::new(&format!("{dir}/shared-maps"))) {
+        // Directories named .load-* only exist while a program is loaded.
+        let name = file_name(&path);
+        if name.starts_with(".load-") || !tracked.shared_maps.contains(&(namespace.clone(), name)) {
+            remove(path, removed);
+        }
+    }
+}
+
+fn prune_dispatcher_dir(dir: &str, tracked: &Tracked, removed: &mut Vec<PathBuf>) {
+    for path in entries(Path::new(dir)) {
+        if file_name(&path).starts_with("dispatcher_") && !tracked.dispatcher_pins.contains(&path) {
+            remove(path, removed);
+        }
+    }
+}
+
+// Removes the prog_<id> and prog_<id>_link pins in `dir` of programs that
+// aren't in `program_ids`.
+fn remove_program_pins(dir: &str, program_ids: &HashSet<u32>, removed: &mut Vec<PathBuf>) {
+    for path in entries(Path::new(dir)) {
+        let name = file_name(&path);
+        let id = name
+            .strip_prefix("prog_")
+            .map(|n| n.strip_suffix("_link").unwrap_or(n))
+            .and_then(|n| n.parse::<u32>().ok());
+        if id.is_some_and(|id| !program_ids.contains(&id)) {
+            remove(path, removed);
+        }
+    }
+}
+
+// A directory that doesn't exist hasn't been used yet and so has no entries.
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    match read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn remove(path: PathBuf, removed: &mut Vec<PathBuf>) {
+    info!("Removing orphaned pin {}", path.display());
+    let result = if path.is_dir() {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    match result {
+        Ok(()) => removed.push(path),
+        Err(e) => warn!("Unable to remove orphaned pin {}: {e}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn test_prune_pin_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let create = |path: &str| {
+            let path = dir.join(path);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(&path, b"").unwrap();
+            path
+        };
+        let create_dir = |path: &str| {
+            let path = dir.join(path);
+            create_dir_all(&path).unwrap();
+            path
+        };
+
+        let kept = [
+            create("prog_7"),
+            create("prog_7_link"),
+            create_dir("maps/7"),
+            create_dir("shared-maps/flows"),
+            create("dispatcher_4_live/link"),
+        ];
+        let orphans = [
+            create("prog_8"),
+            create("prog_8_link"),
+            create_dir("maps/8"),
+            create_dir("shared-maps/stats"),
+            create_dir("shared-maps/.load-1234"),
+            create_dir("dispatcher_4_stale"),
+        ];
+        // Entries not named the way bpfman names its pins belong to others.
+        let foreign = [
+            create("cilium_calls"),
+            create("prog_cilium"),
+            create_dir("maps/cilium"),
+            create("tc/globals/cilium_policy"),
+        ];
+
+        let tracked = Tracked {
+            program_ids: HashSet::from([7]),
+            map_dirs: HashSet::from([dir.join("maps/7")]),
+            shared_maps: HashSet::from([(None, "flows".to_string())]),
+            dispatcher_pins: HashSet::from([dir.join("dispatcher_4_live")]),
+        };
+        let dir = dir.to_str().unwrap();
+        let mut removed = vec![];
+        prune_pin_dir(dir, None, &tracked, &mut removed);
+        prune_dispatcher_dir(dir, &tracked, &mut removed);
+
+        removed.sort();
+        let mut expected = orphans.to_vec();
+        expected.sort();
+        assert_eq!(removed, expected);
+        for path in kept.iter().chain(&foreign) {
+            assert!(path.exists(), "{} was removed", path.display());
+        }
+        for path in &orphans {
+            assert!(!path.exists(), "{} was kept", path.display());
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Programs loaded into a pin namespace keep their pins in a directory of that
+// name in the bpffs, laid out the way the top of the bpffs is: prog_<id> and
+// prog_<id>_link pins, a maps directory holding a directory per map owner,
+// and a shared-maps directory. XDP and TC links belong to the dispatcher, so
+// those stay where the dispatcher pins them.
+
+use std::{
+    fs::{self, create_dir_all, read_dir},
+    path::Path,
+};
+
+use log::debug;
+
+use crate::{directories::RTDIR_FS, errors::BpfmanError, utils::set_dir_permissions};
+
+// The directory can be traversed by members of the bpfman group, as the maps
+// in it are made accessible to them.
+const PIN_NAMESPACE_MODE: u32 = 0o0770;
+
+// Names bpfman uses itself at the top of the bpffs.
+const RESERVED: [&str; 6] = [
+    "maps",
+    "shared-maps",
+    "xdp",
+    "tc-ingress",
+    "tc-egress",
+    "iface",
+];
+
+/// Returns the directory the pins of programs in `namespace` are kept in.
+pub(crate) fn pin_dir(namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) => format!("{}/{namespace}", *RTDIR_FS),
+        None => RTDIR_FS.to_string(),
+    }
+}
+
+/// Checks that `namespace` can be used as a directory in the bpffs without
+/// clashing with bpfman's own pins.
+pub(crate) fn validate(namespace: &str) -> Result<(), BpfmanError> {
+    let reason = if namespace.is_empty() || namespace.len() > 64 {
+        "it must be between 1 and 64 characters long"
+    } else if !namespace
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        "only letters, digits, '-' and '_' are allowed"
+    } else if RESERVED.contains(&namespace)
+        || namespace.starts_with("prog_")
+        || namespace.starts_with("dispatcher_")
+    {
+        "the name is used by bpfman"
+    } else {
+        return Ok(());
+    };
+    Err(BpfmanError::InvalidPinNamespace {
+        namespace: namespace.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+/// Creates the directory for `namespace` if it doesn't exist yet.
+pub(crate) fn create(namespace: &str) -> Result<(), BpfmanError> {
+    let dir = pin_dir(Some(namespace));
+    create_dir_all(format!("{dir}/maps"))
+        .map_err(|e| BpfmanError::Error(format!("can't create pin namespace {namespace}: {e}")))?;
+    debug!("bpf set dir permissions for {dir}");
+    set_dir_permissions(&dir, PIN_NAMESPACE_MODE)
+}
+
+/// Removes the directory for `namespace` once the last program in it is
+/// gone. Directories that still have pins in them are left alone.
+pub(crate) fn remove_if_empty(namespace: &str) {
+    let dir = pin_dir(Some(namespace));
+    for sub in ["maps", "shared-maps"] {
+        let _ = fs::remove_dir(format!("{dir}/{sub}"));
+    }
+    if fs::remove_dir(&dir).is_ok() {
+        debug!("Removed pin namespace {namespace}");
+    }
+}
+
+/// Returns the directories in the bpffs that are named like pin namespaces.
+pub(crate) fn dirs() -> Vec<String> {
+    match read_dir(Path::new(&*RTDIR_FS)) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(|n| n.to_string()))
+            .filter(|n| validate(n).is_ok())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("tenant-a").is_ok());
+        assert!(validate("team_1").is_ok());
+        for namespace in [
+            "",
+            "a/b",
+            "..",
+            ".hidden",
+            "maps",
+            "xdp",
+            "iface",
+            "prog_12",
+            "dispatcher_3",
+        ] {
+            assert!(
+                matches!(
+                    validate(namespace),
+                    Err(BpfmanError::InvalidPinNamespace { .. })
+                ),
+                "{namespace} should be rejected"
+            );
+        }
+        assert!(validate(&"a".repeat(65)).is_err());
+    }
+}
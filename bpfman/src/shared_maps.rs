@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Maps are shared by name through aya's support for maps declared with
+// LIBBPF_PIN_BY_NAME: when a program is loaded, such a map is reused if a map
+// is already pinned under its name in the loader's map pin path, and created
+// and pinned there otherwise. Each program that shares maps is loaded with a
+// directory of its own as the map pin path, holding a link to each shared map
+// that already exists. Maps the load creates are then pinned under their
+// shared name, and that pin is removed once the last program sharing the map
+// is unloaded. Programs in a pin namespace only share maps with other programs
+// in the same namespace.
+
+use std::{
+    fs::{self, create_dir_all, remove_dir_all},
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use aya::Bpf;
+use aya_obj::{maps::PinningType, Object};
+use log::{debug, warn};
+use sled::Db;
+
+use crate::{errors::BpfmanError, get_programs_iter, pin_namespace::pin_dir, types::ProgramData};
+
+fn shared_maps_dir(namespace: Option<&str>) -> String {
+    format!("{}/shared-maps", pin_dir(namespace))
+}
+
+pub(crate) fn shared_map_path(namespace: Option<&str>, name: &str) -> PathBuf {
+    PathBuf::from(format!("{}/{name}", shared_maps_dir(namespace)))
+}
+
+/// Checks that every map the program shares is declared in `obj` with
+/// pinning by name, which is what lets it be reused.
+pub(crate) fn validate(data: &ProgramData, obj: &Object) -> Result<(), BpfmanError> {
+    let names = data.get_shared_maps()?;
+    if names.is_empty() {
+        return Ok(());
+    }
+    if data.get_map_owner_id()?.is_some() {
+        return Err(BpfmanError::Error(
+            "shared maps can't be used together with a map owner".to_string(),
+        ));
+    }
+
+    for name in names {
+        let reason = if name.is_empty() || name.starts_with('.') || name.contains('/') {
+            "not a valid map name"
+        } else {
+            match obj.maps.get(&name) {
+                None => "no map with that name in the bytecode",
+                Some(map) if map.pinning() != PinningType::ByName => {
+                    "the map must be declared with LIBBPF_PIN_BY_NAME"
+                }
+                Some(_) => continue,
+            }
+        };
+        return Err(BpfmanError::InvalidSharedMap {
+            name,
+            reason: reason.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// The map pin path a program that shares maps is loaded with. The directory
+/// is removed again when this is dropped.
+pub(crate) struct SharedMaps {
+    dir: PathBuf,
+    namespace: Option<String>,
+    names: Vec<String>,
+}
+
+impl SharedMaps {
+    /// Returns `None` if the program doesn't share any maps.
+    pub(crate) fn prepare(data: &ProgramData) -> Result<Option<Self>, BpfmanError> {
+        let names = data.get_shared_maps()?;
+        if names.is_empty() {
+            return Ok(None);
+        }
+
+        let namespace = data.get_pin_namespace()?;
+        let dir = PathBuf::from(format!(
+            "{}/.load-{}",
+            shared_maps_dir(namespace.as_deref()),
+            rand::random::<u32>()
+        ));
+        create_dir_all(&dir)
+            .map_err(|e| BpfmanError::Error(format!("can't create shared map dir: {e}")))?;
+        let shared = Self {
+            dir,
+            namespace,
+            names,
+        };
+
+        for name in &shared.names {
+            let path = shared_map_path(shared.namespace.as_deref(), name);
+            if path.exists() {
+                debug!("Reusing shared map {name}");
+                symlink(&path, shared.dir.join(name)).map_err(|e| {
+                    BpfmanError::Error(format!("can't link shared map {name}: {e}"))
+                })?;
+            }
+        }
+        Ok(Some(shared))
+    }
+
+    pub(crate) fn pin_path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Pins the maps that `loader` created under their shared names, so that
+    /// programs loaded after it reuse them.
+    pub(crate) fn publish(&self, loader: &Bpf) -> Result<(), BpfmanError> {
+        for name in &self.names {
+            let path = shared_map_path(self.namespace.as_deref(), name);
+            if path.exists() {
+                continue;
+            }
+            debug!("Pinning shared map {name} to path: {}", path.display());
+            loader
+                .map(name)
+                .ok_or_else(|| BpfmanError::InvalidSharedMap {
+                    name: name.to_string(),
+                    reason: "no map with that name in the bytecode".to_string(),
+                })?
+                .pin(&path)
+                .map_err(BpfmanError::UnableToPinMap)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SharedMaps {
+    fn drop(&mut self) {
+        // The maps stay pinned under their shared names and the program's own
+        // map pin path, so only the links and pins made for the load go.
+        if let Err(e) = remove_dir_all(&self.dir) {
+            warn!("Unable to remove {}: {e}", self.dir.display());
+        }
+    }
+}
+
+/// Removes the pins of the shared maps in `names` that no program in the
+/// database shares in `namespace` any more.
+pub(crate) fn release(root_db: &Db, namespace: Option<&str>, names: &[String]) {
+    for name in names {
+        let in_use = get_programs_iter(root_db).any(|(_, p)| {
+            let data = p.get_data();
+            data.get_pin_namespace()
+                .is_ok_and(|n| n.as_deref() == namespace)
+                && data
+                    .get_shared_maps()
+                    .is_ok_and(|shared| shared.contains(name))
+        });
+        let path = shared_map_path(namespace, name);
+        if in_use || !path.exists() {
+            continue;
+        }
+        debug!("Removing shared map {name}");
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Unable to remove shared map {name}: {e}");
+        }
+    }
+}
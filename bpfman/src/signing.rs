@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Bytecode files are signed the way `cosign sign-blob` signs them: the
+// signature is a base64 encoded signature over the whole file, kept next to it
+// with a .sig suffix. Bytecode downloaded from a URL is signed the same way,
+// with the signature at the URL with .sig appended. Images are verified by
+// cosign while they are pulled instead, see oci_utils::cosign.
+
+use std::fs;
+
+use log::debug;
+use sigstore::crypto::{CosignVerificationKey, Signature};
+
+use crate::{config::SigningConfig, errors::BpfmanError};
+
+pub(crate) fn signature_path(path: &str) -> String {
+    format!("{path}.sig")
+}
+
+/// Checks that the bytecode read from the file at `path` is signed by one of
+/// the trusted keys, if the [signing] config requires it.
+pub(crate) fn verify_file(
+    config: &SigningConfig,
+    path: &str,
+    bytecode: &[u8],
+) -> Result<(), BpfmanError> {
+    if !config.require_signed_files {
+        return Ok(());
+    }
+    let sig_path = signature_path(path);
+    let signature = fs::read(&sig_path).map_err(|e| BpfmanError::SignatureInvalid {
+        path: path.to_string(),
+        reason: format!("can't read {sig_path}: {e}"),
+    })?;
+    verify(config, path, &signature, bytecode)
+}
+
+/// Checks that `signature` is a signature over `bytecode` by one of the
+/// trusted keys. `path` names where the bytecode came from in errors.
+pub(crate) fn verify(
+    config: &SigningConfig,
+    path: &str,
+    signature: &[u8],
+    bytecode: &[u8],
+) -> Result<(), BpfmanError> {
+    let invalid = |reason: String| BpfmanError::SignatureInvalid {
+        path: path.to_string(),
+        reason,
+    };
+    let signature = signature.trim_ascii();
+
+    if config.trusted_keys.is_empty() {
+        return Err(invalid("no trusted keys are configured".to_string()));
+    }
+    for key_path in &config.trusted_keys {
+        let pem = fs::read(key_path)
+            .map_err(|e| invalid(format!("can't read key {}: {e}", key_path.display())))?;
+        let key = CosignVerificationKey::try_from_pem(&pem)
+            .map_err(|e| invalid(format!("can't parse key {}: {e}", key_path.display())))?;
+        if key
+            .verify_signature(Signature::Base64Encoded(signature), bytecode)
+            .is_ok()
+        {
+            debug!("{path} is signed by {}", key_path.display());
+            return Ok(());
+        }
+    }
+    Err(invalid(
+        "it isn't signed by any of the trusted keys".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use sigstore::crypto::SigningScheme;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn write_key(dir: &TempDir, name: &str) -> (PathBuf, sigstore::crypto::SigStoreSigner) {
+        let signer = SigningScheme::ECDSA_P256_SHA256_ASN1
+            .create_signer()
+            .expect("unable to create signer");
+        let pem = signer
+            .to_sigstore_keypair()
+            .and_then(|k| k.public_key_to_pem())
+            .expect("unable to encode public key");
+        let path = dir.path().join(name);
+        fs::write(&path, pem).expect("unable to write key");
+        (path, signer)
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let dir = TempDir::new().expect("unable to create temp dir");
+        let (trusted, signer) = write_key(&dir, "trusted.pub");
+        let (_, other) = write_key(&dir, "other.pub");
+        let path = dir.path().join("prog.o").to_string_lossy().to_string();
+        let bytecode = b"bytecode";
+
+        let config = SigningConfig {
+            require_signed_files: true,
+            trusted_keys: vec![trusted],
+            ..Default::default()
+        };
+        let sign = |signer: &sigstore::crypto::SigStoreSigner| {
+            let signature = signer.sign(bytecode).expect("unable to sign");
+            fs::write(signature_path(&path), STANDARD.encode(signature) + "\n")
+                .expect("unable to write signature");
+        };
+
+        // Unsigned files are only loaded if signatures aren't required.
+        assert!(matches!(
+            verify_file(&config, &path, bytecode),
+            Err(BpfmanError::SignatureInvalid { .. })
+        ));
+        assert!(verify_file(&SigningConfig::default(), &path, bytecode).is_ok());
+
+        sign(&signer);
+        assert!(verify_file(&config, &path, bytecode).is_ok());
+        assert!(verify_file(&config, &path, b"tampered").is_err());
+
+        sign(&other);
+        assert!(verify_file(&config, &path, bytecode).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// A JSON dump of everything bpfman tracks: its programs, the maps pinned for
+// them and the dispatchers on each interface. It is read from the database
+// and the bpffs only, so it can be taken at any time, for backups or to see
+// what bpfman believes is loaded. Registry credentials are never included.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+use sled::Db;
+
+use crate::{
+    errors::BpfmanError,
+    get_programs_iter,
+    multiprog::{Dispatcher, TC_DISPATCHER_PREFIX, XDP_DISPATCHER_PREFIX},
+    pinned_maps,
+    types::{Location, Program},
+    utils::bytes_to_string,
+};
+
+// Bumped when fields are renamed or removed, so that tools reading a dump
+// can tell which layout it has.
+const STATE_VERSION: u32 = 1;
+
+pub(crate) fn dump(root_db: &Db) -> Result<Value, BpfmanError> {
+    let mut programs: Vec<(u32, Program)> = get_programs_iter(root_db).collect();
+    programs.sort_by_key(|(id, _)| *id);
+
+    let mut maps = vec![];
+    for (id, program) in &programs {
+        let data = program.get_data();
+        // Programs that share another program's maps are listed under it.
+        if data.get_map_owner_id()?.is_some() {
+            continue;
+        }
+        if let Some(pin_path) = data.get_map_pin_path()? {
+            maps.push(json!({
+                "owner_id": id,
+                "pin_path": pin_path,
+                "used_by": data.get_maps_used_by()?,
+                "maps": pinned_maps(&pin_path).unwrap_or_default(),
+            }));
+        }
+    }
+
+    let mut dispatchers = vec![];
+    for name in root_db.tree_names() {
+        let name = bytes_to_string(&name);
+        if !name.starts_with(XDP_DISPATCHER_PREFIX) && !name.starts_with(TC_DISPATCHER_PREFIX) {
+            continue;
+        }
+        let tree = root_db
+            .open_tree(&name)
+            .map_err(|e| BpfmanError::DatabaseError(name.clone(), e.to_string()))?;
+        dispatchers.push(dispatcher(&Dispatcher::new_from_db(tree))?);
+    }
+
+    Ok(json!({
+        "version": STATE_VERSION,
+        "programs": programs
+            .iter()
+            .map(|(_, p)| program(p))
+            .collect::<Result<Vec<_>, _>>()?,
+        "maps": maps,
+        "dispatchers": dispatchers,
+    }))
+}
+
+fn program(program: &Program) -> Result<Value, BpfmanError> {
+    let data = program.get_data();
+    let global_data: BTreeMap<String, String> = data
+        .get_global_data()?
+        .into_iter()
+        .map(|(name, value)| (name, hex::encode(value)))
+        .collect();
+
+    Ok(json!({
+        "id": data.get_id()?,
+        "name": data.get_name()?,
+        "kind": program.kind().to_string(),
+        "location": location(&data.get_location()?),
+        "metadata": data.get_metadata()?,
+        "global_data": global_data,
+        "map_owner_id": data.get_map_owner_id()?,
+        "map_pin_path": data.get_map_pin_path()?,
+        "shared_maps": data.get_shared_maps()?,
+        "maps_to_pin": data.get_maps_to_pin()?,
+        "maps_to_skip": data.get_maps_to_skip()?,
+        "map_max_entries": data.get_map_max_entries()?,
+        "pin_namespace": data.get_pin_namespace()?,
+        "group_id": data.get_group_id()?,
+        "duplicate_of": data.get_duplicate_of()?,
+        "loaded_at": data.get_loaded_at()?,
+        "attached_at": data.get_attached_at()?,
+        "pins": {
+            "program": data.program_pin_path()?,
+            "link": data.link_pin_path()?,
+        },
+        "attach": attach(program)?,
+        "kernel": {
+            "name": data.get_kernel_name()?,
+            "loaded_at": data.get_kernel_loaded_at()?,
+            "tag": data.get_kernel_tag()?,
+            "map_ids": data.get_kernel_map_ids()?,
+        },
+    }))
+}
+
+fn location(location: &Location) -> Value {
+    match location {
+        Location::File(path) => json!({ "file": path }),
+        Location::Url(url) => json!({ "url": url.url, "sha256": url.sha256 }),
+        Location::Image(image) => {
+            let mut value = json!({
+                "image": image.image_url,
+                "pull_policy": image.image_pull_policy.to_string(),
+            });
+            if image.username.is_some() || image.password.is_some() {
+                value["credentials"] = json!("redacted");
+            }
+            value
+        }
+    }
+}
+
+fn attach(program: &Program) -> Result<Value, BpfmanError> {
+    Ok(match program {
+        Program::Xdp(p) => json!({
+            "iface": p.get_iface()?,
+            "if_index": p.get_if_index()?,
+            "netns": p.get_netns()?,
+            "priority": p.get_priority()?,
+            "position": p.get_current_position()?,
+            "dispatcher_revision": p.get_dispatcher_revision()?,
+            "dispatcher_replaced": p.get_dispatcher_replaced()?,
+            "proceed_on": p.get_proceed_on()?.to_string(),
+            "mode": p.get_mode()?.map(|m| m.to_string()),
+            "mode_preference": p.get_mode_preference()?.map(|m| m.to_string()),
+            "members": p.get_members()?,
+            "detached": p.get_detached()?,
+            "enabled": p.get_enabled()?,
+        }),
+        Program::Tc(p) => json!({
+            "iface": p.get_iface()?,
+            "if_index": p.get_if_index()?,
+            "netns": p.get_netns()?,
+            "direction": p.get_direction()?.to_string(),
+            "priority": p.get_priority()?,
+            "position": p.get_current_position()?,
+            "dispatcher_revision": p.get_dispatcher_revision()?,
+            "proceed_on": p.get_proceed_on()?.to_string(),
+            "detached": p.get_detached()?,
+            "enabled": p.get_enabled()?,
+        }),
+        Program::Tracepoint(p) => json!({ "tracepoint": p.get_tracepoint()? }),
+        Program::Kprobe(p) => json!({
+            "fn_name": p.get_fn_name()?,
+            "offset": p.get_offset()?,
+            "retprobe": p.get_retprobe()?,
+            "container_pid": p.get_container_pid()?,
+        }),
+        Program::Uprobe(p) => json!({
+            "fn_name": p.get_fn_name()?,
+            "offset": p.get_offset()?,
+            "target": p.get_target()?,
+            "retprobe": p.get_retprobe()?,
+            "pid": p.get_pid()?,
+            "container_pid": p.get_container_pid()?,
+        }),
+        Program::Fentry(p) => json!({ "fn_name": p.get_fn_name()? }),
+        Program::Fexit(p) => json!({ "fn_name": p.get_fn_name()? }),
+        Program::Cgroup(p) => json!({
+            "path": p.get_path()?,
+            "attach_type": p.get_attach_type()?.to_string(),
+        }),
+        Program::TailCall(p) => json!({
+            "map_name": p.get_map_name()?,
+            "index": p.get_index()?,
+        }),
+        Program::Unsupported(_) => Value::Null,
+    })
+}
+
+fn dispatcher(dispatcher: &Dispatcher) -> Result<Value, BpfmanError> {
+    let pin_paths: Vec<String> = dispatcher
+        .pin_paths()?
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    Ok(match dispatcher {
+        Dispatcher::Xdp(d) => json!({
+            "kind": "xdp",
+            "iface": d.get_ifname()?,
+            "if_index": d.get_ifindex()?,
+            "netns": d.get_netns()?,
+            "revision": d.get_revision()?,
+            "mode": d.get_mode()?.to_string(),
+            "programs": d.get_num_extensions()?,
+            "pin_paths": pin_paths,
+        }),
+        Dispatcher::Tc(d) => json!({
+            "kind": "tc",
+            "iface": d.get_ifname()?,
+            "if_index": d.get_ifindex()?,
+            "netns": d.get_netns()?,
+            "direction": d.get_direction()?.to_string(),
+            "revision": d.get_revision()?,
+            "programs": d.get_num_extensions()?,
+            "pin_paths": pin_paths,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{BytecodeImage, ImagePullPolicy};
+
+    #[test]
+    fn test_location_redacts_credentials() {
+        let image = Location::Image(BytecodeImage {
+            image_url: "quay.io/bpfman-bytecode/xdp_pass:latest".to_string(),
+            image_pull_policy: ImagePullPolicy::IfNotPresent,
+            username: Some("user".to_string()),
+            password: Some("secret".to_string()),
+        });
+        let value = location(&image);
+        assert_eq!(value["credentials"], "redacted");
+        let dumped = value.to_string();
+        assert!(
+            !dumped.contains("user") && !dumped.contains("secret"),
+            "{dumped}"
+        );
+
+        let file = location(&Location::File("/tmp/xdp_pass.o".to_string()));
+        assert_eq!(file, json!({ "file": "/tmp/xdp_pass.o" }));
+    }
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// The kernel only accumulates run_cnt and run_time_ns while BPF statistics
+// are enabled, either through the kernel.bpf_stats_enabled sysctl or for as
+// long as a file descriptor returned by BPF_ENABLE_STATS is held open. Aya
+// exposes neither the command nor the counters, so both are issued directly.
+
+use std::{
+    fs, mem,
+    os::fd::{AsFd, FromRawFd, OwnedFd},
+    sync::Mutex,
+};
+
+use aya::programs::loaded_programs;
+use aya_obj::generated::bpf_prog_info;
+use log::{debug, info};
+
+use crate::{
+    errors::BpfmanError,
+    sys::{bpf, obj_get_info_by_fd, BPF_ENABLE_STATS},
+};
+
+// bpf_stats_type values from include/uapi/linux/bpf.h
+const BPF_STATS_RUN_TIME: u32 = 0;
+
+const BPF_STATS_SYSCTL: &str = "/proc/sys/kernel/bpf_stats_enabled";
+
+// Held open while bpfman has statistics enabled. Closing it disables them
+// again, unless something else keeps them enabled.
+static STATS_FD: Mutex<Option<OwnedFd>> = Mutex::new(None);
+
+// The anonymous struct of union bpf_attr used by BPF_ENABLE_STATS.
+#[repr(C)]
+struct EnableStatsAttr {
+    stats_type: u32,
+}
+
+/// Enables BPF statistics if neither bpfman nor the sysctl already has.
+pub(crate) fn enable() -> Result<(), BpfmanError> {
+    let mut fd = STATS_FD.lock().expect("stats lock poisoned");
+    if fd.is_some() || sysctl_enabled() {
+        return Ok(());
+    }
+
+    let mut attr = EnableStatsAttr {
+        stats_type: BPF_STATS_RUN_TIME,
+    };
+    let ret = bpf(BPF_ENABLE_STATS, &mut attr)
+        .map_err(|e| BpfmanError::Error(format!("unable to enable BPF statistics: {e}")))?;
+
+    // SAFETY: BPF_ENABLE_STATS returns a new file descriptor owned by us.
+    *fd = Some(unsafe { OwnedFd::from_raw_fd(ret as i32) });
+    info!("Enabled BPF statistics");
+    Ok(())
+}
+
+/// Disables BPF statistics if they were enabled by [`enable`].
+pub(crate) fn disable() {
+    if STATS_FD
+        .lock()
+        .expect("stats lock poisoned")
+        .take()
+        .is_some()
+    {
+        info!("Disabled BPF statistics");
+    }
+}
+
+/// Returns whether BPF statistics are currently being collected.
+pub(crate) fn enabled() -> bool {
+    STATS_FD.lock().expect("stats lock poisoned").is_some() || sysctl_enabled()
+}
+
+/// Returns the run count and total run time in nanoseconds of the program
+/// with kernel ID `id`.
+pub(crate) fn run_stats(id: u32) -> Result<(u64, u64), BpfmanError> {
+    let prog = loaded_programs()
+        .filter_map(|p| p.ok())
+        .find(|p| p.id() == id)
+        .ok_or(BpfmanError::Error(format!("Program {id} does not exist")))?;
+    let fd = prog.fd()?;
+
+    // SAFETY: bpf_prog_info is a plain C struct for which all zeroes is valid.
+    let mut prog_info: bpf_prog_info = unsafe { mem::zeroed() };
+    obj_get_info_by_fd(fd.as_fd(), &mut prog_info)
+        .map_err(|e| BpfmanError::Error(format!("unable to get info for program {id}: {e}")))?;
+
+    debug!(
+        "Program {id} has run {} times for {}ns",
+        prog_info.run_cnt, prog_info.run_time_ns
+    );
+    Ok((prog_info.run_cnt, prog_info.run_time_ns))
+}
+
+fn sysctl_enabled() -> bool {
+    fs::read_to_string(BPF_STATS_SYSCTL).is_ok_and(|v| v.trim() == "1")
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Aya doesn't expose every bpf command bpfman needs, such as the map element
+// commands on untyped maps, cgroup links and BPF_ENABLE_STATS, so those are
+// issued directly. Each command is passed the part of union bpf_attr it
+// reads, which the kernel zero extends to the size of the whole union.
+
+use std::{
+    io, mem,
+    os::fd::{AsRawFd, BorrowedFd},
+};
+
+// bpf_cmd values from include/uapi/linux/bpf.h
+pub(crate) const BPF_MAP_LOOKUP_ELEM: libc::c_long = 1;
+pub(crate) const BPF_MAP_UPDATE_ELEM: libc::c_long = 2;
+pub(crate) const BPF_MAP_DELETE_ELEM: libc::c_long = 3;
+pub(crate) const BPF_MAP_GET_NEXT_KEY: libc::c_long = 4;
+pub(crate) const BPF_OBJ_PIN: libc::c_long = 6;
+pub(crate) const BPF_OBJ_GET: libc::c_long = 7;
+pub(crate) const BPF_PROG_GET_FD_BY_ID: libc::c_long = 13;
+const BPF_OBJ_GET_INFO_BY_FD: libc::c_long = 15;
+pub(crate) const BPF_MAP_FREEZE: libc::c_long = 22;
+pub(crate) const BPF_MAP_LOOKUP_BATCH: libc::c_long = 24;
+pub(crate) const BPF_LINK_CREATE: libc::c_long = 28;
+pub(crate) const BPF_LINK_UPDATE: libc::c_long = 29;
+pub(crate) const BPF_ENABLE_STATS: libc::c_long = 32;
+
+// The anonymous struct of union bpf_attr used by BPF_OBJ_GET_INFO_BY_FD.
+#[repr(C)]
+struct InfoAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+/// Issues the bpf command `cmd` with `attr`, which must be the part of union
+/// bpf_attr the command reads, and returns what the command returned.
+pub(crate) fn bpf<T>(cmd: libc::c_long, attr: &mut T) -> Result<libc::c_long, io::Error> {
+    // SAFETY: callers pass the bpf_attr struct for cmd, whose pointers are to
+    // buffers of the sizes it gives.
+    let ret = unsafe { libc::syscall(libc::SYS_bpf, cmd, attr as *mut T, mem::size_of::<T>()) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret)
+}
+
+/// Fills `info` with the kernel's info about the object `fd` refers to. `I`
+/// must be the info struct for the type of object, such as bpf_prog_info for
+/// a program or bpf_link_info for a link.
+pub(crate) fn obj_get_info_by_fd<I>(fd: BorrowedFd<'_>, info: &mut I) -> Result<(), io::Error> {
+    let mut attr = InfoAttr {
+        bpf_fd: fd.as_raw_fd() as u32,
+        info_len: mem::size_of::<I>() as u32,
+        info: info as *mut I as u64,
+    };
+    bpf(BPF_OBJ_GET_INFO_BY_FD, &mut attr).map(|_| ())
+}
@@ -3,34 +3,46 @@
 
 //! Commands between the RPC thread and the BPF thread
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt, fs,
     path::{Path, PathBuf},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 use aya::programs::ProgramInfo as AyaProgInfo;
 use chrono::{prelude::DateTime, Local};
 use clap::ValueEnum;
-use log::{info, warn};
+use log::{debug, info, warn};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sled::Db;
 
 use crate::{
-    directories::RTDIR_FS,
+    config::XdpMode,
+    directories::{RTDIR_FS_TC_EGRESS, RTDIR_FS_TC_INGRESS, RTDIR_FS_XDP},
+    download,
     errors::{BpfmanError, ParseError},
-    multiprog::{DispatcherId, DispatcherInfo},
+    map_watch::EventReader,
+    maps::{check_max_entries, sum_counters, BpfMap, ValueDecoder},
+    multiprog::{dispatcher_key, DispatcherId, DispatcherInfo},
+    netns::in_netns,
     oci_utils::image_manager::ImageManager,
+    pin_namespace,
+    shared_maps::shared_map_path,
+    signing,
     utils::{
         bytes_to_bool, bytes_to_i32, bytes_to_string, bytes_to_u32, bytes_to_u64, bytes_to_usize,
-        sled_get, sled_get_option, sled_insert,
+        if_indextoname, open_config_file, should_map_be_pinned, sled_get, sled_get_option,
+        sled_insert, sled_remove,
     },
 };
 
 /// These constants define the key of SLED DB
 pub(crate) const PROGRAM_PREFIX: &str = "program_";
 pub(crate) const PROGRAM_PRE_LOAD_PREFIX: &str = "pre_load_program_";
+
+// Size of the kernel's program name buffer, including the trailing NUL.
+const BPF_OBJ_NAME_LEN: usize = 16;
 const KIND: &str = "kind";
 const NAME: &str = "name";
 const ID: &str = "id";
@@ -39,11 +51,28 @@ const LOCATION_IMAGE_URL: &str = "location_image_url";
 const LOCATION_IMAGE_PULL_POLICY: &str = "location_image_pull_policy";
 const LOCATION_USERNAME: &str = "location_username";
 const LOCATION_PASSWORD: &str = "location_password";
+const LOCATION_URL: &str = "location_url";
+const LOCATION_URL_SHA256: &str = "location_url_sha256";
 const MAP_OWNER_ID: &str = "map_owner_id";
 const MAP_PIN_PATH: &str = "map_pin_path";
+const GROUP_ID: &str = "group_id";
+const PIN_NAMESPACE: &str = "pin_namespace";
+const IFACE_PIN_DIR: &str = "iface_pin_dir";
+const ALLOW_DUPLICATE: &str = "allow_duplicate";
+const DUPLICATE_OF: &str = "duplicate_of";
+const IDEMPOTENCY_OWNER: &str = "idempotency_owner";
+const IDEMPOTENCY_KEY: &str = "idempotency_key";
+const IDEMPOTENCY_TIME: &str = "idempotency_time";
+const IDEMPOTENCY_DIGEST: &str = "idempotency_digest";
+const LOADED_AT: &str = "loaded_at";
+const ATTACHED_AT: &str = "attached_at";
 const PREFIX_GLOBAL_DATA: &str = "global_data_";
 const PREFIX_METADATA: &str = "metadata_";
 const PREFIX_MAPS_USED_BY: &str = "maps_used_by_";
+const PREFIX_SHARED_MAPS: &str = "shared_maps_";
+const PREFIX_MAPS_TO_PIN: &str = "maps_to_pin_";
+const PREFIX_MAPS_TO_SKIP: &str = "maps_to_skip_";
+const PREFIX_MAP_MAX_ENTRIES: &str = "map_max_entries_";
 const PROGRAM_BYTES: &str = "program_bytes";
 
 const KERNEL_NAME: &str = "kernel_name";
@@ -62,16 +91,32 @@ const PREFIX_KERNEL_MAP_IDS: &str = "kernel_map_ids_";
 const XDP_PRIORITY: &str = "xdp_priority";
 const XDP_IFACE: &str = "xdp_iface";
 const XDP_CURRENT_POSITION: &str = "xdp_current_position";
+const XDP_DISPATCHER_REVISION: &str = "xdp_dispatcher_revision";
+const XDP_DISPATCHER_PIN_DIR: &str = "xdp_dispatcher_pin_dir";
+const XDP_DISPATCHER_REPLACED: &str = "xdp_dispatcher_replaced";
 const XDP_IF_INDEX: &str = "xdp_if_index";
 const XDP_ATTACHED: &str = "xdp_attached";
+const XDP_DETACHED: &str = "xdp_detached";
+const XDP_DISABLED: &str = "xdp_disabled";
+const XDP_NETNS: &str = "xdp_netns";
+const XDP_NETNS_ID: &str = "xdp_netns_id";
+const XDP_MODE_PREFERENCE: &str = "xdp_mode_preference";
+const XDP_MODE: &str = "xdp_mode";
+const XDP_MEMBERS: &str = "xdp_members";
 const PREFIX_XDP_PROCEED_ON: &str = "xdp_proceed_on_";
 
 const TC_PRIORITY: &str = "tc_priority";
 const TC_IFACE: &str = "tc_iface";
 const TC_CURRENT_POSITION: &str = "tc_current_position";
+const TC_DISPATCHER_REVISION: &str = "tc_dispatcher_revision";
+const TC_DISPATCHER_PIN_DIR: &str = "tc_dispatcher_pin_dir";
 const TC_IF_INDEX: &str = "tc_if_index";
 const TC_ATTACHED: &str = "tc_attached";
+const TC_DETACHED: &str = "tc_detached";
+const TC_DISABLED: &str = "tc_disabled";
 const TC_DIRECTION: &str = "tc_direction";
+const TC_NETNS: &str = "tc_netns";
+const TC_NETNS_ID: &str = "tc_netns_id";
 const PREFIX_TC_PROCEED_ON: &str = "tc_proceed_on_";
 
 const TRACEPOINT_NAME: &str = "tracepoint_name";
@@ -91,6 +136,12 @@ const UPROBE_TARGET: &str = "uprobe_target";
 const FENTRY_FN_NAME: &str = "fentry_fn_name";
 const FEXIT_FN_NAME: &str = "fexit_fn_name";
 
+const CGROUP_PATH: &str = "cgroup_path";
+const CGROUP_ATTACH_TYPE: &str = "cgroup_attach_type";
+
+const TAIL_CALL_MAP: &str = "tail_call_map";
+const TAIL_CALL_INDEX: &str = "tail_call_index";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BytecodeImage {
     pub image_url: String,
@@ -124,11 +175,609 @@ impl BytecodeImage {
         &self.image_pull_policy
     }
 }
+/// Bytecode to download from an http:// or https:// URL. If `sha256` is set,
+/// the download must have that SHA-256 checksum, given in hex.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BytecodeUrl {
+    pub url: String,
+    pub sha256: Option<String>,
+}
+
+/// The health of bpfman, as reported by [`crate::health`].
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub version: String,
+    pub programs_loaded: u32,
+    pub bpffs_mounted: bool,
+    pub features: KernelFeatures,
+}
+
+/// An operation in a batch applied by [`crate::apply_batch`].
+#[derive(Debug)]
+pub enum BatchOp {
+    /// Loads a single program.
+    Load(Program),
+    /// Unloads the program with the given ID, along with the rest of its
+    /// group.
+    Unload(u32),
+}
+
+/// The outcome of a [`BatchOp`] that was applied.
+#[derive(Debug)]
+pub enum BatchResult {
+    Loaded(Program),
+    Unloaded,
+}
+
+/// A change made to a program, as sent to the subscribers of
+/// [`crate::subscribe_events`].
+#[derive(Debug, Clone)]
+pub struct ProgramEvent {
+    pub kind: ProgramEventKind,
+    pub id: u32,
+    pub name: String,
+    pub program_type: ProgramType,
+    /// The interface of an XDP or TC program.
+    pub iface: Option<String>,
+    pub timestamp: SystemTime,
+}
+
+/// What happened to the program in a [`ProgramEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramEventKind {
+    Loaded,
+    Unloaded,
+    /// Attached again after being detached, by bpfman or by another tool.
+    Attached,
+    Detached,
+}
+
+impl std::fmt::Display for ProgramEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = match self {
+            ProgramEventKind::Loaded => "loaded",
+            ProgramEventKind::Unloaded => "unloaded",
+            ProgramEventKind::Attached => "attached",
+            ProgramEventKind::Detached => "detached",
+        };
+        write!(f, "{v}")
+    }
+}
+
+/// How the programs bpfman has loaded differ from a desired set of programs,
+/// as returned by [`crate::diff_state`]. Desired programs are referred to by
+/// their index in the set, loaded programs by their ID.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StateDiff {
+    /// The desired programs that aren't loaded.
+    pub add: Vec<usize>,
+    /// The loaded programs that aren't desired.
+    pub remove: Vec<u32>,
+    /// The desired programs that are loaded, but with other settings.
+    pub change: Vec<StateChange>,
+    /// The desired programs that are loaded as they are, with their IDs.
+    pub unchanged: Vec<(usize, u32)>,
+}
+
+/// A desired program in a [`StateDiff`] that is loaded with other settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+    pub index: usize,
+    pub id: u32,
+    /// The settings that differ, such as "priority" or "bytecode".
+    pub fields: Vec<String>,
+}
+
+/// The number of programs loaded by bpfman, as reported by
+/// [`crate::program_counts`], along with the limits set in the [limits] config.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramCounts {
+    pub programs: u32,
+    pub max_programs: Option<u32>,
+    /// XDP and TC programs by interface.
+    pub per_iface: BTreeMap<String, u32>,
+    pub max_programs_per_iface: Option<u32>,
+}
+
+/// What a bytecode object holds and anything in it bpfman can't load, as
+/// reported by [`crate::validate_bytecode`] without involving the kernel.
+#[derive(Debug, Clone, Default)]
+pub struct BytecodeReport {
+    pub programs: Vec<BytecodeProgram>,
+    pub maps: Vec<BytecodeMap>,
+    pub license: String,
+    /// Whether the object has BTF, and whether it has CO-RE relocations,
+    /// which need the BTF of the kernel it is loaded on.
+    pub btf: bool,
+    pub uses_core: bool,
+    /// Problems that would stop the object, or some of its programs, from
+    /// being loaded. The object is valid if there are none.
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BytecodeProgram {
+    pub name: String,
+    pub section: String,
+    /// The kind of program the section makes it, such as "xdp" or "kprobe".
+    pub program_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BytecodeMap {
+    pub name: String,
+    pub map_type: String,
+    pub key_size: u32,
+    pub value_size: u32,
+    pub max_entries: u32,
+}
+
+/// The BPF programs and links in the kernel, whoever loaded them, as reported
+/// by [`crate::list_kernel`].
+#[derive(Debug, Clone, Default)]
+pub struct KernelObjects {
+    pub programs: Vec<KernelProgram>,
+    pub links: Vec<KernelLink>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KernelProgram {
+    pub id: u32,
+    pub name: String,
+    pub program_type: u32,
+    /// Whether bpfman loaded the program, either as one of its programs or
+    /// as the dispatcher of an interface.
+    pub managed: bool,
+    pub dispatcher: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct KernelLink {
+    pub id: u32,
+    pub prog_id: u32,
+    pub link_type: String,
+    /// The interface an XDP link is attached to.
+    pub if_index: Option<u32>,
+    pub iface: Option<String>,
+    /// Whether the program the link attaches is managed by bpfman.
+    pub managed: bool,
+}
+
+/// The kernel link a program is attached by, as reported by
+/// [`crate::link_status`].
+#[derive(Debug, Clone, Default)]
+pub struct LinkStatus {
+    pub link_id: Option<u32>,
+    /// Whether the link still exists, and for XDP and TC programs whether the
+    /// kernel still runs their dispatcher. Unset for tail call programs, which
+    /// are attached by their prog array entry rather than a link.
+    pub attached: Option<bool>,
+}
+
+/// The slots of an XDP or TC dispatcher, as reported by
+/// [`crate::dispatcher_info`].
+#[derive(Debug, Clone)]
+pub struct DispatcherSlots {
+    pub kind: ProgramType,
+    /// Only set for TC dispatchers.
+    pub direction: Option<Direction>,
+    /// The number of programs the dispatcher can run.
+    pub capacity: u32,
+    /// The number of attached programs. Detached programs don't take a slot.
+    pub used: u32,
+    pub revision: u32,
+}
+
+/// Where a program and the objects that belong to it are pinned on the bpffs,
+/// as returned by [`Program::pin_paths`].
+#[derive(Debug, Clone, Default)]
+pub struct PinPaths {
+    pub program: PathBuf,
+    /// Not set for programs that aren't attached through a link, such as
+    /// those in a prog array or detached from their dispatcher.
+    pub link: Option<PathBuf>,
+    /// The maps the program uses, by name.
+    pub maps: BTreeMap<String, PathBuf>,
+}
+
+/// The kernel features bpfman relies on, as probed by
+/// [`crate::kernel_features`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelFeatures {
+    /// The kernel exposes its own BTF, which CO-RE programs are relocated
+    /// against.
+    pub btf: bool,
+    /// Programs can be attached through BPF links, which is what lets
+    /// tracing programs stay attached when they are pinned.
+    pub bpf_link: bool,
+    /// XDP programs can be attached through the XDP dispatcher.
+    pub xdp_dispatcher: bool,
+    /// fentry and fexit programs can be attached through BPF trampolines.
+    pub trampoline: bool,
+}
+
+/// The lowest priority an XDP or TC program can be given. Programs with a
+/// lower priority value run first.
+pub const MIN_PRIORITY: i32 = 0;
+/// The highest priority an XDP or TC program can be given.
+pub const MAX_PRIORITY: i32 = 1000;
+
+/// Run statistics of a program, as reported by [`crate::program_stats`].
+#[derive(Debug, Clone)]
+pub struct ProgramStats {
+    pub id: u32,
+    pub run_count: u64,
+    pub run_time_ns: u64,
+    /// Whether the kernel is currently collecting statistics. The counters
+    /// only advance while it is.
+    pub stats_enabled: bool,
+    pub loaded_at: String,
+    /// How the program is attached, such as "xdp" or "tc_ingress". Only known
+    /// for programs loaded by bpfman.
+    pub attach_type: Option<String>,
+}
+
+/// The idempotency key of a load, scoped to the client that made it, such as
+/// the user of a bpfman-rpc peer. Keys given by different owners never match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey {
+    pub owner: String,
+    pub key: String,
+}
+
+/// A map pinned for a program, as listed by [`crate::list_maps`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramMap {
+    pub name: String,
+    /// The kind of map, named as bpftool names it, such as "hash" or
+    /// "percpu_array".
+    pub map_type: String,
+    pub key_size: u32,
+    pub value_size: u32,
+    pub max_entries: u32,
+    pub pin_path: PathBuf,
+}
+
+/// How [`crate::map_lookup`] and [`crate::map_dump`] decode map values, in
+/// addition to returning their bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MapValueFormat {
+    /// Values are only returned as bytes.
+    #[default]
+    Raw,
+    /// Values are decoded as arrays of native endian u32s.
+    U32,
+    /// Values are decoded as arrays of native endian u64s.
+    U64,
+    /// Values are decoded using the program's BTF, as the map's value type or
+    /// the named type. Programs without BTF return the bytes only.
+    Btf(Option<String>),
+}
+
+impl TryFrom<String> for MapValueFormat {
+    type Error = ParseError;
+
+    fn try_from(v: String) -> Result<Self, Self::Error> {
+        Ok(match v.as_str() {
+            "" | "raw" => Self::Raw,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "btf" => Self::Btf(None),
+            f => match f.strip_prefix("btf:") {
+                Some(name) if !name.is_empty() => Self::Btf(Some(name.to_string())),
+                _ => {
+                    return Err(ParseError::InvalidMapValueFormat {
+                        format: f.to_string(),
+                    })
+                }
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for MapValueFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapValueFormat::Raw => f.write_str("raw"),
+            MapValueFormat::U32 => f.write_str("u32"),
+            MapValueFormat::U64 => f.write_str("u64"),
+            MapValueFormat::Btf(None) => f.write_str("btf"),
+            MapValueFormat::Btf(Some(name)) => write!(f, "btf:{name}"),
+        }
+    }
+}
+
+/// The key of the map entry [`crate::map_lookup`] reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapKey {
+    /// The bytes of the key, which must be the map's key size.
+    Bytes(Vec<u8>),
+    /// A key given as text, such as `192.168.1.1`, which is encoded as the
+    /// type before the lookup.
+    Typed(MapKeyType, String),
+}
+
+/// Types that map keys given as text can be encoded as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapKeyType {
+    /// An IPv4 address, in network byte order.
+    Ipv4,
+    /// An IPv6 address, in network byte order.
+    Ipv6,
+    /// A MAC address such as 00:11:22:33:44:55.
+    Mac,
+    /// Native endian unsigned integers.
+    U16,
+    U32,
+    U64,
+    /// Big endian unsigned integers, such as ports as they appear in packets.
+    Be16,
+    Be32,
+}
+
+impl MapKeyType {
+    /// Encodes `key` as the bytes of a key of this type.
+    pub fn encode(&self, key: &str) -> Result<Vec<u8>, BpfmanError> {
+        let invalid = || BpfmanError::InvalidMapKey {
+            key_type: self.to_string(),
+            key: key.to_string(),
+        };
+        let key = key.trim();
+        Ok(match self {
+            MapKeyType::Ipv4 => key
+                .parse::<std::net::Ipv4Addr>()
+                .map_err(|_| invalid())?
+                .octets()
+                .to_vec(),
+            MapKeyType::Ipv6 => key
+                .parse::<std::net::Ipv6Addr>()
+                .map_err(|_| invalid())?
+                .octets()
+                .to_vec(),
+            MapKeyType::Mac => {
+                let octets = key
+                    .split([':', '-'])
+                    .map(|o| match o.len() {
+                        1 | 2 => u8::from_str_radix(o, 16).ok(),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<u8>>>()
+                    .filter(|o| o.len() == 6);
+                octets.ok_or_else(invalid)?
+            }
+            MapKeyType::U16 => key
+                .parse::<u16>()
+                .map_err(|_| invalid())?
+                .to_ne_bytes()
+                .to_vec(),
+            MapKeyType::U32 => key
+                .parse::<u32>()
+                .map_err(|_| invalid())?
+                .to_ne_bytes()
+                .to_vec(),
+            MapKeyType::U64 => key
+                .parse::<u64>()
+                .map_err(|_| invalid())?
+                .to_ne_bytes()
+                .to_vec(),
+            MapKeyType::Be16 => key
+                .parse::<u16>()
+                .map_err(|_| invalid())?
+                .to_be_bytes()
+                .to_vec(),
+            MapKeyType::Be32 => key
+                .parse::<u32>()
+                .map_err(|_| invalid())?
+                .to_be_bytes()
+                .to_vec(),
+        })
+    }
+}
+
+impl TryFrom<String> for MapKeyType {
+    type Error = ParseError;
+
+    fn try_from(v: String) -> Result<Self, Self::Error> {
+        Ok(match v.as_str() {
+            "ipv4" => Self::Ipv4,
+            "ipv6" => Self::Ipv6,
+            "mac" => Self::Mac,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "be16" => Self::Be16,
+            "be32" => Self::Be32,
+            _ => return Err(ParseError::InvalidMapKeyType { key_type: v }),
+        })
+    }
+}
+
+impl std::fmt::Display for MapKeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MapKeyType::Ipv4 => "ipv4",
+            MapKeyType::Ipv6 => "ipv6",
+            MapKeyType::Mac => "mac",
+            MapKeyType::U16 => "u16",
+            MapKeyType::U32 => "u32",
+            MapKeyType::U64 => "u64",
+            MapKeyType::Be16 => "be16",
+            MapKeyType::Be32 => "be32",
+        })
+    }
+}
+
+/// An entry of a map, as returned by [`MapDump::next_batch`]. Per-CPU maps
+/// have one value per possible CPU, other maps have exactly one.
+#[derive(Debug, Clone)]
+pub struct MapEntry {
+    pub key: Vec<u8>,
+    pub values: Vec<Vec<u8>>,
+    /// Each value decoded as JSON, following the [`MapValueFormat`] asked
+    /// for. Empty if the values weren't decoded.
+    pub decoded: Vec<String>,
+    /// The counters in the values of a per-CPU map, each summed across
+    /// CPUs. Empty unless a counter width was given.
+    pub sums: Vec<u64>,
+}
+
+enum DumpCursor {
+    // Continue with BPF_MAP_LOOKUP_BATCH from the given position.
+    Batch(Option<Vec<u8>>),
+    // Continue with BPF_MAP_GET_NEXT_KEY after the given key.
+    Keys(Option<Vec<u8>>),
+    Done,
+}
+
+/// Iterates over the entries of a map in batches, as returned by
+/// [`crate::map_dump`]. Batch lookups are used where the kernel supports them
+/// for the map, otherwise the map is walked one key at a time.
+pub struct MapDump {
+    map: BpfMap,
+    batch_size: usize,
+    cursor: DumpCursor,
+    decoder: Option<ValueDecoder>,
+    sum_width: Option<usize>,
+}
+
+impl MapDump {
+    pub(crate) fn new(
+        map: BpfMap,
+        batch_size: usize,
+        decoder: Option<ValueDecoder>,
+        sum_width: Option<usize>,
+    ) -> Self {
+        Self {
+            map,
+            batch_size: batch_size.max(1),
+            cursor: DumpCursor::Batch(None),
+            decoder,
+            sum_width,
+        }
+    }
+
+    fn entry(&self, key: Vec<u8>, values: Vec<Vec<u8>>) -> MapEntry {
+        let decoded = match &self.decoder {
+            Some(d) => d.decode(&values),
+            None => vec![],
+        };
+        let sums = match self.sum_width {
+            Some(width) => sum_counters(&values, width),
+            None => vec![],
+        };
+        MapEntry {
+            key,
+            values,
+            decoded,
+            sums,
+        }
+    }
+
+    pub fn is_per_cpu(&self) -> bool {
+        self.map.is_per_cpu()
+    }
+
+    /// Returns the next batch of entries, or None once all entries have been
+    /// returned. Entries added or removed while the dump is in progress may
+    /// or may not be returned.
+    pub fn next_batch(&mut self) -> Result<Option<Vec<MapEntry>>, BpfmanError> {
+        loop {
+            match &self.cursor {
+                DumpCursor::Done => return Ok(None),
+                DumpCursor::Batch(position) => {
+                    let Some(batch) = self
+                        .map
+                        .lookup_batch(position.as_deref(), self.batch_size)?
+                    else {
+                        debug!("Batch lookups unsupported, iterating over keys instead");
+                        self.cursor = DumpCursor::Keys(None);
+                        continue;
+                    };
+                    self.cursor = match batch.next {
+                        Some(next) => DumpCursor::Batch(Some(next)),
+                        None => DumpCursor::Done,
+                    };
+                    // The final call may return no entries at all.
+                    if batch.entries.is_empty() {
+                        continue;
+                    }
+                    return Ok(Some(
+                        batch
+                            .entries
+                            .into_iter()
+                            .map(|(key, values)| self.entry(key, values))
+                            .collect(),
+                    ));
+                }
+                DumpCursor::Keys(last) => {
+                    let mut last = last.clone();
+                    let mut entries = Vec::with_capacity(self.batch_size);
+                    while entries.len() < self.batch_size {
+                        let Some(key) = self.map.next_key(last.as_deref())? else {
+                            break;
+                        };
+                        match self.map.lookup(&key) {
+                            Ok(values) => entries.push(self.entry(key.clone(), values)),
+                            // Deleted since it was returned as the next key.
+                            Err(BpfmanError::MapKeyNotFound) => {}
+                            Err(e) => return Err(e),
+                        }
+                        last = Some(key);
+                    }
+                    if entries.len() < self.batch_size {
+                        self.cursor = DumpCursor::Done;
+                    } else {
+                        self.cursor = DumpCursor::Keys(last);
+                    }
+                    if entries.is_empty() {
+                        return Ok(None);
+                    }
+                    return Ok(Some(entries));
+                }
+            }
+        }
+    }
+}
+
+/// Records read from a ringbuf or perf event array by [`MapWatch::next_events`].
+#[derive(Debug, Clone, Default)]
+pub struct MapEvents {
+    pub records: Vec<Vec<u8>>,
+    /// The number of records the kernel dropped because the buffer was full,
+    /// as reported by perf event arrays.
+    pub lost: u64,
+}
+
+/// Reads the records written to a ringbuf or perf event array, as returned by
+/// [`crate::map_watch`]. The map stops being read once this is dropped.
+pub struct MapWatch {
+    reader: EventReader,
+}
+
+impl MapWatch {
+    pub(crate) fn new(reader: EventReader) -> Self {
+        Self { reader }
+    }
+
+    /// Returns the records written since the last call, waiting up to
+    /// `timeout` for one if there are none. The records may be empty if the
+    /// timeout expired.
+    pub fn next_events(&mut self, timeout: Duration) -> Result<MapEvents, BpfmanError> {
+        let events = self.reader.read(timeout)?;
+        Ok(MapEvents {
+            records: events.records,
+            lost: events.lost,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ListFilter {
     pub(crate) program_type: Option<u32>,
     pub(crate) metadata_selector: HashMap<String, String>,
     pub(crate) bpfman_programs_only: bool,
+    pub(crate) name: Option<String>,
+    pub(crate) pin_namespace: Option<String>,
 }
 
 impl ListFilter {
@@ -136,20 +785,45 @@ impl ListFilter {
         program_type: Option<u32>,
         metadata_selector: HashMap<String, String>,
         bpfman_programs_only: bool,
+        name: Option<String>,
+        pin_namespace: Option<String>,
     ) -> Self {
         Self {
             program_type,
             metadata_selector,
             bpfman_programs_only,
+            name,
+            pin_namespace,
         }
     }
 
     pub(crate) fn matches(&self, program: &Program) -> bool {
         if let Program::Unsupported(_) = program {
-            if self.bpfman_programs_only {
+            // Only programs loaded by bpfman are in a pin namespace.
+            if self.bpfman_programs_only || self.pin_namespace.is_some() {
                 return false;
             }
 
+            // The kernel truncates program names to BPF_OBJ_NAME_LEN - 1
+            // characters, so a truncated kernel name matches the start of the
+            // requested name.
+            if let Some(name) = &self.name {
+                match program.get_data().get_kernel_name() {
+                    Ok(kernel_name) => {
+                        if kernel_name != *name
+                            && !(kernel_name.len() == BPF_OBJ_NAME_LEN - 1
+                                && name.starts_with(&kernel_name))
+                        {
+                            return false;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to get kernel program name during list match: {}", e);
+                        return false;
+                    }
+                }
+            }
+
             if let Some(prog_type) = self.program_type {
                 match program.get_data().get_kernel_program_type() {
                     Ok(kernel_prog_type) => {
@@ -178,6 +852,32 @@ impl ListFilter {
                     return false;
                 }
             }
+            if let Some(namespace) = &self.pin_namespace {
+                match program.get_data().get_pin_namespace() {
+                    Ok(n) => {
+                        if n.as_ref() != Some(namespace) {
+                            return false;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to get pin namespace during list match: {}", e);
+                        return false;
+                    }
+                }
+            }
+            if let Some(name) = &self.name {
+                match program.get_data().get_name() {
+                    Ok(n) => {
+                        if n != *name {
+                            return false;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to get program name during list match: {}", e);
+                        return false;
+                    }
+                }
+            }
             // Filter on the input metadata field if provided
             for (key, value) in &self.metadata_selector {
                 match program.get_data().get_metadata() {
@@ -201,6 +901,46 @@ impl ListFilter {
     }
 }
 
+/// Picks out an XDP or TC program by the name it was loaded with and the
+/// interface it's attached to, for callers that no longer know its ID.
+#[derive(Debug, Clone)]
+pub struct ProgramSelector {
+    pub(crate) name: String,
+    pub(crate) iface: String,
+    pub(crate) priority: Option<i32>,
+}
+
+impl ProgramSelector {
+    pub fn new(name: String, iface: String, priority: Option<i32>) -> Self {
+        Self {
+            name,
+            iface,
+            priority,
+        }
+    }
+
+    pub(crate) fn matches(&self, program: &Program) -> bool {
+        if !matches!(program, Program::Xdp(_) | Program::Tc(_)) {
+            return false;
+        }
+        program.get_data().get_name().is_ok_and(|n| n == self.name)
+            && program.if_name().is_ok_and(|i| i == self.iface)
+            && self
+                .priority
+                .is_none_or(|p| program.priority().is_ok_and(|q| q == p))
+    }
+}
+
+impl std::fmt::Display for ProgramSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "program {} on {}", self.name, self.iface)?;
+        if let Some(priority) = self.priority {
+            write!(f, " at priority {priority}")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Program {
     Xdp(XdpProgram),
@@ -210,6 +950,8 @@ pub enum Program {
     Uprobe(UprobeProgram),
     Fentry(FentryProgram),
     Fexit(FexitProgram),
+    Cgroup(CgroupProgram),
+    TailCall(TailCallProgram),
     Unsupported(ProgramData),
 }
 
@@ -217,16 +959,36 @@ pub enum Program {
 pub enum Location {
     Image(BytecodeImage),
     File(String),
+    Url(BytecodeUrl),
 }
 
 impl Location {
-    async fn get_program_bytes(
+    /// Returns the path, image URL or URL the bytecode is loaded from.
+    pub(crate) fn source(&self) -> &str {
+        match self {
+            Location::File(path) => path,
+            Location::Image(image) => &image.image_url,
+            Location::Url(url) => &url.url,
+        }
+    }
+
+    pub(crate) async fn get_program_bytes(
         &self,
         root_db: &Db,
         image_manager: &mut ImageManager,
     ) -> Result<(Vec<u8>, String), BpfmanError> {
         match self {
-            Location::File(l) => Ok((crate::utils::read(l)?, "".to_owned())),
+            Location::File(l) => {
+                let config = open_config_file();
+                let limits = config.limits().clone().unwrap_or_default();
+                let bytecode = crate::utils::read(l, limits.bytecode_size_limit())?;
+                signing::verify_file(&config.signing().clone().unwrap_or_default(), l, &bytecode)?;
+                Ok((bytecode, "".to_owned()))
+            }
+            Location::Url(l) => {
+                let bytecode = download::download(l, &open_config_file()).await?;
+                Ok((bytecode, "".to_owned()))
+            }
             Location::Image(l) => {
                 let (path, bpf_function_name) = image_manager
                     .get_image(
@@ -288,6 +1050,110 @@ impl std::fmt::Display for Direction {
     }
 }
 
+/// Where in a cgroup a program is attached. Ingress and egress are hooks
+/// for cgroup_skb programs, the rest are hooks for cgroup_sock programs.
+#[derive(Debug, Serialize, Hash, Deserialize, Eq, PartialEq, Copy, Clone)]
+pub enum CgroupAttachType {
+    Ingress,
+    Egress,
+    SockCreate,
+    SockRelease,
+    PostBind4,
+    PostBind6,
+}
+
+impl CgroupAttachType {
+    /// Returns the kernel program type that attaches at this hook.
+    pub fn program_type(&self) -> ProgramType {
+        match self {
+            CgroupAttachType::Ingress | CgroupAttachType::Egress => ProgramType::CgroupSkb,
+            _ => ProgramType::CgroupSock,
+        }
+    }
+}
+
+impl TryFrom<String> for CgroupAttachType {
+    type Error = ParseError;
+
+    fn try_from(v: String) -> Result<Self, Self::Error> {
+        match v.as_str() {
+            "ingress" => Ok(Self::Ingress),
+            "egress" => Ok(Self::Egress),
+            "sock_create" => Ok(Self::SockCreate),
+            "sock_release" => Ok(Self::SockRelease),
+            "post_bind4" => Ok(Self::PostBind4),
+            "post_bind6" => Ok(Self::PostBind6),
+            m => Err(ParseError::InvalidCgroupAttachType {
+                attach_type: m.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for CgroupAttachType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CgroupAttachType::Ingress => f.write_str("ingress"),
+            CgroupAttachType::Egress => f.write_str("egress"),
+            CgroupAttachType::SockCreate => f.write_str("sock_create"),
+            CgroupAttachType::SockRelease => f.write_str("sock_release"),
+            CgroupAttachType::PostBind4 => f.write_str("post_bind4"),
+            CgroupAttachType::PostBind6 => f.write_str("post_bind6"),
+        }
+    }
+}
+
+/// The mode an XDP program asks for its interface's dispatcher to be attached
+/// in. Every program on an interface shares the dispatcher and so its mode.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Copy, Clone)]
+pub enum XdpModePreference {
+    Skb,
+    Drv,
+    Hw,
+    /// Native mode where the driver supports it, generic mode otherwise.
+    Auto,
+}
+
+impl XdpModePreference {
+    /// Returns whether a dispatcher attached in `mode` satisfies the
+    /// preference.
+    pub(crate) fn accepts(&self, mode: XdpMode) -> bool {
+        match self {
+            XdpModePreference::Skb => mode == XdpMode::Skb,
+            XdpModePreference::Drv => mode == XdpMode::Drv,
+            XdpModePreference::Hw => mode == XdpMode::Hw,
+            XdpModePreference::Auto => mode != XdpMode::Hw,
+        }
+    }
+}
+
+impl TryFrom<String> for XdpModePreference {
+    type Error = ParseError;
+
+    fn try_from(v: String) -> Result<Self, Self::Error> {
+        match v.as_str() {
+            "skb" => Ok(Self::Skb),
+            "drv" => Ok(Self::Drv),
+            "hw" => Ok(Self::Hw),
+            "auto" => Ok(Self::Auto),
+            m => Err(ParseError::InvalidXdpMode {
+                mode: m.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for XdpModePreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XdpModePreference::Skb => f.write_str("skb"),
+            XdpModePreference::Drv => f.write_str("drv"),
+            XdpModePreference::Hw => f.write_str("hw"),
+            XdpModePreference::Auto => f.write_str("auto"),
+        }
+    }
+}
+
 /// ProgramData stores information about bpf programs that are loaded and managed
 /// by bpfman.
 #[derive(Debug, Clone)]
@@ -421,6 +1287,14 @@ impl ProgramData {
     pub(crate) fn set_location(&mut self, loc: Location) -> Result<(), BpfmanError> {
         match loc {
             Location::File(l) => sled_insert(&self.db_tree, LOCATION_FILENAME, l.as_bytes()),
+            Location::Url(l) => {
+                download::check_url(&l)?;
+                sled_insert(&self.db_tree, LOCATION_URL, l.url.as_bytes())?;
+                if let Some(sha256) = &l.sha256 {
+                    sled_insert(&self.db_tree, LOCATION_URL_SHA256, sha256.as_bytes())?;
+                }
+                Ok(())
+            }
             Location::Image(l) => {
                 sled_insert(&self.db_tree, LOCATION_IMAGE_URL, l.image_url.as_bytes())?;
                 sled_insert(
@@ -452,6 +1326,12 @@ impl ProgramData {
     pub fn get_location(&self) -> Result<Location, BpfmanError> {
         if let Ok(l) = sled_get(&self.db_tree, LOCATION_FILENAME) {
             Ok(Location::File(bytes_to_string(&l).to_string()))
+        } else if let Some(url) = sled_get_option(&self.db_tree, LOCATION_URL)? {
+            Ok(Location::Url(BytecodeUrl {
+                url: bytes_to_string(&url),
+                sha256: sled_get_option(&self.db_tree, LOCATION_URL_SHA256)?
+                    .map(|v| bytes_to_string(&v)),
+            }))
         } else {
             Ok(Location::Image(BytecodeImage {
                 image_url: bytes_to_string(&sled_get(&self.db_tree, LOCATION_IMAGE_URL)?)
@@ -552,6 +1432,177 @@ impl ProgramData {
         sled_get_option(&self.db_tree, MAP_OWNER_ID).map(|v| v.map(bytes_to_u32))
     }
 
+    /// Sets the pin namespace the program's pins are kept in, see
+    /// [`Self::get_pin_namespace`].
+    pub fn set_pin_namespace(&mut self, namespace: &str) -> Result<(), BpfmanError> {
+        pin_namespace::validate(namespace)?;
+        sled_insert(&self.db_tree, PIN_NAMESPACE, namespace.as_bytes())
+    }
+
+    /// Returns the pin namespace of the program, which scopes where its
+    /// program, link and map pins are kept in the bpffs. Programs without one
+    /// are pinned at the top of the bpffs.
+    pub fn get_pin_namespace(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.db_tree, PIN_NAMESPACE).map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    pub(crate) fn set_iface_pin_dir(&mut self, dir: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, IFACE_PIN_DIR, dir.as_bytes())
+    }
+
+    /// Returns the directory of the interface the program is pinned in, if it
+    /// was loaded with [bpffs] per_interface_dirs set.
+    pub(crate) fn get_iface_pin_dir(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.db_tree, IFACE_PIN_DIR).map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    /// Sets whether an XDP or TC program may be loaded even though the same
+    /// program is already loaded at its hook, see [`Self::get_duplicate_of`].
+    pub fn set_allow_duplicate(&mut self, allow: bool) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, ALLOW_DUPLICATE, &(allow as i8).to_ne_bytes())
+    }
+
+    pub fn get_allow_duplicate(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, ALLOW_DUPLICATE)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_duplicate_of(&mut self, id: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, DUPLICATE_OF, &id.to_ne_bytes())
+    }
+
+    /// Returns the ID of the program that already ran the same program on
+    /// the same interface and hook when this one was loaded with duplicates
+    /// allowed.
+    pub fn get_duplicate_of(&self) -> Result<Option<u32>, BpfmanError> {
+        sled_get_option(&self.db_tree, DUPLICATE_OF).map(|v| v.map(bytes_to_u32))
+    }
+
+    /// Sets the idempotency key the program is loaded with, so that loading
+    /// with the same key again returns this program rather than loading
+    /// another, see [`crate::add_programs`]. The time it is set is recorded
+    /// for [limits] idempotency_ttl_secs.
+    pub fn set_idempotency_key(&mut self, key: &IdempotencyKey) -> Result<(), BpfmanError> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        sled_insert(&self.db_tree, IDEMPOTENCY_OWNER, key.owner.as_bytes())?;
+        sled_insert(&self.db_tree, IDEMPOTENCY_KEY, key.key.as_bytes())?;
+        sled_insert(&self.db_tree, IDEMPOTENCY_TIME, &now.to_ne_bytes())
+    }
+
+    pub fn get_idempotency_key(&self) -> Result<Option<IdempotencyKey>, BpfmanError> {
+        let Some(key) = sled_get_option(&self.db_tree, IDEMPOTENCY_KEY)? else {
+            return Ok(None);
+        };
+        Ok(Some(IdempotencyKey {
+            owner: sled_get_option(&self.db_tree, IDEMPOTENCY_OWNER)?
+                .map(|v| bytes_to_string(&v))
+                .unwrap_or_default(),
+            key: bytes_to_string(&key),
+        }))
+    }
+
+    /// Records a digest of the load request the program's idempotency key was
+    /// given with, so that the key can't be reused for a different request.
+    pub(crate) fn set_idempotency_digest(&mut self, digest: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, IDEMPOTENCY_DIGEST, digest.as_bytes())
+    }
+
+    pub(crate) fn get_idempotency_digest(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.db_tree, IDEMPOTENCY_DIGEST).map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    /// Returns whether the program's idempotency key was set longer than
+    /// `ttl` ago.
+    pub(crate) fn idempotency_key_expired(
+        &self,
+        ttl: Option<Duration>,
+    ) -> Result<bool, BpfmanError> {
+        let Some(ttl) = ttl else {
+            return Ok(false);
+        };
+        let set = sled_get_option(&self.db_tree, IDEMPOTENCY_TIME)?.map(bytes_to_u64);
+        let age = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(Duration::from_secs(set.unwrap_or_default()));
+        Ok(age >= ttl)
+    }
+
+    /// Records when the program was loaded by bpfman. Unlike the kernel's
+    /// load time, which starts again each time the dispatcher an XDP or TC
+    /// program is on is rebuilt, this is kept as it is until the program is
+    /// unloaded, across restarts of bpfman too.
+    pub(crate) fn set_loaded_at(&mut self, loaded_at: SystemTime) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            LOADED_AT,
+            &unix_secs(loaded_at).to_ne_bytes(),
+        )
+    }
+
+    /// Returns when the program was loaded by bpfman, or None for programs
+    /// it didn't load, or loaded before it recorded the time.
+    pub fn get_loaded_at(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.db_tree, LOADED_AT)
+            .map(|v| v.map(|v| format_time(from_unix_secs(bytes_to_u64(v)))))
+    }
+
+    /// Records when the program was last attached by bpfman, or clears it
+    /// when the program is detached.
+    pub(crate) fn set_attached_at(
+        &mut self,
+        attached_at: Option<SystemTime>,
+    ) -> Result<(), BpfmanError> {
+        match attached_at {
+            Some(t) => sled_insert(&self.db_tree, ATTACHED_AT, &unix_secs(t).to_ne_bytes()),
+            None => sled_remove(&self.db_tree, ATTACHED_AT),
+        }
+    }
+
+    /// Returns when the program was last attached by bpfman, or None while
+    /// it is detached.
+    pub fn get_attached_at(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.db_tree, ATTACHED_AT)
+            .map(|v| v.map(|v| format_time(from_unix_secs(bytes_to_u64(v)))))
+    }
+
+    /// Returns how long ago the program was loaded by bpfman, see
+    /// [`ProgramData::get_loaded_at`].
+    pub fn get_uptime(&self) -> Result<Option<Duration>, BpfmanError> {
+        let Some(loaded_at) = sled_get_option(&self.db_tree, LOADED_AT)?.map(bytes_to_u64) else {
+            return Ok(None);
+        };
+        Ok(Some(Duration::from_secs(
+            unix_secs(SystemTime::now()).saturating_sub(loaded_at),
+        )))
+    }
+
+    pub(crate) fn program_pin_path(&self) -> Result<String, BpfmanError> {
+        let dir = match self.get_iface_pin_dir()? {
+            Some(dir) => dir,
+            None => pin_namespace::pin_dir(self.get_pin_namespace()?.as_deref()),
+        };
+        Ok(format!("{dir}/prog_{}", self.get_id()?))
+    }
+
+    pub(crate) fn link_pin_path(&self) -> Result<String, BpfmanError> {
+        Ok(format!("{}_link", self.program_pin_path()?))
+    }
+
+    pub(crate) fn set_group_id(&mut self, id: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, GROUP_ID, &id.to_ne_bytes())
+    }
+
+    /// Returns the ID of the group the program was loaded in, which is the ID
+    /// of the first program in the group.
+    pub fn get_group_id(&self) -> Result<Option<u32>, BpfmanError> {
+        sled_get_option(&self.db_tree, GROUP_ID).map(|v| v.map(bytes_to_u32))
+    }
+
     pub(crate) fn set_map_pin_path(&mut self, path: &Path) -> Result<(), BpfmanError> {
         sled_insert(
             &self.db_tree,
@@ -601,6 +1652,165 @@ impl ProgramData {
         });
     }
 
+    /// Sets the names of the maps the program shares with other programs that
+    /// name the same maps. The first program to be loaded creates each map,
+    /// and the rest reuse it for as long as any of them is still loaded.
+    pub fn set_shared_maps(&mut self, names: &[String]) -> Result<(), BpfmanError> {
+        self.set_map_names(PREFIX_SHARED_MAPS, names)
+    }
+
+    pub fn get_shared_maps(&self) -> Result<Vec<String>, BpfmanError> {
+        self.get_map_names(PREFIX_SHARED_MAPS, "shared maps")
+    }
+
+    /// Sets the maps to pin even if they would otherwise be skipped, such as
+    /// the `.data` map.
+    pub fn set_maps_to_pin(&mut self, names: &[String]) -> Result<(), BpfmanError> {
+        self.set_map_names(PREFIX_MAPS_TO_PIN, names)
+    }
+
+    pub fn get_maps_to_pin(&self) -> Result<Vec<String>, BpfmanError> {
+        self.get_map_names(PREFIX_MAPS_TO_PIN, "maps to pin")
+    }
+
+    /// Sets the maps to leave unpinned even if they would otherwise be pinned.
+    pub fn set_maps_to_skip(&mut self, names: &[String]) -> Result<(), BpfmanError> {
+        self.set_map_names(PREFIX_MAPS_TO_SKIP, names)
+    }
+
+    pub fn get_maps_to_skip(&self) -> Result<Vec<String>, BpfmanError> {
+        self.get_map_names(PREFIX_MAPS_TO_SKIP, "maps to skip")
+    }
+
+    /// Returns whether the map `name` is pinned when this program owns its
+    /// maps. The program's maps to pin and skip take precedence over the
+    /// default of pinning every map other than `.rodata`, `.bss` and `.data`.
+    /// A map can't be in both lists, see [`Self::check_map_pin_overrides`].
+    pub(crate) fn should_pin_map(&self, name: &str) -> Result<bool, BpfmanError> {
+        let name = name.to_string();
+        if self.get_maps_to_pin()?.contains(&name) {
+            return Ok(true);
+        }
+        if self.get_maps_to_skip()?.contains(&name) {
+            return Ok(false);
+        }
+        Ok(should_map_be_pinned(&name))
+    }
+
+    /// Checks that every map named in the maps to pin or skip is one that
+    /// `has_map` knows of, and that none is named in both.
+    pub(crate) fn check_map_pin_overrides(
+        &self,
+        has_map: impl Fn(&str) -> bool,
+    ) -> Result<(), BpfmanError> {
+        let to_pin = self.get_maps_to_pin()?;
+        let to_skip = self.get_maps_to_skip()?;
+        for name in to_pin.iter().chain(to_skip.iter()) {
+            let reason = if to_pin.contains(name) && to_skip.contains(name) {
+                "the map is in both the maps to pin and the maps to skip"
+            } else if !has_map(name) {
+                "no map with that name in the bytecode"
+            } else {
+                continue;
+            };
+            return Err(BpfmanError::InvalidMapPinOverride {
+                name: name.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets the number of entries to create maps with in place of the
+    /// max_entries the bytecode declares, by map name.
+    pub fn set_map_max_entries(
+        &mut self,
+        max_entries: &HashMap<String, u32>,
+    ) -> Result<(), BpfmanError> {
+        max_entries.iter().try_for_each(|(name, n)| {
+            sled_insert(
+                &self.db_tree,
+                format!("{PREFIX_MAP_MAX_ENTRIES}{name}").as_str(),
+                &n.to_ne_bytes(),
+            )
+        })
+    }
+
+    pub fn get_map_max_entries(&self) -> Result<HashMap<String, u32>, BpfmanError> {
+        self.db_tree
+            .scan_prefix(PREFIX_MAP_MAX_ENTRIES)
+            .map(|n| {
+                n.map(|(k, v)| {
+                    (
+                        bytes_to_string(&k)
+                            .strip_prefix(PREFIX_MAP_MAX_ENTRIES)
+                            .unwrap()
+                            .to_string(),
+                        bytes_to_u32(v.to_vec()),
+                    )
+                })
+                .map_err(|e| {
+                    BpfmanError::DatabaseError(
+                        "Failed to get map max entries".to_string(),
+                        e.to_string(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that every map with a max_entries override is one `map_type`
+    /// gives the type of, and that the map can be created with that many
+    /// entries. Maps the program doesn't create itself, those of its map
+    /// owner and shared maps, can't be overridden.
+    pub(crate) fn check_map_max_entries(
+        &self,
+        map_type: impl Fn(&str) -> Option<u32>,
+    ) -> Result<(), BpfmanError> {
+        let shared = self.get_shared_maps()?;
+        for (name, max_entries) in self.get_map_max_entries()? {
+            let reason = if self.get_map_owner_id()?.is_some() {
+                "the program uses the maps of its map owner".to_string()
+            } else if shared.contains(&name) {
+                "shared maps are created by the first program that shares them".to_string()
+            } else if name.starts_with('.') {
+                "the size of a global data map is fixed by the bytecode".to_string()
+            } else {
+                match map_type(&name) {
+                    None => "no map with that name in the bytecode".to_string(),
+                    Some(t) => match check_max_entries(t, max_entries) {
+                        Ok(()) => continue,
+                        Err(reason) => reason,
+                    },
+                }
+            };
+            return Err(BpfmanError::InvalidMapMaxEntries { name, reason });
+        }
+        Ok(())
+    }
+
+    fn set_map_names(&mut self, prefix: &str, names: &[String]) -> Result<(), BpfmanError> {
+        names.iter().try_for_each(|name| {
+            sled_insert(
+                &self.db_tree,
+                format!("{prefix}{name}").as_str(),
+                name.as_bytes(),
+            )
+        })
+    }
+
+    fn get_map_names(&self, prefix: &str, what: &str) -> Result<Vec<String>, BpfmanError> {
+        self.db_tree
+            .scan_prefix(prefix)
+            .map(|n| n.map(|(_, v)| bytes_to_string(&v)))
+            .map(|n| {
+                n.map_err(|e| {
+                    BpfmanError::DatabaseError(format!("Failed to get {what}"), e.to_string())
+                })
+            })
+            .collect()
+    }
+
     pub(crate) fn get_program_bytes(&self) -> Result<Vec<u8>, BpfmanError> {
         sled_get(&self.db_tree, PROGRAM_BYTES)
     }
@@ -637,6 +1847,9 @@ impl ProgramData {
                     Location::File(l) => {
                         info!("Loading program bytecode from file: {}", l);
                     }
+                    Location::Url(l) => {
+                        info!("Loading program bytecode from URL: {}", l.url);
+                    }
                 }
                 sled_insert(&self.db_tree, PROGRAM_BYTES, &v)?;
                 Ok(())
@@ -683,10 +1896,7 @@ impl ProgramData {
         sled_insert(
             &self.db_tree,
             KERNEL_LOADED_AT,
-            DateTime::<Local>::from(loaded_at)
-                .format("%Y-%m-%dT%H:%M:%S%z")
-                .to_string()
-                .as_bytes(),
+            format_time(loaded_at).as_bytes(),
         )
     }
 
@@ -859,12 +2069,16 @@ impl XdpProgram {
         priority: i32,
         iface: String,
         proceed_on: XdpProceedOn,
+        netns: Option<PathBuf>,
     ) -> Result<Self, BpfmanError> {
         let mut xdp_prog = Self { data };
 
         xdp_prog.set_priority(priority)?;
         xdp_prog.set_iface(iface)?;
         xdp_prog.set_proceed_on(proceed_on)?;
+        if let Some(n) = netns {
+            xdp_prog.set_netns(&n)?;
+        }
         xdp_prog.get_data_mut().set_kind(ProgramType::Xdp)?;
 
         Ok(xdp_prog)
@@ -919,34 +2133,187 @@ impl XdpProgram {
             .collect()
     }
 
-    pub(crate) fn set_current_position(&mut self, pos: usize) -> Result<(), BpfmanError> {
-        sled_insert(&self.data.db_tree, XDP_CURRENT_POSITION, &pos.to_ne_bytes())
+    pub(crate) fn set_current_position(&mut self, pos: usize) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_CURRENT_POSITION, &pos.to_ne_bytes())
+    }
+
+    pub fn get_current_position(&self) -> Result<Option<usize>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_CURRENT_POSITION)?.map(bytes_to_usize))
+    }
+
+    pub(crate) fn set_dispatcher_revision(&mut self, revision: u32) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_DISPATCHER_REVISION,
+            &revision.to_ne_bytes(),
+        )
+    }
+
+    /// Returns the revision of the dispatcher the program is attached to.
+    pub fn get_dispatcher_revision(&self) -> Result<Option<u32>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_DISPATCHER_REVISION)?.map(bytes_to_u32))
+    }
+
+    pub(crate) fn set_dispatcher_replaced(&mut self, replaced: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_DISPATCHER_REPLACED,
+            &(replaced as i8).to_ne_bytes(),
+        )
+    }
+
+    /// Returns true if the dispatcher the program is attached to took over
+    /// the interface from the previous revision in place, so that packets
+    /// were processed throughout the rebuild.
+    pub fn get_dispatcher_replaced(&self) -> Result<bool, BpfmanError> {
+        Ok(
+            sled_get_option(&self.data.db_tree, XDP_DISPATCHER_REPLACED)?
+                .map(bytes_to_bool)
+                .unwrap_or(false),
+        )
+    }
+
+    pub(crate) fn set_dispatcher_pin_dir(&mut self, dir: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_DISPATCHER_PIN_DIR, dir.as_bytes())
+    }
+
+    /// Returns the directory the dispatcher the program is attached to is
+    /// pinned in, if it was recorded.
+    pub(crate) fn get_dispatcher_pin_dir(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, XDP_DISPATCHER_PIN_DIR)
+            .map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    pub(crate) fn set_if_index(&mut self, if_index: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_IF_INDEX, &if_index.to_ne_bytes())
+    }
+
+    pub fn get_if_index(&self) -> Result<Option<u32>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_IF_INDEX)?.map(bytes_to_u32))
+    }
+
+    pub(crate) fn set_attached(&mut self, attached: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_ATTACHED,
+            &(attached as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_attached(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_ATTACHED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_detached(&mut self, detached: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_DETACHED,
+            &(detached as i8).to_ne_bytes(),
+        )
+    }
+
+    /// Returns true if the program has been detached from its dispatcher but
+    /// is still loaded, ready to be attached again.
+    pub fn get_detached(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_DETACHED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    /// Sets whether the program acts on packets. A disabled program still runs
+    /// in its dispatcher, but what it returns is ignored and the next program
+    /// runs, as if it had asked to proceed on every action.
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_DISABLED,
+            &(!enabled as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_enabled(&self) -> Result<bool, BpfmanError> {
+        Ok(!sled_get_option(&self.data.db_tree, XDP_DISABLED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_netns(&mut self, netns: &Path) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_NETNS,
+            netns.to_str().unwrap().as_bytes(),
+        )
     }
 
-    pub fn get_current_position(&self) -> Result<Option<usize>, BpfmanError> {
-        Ok(sled_get_option(&self.data.db_tree, XDP_CURRENT_POSITION)?.map(bytes_to_usize))
+    pub fn get_netns(&self) -> Result<Option<PathBuf>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, XDP_NETNS)
+            .map(|v| v.map(|f| PathBuf::from(bytes_to_string(&f))))
     }
 
-    pub(crate) fn set_if_index(&mut self, if_index: u32) -> Result<(), BpfmanError> {
-        sled_insert(&self.data.db_tree, XDP_IF_INDEX, &if_index.to_ne_bytes())
+    pub(crate) fn set_netns_id(&mut self, netns_id: u64) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_NETNS_ID, &netns_id.to_ne_bytes())
     }
 
-    pub fn get_if_index(&self) -> Result<Option<u32>, BpfmanError> {
-        Ok(sled_get_option(&self.data.db_tree, XDP_IF_INDEX)?.map(bytes_to_u32))
+    pub fn get_netns_id(&self) -> Result<Option<u64>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_NETNS_ID)?.map(bytes_to_u64))
     }
 
-    pub(crate) fn set_attached(&mut self, attached: bool) -> Result<(), BpfmanError> {
+    /// Sets the mode the program needs its interface's dispatcher to be
+    /// attached in. Without one, the mode set for the interface in the
+    /// [interfaces] config is used, or generic mode if there is none.
+    pub fn set_mode_preference(&mut self, mode: XdpModePreference) -> Result<(), BpfmanError> {
         sled_insert(
             &self.data.db_tree,
-            XDP_ATTACHED,
-            &(attached as i8).to_ne_bytes(),
+            XDP_MODE_PREFERENCE,
+            mode.to_string().as_bytes(),
         )
     }
 
-    pub fn get_attached(&self) -> Result<bool, BpfmanError> {
-        Ok(sled_get_option(&self.data.db_tree, XDP_ATTACHED)?
-            .map(bytes_to_bool)
-            .unwrap_or(false))
+    pub fn get_mode_preference(&self) -> Result<Option<XdpModePreference>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, XDP_MODE_PREFERENCE)?
+            .map(|v| {
+                XdpModePreference::try_from(bytes_to_string(&v))
+                    .map_err(|e| BpfmanError::Error(e.to_string()))
+            })
+            .transpose()
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: XdpMode) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_MODE, &(mode as u32).to_ne_bytes())
+    }
+
+    /// Returns the mode the dispatcher the program is attached to runs in.
+    pub fn get_mode(&self) -> Result<Option<XdpMode>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, XDP_MODE)?
+            .map(|v| {
+                XdpMode::try_from(bytes_to_u32(v)).map_err(|e| BpfmanError::Error(e.to_string()))
+            })
+            .transpose()
+    }
+
+    // Interface names can't contain spaces.
+    pub(crate) fn set_members(&mut self, members: &[String]) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_MEMBERS,
+            members.join(" ").as_bytes(),
+        )
+    }
+
+    /// Returns the interfaces the kernel runs the program on in place of its
+    /// interface, which are the members of a bond the program is attached to
+    /// in native mode. Empty for programs that run on their own interface.
+    pub fn get_members(&self) -> Result<Vec<String>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_MEMBERS)?
+            .map(|v| {
+                bytes_to_string(&v)
+                    .split_whitespace()
+                    .map(|m| m.to_string())
+                    .collect()
+            })
+            .unwrap_or_default())
     }
 
     pub(crate) fn get_data(&self) -> &ProgramData {
@@ -970,6 +2337,7 @@ impl TcProgram {
         iface: String,
         proceed_on: TcProceedOn,
         direction: Direction,
+        netns: Option<PathBuf>,
     ) -> Result<Self, BpfmanError> {
         let mut tc_prog = Self { data };
 
@@ -977,6 +2345,9 @@ impl TcProgram {
         tc_prog.set_iface(iface)?;
         tc_prog.set_proceed_on(proceed_on)?;
         tc_prog.set_direction(direction)?;
+        if let Some(n) = netns {
+            tc_prog.set_netns(&n)?;
+        }
         tc_prog.get_data_mut().set_kind(ProgramType::Tc)?;
 
         Ok(tc_prog)
@@ -1036,6 +2407,30 @@ impl TcProgram {
         Ok(sled_get_option(&self.data.db_tree, TC_CURRENT_POSITION)?.map(bytes_to_usize))
     }
 
+    pub(crate) fn set_dispatcher_revision(&mut self, revision: u32) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            TC_DISPATCHER_REVISION,
+            &revision.to_ne_bytes(),
+        )
+    }
+
+    /// Returns the revision of the dispatcher the program is attached to.
+    pub fn get_dispatcher_revision(&self) -> Result<Option<u32>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, TC_DISPATCHER_REVISION)?.map(bytes_to_u32))
+    }
+
+    pub(crate) fn set_dispatcher_pin_dir(&mut self, dir: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, TC_DISPATCHER_PIN_DIR, dir.as_bytes())
+    }
+
+    /// Returns the directory the dispatcher the program is attached to is
+    /// pinned in, if it was recorded.
+    pub(crate) fn get_dispatcher_pin_dir(&self) -> Result<Option<String>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, TC_DISPATCHER_PIN_DIR)
+            .map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
     pub(crate) fn set_if_index(&mut self, if_index: u32) -> Result<(), BpfmanError> {
         sled_insert(&self.data.db_tree, TC_IF_INDEX, &if_index.to_ne_bytes())
     }
@@ -1058,6 +2453,60 @@ impl TcProgram {
             .unwrap_or(false))
     }
 
+    pub(crate) fn set_detached(&mut self, detached: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            TC_DETACHED,
+            &(detached as i8).to_ne_bytes(),
+        )
+    }
+
+    /// Returns true if the program has been detached from its dispatcher but
+    /// is still loaded, ready to be attached again.
+    pub fn get_detached(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, TC_DETACHED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    /// Sets whether the program acts on packets. A disabled program still runs
+    /// in its dispatcher, but what it returns is ignored and the next program
+    /// runs, as if it had asked to proceed on every action.
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            TC_DISABLED,
+            &(!enabled as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_enabled(&self) -> Result<bool, BpfmanError> {
+        Ok(!sled_get_option(&self.data.db_tree, TC_DISABLED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_netns(&mut self, netns: &Path) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            TC_NETNS,
+            netns.to_str().unwrap().as_bytes(),
+        )
+    }
+
+    pub fn get_netns(&self) -> Result<Option<PathBuf>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, TC_NETNS)
+            .map(|v| v.map(|f| PathBuf::from(bytes_to_string(&f))))
+    }
+
+    pub(crate) fn set_netns_id(&mut self, netns_id: u64) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, TC_NETNS_ID, &netns_id.to_ne_bytes())
+    }
+
+    pub fn get_netns_id(&self) -> Result<Option<u64>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, TC_NETNS_ID)?.map(bytes_to_u64))
+    }
+
     pub(crate) fn set_direction(&mut self, direction: Direction) -> Result<(), BpfmanError> {
         sled_insert(
             &self.data.db_tree,
@@ -1129,8 +2578,8 @@ impl KprobeProgram {
         kprobe_prog.set_offset(offset)?;
         kprobe_prog.set_retprobe(retprobe)?;
         kprobe_prog.get_data_mut().set_kind(ProgramType::Probe)?;
-        if container_pid.is_some() {
-            kprobe_prog.set_container_pid(container_pid.unwrap())?;
+        if let Some(p) = container_pid {
+            kprobe_prog.set_container_pid(p)?;
         }
         Ok(kprobe_prog)
     }
@@ -1203,8 +2652,8 @@ impl UprobeProgram {
     ) -> Result<Self, BpfmanError> {
         let mut uprobe_prog = Self { data };
 
-        if fn_name.is_some() {
-            uprobe_prog.set_fn_name(fn_name.unwrap())?;
+        if let Some(f) = fn_name {
+            uprobe_prog.set_fn_name(f)?;
         }
 
         uprobe_prog.set_offset(offset)?;
@@ -1349,6 +2798,109 @@ impl FexitProgram {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct CgroupProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl CgroupProgram {
+    pub fn new(
+        data: ProgramData,
+        path: String,
+        attach_type: CgroupAttachType,
+    ) -> Result<Self, BpfmanError> {
+        let mut cgroup_prog = Self { data };
+        cgroup_prog.set_path(path)?;
+        cgroup_prog.set_attach_type(attach_type)?;
+        cgroup_prog
+            .get_data_mut()
+            .set_kind(attach_type.program_type())?;
+
+        Ok(cgroup_prog)
+    }
+
+    pub(crate) fn set_path(&mut self, path: String) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, CGROUP_PATH, path.as_bytes())
+    }
+
+    pub fn get_path(&self) -> Result<String, BpfmanError> {
+        sled_get(&self.data.db_tree, CGROUP_PATH).map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn set_attach_type(
+        &mut self,
+        attach_type: CgroupAttachType,
+    ) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            CGROUP_ATTACH_TYPE,
+            attach_type.to_string().as_bytes(),
+        )
+    }
+
+    pub fn get_attach_type(&self) -> Result<CgroupAttachType, BpfmanError> {
+        sled_get(&self.data.db_tree, CGROUP_ATTACH_TYPE)
+            .map(|v| bytes_to_string(&v).to_string().try_into().unwrap())
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+/// A program that isn't attached anywhere, but is instead registered in a
+/// prog array map of its map owner so that the owner can tail call it. The
+/// program type comes from the program's section and is only known once it
+/// has been loaded.
+#[derive(Debug, Clone)]
+pub struct TailCallProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl TailCallProgram {
+    pub fn new(data: ProgramData, map_name: String, index: u32) -> Result<Self, BpfmanError> {
+        let mut tail_call_prog = Self { data };
+        tail_call_prog.set_map_name(map_name)?;
+        tail_call_prog.set_index(index)?;
+
+        Ok(tail_call_prog)
+    }
+
+    pub(crate) fn set_map_name(&mut self, map_name: String) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, TAIL_CALL_MAP, map_name.as_bytes())
+    }
+
+    /// Returns the name of the prog array the program is registered in.
+    pub fn get_map_name(&self) -> Result<String, BpfmanError> {
+        sled_get(&self.data.db_tree, TAIL_CALL_MAP).map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn set_index(&mut self, index: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, TAIL_CALL_INDEX, &index.to_ne_bytes())
+    }
+
+    /// Returns the index of the prog array the program is registered at.
+    pub fn get_index(&self) -> Result<u32, BpfmanError> {
+        sled_get(&self.data.db_tree, TAIL_CALL_INDEX).map(bytes_to_u32)
+    }
+
+    pub(crate) fn set_program_type(&mut self, kind: ProgramType) -> Result<(), BpfmanError> {
+        self.data.set_kind(kind)
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
 impl Program {
     pub fn kind(&self) -> ProgramType {
         match self {
@@ -1359,21 +2911,101 @@ impl Program {
             Program::Uprobe(_) => ProgramType::Probe,
             Program::Fentry(_) => ProgramType::Tracing,
             Program::Fexit(_) => ProgramType::Tracing,
+            Program::Cgroup(p) => p.get_attach_type().unwrap().program_type(),
+            Program::TailCall(p) => p.data.get_kind().unwrap().unwrap_or(ProgramType::Unspec),
             Program::Unsupported(i) => i.get_kernel_program_type().unwrap().try_into().unwrap(),
         }
     }
 
+    /// Returns how the program is attached, or None for programs that weren't
+    /// loaded by bpfman.
+    pub fn attach_type(&self) -> Result<Option<String>, BpfmanError> {
+        Ok(Some(match self {
+            Program::Xdp(_) => "xdp".to_string(),
+            Program::Tc(p) => format!("tc_{}", p.get_direction()?),
+            Program::Tracepoint(_) => "tracepoint".to_string(),
+            Program::Kprobe(p) if p.get_retprobe()? => "kretprobe".to_string(),
+            Program::Kprobe(_) => "kprobe".to_string(),
+            Program::Uprobe(p) if p.get_retprobe()? => "uretprobe".to_string(),
+            Program::Uprobe(_) => "uprobe".to_string(),
+            Program::Fentry(_) => "fentry".to_string(),
+            Program::Fexit(_) => "fexit".to_string(),
+            Program::Cgroup(p) => format!("cgroup_{}", p.get_attach_type()?),
+            Program::TailCall(_) => "tail_call".to_string(),
+            Program::Unsupported(_) => return Ok(None),
+        }))
+    }
+
+    /// Returns the paths the program, its link and its maps are pinned to.
+    /// Only paths that exist are returned, so the link of a program that has
+    /// been detached, for example, is left out.
+    pub fn pin_paths(&self) -> Result<PinPaths, BpfmanError> {
+        let data = self.get_data();
+        let id = data.get_id()?;
+
+        let link = match self {
+            Program::Xdp(p) => p.get_dispatcher_revision()?.map(|revision| {
+                let key = dispatcher_key(p.get_if_index()?.unwrap_or_default(), p.get_netns_id()?);
+                let base = p
+                    .get_dispatcher_pin_dir()?
+                    .unwrap_or_else(|| RTDIR_FS_XDP.to_string());
+                Ok::<_, BpfmanError>(format!("{base}/dispatcher_{key}_{revision}/link_{id}"))
+            }),
+            Program::Tc(p) => p.get_dispatcher_revision()?.map(|revision| {
+                let key = dispatcher_key(p.get_if_index()?.unwrap_or_default(), p.get_netns_id()?);
+                let base = match (p.get_dispatcher_pin_dir()?, p.get_direction()?) {
+                    (Some(dir), _) => dir,
+                    (None, Direction::Ingress) => RTDIR_FS_TC_INGRESS.to_string(),
+                    (None, Direction::Egress) => RTDIR_FS_TC_EGRESS.to_string(),
+                };
+                Ok(format!("{base}/dispatcher_{key}_{revision}/link_{id}"))
+            }),
+            Program::TailCall(_) | Program::Unsupported(_) => None,
+            _ => Some(data.link_pin_path()),
+        }
+        .transpose()?
+        .map(PathBuf::from)
+        .filter(|path| !self.detached() && path.exists());
+
+        let mut maps = BTreeMap::new();
+        if let Some(dir) = data.get_map_pin_path()? {
+            if let Ok(entries) = fs::read_dir(&dir) {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    maps.insert(
+                        entry.file_name().to_string_lossy().to_string(),
+                        entry.path(),
+                    );
+                }
+            }
+        }
+        let namespace = data.get_pin_namespace()?;
+        for name in data.get_shared_maps()? {
+            let path = shared_map_path(namespace.as_deref(), &name);
+            if path.exists() {
+                maps.insert(name, path);
+            }
+        }
+
+        Ok(PinPaths {
+            program: PathBuf::from(data.program_pin_path()?),
+            link,
+            maps,
+        })
+    }
+
     pub(crate) fn dispatcher_id(&self) -> Result<Option<DispatcherId>, BpfmanError> {
         Ok(match self {
             Program::Xdp(p) => Some(DispatcherId::Xdp(DispatcherInfo(
                 p.get_if_index()?
                     .expect("if_index should be known at this point"),
                 None,
+                p.get_netns_id()?,
             ))),
             Program::Tc(p) => Some(DispatcherId::Tc(DispatcherInfo(
                 p.get_if_index()?
                     .expect("if_index should be known at this point"),
                 Some(p.get_direction()?),
+                p.get_netns_id()?,
             ))),
             _ => None,
         })
@@ -1388,6 +3020,8 @@ impl Program {
             Program::Uprobe(p) => &mut p.data,
             Program::Fentry(p) => &mut p.data,
             Program::Fexit(p) => &mut p.data,
+            Program::Cgroup(p) => &mut p.data,
+            Program::TailCall(p) => &mut p.data,
             Program::Unsupported(p) => p,
         }
     }
@@ -1400,6 +3034,26 @@ impl Program {
         }
     }
 
+    /// Returns true if the program is an XDP or TC program that is loaded but
+    /// detached from its dispatcher.
+    pub fn detached(&self) -> bool {
+        match self {
+            Program::Xdp(p) => p.get_detached().unwrap(),
+            Program::Tc(p) => p.get_detached().unwrap(),
+            _ => false,
+        }
+    }
+
+    /// Returns false if the program is an XDP or TC program that has been
+    /// disabled, see [`XdpProgram::set_enabled`].
+    pub fn enabled(&self) -> bool {
+        match self {
+            Program::Xdp(p) => p.get_enabled().unwrap(),
+            Program::Tc(p) => p.get_enabled().unwrap(),
+            _ => true,
+        }
+    }
+
     pub(crate) fn set_attached(&mut self) {
         match self {
             Program::Xdp(p) => p.set_attached(true).unwrap(),
@@ -1419,16 +3073,14 @@ impl Program {
     }
 
     pub(crate) fn delete(&self, root_db: &Db) -> Result<(), anyhow::Error> {
-        let id = self.get_data().get_id()?;
+        let program_pin_path = self.get_data().program_pin_path()?;
+        let link_pin_path = self.get_data().link_pin_path()?;
         root_db.drop_tree(self.get_data().db_tree.name())?;
 
-        let path = format!("{RTDIR_FS}/prog_{id}");
-        if PathBuf::from(&path).exists() {
-            fs::remove_file(path)?;
-        }
-        let path = format!("{RTDIR_FS}/prog_{id}_link");
-        if PathBuf::from(&path).exists() {
-            fs::remove_file(path)?;
+        for path in [program_pin_path, link_pin_path] {
+            if PathBuf::from(&path).exists() {
+                fs::remove_file(path)?;
+            }
         }
         Ok(())
     }
@@ -1453,6 +3105,42 @@ impl Program {
         }
     }
 
+    pub(crate) fn netns(&self) -> Result<Option<PathBuf>, BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.get_netns(),
+            Program::Tc(p) => p.get_netns(),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn netns_id(&self) -> Result<Option<u64>, BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.get_netns_id(),
+            Program::Tc(p) => p.get_netns_id(),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn set_netns(&mut self, netns: &Path) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_netns(netns),
+            Program::Tc(p) => p.set_netns(netns),
+            _ => Err(BpfmanError::Error(
+                "cannot set netns on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn set_netns_id(&mut self, netns_id: u64) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_netns_id(netns_id),
+            Program::Tc(p) => p.set_netns_id(netns_id),
+            _ => Err(BpfmanError::Error(
+                "cannot set netns on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
     pub(crate) fn if_name(&self) -> Result<String, BpfmanError> {
         match self {
             Program::Xdp(p) => p.get_iface(),
@@ -1463,6 +3151,16 @@ impl Program {
         }
     }
 
+    pub(crate) fn set_if_name(&mut self, if_name: String) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_iface(if_name),
+            Program::Tc(p) => p.set_iface(if_name),
+            _ => Err(BpfmanError::Error(
+                "cannot set interface on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
     pub(crate) fn priority(&self) -> Result<i32, BpfmanError> {
         match self {
             Program::Xdp(p) => p.get_priority(),
@@ -1480,6 +3178,59 @@ impl Program {
         }
     }
 
+    /// Returns true if both are XDP or TC programs of the same name, loaded
+    /// from the same file or image, on the same interface and, for TC, in the
+    /// same direction, so that a dispatcher would run the program twice.
+    pub(crate) fn is_duplicate_of(&self, other: &Program) -> Result<bool, BpfmanError> {
+        let hook = |p: &Program| -> Result<_, BpfmanError> {
+            Ok(match p {
+                Program::Xdp(_) | Program::Tc(_) => Some((
+                    std::mem::discriminant(p),
+                    p.if_name()?,
+                    p.if_index()?,
+                    p.netns_id()?,
+                    p.direction()?,
+                )),
+                _ => None,
+            })
+        };
+        let source = |p: &Program| -> Result<_, BpfmanError> {
+            let data = p.get_data();
+            Ok((data.get_name()?, data.get_location()?.source().to_string()))
+        };
+        let hook_self = hook(self)?;
+        Ok(hook_self.is_some() && hook_self == hook(other)? && source(self)? == source(other)?)
+    }
+
+    /// Returns true if the interface an XDP or TC program was attached to has
+    /// been removed, along with the network namespace it was in if that is
+    /// gone too. The kernel detaches a dispatcher when its interface goes
+    /// away, so all that is left of the program is bpfman's state for it.
+    pub fn interface_missing(&self) -> Result<bool, BpfmanError> {
+        let (Program::Xdp(_) | Program::Tc(_)) = self else {
+            return Ok(false);
+        };
+        let Some(if_index) = self.if_index()? else {
+            return Ok(false);
+        };
+        let netns = self.netns()?;
+        if let Some(path) = &netns {
+            match fs::metadata(path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+                Err(e) => {
+                    return Err(BpfmanError::InvalidNetns {
+                        netns: path.display().to_string(),
+                        error: e.to_string(),
+                    })
+                }
+                Ok(_) => (),
+            }
+        }
+        // The index of a removed interface can be given to a new one.
+        let name = in_netns(netns.as_deref(), || Ok(if_indextoname(if_index)))?;
+        Ok(name != Some(self.if_name()?))
+    }
+
     pub fn get_data(&self) -> &ProgramData {
         match self {
             Program::Xdp(p) => p.get_data(),
@@ -1489,6 +3240,8 @@ impl Program {
             Program::Uprobe(p) => p.get_data(),
             Program::Fentry(p) => p.get_data(),
             Program::Fexit(p) => p.get_data(),
+            Program::Cgroup(p) => p.get_data(),
+            Program::TailCall(p) => p.get_data(),
             Program::Unsupported(p) => p,
         }
     }
@@ -1501,6 +3254,11 @@ impl Program {
                 "Program id does not match database id program isn't fully loaded".to_string(),
             ));
         }
+        // A tail called program has the kind of its section, so it is told
+        // apart by the prog array it is registered in instead.
+        if data.db_tree.get(TAIL_CALL_MAP).unwrap().is_some() {
+            return Ok(Program::TailCall(TailCallProgram { data }));
+        }
         match data.get_kind()? {
             Some(p) => match p {
                 ProgramType::Xdp => Ok(Program::Xdp(XdpProgram { data })),
@@ -1522,6 +3280,9 @@ impl Program {
                         Ok(Program::Fexit(FexitProgram { data }))
                     }
                 }
+                ProgramType::CgroupSkb | ProgramType::CgroupSock => {
+                    Ok(Program::Cgroup(CgroupProgram { data }))
+                }
                 _ => Err(BpfmanError::Error("Unsupported program type".to_string())),
             },
             None => Err(BpfmanError::Error("Unsupported program type".to_string())),
@@ -2159,6 +3920,437 @@ impl std::fmt::Display for Location {
                 TryInto::<ImagePullPolicy>::try_into(i.image_pull_policy.clone()).unwrap()
             ),
             Location::File(p) => write!(f, "file: {{ path: {p} }}"),
+            Location::Url(u) => write!(f, "url: {{ url: {} }}", u.url),
+        }
+    }
+}
+
+// Times are shown the same way as the kernel's load time.
+fn format_time(t: SystemTime) -> String {
+    DateTime::<Local>::from(t)
+        .format("%Y-%m-%dT%H:%M:%S%z")
+        .to_string()
+}
+
+fn unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn xdp_program(name: &str) -> Program {
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pass.o".to_string()),
+            name.to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        Program::Xdp(
+            XdpProgram::new(data, 50, "eth0".to_string(), XdpProceedOn::default(), None)
+                .expect("unable to create xdp program"),
+        )
+    }
+
+    #[test]
+    fn test_list_filter_name() {
+        let prog = xdp_program("pass");
+
+        let filter = ListFilter::new(None, HashMap::new(), true, Some("pass".to_string()), None);
+        assert!(filter.matches(&prog));
+
+        let filter = ListFilter::new(None, HashMap::new(), true, Some("drop".to_string()), None);
+        assert!(!filter.matches(&prog));
+
+        let filter = ListFilter::new(None, HashMap::new(), true, None, None);
+        assert!(filter.matches(&prog));
+    }
+
+    #[test]
+    fn test_list_filter_name_and_type() {
+        let prog = xdp_program("pass");
+
+        let filter = ListFilter::new(
+            Some(ProgramType::Xdp.into()),
+            HashMap::new(),
+            true,
+            Some("pass".to_string()),
+            None,
+        );
+        assert!(filter.matches(&prog));
+
+        let filter = ListFilter::new(
+            Some(ProgramType::Tc.into()),
+            HashMap::new(),
+            true,
+            Some("pass".to_string()),
+            None,
+        );
+        assert!(!filter.matches(&prog));
+    }
+
+    #[test]
+    fn test_cgroup_program() {
+        let data = ProgramData::new(
+            Location::File("/tmp/cgroup_sock.o".to_string()),
+            "sock".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .expect("unable to create program data");
+        let prog = Program::Cgroup(
+            CgroupProgram::new(
+                data,
+                "/sys/fs/cgroup/test".to_string(),
+                CgroupAttachType::SockCreate,
+            )
+            .expect("unable to create cgroup program"),
+        );
+
+        assert_eq!(prog.kind(), ProgramType::CgroupSock);
+        assert_eq!(
+            prog.attach_type().unwrap(),
+            Some("cgroup_sock_create".to_string())
+        );
+
+        for attach_type in [
+            CgroupAttachType::Ingress,
+            CgroupAttachType::Egress,
+            CgroupAttachType::SockCreate,
+            CgroupAttachType::SockRelease,
+            CgroupAttachType::PostBind4,
+            CgroupAttachType::PostBind6,
+        ] {
+            assert_eq!(
+                CgroupAttachType::try_from(attach_type.to_string()).unwrap(),
+                attach_type
+            );
+        }
+        assert!(CgroupAttachType::try_from("connect4".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_tail_call_program() {
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pipeline.o".to_string()),
+            "parse_ipv4".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            Some(6371),
+        )
+        .expect("unable to create program data");
+        let prog = Program::TailCall(
+            TailCallProgram::new(data, "jump_table".to_string(), 3)
+                .expect("unable to create tail call program"),
+        );
+
+        // The type is only known once the program has been loaded.
+        assert_eq!(prog.kind(), ProgramType::Unspec);
+        assert_eq!(prog.attach_type().unwrap(), Some("tail_call".to_string()));
+        if let Program::TailCall(p) = &prog {
+            assert_eq!(p.get_map_name().unwrap(), "jump_table");
+            assert_eq!(p.get_index().unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn test_xdp_program_detached() {
+        let mut prog = xdp_program("pass");
+        assert!(!prog.detached());
+
+        if let Program::Xdp(p) = &mut prog {
+            p.set_detached(true).unwrap();
+        }
+        assert!(prog.detached());
+    }
+
+    #[test]
+    fn test_xdp_program_enabled() {
+        let mut prog = xdp_program("pass");
+        assert!(prog.enabled());
+
+        if let Program::Xdp(p) = &mut prog {
+            p.set_enabled(false).unwrap();
+        }
+        assert!(!prog.enabled());
+        if let Program::Xdp(p) = &mut prog {
+            p.set_enabled(true).unwrap();
+        }
+        assert!(prog.enabled());
+    }
+
+    #[test]
+    fn test_program_times() {
+        let mut prog = xdp_program("pass");
+        let data = prog.get_data_mut();
+        assert_eq!(data.get_loaded_at().unwrap(), None);
+        assert_eq!(data.get_uptime().unwrap(), None);
+
+        let loaded_at = SystemTime::now() - Duration::from_secs(90);
+        data.set_loaded_at(loaded_at).unwrap();
+        data.set_attached_at(Some(loaded_at)).unwrap();
+        assert_eq!(data.get_loaded_at().unwrap(), Some(format_time(loaded_at)));
+        assert_eq!(
+            data.get_attached_at().unwrap(),
+            data.get_loaded_at().unwrap()
+        );
+        let uptime = data.get_uptime().unwrap().unwrap().as_secs();
+        assert!((90..=91).contains(&uptime), "{uptime}");
+
+        data.set_attached_at(None).unwrap();
+        assert_eq!(data.get_attached_at().unwrap(), None);
+        assert!(data.get_loaded_at().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_map_value_format() {
+        for (format, expected) in [
+            ("", MapValueFormat::Raw),
+            ("u64", MapValueFormat::U64),
+            ("btf", MapValueFormat::Btf(None)),
+            (
+                "btf:counters",
+                MapValueFormat::Btf(Some("counters".to_string())),
+            ),
+        ] {
+            assert_eq!(
+                MapValueFormat::try_from(format.to_string()).unwrap(),
+                expected
+            );
+        }
+        for format in ["btf:", "u16"] {
+            assert!(MapValueFormat::try_from(format.to_string()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_map_key_type() {
+        for (key_type, key, expected) in [
+            ("ipv4", "192.168.1.1", vec![192, 168, 1, 1]),
+            (
+                "mac",
+                "00:11:22:aa:BB:c",
+                vec![0, 0x11, 0x22, 0xaa, 0xbb, 0xc],
+            ),
+            ("be16", "8080", vec![0x1f, 0x90]),
+            ("u32", "80", 80u32.to_ne_bytes().to_vec()),
+        ] {
+            let key_type = MapKeyType::try_from(key_type.to_string()).unwrap();
+            assert_eq!(key_type.encode(key).unwrap(), expected, "{key_type} {key}");
+        }
+        assert_eq!(
+            MapKeyType::Ipv6.encode("::1").unwrap(),
+            std::net::Ipv6Addr::LOCALHOST.octets()
+        );
+        for (key_type, key) in [
+            (MapKeyType::Ipv4, "192.168.1"),
+            (MapKeyType::Mac, "00:11:22:33:44"),
+            (MapKeyType::Mac, "00:11:22:33:44:555"),
+            (MapKeyType::Be16, "65536"),
+        ] {
+            assert!(matches!(
+                key_type.encode(key),
+                Err(BpfmanError::InvalidMapKey { .. })
+            ));
         }
+        assert!(MapKeyType::try_from("ipv5".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_xdp_mode_preference() {
+        let mut prog = xdp_program("pass");
+        let Program::Xdp(p) = &mut prog else {
+            unreachable!()
+        };
+        assert_eq!(p.get_mode_preference().unwrap(), None);
+        assert_eq!(p.get_mode().unwrap(), None);
+
+        let pref = XdpModePreference::try_from("auto".to_string()).unwrap();
+        p.set_mode_preference(pref).unwrap();
+        p.set_mode(XdpMode::Skb).unwrap();
+        assert_eq!(
+            p.get_mode_preference().unwrap(),
+            Some(XdpModePreference::Auto)
+        );
+        assert_eq!(p.get_mode().unwrap(), Some(XdpMode::Skb));
+
+        assert!(pref.accepts(XdpMode::Drv));
+        assert!(!pref.accepts(XdpMode::Hw));
+        assert!(!XdpModePreference::Drv.accepts(XdpMode::Skb));
+        assert!(XdpModePreference::try_from("native".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_shared_maps() {
+        let mut prog = xdp_program("pass");
+        assert!(prog.get_data().get_shared_maps().unwrap().is_empty());
+
+        let names = vec!["counters".to_string(), "config".to_string()];
+        prog.get_data_mut().set_shared_maps(&names).unwrap();
+        let mut shared = prog.get_data().get_shared_maps().unwrap();
+        shared.sort();
+        assert_eq!(shared, vec!["config".to_string(), "counters".to_string()]);
+    }
+
+    #[test]
+    fn test_program_selector() {
+        let prog = xdp_program("pass");
+
+        let selector = ProgramSelector::new("pass".to_string(), "eth0".to_string(), None);
+        assert!(selector.matches(&prog));
+        let selector = ProgramSelector::new("pass".to_string(), "eth0".to_string(), Some(50));
+        assert!(selector.matches(&prog));
+        assert_eq!(selector.to_string(), "program pass on eth0 at priority 50");
+
+        for selector in [
+            ProgramSelector::new("pass".to_string(), "eth1".to_string(), None),
+            ProgramSelector::new("drop".to_string(), "eth0".to_string(), None),
+            ProgramSelector::new("pass".to_string(), "eth0".to_string(), Some(100)),
+        ] {
+            assert!(!selector.matches(&prog), "{selector} should not match");
+        }
+    }
+
+    #[test]
+    fn test_pin_namespace() {
+        let mut prog = xdp_program("pass");
+        prog.get_data_mut().set_id(7).unwrap();
+        assert_eq!(prog.get_data().get_pin_namespace().unwrap(), None);
+        let filter = ListFilter::new(
+            None,
+            HashMap::new(),
+            true,
+            None,
+            Some("tenant-a".to_string()),
+        );
+        assert!(!filter.matches(&prog));
+
+        assert!(prog.get_data_mut().set_pin_namespace("../maps").is_err());
+        prog.get_data_mut().set_pin_namespace("tenant-a").unwrap();
+        assert!(filter.matches(&prog));
+        assert!(prog
+            .get_data()
+            .link_pin_path()
+            .unwrap()
+            .ends_with("/tenant-a/prog_7_link"));
+    }
+
+    #[test]
+    fn test_idempotency_key() {
+        let mut prog = xdp_program("pass");
+        let data = prog.get_data_mut();
+        assert_eq!(data.get_idempotency_key().unwrap(), None);
+
+        let key = IdempotencyKey {
+            owner: "uid 1000".to_string(),
+            key: "deploy-42".to_string(),
+        };
+        data.set_idempotency_key(&key).unwrap();
+        assert_eq!(data.get_idempotency_key().unwrap(), Some(key));
+        assert!(!data.idempotency_key_expired(None).unwrap());
+        assert!(!data
+            .idempotency_key_expired(Some(Duration::from_secs(3600)))
+            .unwrap());
+        assert!(data.idempotency_key_expired(Some(Duration::ZERO)).unwrap());
+    }
+
+    #[test]
+    fn test_map_pin_overrides() {
+        let mut prog = xdp_program("pass");
+        let data = prog.get_data_mut();
+        assert!(data.should_pin_map("counters").unwrap());
+        assert!(!data.should_pin_map(".data").unwrap());
+
+        data.set_maps_to_pin(&[".data".to_string()]).unwrap();
+        data.set_maps_to_skip(&["counters".to_string()]).unwrap();
+        assert!(data.should_pin_map(".data").unwrap());
+        assert!(!data.should_pin_map("counters").unwrap());
+        assert!(!data.should_pin_map(".bss").unwrap());
+
+        let maps = [".data", "counters"];
+        assert!(data.check_map_pin_overrides(|n| maps.contains(&n)).is_ok());
+        assert!(data.check_map_pin_overrides(|n| n == "counters").is_err());
+
+        data.set_maps_to_pin(&["counters".to_string()]).unwrap();
+        assert!(data.check_map_pin_overrides(|n| maps.contains(&n)).is_err());
+    }
+
+    #[test]
+    fn test_map_max_entries() {
+        let mut prog = xdp_program("pass");
+        let data = prog.get_data_mut();
+        let map_type = |n: &str| (n == "counters").then_some(1);
+
+        data.set_map_max_entries(&HashMap::from([("counters".to_string(), 1 << 16)]))
+            .unwrap();
+        assert_eq!(data.get_map_max_entries().unwrap()["counters"], 1 << 16);
+        assert!(data.check_map_max_entries(map_type).is_ok());
+
+        data.set_shared_maps(&["counters".to_string()]).unwrap();
+        assert!(data.check_map_max_entries(map_type).is_err());
+
+        let mut prog = xdp_program("pass");
+        let data = prog.get_data_mut();
+        data.set_map_max_entries(&HashMap::from([("events".to_string(), 1 << 16)]))
+            .unwrap();
+        assert!(matches!(
+            data.check_map_max_entries(map_type),
+            Err(BpfmanError::InvalidMapMaxEntries { .. })
+        ));
+    }
+
+    #[test]
+    fn test_is_duplicate_of() {
+        let prog = xdp_program("pass");
+        assert!(prog.is_duplicate_of(&xdp_program("pass")).unwrap());
+        assert!(!prog.is_duplicate_of(&xdp_program("drop")).unwrap());
+
+        let data = ProgramData::new(
+            Location::File("/tmp/xdp_pass.o".to_string()),
+            "pass".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let other_iface = Program::Xdp(
+            XdpProgram::new(data, 50, "eth1".to_string(), XdpProceedOn::default(), None).unwrap(),
+        );
+        assert!(!prog.is_duplicate_of(&other_iface).unwrap());
+    }
+
+    #[test]
+    fn test_interface_missing() {
+        let mut prog = xdp_program("pass");
+        // Not attached yet.
+        assert!(!prog.interface_missing().unwrap());
+
+        // The index was given to another interface.
+        let lo = fs::read_to_string("/sys/class/net/lo/ifindex").unwrap();
+        prog.set_if_index(lo.trim().parse().unwrap()).unwrap();
+        assert!(prog.interface_missing().unwrap());
+
+        prog.set_if_name("lo".to_string()).unwrap();
+        assert!(!prog.interface_missing().unwrap());
+
+        // An interface that was deleted, such as one end of a veth pair.
+        prog.set_if_index(u32::MAX).unwrap();
+        assert!(prog.interface_missing().unwrap());
+
+        // The namespace went away with its interfaces.
+        prog.set_if_index(lo.trim().parse().unwrap()).unwrap();
+        prog.set_netns(Path::new("/run/netns/bpfman-test-deleted"))
+            .unwrap();
+        assert!(prog.interface_missing().unwrap());
     }
 }
@@ -9,47 +9,162 @@ use std::{
 };
 
 use anyhow::{anyhow, bail, Context, Result};
+use caps::{CapSet, Capability};
 use log::{debug, info, warn};
 use nix::{
+    ifaddrs::getifaddrs,
     libc::RLIM_INFINITY,
-    mount::{mount, MsFlags},
-    net::if_::if_nametoindex,
-    sys::resource::{setrlimit, Resource},
+    mount::mount,
+    net::if_::{if_nametoindex, InterfaceFlags},
+    sys::{
+        resource::{getrlimit, setrlimit, Resource},
+        statfs::{statfs, BPF_FS_MAGIC},
+    },
 };
 use sled::Tree;
 
-use crate::{config::Config, directories::*, errors::BpfmanError};
+use crate::{
+    config::Config,
+    directories::*,
+    errors::{BpfmanError, ParseError},
+    netns::{in_netns, netns_path},
+};
 
 // The bpfman socket should always allow the same users and members of the same group
 // to Read/Write to it.
 pub const SOCK_MODE: u32 = 0o0660;
 
-// Like tokio::fs::read, but with O_NOCTTY set
-pub(crate) fn read<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, BpfmanError> {
-    let mut data = vec![];
-    OpenOptions::new()
+const KALLSYMS: &str = "/proc/kallsyms";
+// The same locations aya looks for tracefs in.
+const TRACEFS_PATHS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+
+// Like tokio::fs::read, but with O_NOCTTY set, and failing with
+// BpfmanError::TooLarge rather than reading more than max_bytes.
+pub(crate) fn read<P: AsRef<Path>>(
+    path: P,
+    max_bytes: Option<u64>,
+) -> Result<Vec<u8>, BpfmanError> {
+    let path = path.as_ref();
+    let too_large = |max| BpfmanError::TooLarge {
+        path: path.display().to_string(),
+        max,
+    };
+    let file = OpenOptions::new()
         .custom_flags(nix::libc::O_NOCTTY)
         .read(true)
         .open(path)
-        .map_err(|e| BpfmanError::Error(format!("can't open file: {e}")))?
+        .map_err(|e| BpfmanError::Error(format!("can't open file: {e}")))?;
+
+    // Fail before reading anything when the size is known up front. Files
+    // such as pipes don't report one, so the read itself is bounded too.
+    let size = file.metadata().map_or(0, |m| m.len());
+    if let Some(max) = max_bytes.filter(|max| size > *max) {
+        return Err(too_large(max));
+    }
+
+    // Objects can be several MB, so the buffer is sized from the file rather
+    // than grown as it is read.
+    let mut data = Vec::with_capacity(usize::try_from(size).unwrap_or_default());
+    file.take(max_bytes.map_or(u64::MAX, |max| max.saturating_add(1)))
         .read_to_end(&mut data)
         .map_err(|e| BpfmanError::Error(format!("can't read file: {e}")))?;
+    if let Some(max) = max_bytes.filter(|max| data.len() as u64 > *max) {
+        return Err(too_large(max));
+    }
     Ok(data)
 }
 
-pub(crate) fn get_ifindex(iface: &str) -> Result<u32, BpfmanError> {
-    match if_nametoindex(iface) {
-        Ok(index) => {
-            debug!("Map {} to {}", iface, index);
-            Ok(index)
+/// Resolves `iface`, either an interface name or an interface index, to the
+/// interface's name and index. Interface names can be all digits, so `iface`
+/// is only taken as an index if there is no interface with that name.
+pub(crate) fn get_ifindex(iface: &str) -> Result<(String, u32), BpfmanError> {
+    let resolved = match if_nametoindex(iface) {
+        Ok(index) => Some((iface.to_string(), index)),
+        Err(_) => iface
+            .parse::<u32>()
+            .ok()
+            .and_then(|index| if_indextoname(index).map(|name| (name, index))),
+    };
+    match resolved {
+        Some((name, index)) => {
+            debug!("Map {} to {} ({})", iface, name, index);
+            Ok((name, index))
         }
-        Err(_) => {
+        None => {
             info!("Unable to validate interface {}", iface);
             Err(BpfmanError::InvalidInterface)
         }
     }
 }
 
+pub(crate) fn if_indextoname(index: u32) -> Option<String> {
+    let mut buf = [0; libc::IF_NAMESIZE];
+    // SAFETY: buf is IF_NAMESIZE bytes long, as if_indextoname requires.
+    let name = unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) };
+    if name.is_null() {
+        return None;
+    }
+    // SAFETY: on success the name is written to buf, NUL terminated.
+    let name = unsafe { std::ffi::CStr::from_ptr(name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Returns whether `name` is a kernel symbol listed in /proc/kallsyms. If the
+/// file can't be read the symbol is assumed to exist, leaving it to the kernel
+/// to reject the attach.
+pub(crate) fn kernel_symbol_exists(name: &str) -> bool {
+    let file = match File::open(KALLSYMS) {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Unable to read {KALLSYMS}, not checking for symbol {name}: {e}");
+            return true;
+        }
+    };
+    // Each line is "<address> <type> <name>", with "\t[<module>]" appended
+    // for symbols in modules.
+    BufReader::new(file)
+        .lines()
+        .map_while(|l| l.ok())
+        .any(|l| l.split_whitespace().nth(2) == Some(name))
+}
+
+/// Returns whether the tracepoint `category/name` exists. If tracefs isn't
+/// mounted the tracepoint is assumed to exist, leaving it to the kernel to
+/// reject the attach.
+pub(crate) fn tracepoint_exists(category: &str, name: &str) -> bool {
+    match TRACEFS_PATHS
+        .iter()
+        .map(Path::new)
+        .find(|p| p.join("events").is_dir())
+    {
+        Some(tracefs) => tracefs.join("events").join(category).join(name).is_dir(),
+        None => {
+            warn!("Unable to find tracefs, not checking for tracepoint {category}/{name}");
+            true
+        }
+    }
+}
+
+/// Returns the names of all interfaces that are up, other than loopback, in
+/// the given network namespace or the current one if `netns` is None.
+pub fn get_up_interfaces(netns: Option<&Path>) -> Result<Vec<String>, BpfmanError> {
+    let netns = netns.map(|n| netns_path(&n.to_string_lossy()));
+    let addrs = in_netns(netns.as_deref(), || {
+        getifaddrs().map_err(|e| BpfmanError::Error(format!("unable to list interfaces: {e}")))
+    })?;
+    let mut ifaces: Vec<String> = addrs
+        .filter(|i| {
+            i.flags.contains(InterfaceFlags::IFF_UP)
+                && !i.flags.contains(InterfaceFlags::IFF_LOOPBACK)
+        })
+        .map(|i| i.interface_name)
+        .collect();
+    // There is an entry per address, so interfaces may appear more than once.
+    ifaces.sort();
+    ifaces.dedup();
+    Ok(ifaces)
+}
+
 pub fn set_file_permissions(path: &Path, mode: u32) {
     // Set the permissions on the file based on input
     if (set_permissions(path, std::fs::Permissions::from_mode(mode))).is_err() {
@@ -60,22 +175,52 @@ pub fn set_file_permissions(path: &Path, mode: u32) {
     }
 }
 
-pub fn set_dir_permissions(directory: &str, mode: u32) {
-    // Iterate through the files in the provided directory
-    let entries = std::fs::read_dir(directory).unwrap();
-    for file in entries.flatten() {
-        // Set the permissions on the file based on input
-        set_file_permissions(&file.path(), mode);
+/// Sets the permissions of the files in `directory` to `mode`. Files that
+/// can't be read or changed are logged and skipped, only a directory that
+/// can't be read is an error.
+pub fn set_dir_permissions(directory: &str, mode: u32) -> Result<(), BpfmanError> {
+    let entries = std::fs::read_dir(directory)
+        .map_err(|e| BpfmanError::Error(format!("unable to read directory {directory}: {e}")))?;
+    for entry in entries {
+        match entry {
+            Ok(file) => set_file_permissions(&file.path(), mode),
+            Err(e) => warn!("Unable to read an entry of {directory}: {e}. Continuing"),
+        }
     }
+    Ok(())
 }
 
+/// Mounts a bpffs at `directory`, unless one is already mounted there, as is
+/// the case after a restart or when the mount is inherited by a container.
 pub fn create_bpffs(directory: &str) -> anyhow::Result<()> {
+    if is_bpffs_mounted(directory)? {
+        debug!("Reusing existing bpffs at {directory}");
+        return Ok(());
+    }
+
     debug!("Creating bpffs at {directory}");
-    let flags = MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC | MsFlags::MS_RELATIME;
+    let flags = open_config_file()
+        .bpffs()
+        .clone()
+        .unwrap_or_default()
+        .ms_flags()?;
     mount::<str, str, str, str>(None, directory, Some("bpf"), flags, None)
         .with_context(|| format!("unable to create bpffs at {directory}"))
 }
 
+/// Checks that `directory` is on a bpffs. Pins made anywhere else fail, and
+/// it's easier to tell why at startup than at the first load.
+pub(crate) fn check_bpffs(directory: &str) -> anyhow::Result<()> {
+    let fs = statfs(directory).with_context(|| format!("unable to statfs {directory}"))?;
+    if fs.filesystem_type() != BPF_FS_MAGIC {
+        bail!(
+            "{directory} is not on a bpffs (filesystem type {:#x}), mount one with `mount -t bpf bpffs {directory}`",
+            fs.filesystem_type().0
+        );
+    }
+    Ok(())
+}
+
 pub(crate) fn should_map_be_pinned(name: &str) -> bool {
     !(name.contains(".rodata") || name.contains(".bss") || name.contains(".data"))
 }
@@ -184,6 +329,18 @@ pub(crate) fn sled_insert(db_tree: &Tree, key: &str, value: &[u8]) -> Result<(),
     })
 }
 
+pub(crate) fn sled_remove(db_tree: &Tree, key: &str) -> Result<(), BpfmanError> {
+    db_tree.remove(key).map(|_| ()).map_err(|e| {
+        BpfmanError::DatabaseError(
+            format!(
+                "Unable to remove database entry {key} from tree {:?}",
+                db_tree.name()
+            ),
+            e.to_string(),
+        )
+    })
+}
+
 // Helper function to get the error message from stderr
 pub(crate) fn get_error_msg_from_stderr(stderr: &[u8]) -> String {
     // Convert to lines
@@ -206,7 +363,27 @@ pub(crate) fn get_error_msg_from_stderr(stderr: &[u8]) -> String {
         .to_string()
 }
 
-pub(crate) fn open_config_file() -> Config {
+/// Returns the path of the bpfman config file read by [`open_config_file`].
+pub fn config_path() -> &'static Path {
+    Path::new(CFGPATH_BPFMAN_CONFIG)
+}
+
+/// Reads and parses the bpfman config file at `path`. Unlike
+/// [`open_config_file`], a missing or malformed file is an error rather than
+/// falling back to the defaults.
+pub fn config_from_file<P: AsRef<Path>>(path: P) -> Result<Config, BpfmanError> {
+    let path = path.as_ref();
+    let load_error = |error: String| BpfmanError::ConfigLoadError {
+        path: path.display().to_string(),
+        error,
+    };
+    std::fs::read_to_string(path)
+        .map_err(|e| load_error(e.to_string()))?
+        .parse()
+        .map_err(|e: ParseError| load_error(e.to_string()))
+}
+
+pub fn open_config_file() -> Config {
     if let Ok(c) = std::fs::read_to_string(CFGPATH_BPFMAN_CONFIG) {
         c.parse().unwrap_or_else(|_| {
             warn!("Unable to parse config file, using defaults");
@@ -218,64 +395,270 @@ pub(crate) fn open_config_file() -> Config {
     }
 }
 
-fn has_cap(cset: caps::CapSet, cap: caps::Capability) {
-    info!("Has {}: {}", cap, caps::has_cap(None, cset, cap).unwrap());
-}
-
-fn is_bpffs_mounted() -> Result<bool, anyhow::Error> {
-    let file = File::open("/proc/mounts").context("Failed to open /proc/mounts")?;
-    let reader = BufReader::new(file);
-    for l in reader.lines() {
-        match l {
-            Ok(line) => {
-                let parts: Vec<&str> = line.split(' ').collect();
-                if parts.len() != 6 {
-                    bail!("expected 6 parts in proc mount")
-                }
-                if parts[0] == "none" && parts[1].contains("bpfman") && parts[2] == "bpf" {
-                    return Ok(true);
-                }
-            }
-            Err(e) => bail!("problem reading lines {}", e),
-        }
+// The capabilities bpfman needs, see docs/developer-guide/linux-capabilities.md.
+const REQUIRED_CAPS: [(Capability, &str); 3] = [
+    (Capability::CAP_BPF, "to load programs and create maps"),
+    (Capability::CAP_NET_ADMIN, "to attach XDP and TC programs"),
+    (
+        Capability::CAP_SYS_ADMIN,
+        "to mount the bpffs and load XDP, TC, probe and tracepoint programs",
+    ),
+];
+
+/// Returns the capabilities bpfman needs but doesn't have in its effective
+/// set, each with what it is needed for.
+pub fn missing_capabilities() -> Vec<(Capability, &'static str)> {
+    let supported = caps::runtime::thread_all_supported();
+    let has = |cap| caps::has_cap(None, CapSet::Effective, cap).unwrap_or(false);
+    // Only raising the hard limit needs a capability.
+    let raises_memlock = getrlimit(Resource::RLIMIT_MEMLOCK)
+        .map_or(true, |(_, hard)| memlock_limit(&open_config_file()) > hard);
+    missing_caps(|cap| supported.contains(&cap), has, raises_memlock)
+}
+
+fn missing_caps(
+    supported: impl Fn(Capability) -> bool,
+    has: impl Fn(Capability) -> bool,
+    raises_memlock: bool,
+) -> Vec<(Capability, &'static str)> {
+    // Kernels before 5.8 don't have CAP_BPF, and check for CAP_SYS_ADMIN
+    // instead. Later kernels accept either of them, as bpf_capable() does.
+    let held = |cap| has(cap) || (cap == Capability::CAP_BPF && has(Capability::CAP_SYS_ADMIN));
+    let mut missing: Vec<_> = REQUIRED_CAPS
+        .into_iter()
+        .filter(|(cap, _)| supported(*cap) && !held(*cap))
+        .collect();
+    if raises_memlock && !has(Capability::CAP_SYS_RESOURCE) {
+        missing.push((
+            Capability::CAP_SYS_RESOURCE,
+            "to raise the locked memory limit",
+        ));
     }
-    Ok(false)
+    missing
 }
 
-pub(crate) fn initialize_bpfman() -> anyhow::Result<()> {
-    has_cap(caps::CapSet::Effective, caps::Capability::CAP_BPF);
-    has_cap(caps::CapSet::Effective, caps::Capability::CAP_SYS_ADMIN);
+// The RLIMIT_MEMLOCK set from the [limits] config.
+fn memlock_limit(config: &Config) -> u64 {
+    config
+        .limits()
+        .clone()
+        .unwrap_or_default()
+        .memlock_limit()
+        .unwrap_or(RLIM_INFINITY)
+}
 
-    if setrlimit(Resource::RLIMIT_MEMLOCK, RLIM_INFINITY, RLIM_INFINITY).is_err() {
-        return Err(anyhow!("must be privileged to run bpfman"));
+/// Fails, naming each capability bpfman is missing and what it is needed for,
+/// rather than leaving it to the mount or load that would fail without it.
+pub fn check_capabilities() -> anyhow::Result<()> {
+    let missing = missing_capabilities();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let missing: Vec<String> = missing
+        .iter()
+        .map(|(cap, needed_for)| format!("{cap} ({needed_for})"))
+        .collect();
+    bail!(
+        "bpfman is missing capabilities: {}. Run it as root, or grant them with \
+        AmbientCapabilities in its systemd unit or securityContext.capabilities in Kubernetes",
+        missing.join(", ")
+    )
+}
+
+/// Returns true if `directory` is on a bpffs, which is mounted either at
+/// `directory` itself or at a directory above it.
+pub(crate) fn is_bpffs_mounted(directory: &str) -> Result<bool, anyhow::Error> {
+    let fs = statfs(directory).with_context(|| format!("unable to statfs {directory}"))?;
+    Ok(fs.filesystem_type() == BPF_FS_MAGIC)
+}
+
+pub(crate) fn initialize_bpfman() -> anyhow::Result<()> {
+    // Kernels before 5.11 charge maps and programs against RLIMIT_MEMLOCK,
+    // later ones use the memory cgroup instead.
+    let memlock = memlock_limit(&open_config_file());
+    if setrlimit(Resource::RLIMIT_MEMLOCK, memlock, memlock).is_err() {
+        return Err(anyhow!(
+            "unable to set the locked memory limit, must be privileged to run bpfman"
+        ));
     }
 
     // Create directories associated with bpfman
     create_dir_all(RTDIR).context("unable to create runtime directory")?;
-    create_dir_all(RTDIR_FS).context("unable to create mountpoint")?;
+    create_dir_all(&*RTDIR_FS).context("unable to create mountpoint")?;
     create_dir_all(RTDIR_TC_INGRESS_DISPATCHER).context("unable to create dispatcher directory")?;
     create_dir_all(RTDIR_TC_EGRESS_DISPATCHER).context("unable to create dispatcher directory")?;
     create_dir_all(RTDIR_XDP_DISPATCHER).context("unable to create dispatcher directory")?;
     create_dir_all(RTDIR_PROGRAMS).context("unable to create programs directory")?;
 
-    if !is_bpffs_mounted()? {
-        create_bpffs(RTDIR_FS)?;
-    }
-    create_dir_all(RTDIR_FS_XDP).context("unable to create xdp dispatcher directory")?;
-    create_dir_all(RTDIR_FS_TC_INGRESS)
+    create_bpffs(&RTDIR_FS)?;
+    check_bpffs(&RTDIR_FS)?;
+    create_dir_all(&*RTDIR_FS_XDP).context("unable to create xdp dispatcher directory")?;
+    create_dir_all(&*RTDIR_FS_TC_INGRESS)
         .context("unable to create tc ingress dispatcher directory")?;
-    create_dir_all(RTDIR_FS_TC_EGRESS)
+    create_dir_all(&*RTDIR_FS_TC_EGRESS)
         .context("unable to create tc egress dispatcher directory")?;
-    create_dir_all(RTDIR_FS_MAPS).context("unable to create maps directory")?;
+    create_dir_all(&*RTDIR_FS_MAPS).context("unable to create maps directory")?;
+    create_dir_all(&*RTDIR_FS_SHARED_MAPS).context("unable to create shared maps directory")?;
     create_dir_all(RTDIR_TUF).context("unable to create TUF directory")?;
 
     create_dir_all(STDIR).context("unable to create state directory")?;
 
     create_dir_all(CFGDIR_STATIC_PROGRAMS).context("unable to create static programs directory")?;
 
-    set_dir_permissions(CFGDIR, CFGDIR_MODE);
-    set_dir_permissions(RTDIR, RTDIR_MODE);
-    set_dir_permissions(STDIR, STDIR_MODE);
+    set_dir_permissions(CFGDIR, CFGDIR_MODE)?;
+    set_dir_permissions(RTDIR, RTDIR_MODE)?;
+    set_dir_permissions(STDIR, STDIR_MODE)?;
 
     Ok(())
 }
+
+/// Returns the memlock line of a BPF object's /proc/<pid>/fdinfo entry.
+pub(crate) fn fdinfo_memlock(fdinfo: &str) -> Option<u64> {
+    fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("memlock:"))
+        .and_then(|bytes| bytes.trim().parse().ok())
+}
+
+/// Returns the end of `log`, at most `max_lines` lines and, if `max_bytes` is
+/// set, no more than that many bytes of whole lines, though the last line is
+/// always kept. The number of lines left out is noted at the start.
+pub(crate) fn log_tail(log: &str, max_lines: usize, max_bytes: Option<usize>) -> String {
+    let lines: Vec<&str> = log.trim_end().lines().collect();
+    let mut keep = 0;
+    let mut bytes = 0;
+    for line in lines.iter().rev().take(max_lines.max(1)) {
+        bytes += line.len() + 1;
+        if keep > 0 && max_bytes.is_some_and(|max| bytes > max) {
+            break;
+        }
+        keep += 1;
+    }
+    let skip = lines.len() - keep;
+    let tail = lines[skip..].join("\n");
+    if skip > 0 {
+        format!("[{skip} earlier lines omitted]\n{tail}")
+    } else {
+        tail
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_get_ifindex() {
+        let lo: u32 = fs::read_to_string("/sys/class/net/lo/ifindex")
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        assert_eq!(get_ifindex("lo").unwrap(), ("lo".to_string(), lo));
+        assert_eq!(
+            get_ifindex(&lo.to_string()).unwrap(),
+            ("lo".to_string(), lo)
+        );
+        assert!(matches!(
+            get_ifindex("bpfman-none"),
+            Err(BpfmanError::InvalidInterface)
+        ));
+    }
+
+    #[test]
+    fn test_read() {
+        let dir = TempDir::new().expect("unable to create temp dir");
+        let file = dir.path().join("prog.o");
+        fs::write(&file, vec![7u8; 100_000]).expect("unable to write file");
+
+        let data = read(&file, None).unwrap();
+        assert_eq!(data.len(), 100_000);
+        assert_eq!(data.capacity(), 100_000);
+        assert_eq!(read(&file, Some(100_000)).unwrap(), data);
+        assert!(matches!(
+            read(&file, Some(99_999)),
+            Err(BpfmanError::TooLarge { max: 99_999, .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_dir_permissions() {
+        let dir = TempDir::new().expect("unable to create temp dir");
+        let file = dir.path().join("pin");
+        fs::write(&file, b"").expect("unable to write file");
+
+        let path = dir.path().to_str().unwrap();
+        assert!(set_dir_permissions(path, 0o0640).is_ok());
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o0640);
+
+        let missing = dir.path().join("missing");
+        assert!(set_dir_permissions(missing.to_str().unwrap(), 0o0640).is_err());
+    }
+
+    #[test]
+    fn test_log_tail() {
+        let log = "0: r0 = 0\n1: exit\nR0 invalid\n";
+        assert_eq!(log_tail(log, 100, None), "0: r0 = 0\n1: exit\nR0 invalid");
+        assert_eq!(
+            log_tail(log, 2, None),
+            "[1 earlier lines omitted]\n1: exit\nR0 invalid"
+        );
+        assert_eq!(
+            log_tail(log, 100, Some(12)),
+            "[2 earlier lines omitted]\nR0 invalid"
+        );
+        // The last line is kept even when it is over the limit.
+        assert_eq!(
+            log_tail(log, 100, Some(4)),
+            "[2 earlier lines omitted]\nR0 invalid"
+        );
+    }
+
+    #[test]
+    fn test_fdinfo_memlock() {
+        let fdinfo = "pos:\t0\nflags:\t02000002\nmnt_id:\t15\nino:\t1057\n\
+            map_type:\t1\nkey_size:\t4\nvalue_size:\t8\nmax_entries:\t1024\n\
+            map_flags:\t0x0\nmap_extra:\t0x0\nmemlock:\t86144\nmap_id:\t42\n";
+        assert_eq!(fdinfo_memlock(fdinfo), Some(86144));
+        assert_eq!(fdinfo_memlock("pos:\t0\n"), None);
+    }
+
+    #[test]
+    fn test_missing_caps() {
+        let caps = |have: &'static [Capability]| move |cap| have.contains(&cap);
+        let all = |_| true;
+        let names = |missing: Vec<(Capability, &str)>| -> Vec<Capability> {
+            missing.into_iter().map(|(cap, _)| cap).collect()
+        };
+
+        const ALL: [Capability; 4] = [
+            Capability::CAP_BPF,
+            Capability::CAP_NET_ADMIN,
+            Capability::CAP_SYS_ADMIN,
+            Capability::CAP_SYS_RESOURCE,
+        ];
+        assert!(missing_caps(all, caps(&ALL), true).is_empty());
+        assert_eq!(names(missing_caps(all, caps(&[]), true)), ALL.to_vec());
+
+        // CAP_SYS_RESOURCE is only needed to raise the memlock limit.
+        assert!(missing_caps(all, caps(&ALL[..3]), false).is_empty());
+        // CAP_SYS_ADMIN stands in for CAP_BPF.
+        assert!(missing_caps(
+            all,
+            caps(&[Capability::CAP_NET_ADMIN, Capability::CAP_SYS_ADMIN]),
+            false
+        )
+        .is_empty());
+        // Kernels without CAP_BPF only check CAP_SYS_ADMIN.
+        let no_cap_bpf = |cap| cap != Capability::CAP_BPF;
+        assert_eq!(
+            names(missing_caps(no_cap_bpf, caps(&[]), false)),
+            vec![Capability::CAP_NET_ADMIN, Capability::CAP_SYS_ADMIN]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+// Static checks of a bytecode object: its programs and maps are read from the
+// ELF file alone, without loading anything or asking the kernel, so they can
+// be run in CI on machines without BPF privileges.
+
+use aya_obj::{Object, ProgramSection};
+
+use crate::{
+    elf_section_name,
+    errors::BpfmanError,
+    feature_probe,
+    maps::map_type_name,
+    section_type,
+    types::{BytecodeMap, BytecodeProgram, BytecodeReport},
+};
+
+// Map types that aya can't create: map-in-maps need an inner map and struct
+// ops need a loader of their own.
+const UNSUPPORTED_MAP_TYPES: [u32; 4] = [0, 12, 13, 26];
+
+pub(crate) fn report(bytes: &[u8]) -> Result<BytecodeReport, BpfmanError> {
+    let obj = Object::parse(bytes).map_err(|e| BpfmanError::InvalidBytecode(e.to_string()))?;
+    let mut issues = vec![];
+
+    let mut programs: Vec<BytecodeProgram> = obj
+        .programs
+        .iter()
+        .map(|(name, p)| {
+            if !supported_section(&p.section) {
+                issues.push(format!(
+                    "program {name} has type {}, which bpfman can't load",
+                    section_type(&p.section)
+                ));
+            }
+            BytecodeProgram {
+                name: name.clone(),
+                section: elf_section_name(bytes, name).unwrap_or_default(),
+                program_type: section_type(&p.section),
+            }
+        })
+        .collect();
+    programs.sort_by(|a, b| a.name.cmp(&b.name));
+    if programs.is_empty() {
+        issues.push("the object has no programs".to_string());
+    }
+
+    let mut maps: Vec<BytecodeMap> = obj
+        .maps
+        .iter()
+        .map(|(name, m)| {
+            if let Some(issue) = map_issue(name, m.map_type()) {
+                issues.push(issue);
+            }
+            BytecodeMap {
+                name: name.clone(),
+                map_type: map_type_name(m.map_type()),
+                key_size: m.key_size(),
+                value_size: m.value_size(),
+                max_entries: m.max_entries(),
+            }
+        })
+        .collect();
+    maps.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let btf = obj.btf.is_some();
+    let uses_core = feature_probe::uses_core(bytes);
+    if uses_core && !btf {
+        issues.push("the object has CO-RE relocations but no BTF to resolve them with".to_string());
+    }
+    issues.sort();
+
+    Ok(BytecodeReport {
+        programs,
+        maps,
+        license: obj.license.to_string_lossy().to_string(),
+        btf,
+        uses_core,
+        issues,
+    })
+}
+
+// The sections of programs bpfman knows how to attach.
+fn supported_section(section: &ProgramSection) -> bool {
+    matches!(
+        section,
+        ProgramSection::KProbe
+            | ProgramSection::KRetProbe
+            | ProgramSection::UProbe { .. }
+            | ProgramSection::URetProbe { .. }
+            | ProgramSection::TracePoint
+            | ProgramSection::Xdp { .. }
+            | ProgramSection::SchedClassifier
+            | ProgramSection::FEntry { .. }
+            | ProgramSection::FExit { .. }
+            | ProgramSection::CgroupSkb
+            | ProgramSection::CgroupSkbIngress
+            | ProgramSection::CgroupSkbEgress
+            | ProgramSection::CgroupSock { .. }
+    )
+}
+
+fn map_issue(name: &str, map_type: u32) -> Option<String> {
+    let type_name = map_type_name(map_type);
+    if UNSUPPORTED_MAP_TYPES.contains(&map_type) {
+        Some(format!(
+            "map {name} has type {type_name}, which can't be created"
+        ))
+    } else if type_name == map_type.to_string() {
+        Some(format!("map {name} has unknown map type {map_type}"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_map_issue() {
+        assert_eq!(map_issue("counters", 6), None);
+        assert_eq!(map_issue("events", 27), None);
+        assert_eq!(
+            map_issue("inner", 12),
+            Some("map inner has type array_of_maps, which can't be created".to_string())
+        );
+        assert_eq!(
+            map_issue("future", 99),
+            Some("map future has unknown map type 99".to_string())
+        );
+    }
+}
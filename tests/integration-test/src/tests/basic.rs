@@ -65,7 +65,7 @@ fn test_load_unload_xdp() {
     }
     assert_eq!(loaded_ids.len(), 10);
 
-    assert!(bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(bpffs_has_entries(&RTDIR_FS_XDP));
 
     // Verify rule persistence between restarts
     // drop(bpfman_guard);
@@ -73,7 +73,7 @@ fn test_load_unload_xdp() {
 
     verify_and_delete_programs(loaded_ids);
 
-    assert!(!bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(!bpffs_has_entries(&RTDIR_FS_XDP));
 }
 
 #[integration_test]
@@ -119,10 +119,7 @@ fn test_map_sharing_load_unload_xdp() {
 
     // Load second program, which will share the map with the first program.
     debug!("Installing xdp_counter map sharing program 2");
-    let map_owner_id_u32 = match map_owner_id.as_ref().unwrap().parse() {
-        Ok(v) => Some(v),
-        Err(_) => None,
-    };
+    let map_owner_id_u32 = map_owner_id.as_ref().unwrap().parse().ok();
     let (shared_owner_id, stdout_2) = add_xdp(
         DEFAULT_BPFMAN_IFACE,
         50,   // priority
@@ -238,11 +235,11 @@ fn test_load_unload_tc() {
     assert!(output.contains("pref 50"));
     assert!(output.contains("handle 0x2"));
 
-    assert!(bpffs_has_entries(RTDIR_FS_TC_INGRESS));
+    assert!(bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
 
     verify_and_delete_programs(loaded_ids);
 
-    assert!(!bpffs_has_entries(RTDIR_FS_TC_INGRESS));
+    assert!(!bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
 
     let output = tc_filter_list(DEFAULT_BPFMAN_IFACE).unwrap();
     assert!(output.trim().is_empty());
@@ -443,11 +440,11 @@ fn test_list_with_metadata() {
 
     assert_eq!(loaded_ids.len(), 5);
 
-    assert!(bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(bpffs_has_entries(&RTDIR_FS_XDP));
 
     verify_and_delete_programs(loaded_ids);
 
-    assert!(!bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(!bpffs_has_entries(&RTDIR_FS_XDP));
 }
 
 #[integration_test]
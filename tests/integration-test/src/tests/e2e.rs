@@ -577,7 +577,7 @@ fn test_program_execution_with_global_variables() {
 
     loaded_ids.push(prog_id.unwrap());
 
-    assert!(bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(bpffs_has_entries(&RTDIR_FS_XDP));
 
     debug!("Installing tc ingress program");
     let (prog_id, _) = add_tc(
@@ -593,7 +593,7 @@ fn test_program_execution_with_global_variables() {
 
     loaded_ids.push(prog_id.unwrap());
 
-    assert!(bpffs_has_entries(RTDIR_FS_TC_INGRESS));
+    assert!(bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
 
     debug!("Installing tc egress program");
     let (prog_id, _) = add_tc(
@@ -609,7 +609,7 @@ fn test_program_execution_with_global_variables() {
 
     loaded_ids.push(prog_id.unwrap());
 
-    assert!(bpffs_has_entries(RTDIR_FS_TC_EGRESS));
+    assert!(bpffs_has_entries(&RTDIR_FS_TC_EGRESS));
 
     debug!("Installing tracepoint program");
     let (prog_id, _) = add_tracepoint(
@@ -699,9 +699,9 @@ fn test_program_execution_with_global_variables() {
     verify_and_delete_programs(loaded_ids);
 
     // Verify the bpffs is empty
-    assert!(!bpffs_has_entries(RTDIR_FS_XDP));
-    assert!(!bpffs_has_entries(RTDIR_FS_TC_INGRESS));
-    assert!(!bpffs_has_entries(RTDIR_FS_TC_EGRESS));
+    assert!(!bpffs_has_entries(&RTDIR_FS_XDP));
+    assert!(!bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
+    assert!(!bpffs_has_entries(&RTDIR_FS_TC_EGRESS));
 }
 
 #[integration_test]
@@ -728,7 +728,7 @@ fn test_load_unload_xdp_maps() {
     );
     let binding = stdout.unwrap();
 
-    assert!(bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(bpffs_has_entries(&RTDIR_FS_XDP));
 
     debug!("Verify xdp_counter map pin directory was created, and maps were pinned");
 
@@ -737,7 +737,7 @@ fn test_load_unload_xdp_maps() {
 
     verify_and_delete_programs(vec![prog_id.unwrap()]);
 
-    assert!(!bpffs_has_entries(RTDIR_FS_XDP));
+    assert!(!bpffs_has_entries(&RTDIR_FS_XDP));
 }
 
 #[integration_test]
@@ -762,7 +762,7 @@ fn test_load_unload_tc_maps() {
     );
     let binding = stdout.unwrap();
 
-    assert!(bpffs_has_entries(RTDIR_FS_TC_INGRESS));
+    assert!(bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
 
     debug!("Verify tc_counter map pin directory was created, and maps were pinned");
 
@@ -771,7 +771,7 @@ fn test_load_unload_tc_maps() {
 
     verify_and_delete_programs(vec![prog_id.unwrap()]);
 
-    assert!(!bpffs_has_entries(RTDIR_FS_TC_INGRESS));
+    assert!(!bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
 }
 
 #[integration_test]
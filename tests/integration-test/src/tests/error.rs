@@ -823,7 +823,7 @@ fn test_invalid_parameters() {
     // Cleanup Installed Programs
     verify_and_delete_programs(loaded_ids);
 
-    assert!(!bpffs_has_entries(RTDIR_FS_XDP));
-    assert!(!bpffs_has_entries(RTDIR_FS_TC_INGRESS));
-    assert!(!bpffs_has_entries(RTDIR_FS_TC_EGRESS));
+    assert!(!bpffs_has_entries(&RTDIR_FS_XDP));
+    assert!(!bpffs_has_entries(&RTDIR_FS_TC_INGRESS));
+    assert!(!bpffs_has_entries(&RTDIR_FS_TC_EGRESS));
 }
@@ -3,6 +3,9 @@ pub mod e2e;
 pub mod error;
 pub mod utils;
 
+use std::sync::LazyLock;
+
+use bpfman::utils::open_config_file;
 pub use integration_test_macros::integration_test;
 
 #[derive(Debug)]
@@ -11,9 +14,16 @@ pub struct IntegrationTest {
     pub test_fn: fn(),
 }
 
-pub(crate) const RTDIR_FS_MAPS: &str = "/run/bpfman/fs/maps";
-pub(crate) const RTDIR_FS_TC_INGRESS: &str = "/run/bpfman/fs/tc-ingress";
-pub(crate) const RTDIR_FS_XDP: &str = "/run/bpfman/fs/xdp";
-pub(crate) const RTDIR_FS_TC_EGRESS: &str = "/run/bpfman/fs/tc-egress";
+// The directories under the bpffs set by [database] dir in the config of the
+// bpfman under test.
+pub(crate) static RTDIR_FS_MAPS: LazyLock<String> = LazyLock::new(|| rtdir_fs("maps"));
+pub(crate) static RTDIR_FS_TC_INGRESS: LazyLock<String> = LazyLock::new(|| rtdir_fs("tc-ingress"));
+pub(crate) static RTDIR_FS_XDP: LazyLock<String> = LazyLock::new(|| rtdir_fs("xdp"));
+pub(crate) static RTDIR_FS_TC_EGRESS: LazyLock<String> = LazyLock::new(|| rtdir_fs("tc-egress"));
+
+fn rtdir_fs(dir: &str) -> String {
+    let database = open_config_file().database().clone().unwrap_or_default();
+    format!("{}/{dir}", database.dir.display())
+}
 
 inventory::collect!(IntegrationTest);